@@ -40,7 +40,9 @@ pub const COLOR_MAP_BRIGHT: [(u32, Color); 8] = [
     (7, Color::RGB(255, 255, 255)), // Bright White
 ];
 
-pub fn parse_m(ansi_code: &str) -> ([Option<Color>; 2], Option<TextAttributes>) {
+/// Parse an SGR sequence. Attributes are applied on top of `current`, so e.g. underline
+/// doesn't drop an earlier bold; only reset (0) clears them all.
+pub fn parse_m(ansi_code: &str, current: TextAttributes) -> ([Option<Color>; 2], Option<TextAttributes>) {
     // TODO: can we make it a constant?
     let color_map_16: HashMap<u32, Color> = COLOR_MAP_16.iter().map(|&(code, color)| (code, color)).collect();
 
@@ -48,7 +50,7 @@ pub fn parse_m(ansi_code: &str) -> ([Option<Color>; 2], Option<TextAttributes>)
 
     let mut fg_color = None;
     let mut bg_color = None;
-    let mut attrs = TextAttributes::default();
+    let mut attrs = current;
     let mut attrs_modified = false;
 
     let ansi_code_parts: Vec<&str> = ansi_code.trim_start_matches("\x1b[").trim_end_matches("m").split(';').collect();
@@ -105,8 +107,8 @@ pub fn parse_m(ansi_code: &str) -> ([Option<Color>; 2], Option<TextAttributes>)
                 attrs_modified = true;
                 i += 1;
             }
-            5 => {
-                /* Slow blink */
+            5 | 6 => {
+                /* Slow or rapid blink */
                 attrs.blink = true;
                 attrs_modified = true;
                 i += 1;
@@ -134,7 +136,7 @@ pub fn parse_m(ansi_code: &str) -> ([Option<Color>; 2], Option<TextAttributes>)
                 i += 1;
             }
             22 => {
-                /* Normal intensity (neither bold nor faint) */
+                /* Normal intensity (neither bold nor faint); faint is already drawn as non-bold */
                 attrs.bold = false;
                 attrs_modified = true;
                 i += 1;
@@ -152,7 +154,7 @@ pub fn parse_m(ansi_code: &str) -> ([Option<Color>; 2], Option<TextAttributes>)
                 i += 1;
             }
             25 => {
-                /* Blink off */
+                /* Blink off (slow and rapid) */
                 attrs.blink = false;
                 attrs_modified = true;
                 i += 1;
@@ -356,3 +358,105 @@ pub fn parse_scroll_region(ansi_code: &str) -> [i32; 2] {
 
     [top, bottom]
 }
+
+/// Parse an X11-style color specification as used by OSC color sequences.
+/// Accepts `#RGB`, `#RRGGBB` and `rgb:R/G/B` with 1 to 4 hex digits per component.
+pub fn parse_color_spec(spec: &str) -> Option<Color> {
+    let spec = spec.trim();
+
+    if let Some(hex) = spec.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        return match hex.len() {
+            3 => {
+                let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|v| v * 17);
+                Some(Color::RGB(digit(0)?, digit(1)?, digit(2)?))
+            }
+            6 => {
+                let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+                Some(Color::RGB(byte(0)?, byte(2)?, byte(4)?))
+            }
+            _ => None,
+        };
+    }
+
+    let components: Vec<&str> = spec.strip_prefix("rgb:")?.split('/').collect();
+    if components.len() != 3 {
+        return None;
+    }
+
+    // Scale each component to 8 bits according to how many digits it has
+    let scale = |component: &str| -> Option<u8> {
+        if component.is_empty() || component.len() > 4 || !component.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1u32 << (4 * component.len())) - 1;
+        Some((value * 255 / max) as u8)
+    };
+
+    Some(Color::RGB(scale(components[0])?, scale(components[1])?, scale(components[2])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_spec() {
+        assert_eq!(parse_color_spec("#ff8000"), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(parse_color_spec("#f80"), Some(Color::RGB(255, 136, 0)));
+        assert_eq!(parse_color_spec("rgb:ff/80/00"), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(parse_color_spec("rgb:ffff/0000/8080"), Some(Color::RGB(255, 0, 128)));
+        assert_eq!(parse_color_spec("rgb:f/0/8"), Some(Color::RGB(255, 0, 136)));
+
+        assert_eq!(parse_color_spec("#ff80"), None);
+        assert_eq!(parse_color_spec("#gg0000"), None);
+        assert_eq!(parse_color_spec("rgb:ff/80"), None);
+        assert_eq!(parse_color_spec("rgb:fffff/0/0"), None);
+        assert_eq!(parse_color_spec("red"), None);
+    }
+
+    #[test]
+    fn test_partial_sgr_resets() {
+        let (_, attrs) = parse_m("\x1b[1;4m", TextAttributes::default());
+        let bold_underline = attrs.unwrap();
+
+        let (colors, attrs) = parse_m("\x1b[24m", bold_underline);
+        let attrs = attrs.unwrap();
+        assert!(attrs.bold);
+        assert!(!attrs.underline);
+        assert_eq!(colors, [None, None], "resetting an attribute leaves the colors alone");
+
+        // Each reset clears exactly its own attribute
+        let (_, all) = parse_m("\x1b[1;3;4;5;7;8;9m", TextAttributes::default());
+        let all = all.unwrap();
+        for code in [22, 23, 24, 25, 27, 28, 29] {
+            let attrs = parse_m(&format!("\x1b[{}m", code), all).1.unwrap();
+            let cleared = match code {
+                22 => !attrs.bold,
+                23 => !attrs.italic,
+                24 => !attrs.underline,
+                25 => !attrs.blink,
+                27 => !attrs.reverse,
+                28 => !attrs.invisible,
+                _ => !attrs.strikethrough,
+            };
+            assert!(cleared, "SGR {} should clear its attribute", code);
+            let set_count = [
+                attrs.bold,
+                attrs.italic,
+                attrs.underline,
+                attrs.blink,
+                attrs.reverse,
+                attrs.invisible,
+                attrs.strikethrough,
+            ]
+            .iter()
+            .filter(|&&set| set)
+            .count();
+            assert_eq!(set_count, 6, "SGR {} should leave the other attributes set", code);
+        }
+    }
+}
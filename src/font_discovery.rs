@@ -158,6 +158,35 @@ const PREFERRED_CJK_FONTS: &[&str] = &[
     "ukai.ttc",
 ];
 
+/// Style words that may follow the family in a font file name, e.g. "Hack-BoldItalic.ttf".
+/// Abbreviations come after the words they are part of, so the longest match is taken first.
+const STYLE_WORDS: &[&str] = &[
+    "regular",
+    "normal",
+    "roman",
+    "book",
+    "bold",
+    "italic",
+    "oblique",
+    "semibold",
+    "demibold",
+    "extrabold",
+    "ultrabold",
+    "extralight",
+    "ultralight",
+    "light",
+    "thin",
+    "medium",
+    "black",
+    "heavy",
+    "semi",
+    "demi",
+    "extra",
+    "ultra",
+    "bd",
+    "it",
+];
+
 /// Common font directories on Windows, Linux, and macOS systems
 const FONT_DIRECTORIES: &[&str] = &[
     // Windows paths
@@ -331,6 +360,104 @@ pub fn find_cjk_font() -> Option<String> {
     None
 }
 
+/// Finds a font file by family name, e.g. "JetBrains Mono", matching file names of the
+/// form `<family>[-_ ]<style>.ttf|otf|ttc` in the font directories. Style words at the end
+/// of the name ("Fira Code Bold Italic") choose the bold and italic variants; otherwise
+/// the regular face is preferred.
+///
+/// # Returns
+///
+/// The full path to the closest matching font file, or None if no file matches the family
+pub fn find_font_by_family(name: &str) -> Option<String> {
+    let search_paths: Vec<PathBuf> = FONT_DIRECTORIES.iter().filter_map(|dir| expand_home_dir(dir)).collect();
+
+    let font_path = find_family_in(&search_paths, name)?;
+    eprintln!("[FONT] Resolved font family \"{}\" to {}", name, font_path.display());
+    Some(font_path.to_string_lossy().to_string())
+}
+
+/// Family lookup behind `find_font_by_family`, over the given directories
+fn find_family_in(search_paths: &[PathBuf], name: &str) -> Option<PathBuf> {
+    // Split trailing style words off the requested name
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    let (mut bold, mut italic) = (false, false);
+    while let Some(word) = words.last().map(|word| word.to_lowercase()) {
+        match word.as_str() {
+            "bold" => bold = true,
+            "italic" | "oblique" => italic = true,
+            "regular" => {}
+            _ => break,
+        }
+        words.pop();
+    }
+    let family = normalize_font_name(&words.concat());
+    if family.is_empty() {
+        return None;
+    }
+
+    let mut files = Vec::new();
+    for base_path in search_paths {
+        collect_font_files(base_path, &mut files);
+    }
+
+    // Rank the faces of the family: wrong bold or italic counts most, any other weight a little
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            let stem = stem.split('[').next().unwrap_or_default();
+            let style = normalize_font_name(stem).strip_prefix(&family)?.to_string();
+            let style_words = split_style_words(&style)?;
+
+            let is_bold = style_words.iter().any(|word| matches!(*word, "bold" | "bd"));
+            let is_italic = style_words.iter().any(|word| matches!(*word, "italic" | "oblique" | "it"));
+            let is_other_weight = style_words
+                .iter()
+                .any(|word| !matches!(*word, "regular" | "normal" | "roman" | "book" | "bold" | "bd" | "italic" | "oblique" | "it"));
+            let score = 2 * (is_bold != bold) as u32 + 2 * (is_italic != italic) as u32 + is_other_weight as u32;
+            Some((score, path))
+        })
+        .min()
+        .map(|(_, path)| path)
+}
+
+/// Lowercase a font name and drop everything but letters and digits, so "JetBrains Mono",
+/// "JetBrainsMono" and "jetbrains_mono" compare equal
+fn normalize_font_name(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// Split a normalized style suffix like "bolditalic" into style words, or None if anything
+/// else is left, as in "nlregular" (a different family)
+fn split_style_words(mut style: &str) -> Option<Vec<&'static str>> {
+    let mut words = Vec::new();
+    while !style.is_empty() {
+        let word = STYLE_WORDS.iter().filter(|word| style.starts_with(**word)).max_by_key(|word| word.len())?;
+        words.push(*word);
+        style = &style[word.len()..];
+    }
+    Some(words)
+}
+
+/// Collects all font files (.ttf, .otf, .ttc) under a directory tree
+fn collect_font_files(base_path: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(base_path) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_font_files(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ["ttf", "otf", "ttc"].iter().any(|known| ext.eq_ignore_ascii_case(known)))
+        {
+            files.push(path);
+        }
+    }
+}
+
 /// Recursively searches for a font file in a directory tree
 ///
 /// # Arguments
@@ -459,6 +586,42 @@ mod tests {
         assert_eq!(expand_home_dir("/usr/share/fonts").unwrap(), PathBuf::from("/usr/share/fonts"));
     }
 
+    #[test]
+    fn test_find_family_in_fixture_directory() {
+        let dir = std::env::temp_dir().join(format!("nist-font-family-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("jetbrains")).unwrap();
+        for file in [
+            "jetbrains/JetBrainsMono-Regular.ttf",
+            "jetbrains/JetBrainsMono-Bold.ttf",
+            "jetbrains/JetBrainsMono-BoldItalic.ttf",
+            "jetbrains/JetBrainsMono-ExtraLight.ttf",
+            "jetbrains/JetBrainsMonoNL-Regular.ttf",
+            "Courier New.ttf",
+            "Courier New Italic.ttf",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        let dirs = [dir.clone()];
+
+        assert_eq!(find_family_in(&dirs, "JetBrains Mono"), Some(dir.join("jetbrains/JetBrainsMono-Regular.ttf")));
+        assert_eq!(find_family_in(&dirs, "jetbrains mono bold"), Some(dir.join("jetbrains/JetBrainsMono-Bold.ttf")));
+        assert_eq!(
+            find_family_in(&dirs, "JetBrains Mono Bold Italic"),
+            Some(dir.join("jetbrains/JetBrainsMono-BoldItalic.ttf"))
+        );
+        assert_eq!(
+            find_family_in(&dirs, "JetBrains Mono NL"),
+            Some(dir.join("jetbrains/JetBrainsMonoNL-Regular.ttf"))
+        );
+        assert_eq!(find_family_in(&dirs, "Courier New Italic"), Some(dir.join("Courier New Italic.ttf")));
+        assert_eq!(find_family_in(&dirs, "JetBrains"), None);
+        assert_eq!(find_family_in(&dirs, "Fira Code"), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_find_best_font_returns_path_or_none() {
         // This test will return Some with a .ttf path, or None if no fonts are found
@@ -3,157 +3,198 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-/// Read shell history for cross-platform support
-pub fn read_shell_history(max_entries: usize) -> Vec<String> {
-    let shell = env::var("SHELL").unwrap_or_else(|_| {
+/// On-disk history formats of the supported shells
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HistoryFormat {
+    /// One command per line (bash, PowerShell), optionally with `#<timestamp>` lines
+    Plain,
+    /// zsh, where lines may use the extended `: <timestamp>:<duration>;<command>` format
+    Zsh,
+    /// fish, a YAML-like list of `- cmd: <command>` entries
+    Fish,
+}
+
+/// Read the most recent `max_entries` distinct commands from the history file of the given shell.
+/// The shell is detected from the command used to start it (e.g. "/usr/bin/fish" or "zsh").
+/// Returns commands with newest first.
+pub fn read_shell_history(shell_command: &str, max_entries: usize) -> Vec<String> {
+    let shell_name = Path::new(shell_command)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("bash")
+        .to_ascii_lowercase();
+    eprintln!("[HISTORY] Reading history for shell: {} (from {})", shell_name, shell_command);
+
+    let Some((path, format)) = history_file(&shell_name) else {
+        // CMD doesn't have a persistent history file, the caller falls back to the terminal's own history
+        return Vec::new();
+    };
+
+    // Shells may write bytes that are not valid UTF-8 (zsh metafies them), so decode lossily
+    let content = match fs::read(&path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => {
+            eprintln!("[HISTORY] Failed to read history file {:?}: {}", path, e);
+            return Vec::new();
+        }
+    };
+
+    let entries = match format {
+        HistoryFormat::Plain => parse_plain_history(&content),
+        HistoryFormat::Zsh => parse_zsh_history(&content),
+        HistoryFormat::Fish => parse_fish_history(&content),
+    };
+    most_recent_unique(entries, max_entries)
+}
+
+/// The user's login shell from $SHELL, for when no terminal is at hand
+pub fn login_shell() -> String {
+    env::var("SHELL").unwrap_or_else(|_| {
         // Default based on OS
         if cfg!(windows) {
             "cmd.exe".to_string()
         } else {
             "/bin/bash".to_string()
         }
-    });
-
-    let shell_name = Path::new(&shell).file_name().and_then(|s| s.to_str()).unwrap_or("bash");
-    eprintln!("[HISTORY] Reading history for shell: {} (from $SHELL={})", shell_name, shell);
+    })
+}
 
-    match shell_name {
-        "bash" => read_bash_history(max_entries),
-        "zsh" => read_zsh_history(max_entries),
-        "fish" => read_fish_history(max_entries),
-        "powershell" | "pwsh" => read_powershell_history(max_entries),
-        "cmd.exe" | "cmd" => read_cmd_history(max_entries),
-        _ => read_bash_history(max_entries), // Fallback
+/// Location and format of the history file for a shell name, None if the shell keeps no history file
+fn history_file(shell_name: &str) -> Option<(PathBuf, HistoryFormat)> {
+    if matches!(shell_name, "cmd") {
+        return None;
     }
-}
 
-/// Read bash history from ~/.bash_history
-fn read_bash_history(max_entries: usize) -> Vec<String> {
-    let home = match env::var("HOME") {
-        Ok(h) => h,
+    let home_var = if cfg!(windows) && matches!(shell_name, "powershell" | "pwsh") {
+        "USERPROFILE"
+    } else {
+        "HOME"
+    };
+    let home = match env::var(home_var) {
+        Ok(h) => PathBuf::from(h),
         Err(_) => {
-            eprintln!("[HISTORY] HOME environment variable not set");
-            return Vec::new();
+            eprintln!("[HISTORY] {} environment variable not set", home_var);
+            return None;
         }
     };
-    let path = PathBuf::from(home).join(".bash_history");
-    read_history_file(&path, max_entries)
+
+    Some(match shell_name {
+        "zsh" => (home.join(".zsh_history"), HistoryFormat::Zsh),
+        "fish" => (home.join(".local/share/fish/fish_history"), HistoryFormat::Fish),
+        "powershell" | "pwsh" => (
+            home.join("AppData/Roaming/Microsoft/Windows/PowerShell/PSReadline/ConsoleHost_history.txt"),
+            HistoryFormat::Plain,
+        ),
+        _ => (home.join(".bash_history"), HistoryFormat::Plain), // bash, sh and fallback
+    })
 }
 
-/// Read zsh history from ~/.zsh_history
-fn read_zsh_history(max_entries: usize) -> Vec<String> {
-    let home = match env::var("HOME") {
-        Ok(h) => h,
-        Err(_) => return Vec::new(),
-    };
-    let path = PathBuf::from(home).join(".zsh_history");
-    let mut entries = Vec::new();
+/// Parse a plain history file, oldest first
+fn parse_plain_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        // Lines starting with '#' are timestamps written by bash with HISTTIMEFORMAT
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        // Clean numbered history entries (e.g., " 1747  nist -v" -> "nist -v")
+        .map(clean_history_line)
+        .filter(|cmd| !cmd.is_empty())
+        .collect()
+}
 
-    if let Ok(content) = fs::read_to_string(&path) {
-        for line in content.lines().rev() {
-            if entries.len() >= max_entries {
-                break;
+/// Parse a zsh history file, oldest first.
+/// Handles both plain lines and the extended `: <timestamp>:<duration>;<command>` format,
+/// including multi-line commands whose lines end with a backslash.
+fn parse_zsh_history(content: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        let part = match current.take() {
+            Some(mut command) => {
+                command.push('\n');
+                command.push_str(line);
+                command
             }
+            None => strip_zsh_extended_prefix(line).to_string(),
+        };
 
-            // Zsh history format: : <timestamp>:<duration>;<command>
-            // Extract the command part after the semicolon
-            if let Some(cmd) = line.split(';').nth(1) {
-                let cmd = cmd.trim();
-                if !cmd.is_empty() && !cmd.starts_with('#') {
-                    entries.push(cmd.to_string());
-                }
-            }
+        if let Some(continued) = part.strip_suffix('\\') {
+            current = Some(continued.to_string());
+            continue;
         }
-    }
 
-    entries
-}
-
-/// Read fish history from ~/.local/share/fish/fish_history
-fn read_fish_history(max_entries: usize) -> Vec<String> {
-    let home = match env::var("HOME") {
-        Ok(h) => h,
-        Err(_) => return Vec::new(),
-    };
-    let path = PathBuf::from(home).join(".local/share/fish/fish_history");
-    let mut entries = Vec::new();
-
-    if let Ok(content) = fs::read_to_string(&path) {
-        for line in content.lines() {
-            if entries.len() >= max_entries {
-                break;
-            }
+        let command = part.trim();
+        if !command.is_empty() {
+            entries.push(command.to_string());
+        }
+    }
 
-            if line.starts_with("- cmd:") {
-                if let Some(cmd) = line.strip_prefix("- cmd:") {
-                    let cmd = cmd.trim().trim_matches('"');
-                    if !cmd.is_empty() {
-                        entries.push(cmd.to_string());
-                    }
-                }
-            }
+    if let Some(command) = current {
+        let command = command.trim();
+        if !command.is_empty() {
+            entries.push(command.to_string());
         }
     }
 
     entries
 }
 
-/// Read PowerShell history
-fn read_powershell_history(max_entries: usize) -> Vec<String> {
-    let home = if cfg!(windows) {
-        match env::var("USERPROFILE") {
-            Ok(h) => h,
-            Err(_) => return Vec::new(),
-        }
-    } else {
-        match env::var("HOME") {
-            Ok(h) => h,
-            Err(_) => return Vec::new(),
-        }
+/// Strip the `: <timestamp>:<duration>;` prefix of an extended zsh history line
+fn strip_zsh_extended_prefix(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix(": ") else {
+        return line;
     };
-
-    let path = PathBuf::from(home).join("AppData/Roaming/Microsoft/Windows/PowerShell/PSReadline/ConsoleHost_history.txt");
-
-    read_history_file(&path, max_entries)
+    let Some((meta, command)) = rest.split_once(';') else {
+        return line;
+    };
+    let is_meta = meta.split_once(':').is_some_and(|(timestamp, duration)| {
+        !timestamp.is_empty() && timestamp.chars().all(|c| c.is_ascii_digit()) && duration.chars().all(|c| c.is_ascii_digit())
+    });
+    if is_meta {
+        command
+    } else {
+        line
+    }
 }
 
-/// Read Windows CMD history (limited support)
-fn read_cmd_history(_max_entries: usize) -> Vec<String> {
-    // CMD doesn't have a persistent history file by default
-    // Return empty Vec - will fall back to terminal's private history
-    Vec::new()
+/// Parse a fish history file, oldest first.
+/// Each entry is `- cmd: <command>` followed by indented `when:` and `paths:` fields;
+/// newlines and backslashes inside commands are escaped as `\n` and `\\`.
+fn parse_fish_history(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(unescape_fish_command)
+        .filter(|cmd| !cmd.trim().is_empty())
+        .collect()
 }
 
-/// Generic history file reader (reads from end, most recent first)
-fn read_history_file(path: &Path, max_entries: usize) -> Vec<String> {
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("[HISTORY] Failed to read history file {:?}: {}", path, e);
-            return Vec::new();
-        }
-    };
-
-    let mut entries = Vec::new();
-
-    // Read from end to get most recent entries
-    for line in content.lines().rev() {
-        let line: &str = line.trim();
-        if line.is_empty() || line.starts_with('#') {
+fn unescape_fish_command(escaped: &str) -> String {
+    let mut command = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            command.push(ch);
             continue;
         }
-
-        // Clean numbered history entries (e.g., " 1747  nist -v" -> "nist -v")
-        let cleaned = clean_history_line(line);
-        if !cleaned.is_empty() {
-            entries.push(cleaned);
-            if entries.len() >= max_entries {
-                break;
+        match chars.next() {
+            Some('n') => command.push('\n'),
+            Some('\\') => command.push('\\'),
+            Some(other) => {
+                command.push('\\');
+                command.push(other);
             }
+            None => command.push('\\'),
         }
     }
+    command
+}
 
-    entries
+/// Keep the newest occurrence of each command, returning at most `max_entries` newest first
+fn most_recent_unique(entries: Vec<String>, max_entries: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    entries.into_iter().rev().filter(|cmd| seen.insert(cmd.clone())).take(max_entries).collect()
 }
 
 /// Clean a history line by removing numbered prefixes
@@ -248,10 +289,66 @@ mod tests {
 
     #[test]
     fn test_read_shell_history() {
-        // Just ensure it doesn't panic
-        let history = read_shell_history(10);
-        // history is Vec<String>, not Result
-        assert!(history.len() >= 0);
+        // Just ensure it doesn't panic, whatever shells are installed
+        for shell in ["bash", "/usr/bin/zsh", "fish", "cmd.exe"] {
+            let history = read_shell_history(shell, 10);
+            assert!(history.len() <= 10);
+        }
+    }
+
+    const BASH_FIXTURE: &str = "ls -la\n#1700000000\ncd /tmp\n 1747  nist -v\nls -la\n\ngit status\n";
+
+    const ZSH_FIXTURE: &str = ": 1700000000:0;ls -la
+: 1700000005:2;for f in *; do echo $f; done
+plain command
+: 1700000010:0;echo one \\
+two
+: 1700000020:0;ls -la
+";
+
+    const FISH_FIXTURE: &str = "- cmd: ls -la
+  when: 1700000000
+- cmd: cd ~/projects
+  when: 1700000005
+  paths:
+    - ~/projects
+- cmd: echo \"a\\\\b\"\\nprintf done
+  when: 1700000010
+- cmd: ls -la
+  when: 1700000020
+";
+
+    #[test]
+    fn test_parse_bash_history() {
+        assert_eq!(parse_plain_history(BASH_FIXTURE), vec!["ls -la", "cd /tmp", "nist -v", "ls -la", "git status"]);
+    }
+
+    #[test]
+    fn test_parse_zsh_history() {
+        assert_eq!(
+            parse_zsh_history(ZSH_FIXTURE),
+            vec!["ls -la", "for f in *; do echo $f; done", "plain command", "echo one \ntwo", "ls -la"]
+        );
+        // Something that only looks like a prefix is kept
+        assert_eq!(strip_zsh_extended_prefix(": not;meta"), ": not;meta");
+    }
+
+    #[test]
+    fn test_parse_fish_history() {
+        assert_eq!(
+            parse_fish_history(FISH_FIXTURE),
+            vec!["ls -la", "cd ~/projects", "echo \"a\\b\"\nprintf done", "ls -la"]
+        );
+    }
+
+    #[test]
+    fn test_most_recent_unique() {
+        let entries = parse_zsh_history(ZSH_FIXTURE);
+        assert_eq!(
+            most_recent_unique(entries.clone(), 10),
+            vec!["ls -la", "echo one \ntwo", "plain command", "for f in *; do echo $f; done"]
+        );
+        assert_eq!(most_recent_unique(entries, 2), vec!["ls -la", "echo one \ntwo"]);
     }
 
     #[test]
@@ -5,13 +5,9 @@ use super::keyboard::KeyboardAction;
 use super::mouse::{MouseAction, MouseState};
 use crate::sdl_renderer::TabBar;
 use crate::settings::Settings;
+use crate::system::clipboard::ClipboardProvider;
 use crate::tab_gui::TabBarGui;
 
-#[cfg(target_os = "linux")]
-use arboard::Clipboard;
-#[cfg(target_os = "linux")]
-use std::sync::mpsc::Sender;
-
 /// Actions that can be requested from event handling
 #[derive(Debug, Clone)]
 pub enum EventAction {
@@ -19,9 +15,14 @@ pub enum EventAction {
     Quit,
     NewTab,
     SplitPane(crate::pane_layout::SplitDirection),
+    DuplicatePane,
+    DuplicateTab,
     CloseTab(usize),
+    CloseTabs(Vec<usize>, usize),
     SwitchTab(usize),
     MinimizeWindow,
+    /// Minimize the window, or bring it back if it is minimized
+    ToggleWindow,
     Resize,
     StartTextInput,
     StopTextInput,
@@ -29,6 +30,10 @@ pub enum EventAction {
     ChangeFontSize(f32),
     TerminalHistorySearch,
     AiCommandGeneration,
+    /// Multi-line or very large paste that needs the user's confirmation before it is sent
+    RequestPasteConfirmation(String),
+    /// Closing the active pane would kill a running job, ask first
+    RequestClosePaneConfirmation,
     None,
 }
 
@@ -80,7 +85,7 @@ pub fn handle_event(
     canvas_window: &sdl3::video::Window,
     event_pump: &sdl3::EventPump,
     settings: &Settings,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
 ) -> EventResult {
     match event {
         Event::Quit { .. } => EventResult::quit(),
@@ -105,8 +110,8 @@ pub fn handle_event(
             tab_bar_height,
             canvas_window,
             event_pump,
-            #[cfg(target_os = "linux")]
-            clipboard_tx,
+            settings,
+            clipboard,
         ),
 
         Event::MouseButtonUp { mouse_btn, x, y, .. } => handle_mouse_button_up_event(
@@ -122,8 +127,8 @@ pub fn handle_event(
             char_height,
             tab_bar_height,
             canvas_window,
-            #[cfg(target_os = "linux")]
-            clipboard_tx,
+            clipboard,
+            settings,
         ),
 
         Event::MouseMotion { x, y, .. } => handle_mouse_motion_event(
@@ -166,12 +171,13 @@ pub fn handle_event(
             tab_bar_height,
             canvas_window,
             settings,
-            #[cfg(target_os = "linux")]
-            clipboard_tx,
+            clipboard,
         ),
 
         Event::TextInput { ref text, .. } => handle_text_input_event(text, tab_bar, tab_bar_gui),
 
+        Event::TextEditing { ref text, start, .. } => handle_text_editing_event(text, *start, tab_bar, tab_bar_gui),
+
         _ => EventResult::none(),
     }
 }
@@ -191,7 +197,8 @@ fn handle_mouse_button_down_event(
     tab_bar_height: u32,
     canvas_window: &sdl3::video::Window,
     event_pump: &sdl3::EventPump,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    settings: &Settings,
+    clipboard: &mut dyn ClipboardProvider,
 ) -> EventResult {
     let (mouse_x, mouse_y) = if mouse_coords_need_scaling {
         ((x as f32 * scale_factor) as i32, (y as f32 * scale_factor) as i32)
@@ -215,19 +222,12 @@ fn handle_mouse_button_down_event(
         h,
         mouse_state,
         event_pump,
-        #[cfg(target_os = "linux")]
-        clipboard_tx,
+        clipboard,
     );
 
-    // Map mouse action to event action
     let event_action = match result.action {
-        MouseAction::CloseWindow => EventAction::Quit,
-        MouseAction::MinimizeWindow => EventAction::MinimizeWindow,
-        MouseAction::NewTab => EventAction::NewTab,
-        MouseAction::CloseTab(idx) => EventAction::CloseTab(idx),
-        MouseAction::SwitchTab(idx) => EventAction::SwitchTab(idx),
-        MouseAction::OpenSettings => EventAction::OpenSettings,
-        MouseAction::None => EventAction::None,
+        MouseAction::Paste(text) => dispatch_paste(text, settings, tab_bar_gui),
+        action => mouse_event_action(action),
     };
 
     // Check if we need to start text input for tab editing
@@ -253,6 +253,21 @@ fn handle_mouse_button_down_event(
     }
 }
 
+/// Map mouse action to event action
+fn mouse_event_action(action: MouseAction) -> EventAction {
+    match action {
+        MouseAction::CloseWindow => EventAction::Quit,
+        MouseAction::MinimizeWindow => EventAction::MinimizeWindow,
+        MouseAction::NewTab => EventAction::NewTab,
+        MouseAction::CloseTab(idx) => EventAction::CloseTab(idx),
+        MouseAction::CloseTabs(indices, keep) => EventAction::CloseTabs(indices, keep),
+        MouseAction::SwitchTab(idx) => EventAction::SwitchTab(idx),
+        MouseAction::OpenSettings => EventAction::OpenSettings,
+        // Pastes need the settings and go through dispatch_paste instead
+        MouseAction::Paste(_) | MouseAction::None => EventAction::None,
+    }
+}
+
 fn handle_mouse_button_up_event(
     mouse_btn: sdl3::mouse::MouseButton,
     x: i32,
@@ -266,7 +281,8 @@ fn handle_mouse_button_up_event(
     char_height: f32,
     tab_bar_height: u32,
     canvas_window: &sdl3::video::Window,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
+    settings: &Settings,
 ) -> EventResult {
     let (mouse_x, mouse_y) = if mouse_coords_need_scaling {
         ((x as f32 * scale_factor) as i32, (y as f32 * scale_factor) as i32)
@@ -288,15 +304,15 @@ fn handle_mouse_button_up_event(
         w,
         h,
         mouse_state,
-        #[cfg(target_os = "linux")]
-        clipboard_tx,
+        clipboard,
+        settings.ui.copy_animation,
     );
 
     // Check if we need to resize after divider drag
     let needs_resize = result.needs_render && !mouse_state.dragging_divider;
 
     EventResult {
-        action: EventAction::None,
+        action: mouse_event_action(result.action),
         needs_render: result.needs_render,
         needs_resize,
     }
@@ -402,7 +418,7 @@ fn handle_key_down_event(
     tab_bar_height: u32,
     canvas_window: &sdl3::video::Window,
     settings: &Settings,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
 ) -> EventResult {
     let Some(keycode) = keycode else {
         return EventResult::none();
@@ -410,6 +426,9 @@ fn handle_key_down_event(
 
     let (is_ctrl_pressed, is_shift_pressed, is_alt_pressed) = super::hotkeys::get_modifiers(keymod);
 
+    // Only text input belonging to the previous Alt+key press is dropped
+    tab_bar.suppress_next_text_input = false;
+
     // Handle tab editing mode
     if tab_bar.editing_tab.is_some() {
         let result = super::keyboard::handle_tab_editing_key(keycode, tab_bar, tab_bar_gui);
@@ -444,8 +463,8 @@ fn handle_key_down_event(
             char_height,
             tab_bar_height,
             canvas_window,
-            #[cfg(target_os = "linux")]
-            clipboard_tx,
+            clipboard,
+            settings.ui.copy_animation,
         );
 
         return EventResult {
@@ -469,8 +488,8 @@ fn handle_key_down_event(
             char_height,
             tab_bar_height,
             canvas_window,
-            #[cfg(target_os = "linux")]
-            clipboard_tx,
+            clipboard,
+            settings.ui.copy_animation,
         );
 
         return EventResult {
@@ -527,18 +546,16 @@ fn handle_key_down_event(
 
             use super::hotkeys::NavigationAction;
 
-            // Map navigation action to keyboard action
-            let keyboard_action = match nav_action {
-                NavigationAction::SplitRight => super::keyboard::KeyboardAction::SplitPane(crate::pane_layout::SplitDirection::Vertical),
-                NavigationAction::SplitDown => super::keyboard::KeyboardAction::SplitPane(crate::pane_layout::SplitDirection::Horizontal),
-                NavigationAction::ClosePane => super::keyboard::KeyboardAction::None, // Will be handled below
-                NavigationAction::NextPane | NavigationAction::PreviousPane => super::keyboard::KeyboardAction::None, // Will be handled below
-                NavigationAction::NewTab => super::keyboard::KeyboardAction::NewTab,
-                NavigationAction::NextTab | NavigationAction::PreviousTab => super::keyboard::KeyboardAction::None, // Will be handled below
-                NavigationAction::GoToPrompt => super::keyboard::KeyboardAction::None,                              // Will be handled below
-                NavigationAction::TerminalHistorySearch => super::keyboard::KeyboardAction::RequestTerminalHistorySearch,
-                NavigationAction::AiCommandGeneration => super::keyboard::KeyboardAction::RequestAiCommandGeneration,
-            };
+            if nav_action == NavigationAction::ClosePane
+                && settings.general.confirm_close_with_jobs
+                && super::keyboard::closing_active_pane_kills_job(tab_bar_gui)
+            {
+                return EventResult {
+                    action: EventAction::RequestClosePaneConfirmation,
+                    needs_render: true,
+                    needs_resize: false,
+                };
+            }
 
             // Handle the action
             let result = super::keyboard::handle_hotkey_action(
@@ -549,18 +566,23 @@ fn handle_key_down_event(
                 char_height,
                 tab_bar_height,
                 canvas_window,
-                #[cfg(target_os = "linux")]
-                clipboard_tx,
+                clipboard,
+                settings.ui.copy_animation,
             );
 
             // Map to event action
-            let event_action = match keyboard_action {
+            let event_action = match result.action {
                 KeyboardAction::NewTab => EventAction::NewTab,
                 KeyboardAction::SplitPane(direction) => EventAction::SplitPane(direction),
+                KeyboardAction::DuplicatePane => EventAction::DuplicatePane,
+                KeyboardAction::DuplicateTab => EventAction::DuplicateTab,
                 KeyboardAction::RequestQuitConfirmation => EventAction::RequestQuitConfirmation,
                 KeyboardAction::Quit => EventAction::Quit,
                 KeyboardAction::RequestTerminalHistorySearch => EventAction::TerminalHistorySearch,
                 KeyboardAction::RequestAiCommandGeneration => EventAction::AiCommandGeneration,
+                KeyboardAction::ToggleWindow => EventAction::ToggleWindow,
+                KeyboardAction::SwitchTab(index) => EventAction::SwitchTab(index),
+                KeyboardAction::Paste(text) => dispatch_paste(text, settings, tab_bar_gui),
                 KeyboardAction::None => EventAction::None,
             };
 
@@ -580,7 +602,10 @@ fn handle_key_down_event(
     // Handle keyboard shortcuts using hotkeys module (hardcoded fallback)
     // Skip if we're passing Ctrl+R through to terminal (grouped terminals)
     if !should_skip_hotkey_for_ctrl_r {
-        if let Some(action) = super::hotkeys::match_hotkey(keycode, is_ctrl_pressed, is_shift_pressed) {
+        // Ctrl+C stays an interrupt when the `ctrlCCopiesSelection` setting is off
+        let hotkey = super::hotkeys::match_hotkey(keycode, is_ctrl_pressed, is_shift_pressed)
+            .filter(|action| *action != super::hotkeys::HotkeyAction::CopySelection || settings.terminal.ctrl_c_copies_selection);
+        if let Some(action) = hotkey {
             let result = super::keyboard::handle_hotkey_action(
                 action,
                 tab_bar_gui,
@@ -589,8 +614,8 @@ fn handle_key_down_event(
                 char_height,
                 tab_bar_height,
                 canvas_window,
-                #[cfg(target_os = "linux")]
-                clipboard_tx,
+                clipboard,
+                settings.ui.copy_animation,
             );
 
             // Only consume the event if the action was actually handled
@@ -601,10 +626,15 @@ fn handle_key_down_event(
                 let event_action = match result.action {
                     KeyboardAction::NewTab => EventAction::NewTab,
                     KeyboardAction::SplitPane(direction) => EventAction::SplitPane(direction),
+                    KeyboardAction::DuplicatePane => EventAction::DuplicatePane,
+                    KeyboardAction::DuplicateTab => EventAction::DuplicateTab,
                     KeyboardAction::RequestQuitConfirmation => EventAction::RequestQuitConfirmation,
                     KeyboardAction::Quit => EventAction::Quit,
                     KeyboardAction::RequestTerminalHistorySearch => EventAction::TerminalHistorySearch,
                     KeyboardAction::RequestAiCommandGeneration => EventAction::AiCommandGeneration,
+                    KeyboardAction::ToggleWindow => EventAction::ToggleWindow,
+                    KeyboardAction::SwitchTab(index) => EventAction::SwitchTab(index),
+                    KeyboardAction::Paste(text) => dispatch_paste(text, settings, tab_bar_gui),
                     KeyboardAction::None => EventAction::None,
                 };
 
@@ -619,20 +649,75 @@ fn handle_key_down_event(
         }
     }
 
+    // Alt+key sends ESC followed by the key, for shell bindings like Alt+b / Alt+f. AltGr is
+    // left to type its character through text input.
+    if super::hotkeys::is_meta(keymod)
+        && !is_ctrl_pressed
+        && settings.terminal.alt_sends_esc
+        && super::keyboard::handle_alt_key(keycode, is_shift_pressed, tab_bar_gui)
+    {
+        // The same key press may also arrive as text input, which must not be typed again
+        tab_bar.suppress_next_text_input = true;
+        return EventResult::none();
+    }
+
     // Other Ctrl+key combinations
     if is_ctrl_pressed && !is_shift_pressed {
         if let Some(scancode_val) = scancode {
-            super::keyboard::handle_ctrl_key(scancode_val, ctrl_keys, tab_bar_gui);
-            return EventResult::none();
+            // Keys without a control character (arrows, function keys) fall through
+            if super::keyboard::handle_ctrl_key(scancode_val, ctrl_keys, tab_bar_gui).needs_render {
+                return EventResult::none();
+            }
         }
     }
 
-    // Send normal keys to terminal
-    super::keyboard::handle_normal_key(keycode, tab_bar_gui);
+    // Send normal keys to terminal, with modifiers encoded the way xterm does
+    let modifier = super::keyboard::key_modifier_param(is_shift_pressed, is_alt_pressed, is_ctrl_pressed);
+    if super::keyboard::handle_normal_key(keycode, modifier, tab_bar_gui) {
+        // Application keypad sequence replaces the digit the key would type
+        tab_bar.suppress_next_text_input = true;
+    }
     EventResult::none()
 }
 
+/// Send pasted text to the terminals, unless it contains newlines that would run as typed
+/// commands or is very large. Then the main loop asks for confirmation first. Apps with
+/// bracketed paste enabled receive multi-line text as a paste and handle it safely themselves.
+/// With confirmation on, a single line loses its trailing newline instead of running at once.
+fn dispatch_paste(text: String, settings: &Settings, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> EventAction {
+    let text = if settings.terminal.confirm_multiline_paste {
+        super::keyboard::trim_single_line_paste(&text).to_string()
+    } else {
+        text
+    };
+    if super::keyboard::is_large_paste(&text) {
+        return EventAction::RequestPasteConfirmation(text);
+    }
+    if settings.terminal.confirm_multiline_paste
+        && super::keyboard::is_multiline_paste(&text)
+        && super::keyboard::paste_reaches_unbracketed_terminal(tab_bar_gui)
+    {
+        return EventAction::RequestPasteConfirmation(text);
+    }
+    super::keyboard::paste_to_group(&text, tab_bar_gui);
+    EventAction::None
+}
+
+fn handle_text_editing_event(text: &str, start: i32, tab_bar: &TabBar, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> EventResult {
+    let result = super::keyboard::handle_text_editing(text, start, tab_bar, tab_bar_gui);
+    EventResult {
+        action: EventAction::None,
+        needs_render: result.needs_render,
+        needs_resize: false,
+    }
+}
+
 fn handle_text_input_event(text: &str, tab_bar: &mut TabBar, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> EventResult {
+    if std::mem::take(&mut tab_bar.suppress_next_text_input) {
+        // Already sent as ESC + key by the Alt handling
+        return EventResult::none();
+    }
+
     let result = super::keyboard::handle_text_input(text, tab_bar, tab_bar_gui);
     EventResult {
         action: EventAction::None,
@@ -0,0 +1,108 @@
+//! Focus reporting (mode 1004)
+//!
+//! Applications that enable focus reporting get CSI I when they gain focus and CSI O when they
+//! lose it. Focus belongs to a pane rather than the window: only the active pane of a focused
+//! window has it, so switching panes moves focus between terminals just like switching windows
+//! does. tmux relies on this to pass focus on to the program in its own active pane.
+
+use crate::terminal::Terminal;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Tracks which terminal has focus and reports changes to the terminals involved
+pub struct FocusTracker {
+    window_focused: bool,
+    focused: Option<Weak<Mutex<Terminal>>>,
+}
+
+impl FocusTracker {
+    pub fn new() -> Self {
+        FocusTracker {
+            window_focused: true,
+            focused: None,
+        }
+    }
+
+    /// Record the window gaining or losing focus, reported on the next `update`
+    pub fn set_window_focused(&mut self, focused: bool) {
+        self.window_focused = focused;
+    }
+
+    /// Compare the active pane's terminal with the one that had focus so far. Returns the
+    /// terminals to notify, the one losing focus first.
+    pub fn update(&mut self, active: Option<Arc<Mutex<Terminal>>>) -> Vec<(Arc<Mutex<Terminal>>, bool)> {
+        let active = active.filter(|_| self.window_focused);
+        let unchanged = match (&self.focused, &active) {
+            (Some(focused), Some(active)) => Weak::ptr_eq(focused, &Arc::downgrade(active)),
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return Vec::new();
+        }
+
+        let mut changes = Vec::new();
+        // A closed pane's terminal is gone and has nobody left to tell
+        if let Some(previous) = self.focused.take().and_then(|focused| focused.upgrade()) {
+            changes.push((previous, false));
+        }
+        if let Some(active) = active {
+            self.focused = Some(Arc::downgrade(&active));
+            changes.push((active, true));
+        }
+        changes
+    }
+
+    /// Send the changes from `update` to the terminals
+    pub fn report(changes: Vec<(Arc<Mutex<Terminal>>, bool)>) {
+        for (terminal, focused) in changes {
+            if let Ok(mut terminal) = terminal.lock() {
+                terminal.send_focus_event(focused);
+            }
+        }
+    }
+}
+
+impl Default for FocusTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::test_support::test_terminal;
+
+    fn targets(changes: &[(Arc<Mutex<Terminal>>, bool)]) -> Vec<(*const Mutex<Terminal>, bool)> {
+        changes.iter().map(|(terminal, focused)| (Arc::as_ptr(terminal), *focused)).collect()
+    }
+
+    #[test]
+    fn test_switching_panes_moves_focus_between_terminals() {
+        let (left, right) = (test_terminal(), test_terminal());
+        let mut tracker = FocusTracker::new();
+
+        assert_eq!(targets(&tracker.update(Some(Arc::clone(&left)))), vec![(Arc::as_ptr(&left), true)]);
+        assert!(
+            tracker.update(Some(Arc::clone(&left))).is_empty(),
+            "nothing changes while the pane stays active"
+        );
+
+        // Switching panes: the old pane loses focus before the new one gains it
+        assert_eq!(
+            targets(&tracker.update(Some(Arc::clone(&right)))),
+            vec![(Arc::as_ptr(&left), false), (Arc::as_ptr(&right), true)]
+        );
+
+        // Leaving the window takes focus from the active pane only, and coming back returns it
+        tracker.set_window_focused(false);
+        assert_eq!(targets(&tracker.update(Some(Arc::clone(&right)))), vec![(Arc::as_ptr(&right), false)]);
+        assert!(tracker.update(Some(Arc::clone(&left))).is_empty(), "no pane has focus in an unfocused window");
+        tracker.set_window_focused(true);
+        assert_eq!(targets(&tracker.update(Some(Arc::clone(&left)))), vec![(Arc::as_ptr(&left), true)]);
+
+        // A closed pane gets no focus-out
+        drop(left);
+        assert_eq!(targets(&tracker.update(Some(Arc::clone(&right)))), vec![(Arc::as_ptr(&right), true)]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::settings::{KeyBinding, NavigationHotkeys};
+use crate::settings::{Key, KeyBinding, NavigationHotkeys};
 use sdl3::keyboard::Keycode;
 use std::time::{Duration, Instant};
 
@@ -52,12 +52,25 @@ pub enum NavigationAction {
     ClosePane,
     NextPane,
     PreviousPane,
+    CyclePane,
+    CyclePaneBack,
+    SwapPane,
+    BalancePanes,
+    DuplicatePane,
+    DuplicateTab,
+    ZoomPaneIn,
+    ZoomPaneOut,
     NewTab,
     NextTab,
     PreviousTab,
+    /// Jump to a tab by the digit pressed (see `tab_index_for_digit`)
+    GoToTab(usize),
     GoToPrompt,
     TerminalHistorySearch,
     AiCommandGeneration,
+    ToggleWindow,
+    ClearScrollback,
+    ToggleOutputLog,
 }
 
 /// Represents actions that can be triggered by hotkeys
@@ -75,8 +88,12 @@ pub enum HotkeyAction {
     // Scrollback navigation
     ScrollPageUp,
     ScrollPageDown,
+    ScrollHalfPageUp,
+    ScrollHalfPageDown,
     ScrollLineUp,
     ScrollLineDown,
+    ScrollToTop,
+    ScrollToBottom,
     GoToPrompt, // Scroll to the prompt (reset scroll position)
 }
 
@@ -108,6 +125,30 @@ pub fn match_navigation_hotkey(
     if matches_any(&navigation_hotkeys.previous_pane) {
         return Some(NavigationAction::PreviousPane);
     }
+    if matches_any(&navigation_hotkeys.cycle_pane) {
+        return Some(NavigationAction::CyclePane);
+    }
+    if matches_any(&navigation_hotkeys.cycle_pane_back) {
+        return Some(NavigationAction::CyclePaneBack);
+    }
+    if matches_any(&navigation_hotkeys.swap_pane) {
+        return Some(NavigationAction::SwapPane);
+    }
+    if matches_any(&navigation_hotkeys.balance_panes) {
+        return Some(NavigationAction::BalancePanes);
+    }
+    if matches_any(&navigation_hotkeys.duplicate_pane) {
+        return Some(NavigationAction::DuplicatePane);
+    }
+    if matches_any(&navigation_hotkeys.duplicate_tab) {
+        return Some(NavigationAction::DuplicateTab);
+    }
+    if matches_any(&navigation_hotkeys.zoom_pane_in) {
+        return Some(NavigationAction::ZoomPaneIn);
+    }
+    if matches_any(&navigation_hotkeys.zoom_pane_out) {
+        return Some(NavigationAction::ZoomPaneOut);
+    }
     if matches_any(&navigation_hotkeys.new_tab) {
         return Some(NavigationAction::NewTab);
     }
@@ -117,6 +158,14 @@ pub fn match_navigation_hotkey(
     if matches_any(&navigation_hotkeys.previous_tab) {
         return Some(NavigationAction::PreviousTab);
     }
+    if let Some(digit) = navigation_hotkeys
+        .go_to_tab
+        .iter()
+        .find(|binding| binding.matches(keycode, is_ctrl, is_shift, is_alt))
+        .and_then(|binding| binding.key.digit())
+    {
+        return Some(NavigationAction::GoToTab(digit));
+    }
     if matches_any(&navigation_hotkeys.go_to_prompt) {
         return Some(NavigationAction::GoToPrompt);
     }
@@ -126,6 +175,15 @@ pub fn match_navigation_hotkey(
     if matches_any(&navigation_hotkeys.ai_command_generation) {
         return Some(NavigationAction::AiCommandGeneration);
     }
+    if matches_any(&navigation_hotkeys.toggle_window) {
+        return Some(NavigationAction::ToggleWindow);
+    }
+    if matches_any(&navigation_hotkeys.clear_scrollback) {
+        return Some(NavigationAction::ClearScrollback);
+    }
+    if matches_any(&navigation_hotkeys.toggle_output_log) {
+        return Some(NavigationAction::ToggleOutputLog);
+    }
 
     None
 }
@@ -162,6 +220,30 @@ pub fn match_sequential_navigation_hotkey(
     if matches_any_sequential(&navigation_hotkeys.previous_pane) {
         return Some(NavigationAction::PreviousPane);
     }
+    if matches_any_sequential(&navigation_hotkeys.cycle_pane) {
+        return Some(NavigationAction::CyclePane);
+    }
+    if matches_any_sequential(&navigation_hotkeys.cycle_pane_back) {
+        return Some(NavigationAction::CyclePaneBack);
+    }
+    if matches_any_sequential(&navigation_hotkeys.swap_pane) {
+        return Some(NavigationAction::SwapPane);
+    }
+    if matches_any_sequential(&navigation_hotkeys.balance_panes) {
+        return Some(NavigationAction::BalancePanes);
+    }
+    if matches_any_sequential(&navigation_hotkeys.duplicate_pane) {
+        return Some(NavigationAction::DuplicatePane);
+    }
+    if matches_any_sequential(&navigation_hotkeys.duplicate_tab) {
+        return Some(NavigationAction::DuplicateTab);
+    }
+    if matches_any_sequential(&navigation_hotkeys.zoom_pane_in) {
+        return Some(NavigationAction::ZoomPaneIn);
+    }
+    if matches_any_sequential(&navigation_hotkeys.zoom_pane_out) {
+        return Some(NavigationAction::ZoomPaneOut);
+    }
     if matches_any_sequential(&navigation_hotkeys.new_tab) {
         return Some(NavigationAction::NewTab);
     }
@@ -171,6 +253,14 @@ pub fn match_sequential_navigation_hotkey(
     if matches_any_sequential(&navigation_hotkeys.previous_tab) {
         return Some(NavigationAction::PreviousTab);
     }
+    if let Some(digit) = navigation_hotkeys
+        .go_to_tab
+        .iter()
+        .find(|binding| binding.is_sequential() && binding.matches_sequence(first_keycode, first_ctrl, first_shift, first_alt, keycode))
+        .and_then(|binding| binding.key2.as_ref().and_then(Key::digit))
+    {
+        return Some(NavigationAction::GoToTab(digit));
+    }
     if matches_any_sequential(&navigation_hotkeys.go_to_prompt) {
         return Some(NavigationAction::GoToPrompt);
     }
@@ -180,6 +270,15 @@ pub fn match_sequential_navigation_hotkey(
     if matches_any_sequential(&navigation_hotkeys.ai_command_generation) {
         return Some(NavigationAction::AiCommandGeneration);
     }
+    if matches_any_sequential(&navigation_hotkeys.toggle_window) {
+        return Some(NavigationAction::ToggleWindow);
+    }
+    if matches_any_sequential(&navigation_hotkeys.clear_scrollback) {
+        return Some(NavigationAction::ClearScrollback);
+    }
+    if matches_any_sequential(&navigation_hotkeys.toggle_output_log) {
+        return Some(NavigationAction::ToggleOutputLog);
+    }
 
     None
 }
@@ -199,12 +298,35 @@ pub fn is_sequential_navigation_hotkey_start(keycode: Keycode, is_ctrl: bool, is
         || starts_with(&navigation_hotkeys.close_pane)
         || starts_with(&navigation_hotkeys.next_pane)
         || starts_with(&navigation_hotkeys.previous_pane)
+        || starts_with(&navigation_hotkeys.cycle_pane)
+        || starts_with(&navigation_hotkeys.cycle_pane_back)
+        || starts_with(&navigation_hotkeys.swap_pane)
+        || starts_with(&navigation_hotkeys.balance_panes)
+        || starts_with(&navigation_hotkeys.duplicate_pane)
+        || starts_with(&navigation_hotkeys.duplicate_tab)
+        || starts_with(&navigation_hotkeys.zoom_pane_in)
+        || starts_with(&navigation_hotkeys.zoom_pane_out)
         || starts_with(&navigation_hotkeys.new_tab)
         || starts_with(&navigation_hotkeys.next_tab)
         || starts_with(&navigation_hotkeys.previous_tab)
+        || starts_with(&navigation_hotkeys.go_to_tab)
         || starts_with(&navigation_hotkeys.go_to_prompt)
         || starts_with(&navigation_hotkeys.terminal_history_search)
         || starts_with(&navigation_hotkeys.ai_command_generation)
+        || starts_with(&navigation_hotkeys.toggle_window)
+        || starts_with(&navigation_hotkeys.clear_scrollback)
+        || starts_with(&navigation_hotkeys.toggle_output_log)
+}
+
+/// Tab index for a go-to-tab digit: 1-9 pick that tab, clamped to the last one, and 0 picks
+/// the last tab. None without tabs.
+pub fn tab_index_for_digit(digit: usize, tab_count: usize) -> Option<usize> {
+    let last = tab_count.checked_sub(1)?;
+    if digit == 0 {
+        Some(last)
+    } else {
+        Some((digit - 1).min(last))
+    }
 }
 
 /// Match a keycode and modifiers to a hotkey action (hardcoded hotkeys)
@@ -212,10 +334,15 @@ pub fn is_sequential_navigation_hotkey_start(keycode: Keycode, is_ctrl: bool, is
 /// Only handles clipboard and scrollback operations now - navigation is handled by settings
 pub fn match_hotkey(keycode: Keycode, is_ctrl: bool, is_shift: bool) -> Option<HotkeyAction> {
     if is_ctrl && is_shift {
-        // Ctrl+Shift combinations (clipboard operations)
+        // Ctrl+Shift combinations (clipboard operations and scrollback jumps)
         match keycode {
             Keycode::C => Some(HotkeyAction::Copy),
             Keycode::V => Some(HotkeyAction::Paste),
+            // Ctrl+Shift scrollback navigation
+            Keycode::PageUp => Some(HotkeyAction::ScrollHalfPageUp),
+            Keycode::PageDown => Some(HotkeyAction::ScrollHalfPageDown),
+            Keycode::Home => Some(HotkeyAction::ScrollToTop),
+            Keycode::End => Some(HotkeyAction::ScrollToBottom),
             _ => None,
         }
     } else if is_ctrl && !is_shift {
@@ -247,6 +374,13 @@ pub fn get_modifiers(keymod: sdl3::keyboard::Mod) -> (bool, bool, bool) {
     (is_ctrl, is_shift, is_alt)
 }
 
+/// Whether Alt is held as Meta, to send ESC + key. Only the left Alt counts: on most non-US
+/// layouts the right Alt is AltGr, which types characters like '@' or '{' as text input.
+pub fn is_meta(keymod: sdl3::keyboard::Mod) -> bool {
+    use sdl3::keyboard::Mod;
+    keymod.contains(Mod::LALTMOD) && !keymod.intersects(Mod::RALTMOD | Mod::MODEMOD)
+}
+
 /// Match sequential hotkey combinations (e.g., Alt-G-P)
 /// Returns Some(HotkeyAction) if the current key completes a sequential hotkey,
 /// or returns None if it doesn't match any known sequential pattern.
@@ -290,6 +424,19 @@ pub fn is_sequential_hotkey_start(keycode: Keycode, is_ctrl: bool, is_shift: boo
 mod tests {
     use super::*;
     use crate::settings::{Key, KeyBinding, NavigationHotkeys};
+    use sdl3::keyboard::Mod;
+
+    #[test]
+    fn test_altgr_is_not_meta() {
+        assert!(is_meta(Mod::LALTMOD));
+        assert!(is_meta(Mod::LALTMOD | Mod::LSHIFTMOD));
+
+        // AltGr+Q types '@' on a German layout rather than sending ESC q
+        assert!(!is_meta(Mod::RALTMOD));
+        assert!(!is_meta(Mod::LCTRLMOD | Mod::RALTMOD), "AltGr reported as Ctrl+Alt on Windows");
+        assert!(!is_meta(Mod::MODEMOD));
+        assert!(!is_meta(Mod::NOMOD));
+    }
 
     #[test]
     fn test_sequential_navigation_hotkeys_from_settings() {
@@ -389,4 +536,55 @@ mod tests {
         let result = match_navigation_hotkey(Keycode::G, false, false, true, &nav_hotkeys);
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn test_toggle_window_hotkey() {
+        let nav_hotkeys = NavigationHotkeys::default();
+        assert_eq!(
+            match_navigation_hotkey(Keycode::F12, true, true, false, &nav_hotkeys),
+            Some(NavigationAction::ToggleWindow)
+        );
+        assert_eq!(match_navigation_hotkey(Keycode::F12, false, false, false, &nav_hotkeys), None);
+    }
+
+    #[test]
+    fn test_go_to_tab_hotkeys() {
+        let nav_hotkeys = NavigationHotkeys::default();
+        let action = match_navigation_hotkey(Keycode::_3, false, false, true, &nav_hotkeys);
+        assert_eq!(action, Some(NavigationAction::GoToTab(3)));
+        assert_eq!(tab_index_for_digit(3, 5), Some(2));
+
+        // Fewer tabs than the digit, Alt+0 and no tabs at all
+        assert_eq!(tab_index_for_digit(9, 4), Some(3));
+        assert_eq!(tab_index_for_digit(0, 4), Some(3));
+        assert_eq!(tab_index_for_digit(1, 0), None);
+
+        // Other modifiers leave the digit to the terminal
+        assert_eq!(match_navigation_hotkey(Keycode::_3, true, false, true, &nav_hotkeys), None);
+        assert_eq!(match_navigation_hotkey(Keycode::_3, false, false, false, &nav_hotkeys), None);
+    }
+
+    #[test]
+    fn test_clear_scrollback_hotkey() {
+        let nav_hotkeys = NavigationHotkeys::default();
+        assert_eq!(
+            match_navigation_hotkey(Keycode::K, true, true, false, &nav_hotkeys),
+            Some(NavigationAction::ClearScrollback)
+        );
+        // Plain Ctrl+K still reaches the shell (kill to end of line)
+        assert_eq!(match_navigation_hotkey(Keycode::K, true, false, false, &nav_hotkeys), None);
+    }
+
+    #[test]
+    fn test_scrollback_jump_hotkeys() {
+        assert_eq!(match_hotkey(Keycode::Home, true, true), Some(HotkeyAction::ScrollToTop));
+        assert_eq!(match_hotkey(Keycode::End, true, true), Some(HotkeyAction::ScrollToBottom));
+        assert_eq!(match_hotkey(Keycode::PageUp, true, true), Some(HotkeyAction::ScrollHalfPageUp));
+        assert_eq!(match_hotkey(Keycode::PageDown, true, true), Some(HotkeyAction::ScrollHalfPageDown));
+        assert_eq!(match_hotkey(Keycode::PageUp, false, true), Some(HotkeyAction::ScrollPageUp));
+
+        // Without both modifiers Home and End go to the shell
+        assert_eq!(match_hotkey(Keycode::Home, false, true), None);
+        assert_eq!(match_hotkey(Keycode::End, true, false), None);
+    }
 }
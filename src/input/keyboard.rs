@@ -5,25 +5,25 @@ use std::sync::{Arc, Mutex};
 use super::hotkeys::{HotkeyAction, NavigationAction};
 use crate::pane_layout::SplitDirection;
 use crate::sdl_renderer::TabBar;
-use crate::tab_gui::TabBarGui;
-
-#[cfg(target_os = "linux")]
-use arboard::Clipboard;
-#[cfg(not(target_os = "linux"))]
-use arboard::Clipboard;
-
-#[cfg(target_os = "linux")]
-use std::sync::mpsc::Sender;
+use crate::system::clipboard::ClipboardProvider;
+use crate::tab_gui::{Composition, TabBarGui};
 
 /// Actions that keyboard handler can request from the main loop
 #[derive(Debug, Clone)]
 pub enum KeyboardAction {
     NewTab,
     SplitPane(SplitDirection),
+    /// Split the active pane, or open a tab, with a fresh shell in the active pane's directory
+    DuplicatePane,
+    DuplicateTab,
     RequestQuitConfirmation,
     Quit,
     RequestTerminalHistorySearch,
     RequestAiCommandGeneration,
+    ToggleWindow,
+    SwitchTab(usize),
+    /// Clipboard text to paste into the active pane group
+    Paste(String),
     None,
 }
 
@@ -167,12 +167,13 @@ pub fn handle_tab_editing_key(keycode: Keycode, tab_bar: &mut TabBar, tab_bar_gu
 pub fn handle_hotkey_action(
     action: HotkeyAction,
     tab_bar_gui: &Arc<Mutex<TabBarGui>>,
-    scale_factor: f32,
+    _scale_factor: f32,
     char_width: f32,
     char_height: f32,
     tab_bar_height: u32,
     canvas_window: &sdl3::video::Window,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
+    copy_animation: bool,
 ) -> KeyboardResult {
     match action {
         HotkeyAction::Navigation(nav_action) => match nav_action {
@@ -182,6 +183,10 @@ pub fn handle_hotkey_action(
 
             NavigationAction::SplitDown => KeyboardResult::with_action(KeyboardAction::SplitPane(SplitDirection::Horizontal)),
 
+            NavigationAction::DuplicatePane => KeyboardResult::with_action(KeyboardAction::DuplicatePane),
+
+            NavigationAction::DuplicateTab => KeyboardResult::with_action(KeyboardAction::DuplicateTab),
+
             NavigationAction::NextTab => {
                 tab_bar_gui.lock().unwrap().cycle_to_next_tab();
                 KeyboardResult::render()
@@ -192,35 +197,16 @@ pub fn handle_hotkey_action(
                 KeyboardResult::render()
             }
 
-            NavigationAction::ClosePane => {
-                let mut gui = tab_bar_gui.lock().unwrap();
-
-                // Check if this is the last pane in the last tab
-                let is_last_pane_in_last_tab = gui.tab_states.len() == 1 && gui.get_active_pane_layout().map(|pl| pl.root.count_leaf_panes()).unwrap_or(0) == 1;
-
-                if is_last_pane_in_last_tab {
-                    // Request confirmation before closing
-                    return KeyboardResult::with_action(KeyboardAction::RequestQuitConfirmation);
-                }
-
-                if let Some(pane_layout) = gui.get_active_pane_layout() {
-                    let active_pane = pane_layout.active_pane();
-                    if pane_layout.close_pane(active_pane) {
-                        // Last pane in tab closed
-                        drop(gui);
-                        let active_tab = tab_bar_gui.lock().unwrap().active_tab;
-                        if tab_bar_gui.lock().unwrap().remove_tab(active_tab) {
-                            return KeyboardResult::with_action(KeyboardAction::Quit);
-                        }
-                    } else {
-                        // Pane closed, need to resize remaining terminals
-                        drop(gui);
-                        return KeyboardResult::with_resize(KeyboardAction::None);
-                    }
+            NavigationAction::GoToTab(digit) => {
+                let tab_count = tab_bar_gui.lock().unwrap().tab_states.len();
+                match super::hotkeys::tab_index_for_digit(digit, tab_count) {
+                    Some(index) => KeyboardResult::with_action(KeyboardAction::SwitchTab(index)),
+                    None => KeyboardResult::none(),
                 }
-                KeyboardResult::render()
             }
 
+            NavigationAction::ClosePane => close_active_pane(tab_bar_gui),
+
             NavigationAction::PreviousPane => {
                 let mut gui = tab_bar_gui.lock().unwrap();
                 if let Some(pane_layout) = gui.get_active_pane_layout() {
@@ -251,6 +237,62 @@ pub fn handle_hotkey_action(
                 KeyboardResult::render()
             }
 
+            NavigationAction::CyclePane => {
+                // Unlike NextPane, stays within the tab
+                if let Some(pane_layout) = tab_bar_gui.lock().unwrap().get_active_pane_layout() {
+                    pane_layout.cycle_to_next_pane();
+                }
+                KeyboardResult::render()
+            }
+
+            NavigationAction::CyclePaneBack => {
+                if let Some(pane_layout) = tab_bar_gui.lock().unwrap().get_active_pane_layout() {
+                    pane_layout.cycle_to_previous_pane();
+                }
+                KeyboardResult::render()
+            }
+
+            NavigationAction::SwapPane => {
+                let mut gui = tab_bar_gui.lock().unwrap();
+                if let Some(pane_layout) = gui.get_active_pane_layout() {
+                    if pane_layout.swap_active_with_next_pane() {
+                        // Both terminals moved into differently sized rects
+                        drop(gui);
+                        return KeyboardResult::with_resize(KeyboardAction::None);
+                    }
+                }
+                KeyboardResult::render()
+            }
+
+            NavigationAction::BalancePanes => {
+                let mut gui = tab_bar_gui.lock().unwrap();
+                if let Some(pane_layout) = gui.get_active_pane_layout() {
+                    if pane_layout.balance() {
+                        // Pane sizes changed, resize terminals as after a split
+                        drop(gui);
+                        return KeyboardResult::with_resize(KeyboardAction::None);
+                    }
+                }
+                KeyboardResult::render()
+            }
+
+            NavigationAction::ZoomPaneIn | NavigationAction::ZoomPaneOut => {
+                let step = if nav_action == NavigationAction::ZoomPaneIn {
+                    crate::pane_layout::FONT_SCALE_STEP
+                } else {
+                    -crate::pane_layout::FONT_SCALE_STEP
+                };
+                let mut gui = tab_bar_gui.lock().unwrap();
+                if let Some(pane_layout) = gui.get_active_pane_layout() {
+                    if pane_layout.zoom_active_pane(step) {
+                        // The pane's cells changed size, so its terminal gets new rows/cols
+                        drop(gui);
+                        return KeyboardResult::with_resize(KeyboardAction::None);
+                    }
+                }
+                KeyboardResult::render()
+            }
+
             NavigationAction::GoToPrompt => {
                 // Reset scroll position to 0 (go back to the prompt)
                 if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
@@ -261,6 +303,34 @@ pub fn handle_hotkey_action(
                 KeyboardResult::render()
             }
 
+            NavigationAction::ClearScrollback => {
+                if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
+                    if let Ok(mut t) = terminal.lock() {
+                        t.screen_buffer.lock().unwrap().clear_all();
+                        // Form feed makes the shell redraw its prompt on the blank screen, a
+                        // program in the foreground would take it as a keypress instead
+                        if !t.has_foreground_job() {
+                            t.write_bytes(b"\x0c");
+                        }
+                    }
+                }
+                KeyboardResult::render()
+            }
+
+            NavigationAction::ToggleOutputLog => {
+                let mut gui = tab_bar_gui.lock().unwrap();
+                if let Some(pane_layout) = gui.get_active_pane_layout() {
+                    if let Some(terminal) = pane_layout.get_active_terminal() {
+                        let message = toggle_output_log(&terminal.lock().unwrap());
+                        pane_layout.toast = Some(crate::ui::animations::Toast {
+                            duration: std::time::Duration::from_secs(3),
+                            ..crate::ui::animations::Toast::new(message)
+                        });
+                    }
+                }
+                KeyboardResult::render()
+            }
+
             NavigationAction::TerminalHistorySearch => {
                 // Request terminal history search dialog
                 // Grouping check will be done in main.rs
@@ -271,38 +341,46 @@ pub fn handle_hotkey_action(
                 // Request AI command generation dialog
                 KeyboardResult::with_action(KeyboardAction::RequestAiCommandGeneration)
             }
+
+            NavigationAction::ToggleWindow => KeyboardResult::with_action(KeyboardAction::ToggleWindow),
         },
 
         HotkeyAction::Copy => {
             // Ctrl+Shift+C: Copy selection to clipboard
-            handle_copy(
+            handle_copy_selection(
                 tab_bar_gui,
-                #[cfg(target_os = "linux")]
-                clipboard_tx,
+                char_width,
+                char_height,
+                tab_bar_height,
+                canvas_window,
+                clipboard,
+                copy_animation,
+                false,
             );
             KeyboardResult::render()
         }
 
         HotkeyAction::Paste => {
             // Ctrl+Shift+V: Paste from clipboard
-            handle_paste(tab_bar_gui);
-            KeyboardResult::render()
+            match clipboard.get() {
+                Some(text) => KeyboardResult::with_action(KeyboardAction::Paste(text)),
+                None => KeyboardResult::render(),
+            }
         }
 
         HotkeyAction::CopySelection => {
             // Ctrl+C: Copy selection to clipboard (only if we have a selection)
             // If there's no selection, we'll return None to let Ctrl+C pass through
-            let copied = handle_copy_selection(
+            if handle_copy_selection(
                 tab_bar_gui,
-                scale_factor,
                 char_width,
                 char_height,
                 tab_bar_height,
                 canvas_window,
-                #[cfg(target_os = "linux")]
-                clipboard_tx,
-            );
-            if copied {
+                clipboard,
+                copy_animation,
+                true,
+            ) {
                 KeyboardResult::render()
             } else {
                 // No selection, so don't consume the event - let Ctrl+C pass through to terminal
@@ -327,8 +405,10 @@ pub fn handle_hotkey_action(
             };
 
             if should_paste {
-                handle_paste(tab_bar_gui);
-                KeyboardResult::render()
+                match clipboard.get() {
+                    Some(text) => KeyboardResult::with_action(KeyboardAction::Paste(text)),
+                    None => KeyboardResult::render(),
+                }
             } else {
                 // Mouse tracking is enabled (app is running), let Ctrl+V pass through
                 KeyboardResult::none()
@@ -355,6 +435,26 @@ pub fn handle_hotkey_action(
             KeyboardResult::render()
         }
 
+        HotkeyAction::ScrollHalfPageUp => {
+            if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
+                if let Ok(t) = terminal.lock() {
+                    let half_page = (t.height as usize / 2).max(1);
+                    t.screen_buffer.lock().unwrap().scroll_view_up(half_page);
+                }
+            }
+            KeyboardResult::render()
+        }
+
+        HotkeyAction::ScrollHalfPageDown => {
+            if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
+                if let Ok(t) = terminal.lock() {
+                    let half_page = (t.height as usize / 2).max(1);
+                    t.screen_buffer.lock().unwrap().scroll_view_down(half_page);
+                }
+            }
+            KeyboardResult::render()
+        }
+
         HotkeyAction::ScrollLineUp => {
             if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
                 if let Ok(t) = terminal.lock() {
@@ -373,6 +473,24 @@ pub fn handle_hotkey_action(
             KeyboardResult::render()
         }
 
+        HotkeyAction::ScrollToTop => {
+            if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
+                if let Ok(t) = terminal.lock() {
+                    t.screen_buffer.lock().unwrap().scroll_view_to_top();
+                }
+            }
+            KeyboardResult::render()
+        }
+
+        HotkeyAction::ScrollToBottom => {
+            if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
+                if let Ok(t) = terminal.lock() {
+                    t.screen_buffer.lock().unwrap().reset_view_offset();
+                }
+            }
+            KeyboardResult::render()
+        }
+
         HotkeyAction::GoToPrompt => {
             // Alt-G-P: Reset scroll position to 0 (go back to the prompt)
             if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
@@ -385,176 +503,161 @@ pub fn handle_hotkey_action(
     }
 }
 
-/// Handle Ctrl+Shift+C: Copy selection to clipboard
-fn handle_copy(tab_bar_gui: &Arc<Mutex<TabBarGui>>, #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>) {
-    if let Some(terminal) = tab_bar_gui.lock().unwrap().get_active_terminal() {
-        let t = terminal.lock().unwrap();
-        if let Some(text) = t.get_selected_text() {
-            if !text.is_empty() {
-                match Clipboard::new() {
-                    Ok(mut clipboard) => {
-                        if let Err(e) = clipboard.set_text(text.clone()) {
-                            eprintln!("[CLIPBOARD] Failed to copy: {}", e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[CLIPBOARD] Failed to create clipboard: {}", e);
-                    }
-                }
-                #[cfg(target_os = "linux")]
-                {
-                    use arboard::{LinuxClipboardKind, SetExtLinux};
-                    let text_copy = text.clone();
-                    let tx = clipboard_tx.clone();
-
-                    // Create clipboard in background thread to avoid blocking
-                    std::thread::spawn(move || {
-                        match Clipboard::new() {
-                            Ok(mut clipboard) => {
-                                if let Err(e) = clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text_copy) {
-                                    eprintln!("[PRIMARY] Failed to copy to primary selection: {}", e);
-                                } else {
-                                    // Send clipboard object back to main thread
-                                    let _ = tx.send(clipboard);
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("[PRIMARY] Failed to create clipboard: {}", e);
-                            }
-                        }
-                    });
-                }
-            }
-        }
+/// Start logging a terminal's output to a new file, or stop if it is logging already. Returns
+/// the message for the user.
+fn toggle_output_log(terminal: &crate::terminal::Terminal) -> String {
+    if let Some(path) = terminal.logging_path() {
+        return match terminal.set_logging(None) {
+            Ok(()) => format!("Stopped logging to {}", path.display()),
+            Err(e) => e,
+        };
+    }
+    let path = crate::terminal::output_log::new_log_path(terminal.options().log_directory.as_deref(), std::time::SystemTime::now());
+    match terminal.set_logging(Some(path.clone())) {
+        Ok(()) => format!("Logging output to {}", path.display()),
+        Err(e) => e,
     }
 }
 
-/// Handle Ctrl+Shift+V: Paste from clipboard
-fn handle_paste(tab_bar_gui: &Arc<Mutex<TabBarGui>>) {
-    // Get clipboard text first (before acquiring locks)
-    let text = match Clipboard::new() {
-        Ok(mut clipboard) => match clipboard.get_text() {
-            Ok(text) => text,
-            Err(e) => {
-                eprintln!("[CLIPBOARD] Failed to get text: {}", e);
-                return;
+fn is_last_pane_in_last_tab(gui: &mut TabBarGui) -> bool {
+    gui.tab_states.len() == 1 && gui.get_active_pane_layout().map(|pl| pl.root.count_leaf_panes()).unwrap_or(0) == 1
+}
+
+/// Close the active pane, and its tab with it if it was the tab's last pane
+pub fn close_active_pane(tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> KeyboardResult {
+    let mut gui = tab_bar_gui.lock().unwrap();
+
+    if is_last_pane_in_last_tab(&mut gui) {
+        // Request confirmation before closing
+        return KeyboardResult::with_action(KeyboardAction::RequestQuitConfirmation);
+    }
+
+    if let Some(pane_layout) = gui.get_active_pane_layout() {
+        let active_pane = pane_layout.active_pane();
+        if pane_layout.close_pane(active_pane) {
+            // Last pane in tab closed
+            let active_tab = gui.active_tab;
+            if gui.remove_tab(active_tab) {
+                return KeyboardResult::with_action(KeyboardAction::Quit);
             }
-        },
-        Err(e) => {
-            eprintln!("[CLIPBOARD] Failed to create clipboard: {}", e);
-            return;
+        } else {
+            // Pane closed, need to resize remaining terminals
+            drop(gui);
+            return KeyboardResult::with_resize(KeyboardAction::None);
         }
-    };
+    }
+    KeyboardResult::render()
+}
+
+/// Whether closing the active pane would kill a running job.
+/// The last pane of the last tab is left to the quit confirmation.
+pub fn closing_active_pane_kills_job(tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> bool {
+    let mut gui = tab_bar_gui.lock().unwrap();
+    if is_last_pane_in_last_tab(&mut gui) {
+        return false;
+    }
+    gui.get_active_terminal()
+        .map(|terminal| !crate::tab_gui::running_jobs(&[terminal]).is_empty())
+        .unwrap_or(false)
+}
+
+/// Pastes larger than this are confirmed first, whatever the settings
+pub const LARGE_PASTE_BYTES: usize = 1024 * 1024;
+
+/// Whether `text` is large enough that pasting it needs confirmation
+pub fn is_large_paste(text: &str) -> bool {
+    text.len() > LARGE_PASTE_BYTES
+}
+
+/// Whether pasting `text` would press Enter in the shell. A trailing newline counts too,
+/// since it runs the pasted command as soon as it arrives.
+pub fn is_multiline_paste(text: &str) -> bool {
+    text.contains(['\r', '\n'])
+}
+
+/// A single line copied along with its line ending, without that ending, so pasting it doesn't
+/// run it straight away. Text of several lines is returned unchanged.
+pub fn trim_single_line_paste(text: &str) -> &str {
+    let trimmed = text
+        .strip_suffix('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .or_else(|| text.strip_suffix('\r'))
+        .unwrap_or(text);
+    if is_multiline_paste(trimmed) {
+        text
+    } else {
+        trimmed
+    }
+}
 
+/// Whether any terminal receiving a paste has not enabled bracketed paste, so newlines
+/// in the pasted text would be executed as typed
+pub fn paste_reaches_unbracketed_terminal(tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> bool {
+    if let Some(pane_layout) = tab_bar_gui.lock().unwrap().get_active_pane_layout() {
+        return pane_layout
+            .get_group_input_terminals()
+            .iter()
+            .any(|terminal| !*terminal.lock().unwrap().bracketed_paste_mode.lock().unwrap());
+    }
+    false
+}
+
+/// Paste text into the active pane group
+pub fn paste_to_group(text: &str, tab_bar_gui: &Arc<Mutex<TabBarGui>>) {
     // Broadcast paste to all selected panes (or just active pane if none selected)
     if let Some(pane_layout) = tab_bar_gui.lock().unwrap().get_active_pane_layout() {
         let terminals = pane_layout.get_group_input_terminals();
         for terminal in terminals {
-            terminal.lock().unwrap().send_paste(&text);
+            terminal.lock().unwrap().send_paste(text);
         }
     }
 }
 
-/// Handle Ctrl+C: Copy selection with animation
+/// Copy the active pane's selection to the clipboard and play the copy animation over it.
+/// Ctrl+C also clears the selection (`clear_selection`), Ctrl+Shift+C leaves it in place.
+/// Returns false if there was nothing to copy.
 #[allow(clippy::too_many_arguments)]
 fn handle_copy_selection(
     tab_bar_gui: &Arc<Mutex<TabBarGui>>,
-    _scale_factor: f32,
     char_width: f32,
     char_height: f32,
     tab_bar_height: u32,
     canvas_window: &sdl3::video::Window,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
+    copy_animation: bool,
+    clear_selection: bool,
 ) -> bool {
-    use sdl3::rect::Rect;
-
     let mut gui = tab_bar_gui.lock().unwrap();
-    if let Some(terminal) = gui.get_active_terminal() {
-        let t = terminal.lock().unwrap();
-        if let Some(text) = t.get_selected_text() {
-            if !text.is_empty() {
-                // Calculate selection rectangle for animation before clearing
-                let selection_rect = if let Some(sel) = *t.selection.lock().unwrap() {
-                    // Get active pane rect
-                    if let Some(pane_layout) = gui.get_active_pane_layout() {
-                        let (window_w, window_h) = canvas_window.size();
-                        let pane_area_y = tab_bar_height as i32;
-                        let pane_area_height = window_h - tab_bar_height;
-                        let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_w, pane_area_height);
-
-                        // Find the active pane rect
-                        pane_rects
-                            .iter()
-                            .find(|(_, _, term, is_active, _)| *is_active && Arc::ptr_eq(term, &terminal))
-                            .map(|(_, rect, _, _, _)| {
-                                // Calculate selection bounds in screen coordinates
-                                let pane_padding = crate::ui::render::get_pane_padding();
-                                let (start_col, start_row, end_col, end_row) = sel.normalized();
-
-                                let x = rect.x() + pane_padding as i32 + (start_col as f32 * char_width) as i32;
-                                let y = rect.y() + pane_padding as i32 + (start_row as f32 * char_height) as i32;
-                                let width = ((end_col - start_col + 1) as f32 * char_width) as u32;
-                                let height = ((end_row - start_row + 1) as f32 * char_height) as u32;
-
-                                Rect::new(x, y, width, height)
-                            })
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-
-                match Clipboard::new() {
-                    Ok(mut clipboard) => {
-                        if let Err(e) = clipboard.set_text(text.clone()) {
-                            eprintln!("[CLIPBOARD] Failed to copy: {}", e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[CLIPBOARD] Failed to create clipboard: {}", e);
-                    }
-                }
-                #[cfg(target_os = "linux")]
-                {
-                    use arboard::{LinuxClipboardKind, SetExtLinux};
-                    let text_copy = text.clone();
-                    let tx = clipboard_tx.clone();
-
-                    // Create clipboard in background thread to avoid blocking
-                    std::thread::spawn(move || {
-                        match Clipboard::new() {
-                            Ok(mut clipboard) => {
-                                if let Err(e) = clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text_copy) {
-                                    eprintln!("[PRIMARY] Failed to copy to primary selection: {}", e);
-                                } else {
-                                    // Send clipboard object back to main thread
-                                    let _ = tx.send(clipboard);
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("[PRIMARY] Failed to create clipboard: {}", e);
-                            }
-                        }
-                    });
-                }
+    let Some(terminal) = gui.get_active_terminal() else {
+        return false;
+    };
+    let t = terminal.lock().unwrap();
+    let Some(text) = t.get_selected_text().filter(|text| !text.is_empty()) else {
+        return false;
+    };
 
-                // Clear selection
-                *t.selection.lock().unwrap() = None;
+    clipboard.set(&text);
+    clipboard.set_primary(&text);
 
-                // Start copy animation
-                if let (Some(rect), Some(pane_layout)) = (selection_rect, gui.get_active_pane_layout()) {
-                    pane_layout.copy_animation = Some(crate::ui::animations::CopyAnimation::new(rect));
-                }
-                return true;
-            }
+    if let Some(pane_layout) = gui.get_active_pane_layout() {
+        let (window_w, window_h) = canvas_window.size_in_pixels();
+        let selection_rect = pane_layout.active_selection_rect(&t, 0, tab_bar_height as i32, window_w, window_h - tab_bar_height, char_width, char_height);
+        if let Some(rect) = selection_rect {
+            pane_layout.trigger_copy_feedback(rect, copy_animation);
         }
     }
-    false
+
+    if clear_selection {
+        *t.selection.lock().unwrap() = None;
+    }
+    true
 }
 
 /// Handle normal key presses (arrow keys, function keys, etc.)
-pub fn handle_normal_key(keycode: Keycode, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> KeyboardResult {
+/// Returns true if a keypad key was sent as an application sequence, in which case the
+/// text input event for the same key press must not be typed as well.
+pub fn handle_normal_key(keycode: Keycode, modifier: u8, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> bool {
+    let mut sent_keypad_sequence = false;
     // Send keys to all selected terminals (or just active if none selected)
     let mut gui = tab_bar_gui.lock().unwrap();
     if let Some(pane_layout) = gui.get_active_pane_layout() {
@@ -566,64 +669,244 @@ pub fn handle_normal_key(keycode: Keycode, tab_bar_gui: &Arc<Mutex<TabBarGui>>)
 
             // Check if application cursor keys mode is enabled
             let app_cursor_mode = *t.application_cursor_keys.lock().unwrap();
+            let app_keypad_mode = *t.keypad_application_mode.lock().unwrap();
 
-            match keycode {
-                Keycode::Return => t.send_key(b"\r"),
-                Keycode::Backspace => t.send_key(&backspace_key),
-                Keycode::Tab => t.send_key(b"\t"),
-                Keycode::Escape => t.send_key(b"\x1b"),
-                Keycode::Up => {
-                    if app_cursor_mode {
-                        t.send_key(b"\x1bOA")
-                    } else {
-                        t.send_key(b"\x1b[A")
-                    }
-                }
-                Keycode::Down => {
-                    if app_cursor_mode {
-                        t.send_key(b"\x1bOB")
-                    } else {
-                        t.send_key(b"\x1b[B")
-                    }
-                }
-                Keycode::Right => {
-                    if app_cursor_mode {
-                        t.send_key(b"\x1bOC")
-                    } else {
-                        t.send_key(b"\x1b[C")
-                    }
-                }
-                Keycode::Left => {
-                    if app_cursor_mode {
-                        t.send_key(b"\x1bOD")
-                    } else {
-                        t.send_key(b"\x1b[D")
-                    }
-                }
-                Keycode::Home => t.send_key(b"\x1b[H"),
-                Keycode::End => t.send_key(b"\x1b[F"),
-                Keycode::PageUp => t.send_key(b"\x1b[5~"),
-                Keycode::PageDown => t.send_key(b"\x1b[6~"),
-                Keycode::Insert => t.send_key(b"\x1b[2~"),
-                Keycode::Delete => t.send_key(b"\x1b[3~"),
-                Keycode::F1 => t.send_key(b"\x1bOP"),
-                Keycode::F2 => t.send_key(b"\x1bOQ"),
-                Keycode::F3 => t.send_key(b"\x1bOR"),
-                Keycode::F4 => t.send_key(b"\x1bOS"),
-                Keycode::F5 => t.send_key(b"\x1b[15~"),
-                Keycode::F6 => t.send_key(b"\x1b[17~"),
-                Keycode::F7 => t.send_key(b"\x1b[18~"),
-                Keycode::F8 => t.send_key(b"\x1b[19~"),
-                Keycode::F9 => t.send_key(b"\x1b[20~"),
-                Keycode::F10 => t.send_key(b"\x1b[21~"),
-                Keycode::F11 => t.send_key(b"\x1b[23~"),
-                Keycode::F12 => t.send_key(b"\x1b[24~"),
-                _ => {}
+            if let Some(bytes) = keypad_key_bytes(keycode, app_keypad_mode) {
+                t.send_key(&bytes);
+                sent_keypad_sequence = true;
+            } else if let Some(bytes) = normal_key_bytes(keycode, modifier, &backspace_key, app_cursor_mode) {
+                t.send_key(&bytes);
             }
         }
     }
-    // Request render after sending key to terminal so visual feedback is immediate
-    KeyboardResult::render()
+    sent_keypad_sequence
+}
+
+/// Handle a key pressed with Alt when Alt sends ESC. Returns true if the key was sent.
+pub fn handle_alt_key(keycode: Keycode, is_shift: bool, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> bool {
+    let mut sent = false;
+    let mut gui = tab_bar_gui.lock().unwrap();
+    if let Some(pane_layout) = gui.get_active_pane_layout() {
+        for terminal in pane_layout.get_group_input_terminals() {
+            let mut t = terminal.lock().unwrap();
+            let backspace_key = t.shell_config.keys.backspace.clone();
+            let app_cursor_mode = *t.application_cursor_keys.lock().unwrap();
+
+            if let Some(bytes) = alt_key_bytes(keycode, is_shift, &backspace_key, app_cursor_mode) {
+                t.send_key(&bytes);
+                sent = true;
+            }
+        }
+    }
+    sent
+}
+
+/// xterm modifier parameter: 1 plus Shift=1, Alt=2, Ctrl=4. A value of 1 means no modifiers.
+pub fn key_modifier_param(is_shift: bool, is_alt: bool, is_ctrl: bool) -> u8 {
+    1 + is_shift as u8 + 2 * is_alt as u8 + 4 * is_ctrl as u8
+}
+
+/// Cursor-style key (arrows, Home, End): `CSI X` normally, `SS3 X` in application cursor
+/// mode, and `CSI 1;m X` when modifiers are held
+fn cursor_key_bytes(final_byte: u8, modifier: u8, app_cursor_mode: bool) -> Vec<u8> {
+    if modifier > 1 {
+        format!("\x1b[1;{}{}", modifier, final_byte as char).into_bytes()
+    } else if app_cursor_mode {
+        vec![0x1b, b'O', final_byte]
+    } else {
+        vec![0x1b, b'[', final_byte]
+    }
+}
+
+/// F1-F4: `SS3 X`, or `CSI 1;m X` when modifiers are held
+fn ss3_function_key_bytes(final_byte: u8, modifier: u8) -> Vec<u8> {
+    if modifier > 1 {
+        format!("\x1b[1;{}{}", modifier, final_byte as char).into_bytes()
+    } else {
+        vec![0x1b, b'O', final_byte]
+    }
+}
+
+/// Editing keypad and F5-F12: `CSI n ~`, or `CSI n;m ~` when modifiers are held
+fn tilde_key_bytes(code: u8, modifier: u8) -> Vec<u8> {
+    if modifier > 1 {
+        format!("\x1b[{};{}~", code, modifier).into_bytes()
+    } else {
+        format!("\x1b[{}~", code).into_bytes()
+    }
+}
+
+/// Bytes a special key (arrows, function keys, etc.) sends to the terminal, following
+/// xterm-256color terminfo
+fn normal_key_bytes(keycode: Keycode, modifier: u8, backspace_key: &[u8], app_cursor_mode: bool) -> Option<Vec<u8>> {
+    let bytes = match keycode {
+        Keycode::Return | Keycode::KpEnter => b"\r".to_vec(),
+        Keycode::Backspace => backspace_key.to_vec(),
+        // Shift+Tab is back-tab (kcbt)
+        Keycode::Tab if modifier == 2 => b"\x1b[Z".to_vec(),
+        Keycode::Tab => b"\t".to_vec(),
+        Keycode::Escape => b"\x1b".to_vec(),
+        Keycode::Up => cursor_key_bytes(b'A', modifier, app_cursor_mode),
+        Keycode::Down => cursor_key_bytes(b'B', modifier, app_cursor_mode),
+        Keycode::Right => cursor_key_bytes(b'C', modifier, app_cursor_mode),
+        Keycode::Left => cursor_key_bytes(b'D', modifier, app_cursor_mode),
+        Keycode::Home => cursor_key_bytes(b'H', modifier, app_cursor_mode),
+        Keycode::End => cursor_key_bytes(b'F', modifier, app_cursor_mode),
+        Keycode::Insert => tilde_key_bytes(2, modifier),
+        Keycode::Delete => tilde_key_bytes(3, modifier),
+        Keycode::PageUp => tilde_key_bytes(5, modifier),
+        Keycode::PageDown => tilde_key_bytes(6, modifier),
+        Keycode::F1 => ss3_function_key_bytes(b'P', modifier),
+        Keycode::F2 => ss3_function_key_bytes(b'Q', modifier),
+        Keycode::F3 => ss3_function_key_bytes(b'R', modifier),
+        Keycode::F4 => ss3_function_key_bytes(b'S', modifier),
+        Keycode::F5 => tilde_key_bytes(15, modifier),
+        Keycode::F6 => tilde_key_bytes(17, modifier),
+        Keycode::F7 => tilde_key_bytes(18, modifier),
+        Keycode::F8 => tilde_key_bytes(19, modifier),
+        Keycode::F9 => tilde_key_bytes(20, modifier),
+        Keycode::F10 => tilde_key_bytes(21, modifier),
+        Keycode::F11 => tilde_key_bytes(23, modifier),
+        Keycode::F12 => tilde_key_bytes(24, modifier),
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// Bytes for a numeric keypad key in application keypad mode (DECKPAM), e.g. `ESC O p`
+/// for keypad 0. In numeric mode the keys type their characters through text input,
+/// so None is returned.
+fn keypad_key_bytes(keycode: Keycode, app_keypad_mode: bool) -> Option<Vec<u8>> {
+    if !app_keypad_mode {
+        return None;
+    }
+    let final_byte = match keycode {
+        Keycode::Kp0 => b'p',
+        Keycode::Kp1 => b'q',
+        Keycode::Kp2 => b'r',
+        Keycode::Kp3 => b's',
+        Keycode::Kp4 => b't',
+        Keycode::Kp5 => b'u',
+        Keycode::Kp6 => b'v',
+        Keycode::Kp7 => b'w',
+        Keycode::Kp8 => b'x',
+        Keycode::Kp9 => b'y',
+        Keycode::KpMultiply => b'j',
+        Keycode::KpPlus => b'k',
+        Keycode::KpMinus => b'm',
+        Keycode::KpPeriod => b'n',
+        Keycode::KpDivide => b'o',
+        Keycode::KpEnter => b'M',
+        _ => return None,
+    };
+    Some(vec![0x1b, b'O', final_byte])
+}
+
+/// Character a letter, digit or punctuation key types, with Shift applied as on a US layout
+fn keycode_char(keycode: Keycode, is_shift: bool) -> Option<char> {
+    let ch = match keycode {
+        Keycode::A => 'a',
+        Keycode::B => 'b',
+        Keycode::C => 'c',
+        Keycode::D => 'd',
+        Keycode::E => 'e',
+        Keycode::F => 'f',
+        Keycode::G => 'g',
+        Keycode::H => 'h',
+        Keycode::I => 'i',
+        Keycode::J => 'j',
+        Keycode::K => 'k',
+        Keycode::L => 'l',
+        Keycode::M => 'm',
+        Keycode::N => 'n',
+        Keycode::O => 'o',
+        Keycode::P => 'p',
+        Keycode::Q => 'q',
+        Keycode::R => 'r',
+        Keycode::S => 's',
+        Keycode::T => 't',
+        Keycode::U => 'u',
+        Keycode::V => 'v',
+        Keycode::W => 'w',
+        Keycode::X => 'x',
+        Keycode::Y => 'y',
+        Keycode::Z => 'z',
+        Keycode::_0 => '0',
+        Keycode::_1 => '1',
+        Keycode::_2 => '2',
+        Keycode::_3 => '3',
+        Keycode::_4 => '4',
+        Keycode::_5 => '5',
+        Keycode::_6 => '6',
+        Keycode::_7 => '7',
+        Keycode::_8 => '8',
+        Keycode::_9 => '9',
+        Keycode::Space => ' ',
+        Keycode::Period => '.',
+        Keycode::Comma => ',',
+        Keycode::Slash => '/',
+        Keycode::Backslash => '\\',
+        Keycode::Minus => '-',
+        Keycode::Equals => '=',
+        Keycode::Semicolon => ';',
+        Keycode::LeftBracket => '[',
+        Keycode::RightBracket => ']',
+        Keycode::Apostrophe => '\'',
+        Keycode::Grave => '`',
+        _ => return None,
+    };
+    if !is_shift {
+        return Some(ch);
+    }
+
+    let shifted = match ch {
+        'a'..='z' => ch.to_ascii_uppercase(),
+        '1' => '!',
+        '2' => '@',
+        '3' => '#',
+        '4' => '$',
+        '5' => '%',
+        '6' => '^',
+        '7' => '&',
+        '8' => '*',
+        '9' => '(',
+        '0' => ')',
+        '.' => '>',
+        ',' => '<',
+        '/' => '?',
+        '\\' => '|',
+        '-' => '_',
+        '=' => '+',
+        ';' => ':',
+        '[' => '{',
+        ']' => '}',
+        '\'' => '"',
+        '`' => '~',
+        _ => ch,
+    };
+    Some(shifted)
+}
+
+/// Bytes for Alt+key when Alt sends ESC: ESC followed by what the key sends on its own,
+/// e.g. Alt+b is `ESC b` and Alt+Backspace is `ESC DEL`. Keys with an xterm modifier
+/// encoding (arrows, function keys) use it instead. Returns None for unmapped keys.
+pub fn alt_key_bytes(keycode: Keycode, is_shift: bool, backspace_key: &[u8], app_cursor_mode: bool) -> Option<Vec<u8>> {
+    let key_bytes = match keycode_char(keycode, is_shift) {
+        Some(ch) => vec![ch as u8],
+        None => {
+            let modifier = key_modifier_param(is_shift, true, false);
+            let key_bytes = normal_key_bytes(keycode, modifier, backspace_key, app_cursor_mode)?;
+            if key_bytes.len() > 1 && key_bytes[0] == 0x1b {
+                // Already carries the Alt modifier
+                return Some(key_bytes);
+            }
+            key_bytes
+        }
+    };
+
+    let mut bytes = vec![0x1b];
+    bytes.extend(key_bytes);
+    Some(bytes)
 }
 
 /// Handle Ctrl+key combinations for control characters
@@ -642,6 +925,26 @@ pub fn handle_ctrl_key(scancode: Scancode, ctrl_keys: &HashMap<Scancode, u8>, ta
     KeyboardResult::none()
 }
 
+/// Handle text editing (IME composition) events. The pre-edit text is only displayed;
+/// it reaches the terminal with the TextInput event that commits it.
+pub fn handle_text_editing(text: &str, start: i32, tab_bar: &TabBar, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> KeyboardResult {
+    if tab_bar.editing_tab.is_some() {
+        // Tab renaming only takes committed text
+        return KeyboardResult::none();
+    }
+
+    let mut gui = tab_bar_gui.lock().unwrap();
+    gui.composition = if text.is_empty() {
+        None
+    } else {
+        Some(Composition {
+            text: text.to_string(),
+            cursor: (start.max(0) as usize).min(text.chars().count()),
+        })
+    };
+    KeyboardResult::render()
+}
+
 /// Handle text input events
 pub fn handle_text_input(text: &str, tab_bar: &mut TabBar, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> KeyboardResult {
     if let Some(idx) = tab_bar.editing_tab {
@@ -654,6 +957,8 @@ pub fn handle_text_input(text: &str, tab_bar: &mut TabBar, tab_bar_gui: &Arc<Mut
     } else {
         // Send text to all selected terminals (or just active if none selected)
         let mut gui = tab_bar_gui.lock().unwrap();
+        // Committed text replaces any composition in progress
+        gui.composition = None;
         if let Some(pane_layout) = gui.get_active_pane_layout() {
             let terminals = pane_layout.get_group_input_terminals();
             for terminal in terminals {
@@ -664,3 +969,116 @@ pub fn handle_text_input(text: &str, tab_bar: &mut TabBar, tab_bar_gui: &Arc<Mut
         KeyboardResult::render()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alt_key_sends_escape_prefix() {
+        assert_eq!(alt_key_bytes(Keycode::B, false, b"\x7f", false), Some(b"\x1bb".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::F, true, b"\x7f", false), Some(b"\x1bF".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::Period, false, b"\x7f", false), Some(b"\x1b.".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::Comma, true, b"\x7f", false), Some(b"\x1b<".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::Slash, true, b"\x7f", false), Some(b"\x1b?".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::_3, false, b"\x7f", false), Some(b"\x1b3".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::_3, true, b"\x7f", false), Some(b"\x1b#".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::Backspace, false, b"\x7f", false), Some(b"\x1b\x7f".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::Left, false, b"\x7f", false), Some(b"\x1b[1;3D".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::Left, true, b"\x7f", true), Some(b"\x1b[1;4D".to_vec()));
+        assert_eq!(alt_key_bytes(Keycode::LAlt, false, b"\x7f", false), None);
+    }
+
+    #[test]
+    fn test_special_key_sequences() {
+        let none = key_modifier_param(false, false, false);
+        let shift = key_modifier_param(true, false, false);
+        let ctrl = key_modifier_param(false, false, true);
+        let ctrl_shift = key_modifier_param(true, false, true);
+
+        // (key, modifier, application cursor mode, expected bytes)
+        let cases: &[(Keycode, u8, bool, &[u8])] = &[
+            (Keycode::Up, none, false, b"\x1b[A"),
+            (Keycode::Up, none, true, b"\x1bOA"),
+            (Keycode::Left, ctrl, false, b"\x1b[1;5D"),
+            (Keycode::Right, ctrl, true, b"\x1b[1;5C"),
+            (Keycode::Down, shift, false, b"\x1b[1;2B"),
+            (Keycode::Home, none, false, b"\x1b[H"),
+            (Keycode::Home, none, true, b"\x1bOH"),
+            (Keycode::End, none, false, b"\x1b[F"),
+            (Keycode::End, none, true, b"\x1bOF"),
+            (Keycode::End, ctrl_shift, false, b"\x1b[1;6F"),
+            (Keycode::Insert, none, false, b"\x1b[2~"),
+            (Keycode::Delete, none, false, b"\x1b[3~"),
+            (Keycode::Delete, ctrl, false, b"\x1b[3;5~"),
+            (Keycode::PageUp, none, false, b"\x1b[5~"),
+            (Keycode::PageDown, shift, false, b"\x1b[6;2~"),
+            (Keycode::F1, none, false, b"\x1bOP"),
+            (Keycode::F4, none, true, b"\x1bOS"),
+            (Keycode::F2, shift, false, b"\x1b[1;2Q"),
+            (Keycode::F5, none, false, b"\x1b[15~"),
+            (Keycode::F6, none, false, b"\x1b[17~"),
+            (Keycode::F10, none, false, b"\x1b[21~"),
+            (Keycode::F11, none, false, b"\x1b[23~"),
+            (Keycode::F12, ctrl, false, b"\x1b[24;5~"),
+            (Keycode::Tab, none, false, b"\t"),
+            (Keycode::Tab, shift, false, b"\x1b[Z"),
+            (Keycode::Return, none, false, b"\r"),
+            (Keycode::KpEnter, none, false, b"\r"),
+            (Keycode::Backspace, none, false, b"\x7f"),
+        ];
+
+        for (keycode, modifier, app_cursor_mode, expected) in cases {
+            assert_eq!(
+                normal_key_bytes(*keycode, *modifier, b"\x7f", *app_cursor_mode).as_deref(),
+                Some(*expected),
+                "{:?} with modifier {} (app cursor: {})",
+                keycode,
+                modifier,
+                app_cursor_mode
+            );
+        }
+        assert_eq!(normal_key_bytes(Keycode::A, none, b"\x7f", false), None);
+    }
+
+    #[test]
+    fn test_keypad_keys_follow_keypad_mode() {
+        // Application mode (DECKPAM)
+        assert_eq!(keypad_key_bytes(Keycode::Kp0, true), Some(b"\x1bOp".to_vec()));
+        assert_eq!(keypad_key_bytes(Keycode::Kp9, true), Some(b"\x1bOy".to_vec()));
+        assert_eq!(keypad_key_bytes(Keycode::KpEnter, true), Some(b"\x1bOM".to_vec()));
+        assert_eq!(keypad_key_bytes(Keycode::KpMinus, true), Some(b"\x1bOm".to_vec()));
+        assert_eq!(keypad_key_bytes(Keycode::Up, true), None);
+
+        // Numeric mode (DECKPNM): digits arrive as text input, Enter is a plain CR
+        assert_eq!(keypad_key_bytes(Keycode::Kp0, false), None);
+        assert_eq!(keypad_key_bytes(Keycode::KpEnter, false), None);
+        assert_eq!(normal_key_bytes(Keycode::KpEnter, 1, b"\x7f", false), Some(b"\r".to_vec()));
+    }
+
+    #[test]
+    fn test_multiline_paste_detection() {
+        assert!(!is_multiline_paste("ls -la"));
+        assert!(!is_multiline_paste(""));
+        // A single command copied with its line ending would run immediately
+        assert!(is_multiline_paste("curl example.com | sh\n"));
+        assert!(is_multiline_paste("cd /tmp\nrm -rf *"));
+        assert!(is_multiline_paste("echo a\recho b"));
+    }
+
+    #[test]
+    fn test_single_line_paste_loses_its_line_ending() {
+        assert_eq!(trim_single_line_paste("ls -la\n"), "ls -la");
+        assert_eq!(trim_single_line_paste("ls -la\r\n"), "ls -la");
+        assert_eq!(trim_single_line_paste("ls -la\r"), "ls -la");
+        assert_eq!(trim_single_line_paste("ls -la"), "ls -la");
+        assert_eq!(trim_single_line_paste("cd /tmp\r\nls\r\n"), "cd /tmp\r\nls\r\n");
+        assert_eq!(trim_single_line_paste("ls\n\n"), "ls\n\n");
+    }
+
+    #[test]
+    fn test_large_paste_detection() {
+        assert!(!is_large_paste(&"x".repeat(LARGE_PASTE_BYTES)));
+        assert!(is_large_paste(&"x".repeat(LARGE_PASTE_BYTES + 1)));
+    }
+}
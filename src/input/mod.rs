@@ -5,8 +5,10 @@
 //! - `keyboard`: Keyboard event handling
 //! - `mouse`: Mouse event handling
 //! - `events`: SDL2 event dispatching
+//! - `focus`: Focus reporting to the active pane
 
 pub mod events;
+pub mod focus;
 pub mod hotkeys;
 pub mod keyboard;
 pub mod mouse;
@@ -1,15 +1,8 @@
-use sdl3::mouse::MouseButton;
+use sdl3::mouse::{Cursor, MouseButton, SystemCursor};
 use std::sync::{Arc, Mutex};
 
-#[cfg(target_os = "linux")]
-use arboard::Clipboard;
-#[cfg(not(target_os = "linux"))]
-use arboard::Clipboard;
-
-#[cfg(target_os = "linux")]
-use std::sync::mpsc::Sender;
-
 use crate::sdl_renderer::TabBar;
+use crate::system::clipboard::ClipboardProvider;
 use crate::tab_gui::TabBarGui;
 
 /// Actions that mouse handler can request from the main loop
@@ -19,8 +12,12 @@ pub enum MouseAction {
     CloseWindow,
     MinimizeWindow,
     CloseTab(usize),
+    /// Close several tabs at once, keeping the given tab
+    CloseTabs(Vec<usize>, usize),
     SwitchTab(usize),
     OpenSettings,
+    /// Paste text from the primary selection, subject to the paste confirmation
+    Paste(String),
     None,
 }
 
@@ -68,6 +65,12 @@ pub struct MouseState {
     pub dragging_tab: bool,
     pub tab_drag_start_pos: (i32, i32),
     pub ready_to_drag_tab: bool,
+    /// Tab under the middle button when it went down; it closes if the button comes up over it too
+    pub middle_pressed_tab: Option<usize>,
+    /// Divider under the pointer or being dragged, drawn highlighted, and the way it drags
+    pub hovered_divider: Option<(crate::pane_layout::PaneId, crate::pane_layout::SplitDirection)>,
+    /// Resize cursor shown over a divider, kept alive while it is set
+    pointer_cursor: Option<Cursor>,
 }
 
 impl MouseState {
@@ -82,7 +85,34 @@ impl MouseState {
             dragging_tab: false,
             tab_drag_start_pos: (0, 0),
             ready_to_drag_tab: false,
+            middle_pressed_tab: None,
+            hovered_divider: None,
+            pointer_cursor: None,
+        }
+    }
+
+    /// Highlight a divider, or none, switching to a resize cursor over it. Returns true if
+    /// the highlight changed.
+    fn set_hovered_divider(&mut self, hovered: Option<(crate::pane_layout::PaneId, crate::pane_layout::SplitDirection)>) -> bool {
+        if hovered == self.hovered_divider {
+            return false;
+        }
+        let system_cursor = match hovered.map(|(_split_id, direction)| direction) {
+            Some(crate::pane_layout::SplitDirection::Horizontal) => SystemCursor::EWResize,
+            Some(crate::pane_layout::SplitDirection::Vertical) => SystemCursor::NSResize,
+            None => SystemCursor::Default,
+        };
+        if self.hovered_divider.map(|(_, direction)| direction) != hovered.map(|(_, direction)| direction) {
+            match Cursor::from_system(system_cursor) {
+                Ok(cursor) => {
+                    cursor.set();
+                    self.pointer_cursor = Some(cursor);
+                }
+                Err(e) => eprintln!("[MOUSE] Failed to create resize cursor: {}", e),
+            }
         }
+        self.hovered_divider = hovered;
+        true
     }
 }
 
@@ -109,8 +139,9 @@ pub fn send_mouse_to_terminal(
         let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
 
         // Find which pane contains the mouse
-        for (_pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
+        for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
             if rect.contains_point((mouse_x, mouse_y)) {
+                let (char_width, char_height) = pane_layout.pane_char_size(pane_id, char_width, char_height);
                 // Convert screen coordinates to terminal coordinates (1-based)
                 let (relative_x, relative_y) = crate::ui::render::adjust_mouse_coords_for_padding(mouse_x, mouse_y, rect.x(), rect.y());
                 let col = ((relative_x as f32 / char_width).floor() as u32 + 1).max(1);
@@ -125,6 +156,39 @@ pub fn send_mouse_to_terminal(
     }
 }
 
+/// If the click landed on a pane's "new output" badge, return that pane to the live view
+#[allow(clippy::too_many_arguments)]
+fn click_new_output_badge(
+    pane_layout: &crate::pane_layout::PaneLayout,
+    pane_id: crate::pane_layout::PaneId,
+    mouse_x: i32,
+    mouse_y: i32,
+    char_width: f32,
+    char_height: f32,
+    pane_area_y: i32,
+    window_width: u32,
+    pane_area_height: u32,
+) -> bool {
+    let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
+    let Some((_, rect, terminal, _, _)) = pane_rects.into_iter().find(|(id, _, _, _, _)| *id == pane_id) else {
+        return false;
+    };
+    let (char_width, char_height) = pane_layout.pane_char_size(pane_id, char_width, char_height);
+    if !crate::ui::render::new_output_badge_rect(rect, char_width, char_height).contains_point((mouse_x, mouse_y)) {
+        return false;
+    }
+
+    let Ok(t) = terminal.lock() else {
+        return false;
+    };
+    let mut sb = t.screen_buffer.lock().unwrap();
+    if !sb.has_new_output_below() {
+        return false;
+    }
+    sb.reset_view_offset();
+    true
+}
+
 /// Handle selection start
 pub fn handle_selection_start(
     tab_bar_gui: &Arc<Mutex<TabBarGui>>,
@@ -145,8 +209,9 @@ pub fn handle_selection_start(
         let pane_area_height = window_height - tab_bar_height;
         let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
 
-        for (_pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
+        for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
             if rect.contains_point((mouse_x, mouse_y)) {
+                let (char_width, char_height) = pane_layout.pane_char_size(pane_id, char_width, char_height);
                 let (relative_x, relative_y) = crate::ui::render::adjust_mouse_coords_for_padding(mouse_x, mouse_y, rect.x(), rect.y());
                 let col = ((relative_x as f32 / char_width).floor() as usize).max(0);
                 let row = ((relative_y as f32 / char_height).floor() as usize).max(0);
@@ -180,8 +245,9 @@ pub fn handle_selection_update(
         let pane_area_height = window_height - tab_bar_height;
         let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
 
-        for (_pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
+        for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
             if rect.contains_point((mouse_x, mouse_y)) {
+                let (char_width, char_height) = pane_layout.pane_char_size(pane_id, char_width, char_height);
                 let (relative_x, relative_y) = crate::ui::render::adjust_mouse_coords_for_padding(mouse_x, mouse_y, rect.x(), rect.y());
                 let col = ((relative_x as f32 / char_width).floor() as usize).max(0);
                 let row = ((relative_y as f32 / char_height).floor() as usize).max(0);
@@ -195,6 +261,45 @@ pub fn handle_selection_update(
     }
 }
 
+/// Handle Shift+click on a pane: move the nearer end of its selection to the clicked cell.
+/// Returns false if the clicked pane has no selection.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_selection_extend(
+    tab_bar_gui: &Arc<Mutex<TabBarGui>>,
+    mouse_x: i32,
+    mouse_y: i32,
+    char_width: f32,
+    char_height: f32,
+    tab_bar_height: u32,
+    window_width: u32,
+    window_height: u32,
+) -> bool {
+    let mut gui = match tab_bar_gui.try_lock() {
+        Ok(g) => g,
+        Err(_) => return false,
+    };
+    if let Some(pane_layout) = gui.get_active_pane_layout() {
+        let pane_area_y = tab_bar_height as i32;
+        let pane_area_height = window_height - tab_bar_height;
+        let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
+
+        for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
+            if rect.contains_point((mouse_x, mouse_y)) {
+                let (char_width, char_height) = pane_layout.pane_char_size(pane_id, char_width, char_height);
+                let (relative_x, relative_y) = crate::ui::render::adjust_mouse_coords_for_padding(mouse_x, mouse_y, rect.x(), rect.y());
+                let col = ((relative_x as f32 / char_width).floor() as usize).max(0);
+                let row = ((relative_y as f32 / char_height).floor() as usize).max(0);
+
+                if let Ok(mut t) = terminal.lock() {
+                    return t.extend_selection(col, row);
+                }
+                break;
+            }
+        }
+    }
+    false
+}
+
 /// Handle mouse button down event
 #[allow(clippy::too_many_arguments)]
 pub fn handle_mouse_button_down(
@@ -211,14 +316,22 @@ pub fn handle_mouse_button_down(
     window_height: u32,
     mouse_state: &mut MouseState,
     event_pump: &sdl3::EventPump,
-    #[allow(unused_variables)]
-    #[cfg(target_os = "linux")]
-    clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
 ) -> MouseResult {
     match mouse_btn {
         MouseButton::Right => {
             // Right-click for context menu
-            if mouse_y >= tab_bar_height as i32 {
+            if mouse_y < tab_bar_height as i32 {
+                if let Some(tab_idx) = tab_bar.get_clicked_tab(mouse_x, mouse_y) {
+                    if let Ok(mut gui) = tab_bar_gui.try_lock() {
+                        if let Some(pane_layout) = gui.get_active_pane_layout() {
+                            pane_layout.context_menu_open = None;
+                            pane_layout.context_menu = None;
+                        }
+                        gui.open_tab_context_menu(tab_idx, mouse_x, mouse_y);
+                    }
+                }
+            } else {
                 // Send right mouse button press to terminal (button 2 = right)
                 send_mouse_to_terminal(
                     tab_bar_gui,
@@ -237,6 +350,7 @@ pub fn handle_mouse_button_down(
                 let pane_area_height = window_height - tab_bar_height;
 
                 if let Ok(mut gui) = tab_bar_gui.try_lock() {
+                    gui.tab_context_menu = None;
                     if let Some(pane_layout) = gui.get_active_pane_layout() {
                         // Find which pane was clicked and open context menu
                         let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
@@ -252,12 +366,9 @@ pub fn handle_mouse_button_down(
             MouseResult::render()
         }
         MouseButton::Middle => {
-            // Check if middle click is on a tab in the tab bar
+            // Middle click on a tab closes it once the button is released over the same tab
             if mouse_y < tab_bar_height as i32 {
-                if let Some(tab_idx) = tab_bar.get_clicked_tab(mouse_x, mouse_y) {
-                    return MouseResult::with_action(MouseAction::CloseTab(tab_idx));
-                }
-                // If in tab bar but not on a tab, just render
+                mouse_state.middle_pressed_tab = tab_bar.get_clicked_tab(mouse_x, mouse_y);
                 return MouseResult::render();
             }
 
@@ -278,30 +389,10 @@ pub fn handle_mouse_button_down(
             }
 
             // Middle click paste
-            if let Ok(gui) = tab_bar_gui.try_lock() {
-                if let Some(terminal) = gui.get_active_terminal() {
-                    if let Ok(mut t) = terminal.try_lock() {
-                        #[cfg(target_os = "linux")]
-                        {
-                            use arboard::{GetExtLinux, LinuxClipboardKind};
-                            match Clipboard::new() {
-                                Ok(mut clipboard) => match clipboard.get().clipboard(LinuxClipboardKind::Primary).text() {
-                                    Ok(text) => {
-                                        t.send_paste(&text);
-                                    }
-                                    Err(e) => {
-                                        eprintln!("[PRIMARY] Failed to get PRIMARY clipboard text: {}", e);
-                                    }
-                                },
-                                Err(e) => {
-                                    eprintln!("[PRIMARY] Failed to create clipboard: {}", e);
-                                }
-                            }
-                        }
-                    }
-                }
+            match clipboard.get_primary() {
+                Some(text) => MouseResult::with_action(MouseAction::Paste(text)),
+                None => MouseResult::render(),
             }
-            MouseResult::render()
         }
         MouseButton::Left => handle_left_button_down(
             mouse_x,
@@ -360,6 +451,8 @@ fn handle_left_button_down(
     let keyboard_state = event_pump.keyboard_state();
     let is_ctrl_pressed =
         keyboard_state.is_scancode_pressed(sdl3::keyboard::Scancode::LCtrl) || keyboard_state.is_scancode_pressed(sdl3::keyboard::Scancode::RCtrl);
+    let is_shift_pressed =
+        keyboard_state.is_scancode_pressed(sdl3::keyboard::Scancode::LShift) || keyboard_state.is_scancode_pressed(sdl3::keyboard::Scancode::RShift);
 
     if let Ok(mut gui) = tab_bar_gui.try_lock() {
         // Check if other tabs have selections (before mutable borrow)
@@ -368,6 +461,12 @@ fn handle_left_button_down(
         if let Some(pane_layout) = gui.get_active_pane_layout() {
             // Try to start dragging a divider
             if pane_layout.start_drag_divider(mouse_x, mouse_y, 0, pane_area_y, window_width, pane_area_height) {
+                // Double-click resets the split to 50/50; the release resizes the terminals as after a drag
+                if clicks == 2 {
+                    if let Some(split_id) = pane_layout.dragging_divider {
+                        pane_layout.reset_split_ratio(split_id);
+                    }
+                }
                 mouse_state.dragging_divider = true;
                 mouse_state.last_mouse_pos = (mouse_x, mouse_y);
                 return MouseResult::with_divider_drag();
@@ -389,6 +488,23 @@ fn handle_left_button_down(
                     }
                 }
                 // Note: handle_click already sets the active pane
+
+                // Clicking the "new output" badge jumps back to the live view
+                if !is_ctrl_pressed
+                    && click_new_output_badge(
+                        pane_layout,
+                        clicked_pane_id,
+                        mouse_x,
+                        mouse_y,
+                        char_width,
+                        char_height,
+                        pane_area_y,
+                        window_width,
+                        pane_area_height,
+                    )
+                {
+                    return MouseResult::render();
+                }
             }
 
             // Handle double-click word selection
@@ -404,6 +520,7 @@ fn handle_left_button_down(
 
                             // Find the active pane rect
                             if let Some((_, rect, _, _, _)) = pane_rects.iter().find(|(pid, _, _, _, _)| *pid == pane_layout.active_pane) {
+                                let (char_width, char_height) = pane_layout.pane_char_size(pane_layout.active_pane, char_width, char_height);
                                 let col = ((mouse_x - rect.x() - pane_padding as i32) as f32 / char_width) as usize;
                                 let row = ((mouse_y - rect.y() - pane_padding as i32) as f32 / char_height) as usize;
 
@@ -423,6 +540,28 @@ fn handle_left_button_down(
         }
     }
 
+    // Shift+click adjusts an existing selection instead of starting a new one; a drag then
+    // keeps extending it, and the click is not reported to the application
+    if is_shift_pressed
+        && !is_ctrl_pressed
+        && clicks == 1
+        && handle_selection_extend(
+            tab_bar_gui,
+            mouse_x,
+            mouse_y,
+            char_width,
+            char_height,
+            tab_bar_height,
+            window_width,
+            window_height,
+        )
+    {
+        mouse_state.mouse_down_for_selection = true;
+        mouse_state.selection_start_pos = (mouse_x, mouse_y);
+        mouse_state.selection_started = true;
+        return MouseResult::render();
+    }
+
     // Prepare for potential selection (don't start yet)
     mouse_state.mouse_down_for_selection = true;
     mouse_state.selection_start_pos = (mouse_x, mouse_y);
@@ -523,7 +662,8 @@ pub fn handle_mouse_button_up(
     window_width: u32,
     window_height: u32,
     mouse_state: &mut MouseState,
-    #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>,
+    clipboard: &mut dyn ClipboardProvider,
+    copy_animation: bool,
 ) -> MouseResult {
     let mut result = MouseResult::none();
 
@@ -535,8 +675,13 @@ pub fn handle_mouse_button_up(
         if mouse_state.selection_started {
             handle_selection_complete(
                 tab_bar_gui,
-                #[cfg(target_os = "linux")]
-                clipboard_tx,
+                tab_bar_height,
+                char_width,
+                char_height,
+                window_width,
+                window_height,
+                clipboard,
+                copy_animation,
             );
         } else {
             // Click without drag - clear any existing selection
@@ -582,6 +727,13 @@ pub fn handle_mouse_button_up(
         result.needs_render = true;
     }
 
+    if mouse_btn == MouseButton::Middle {
+        if let Some(tab_idx) = middle_click_tab(tab_bar, mouse_state.middle_pressed_tab.take(), mouse_x, mouse_y) {
+            result.action = MouseAction::CloseTab(tab_idx);
+            result.needs_render = true;
+        }
+    }
+
     // If left button released but no drag happened, clear ready state
     if mouse_btn == MouseButton::Left && mouse_state.ready_to_drag_tab {
         mouse_state.ready_to_drag_tab = false;
@@ -620,78 +772,66 @@ pub fn handle_mouse_button_up(
     result
 }
 
+/// The tab closed by a middle click: the button has to go down and come up over the same tab,
+/// so dragging off a tab before releasing cancels the close
+fn middle_click_tab(tab_bar: &TabBar, pressed_tab: Option<usize>, mouse_x: i32, mouse_y: i32) -> Option<usize> {
+    let pressed_tab = pressed_tab?;
+    (tab_bar.get_clicked_tab(mouse_x, mouse_y) == Some(pressed_tab)).then_some(pressed_tab)
+}
+
 /// Handle context menu clicks
 fn handle_context_menu_click(mouse_x: i32, mouse_y: i32, tab_bar_gui: &Arc<Mutex<TabBarGui>>) -> Option<MouseAction> {
     let mut gui = tab_bar_gui.lock().unwrap();
+    if let Some((tab_idx, indices)) = gui.handle_tab_context_menu_click(mouse_x, mouse_y) {
+        if !indices.is_empty() {
+            return Some(MouseAction::CloseTabs(indices, tab_idx));
+        }
+        return None;
+    }
     if let Some(pane_layout) = gui.get_active_pane_layout() {
         pane_layout.handle_context_menu_click(mouse_x, mouse_y);
     }
     None
 }
 
+/// Copy a finished mouse selection: to the primary selection (middle-click paste) on Linux,
+/// to the clipboard on platforms without one
+fn copy_on_select(text: &str, clipboard: &mut dyn ClipboardProvider) {
+    if cfg!(target_os = "linux") {
+        clipboard.set_primary(text);
+    } else {
+        clipboard.set(text);
+    }
+}
+
 /// Handle selection complete (copy to clipboard)
-fn handle_selection_complete(tab_bar_gui: &Arc<Mutex<TabBarGui>>, #[cfg(target_os = "linux")] clipboard_tx: &Sender<Clipboard>) {
-    if let Ok(gui) = tab_bar_gui.try_lock() {
+#[allow(clippy::too_many_arguments)]
+fn handle_selection_complete(
+    tab_bar_gui: &Arc<Mutex<TabBarGui>>,
+    tab_bar_height: u32,
+    char_width: f32,
+    char_height: f32,
+    window_width: u32,
+    window_height: u32,
+    clipboard: &mut dyn ClipboardProvider,
+    copy_animation: bool,
+) {
+    if let Ok(mut gui) = tab_bar_gui.try_lock() {
         if let Some(terminal) = gui.get_active_terminal() {
-            if let Ok(t) = terminal.try_lock() {
-                if let Some(text) = t.get_selected_text() {
-                    if !text.is_empty() {
-                        // Copy selected text to PRIMARY clipboard (Linux middle-click clipboard)
-                        #[cfg(target_os = "linux")]
-                        {
-                            use arboard::{LinuxClipboardKind, SetExtLinux};
-                            let text_copy = text.clone();
-                            let tx = clipboard_tx.clone();
-
-                            // Create clipboard in background thread to avoid blocking
-                            std::thread::spawn(move || {
-                                match Clipboard::new() {
-                                    Ok(mut clipboard) => {
-                                        if let Err(e) = clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text_copy) {
-                                            eprintln!("[PRIMARY] Failed to copy to primary selection: {}", e);
-                                        } else {
-                                            // Send clipboard object back to main thread
-                                            let _ = tx.send(clipboard);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        eprintln!("[PRIMARY] Failed to create clipboard: {}", e);
-                                    }
-                                }
-                            });
-                        }
-                        #[cfg(not(target_os = "linux"))]
-                        {
-                            match Clipboard::new() {
-                                Ok(mut clipboard) => {
-                                    if let Err(e) = clipboard.set_text(text) {
-                                        eprintln!("[CLIPBOARD] Failed to copy: {}", e);
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("[CLIPBOARD] Failed to create clipboard: {}", e);
-                                }
-                            }
-                        }
-                    } else {
-                        drop(t);
-                        if let Ok(gui) = tab_bar_gui.try_lock() {
-                            if let Some(terminal) = gui.get_active_terminal() {
-                                if let Ok(mut t) = terminal.try_lock() {
-                                    t.clear_selection();
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    drop(t);
-                    if let Ok(gui) = tab_bar_gui.try_lock() {
-                        if let Some(terminal) = gui.get_active_terminal() {
-                            if let Ok(mut t) = terminal.try_lock() {
-                                t.clear_selection();
+            if let Ok(mut t) = terminal.try_lock() {
+                match t.get_selected_text().filter(|text| !text.is_empty()) {
+                    Some(text) => {
+                        copy_on_select(&text, clipboard);
+                        if let Some(pane_layout) = gui.get_active_pane_layout() {
+                            let pane_area_height = window_height - tab_bar_height;
+                            let selection_rect =
+                                pane_layout.active_selection_rect(&t, 0, tab_bar_height as i32, window_width, pane_area_height, char_width, char_height);
+                            if let Some(rect) = selection_rect {
+                                pane_layout.trigger_copy_feedback(rect, copy_animation);
                             }
                         }
                     }
+                    None => t.clear_selection(),
                 }
             }
         }
@@ -719,6 +859,10 @@ pub fn handle_mouse_motion(
 
     // Update context menu hover if open
     if let Ok(mut gui) = tab_bar_gui.try_lock() {
+        if gui.tab_context_menu.is_some() {
+            gui.update_tab_context_menu_hover(mouse_x, mouse_y);
+            needs_render = true;
+        }
         if let Some(pane_layout) = gui.get_active_pane_layout() {
             if pane_layout.context_menu.is_some() {
                 pane_layout.update_context_menu_hover(mouse_x, mouse_y);
@@ -788,6 +932,26 @@ pub fn handle_mouse_motion(
         }
     }
 
+    // Highlight the divider under the pointer; one being dragged stays lit even if the pointer
+    // runs ahead of it
+    if !mouse_state.dragging_divider {
+        let hovered = if mouse_y >= tab_bar_height as i32 {
+            let pane_area_y = tab_bar_height as i32;
+            let pane_area_height = window_height - tab_bar_height;
+            match tab_bar_gui.try_lock() {
+                Ok(mut gui) => gui
+                    .get_active_pane_layout()
+                    .and_then(|pane_layout| pane_layout.divider_hit(mouse_x, mouse_y, 0, pane_area_y, window_width, pane_area_height)),
+                Err(_) => mouse_state.hovered_divider,
+            }
+        } else {
+            None
+        };
+        if mouse_state.set_hovered_divider(hovered) {
+            needs_render = true;
+        }
+    }
+
     if mouse_state.dragging_divider {
         let drag_start = std::time::Instant::now();
         let delta_x = mouse_x - mouse_state.last_mouse_pos.0;
@@ -804,7 +968,7 @@ pub fn handle_mouse_motion(
             if let Ok(mut gui) = tab_bar_gui.try_lock() {
                 let lock_acquired = lock_start.elapsed();
                 if let Some(pane_layout) = gui.get_active_pane_layout() {
-                    pane_layout.update_drag_divider(delta_x, delta_y, 0, pane_area_y, window_width, pane_area_height);
+                    pane_layout.update_drag_divider(delta_x, delta_y, 0, pane_area_y, window_width, pane_area_height, char_width, char_height);
                     // Only update last_mouse_pos after successfully applying the delta
                     mouse_state.last_mouse_pos = (mouse_x, mouse_y);
                 }
@@ -909,3 +1073,48 @@ pub fn handle_mouse_wheel(
         needs_render,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdl_renderer::tab_bar_with_tab_rects;
+    use crate::system::clipboard::MockClipboard;
+    use sdl3::rect::Rect;
+
+    fn tab_bar_with_two_tabs() -> TabBar {
+        tab_bar_with_tab_rects(&[Rect::new(0, 3, 200, 34), Rect::new(201, 3, 200, 34)])
+    }
+
+    #[test]
+    fn test_middle_click_closes_tab_released_over_it() {
+        let tab_bar = tab_bar_with_two_tabs();
+
+        // Both the × and a middle click find tabs through the same hit-test
+        assert_eq!(tab_bar.get_clicked_tab(250, 20), Some(1));
+        assert_eq!(tab_bar.get_clicked_close_button(385, 20), Some(1));
+
+        // Any tab closes, not only the active one
+        assert_eq!(tab_bar.active_tab, 0);
+        assert_eq!(middle_click_tab(&tab_bar, Some(1), 260, 20), Some(1));
+        assert_eq!(middle_click_tab(&tab_bar, Some(0), 10, 20), Some(0));
+
+        // Released over another tab, off the tabs, or pressed off the tabs: no close
+        assert_eq!(middle_click_tab(&tab_bar, Some(1), 10, 20), None);
+        assert_eq!(middle_click_tab(&tab_bar, Some(1), 600, 20), None);
+        assert_eq!(middle_click_tab(&tab_bar, Some(1), 250, 300), None);
+        assert_eq!(middle_click_tab(&tab_bar, None, 250, 20), None);
+    }
+
+    #[test]
+    fn test_copy_on_select_targets_primary_on_linux() {
+        let mut clipboard = MockClipboard::default();
+        copy_on_select("selected", &mut clipboard);
+
+        if cfg!(target_os = "linux") {
+            assert_eq!(clipboard.primary.as_deref(), Some("selected"));
+            assert_eq!(clipboard.text, None);
+        } else {
+            assert_eq!(clipboard.text.as_deref(), Some("selected"));
+        }
+    }
+}
@@ -28,8 +28,10 @@ use ui::render;
 const BUILD_DATE: &str = env!("BUILD_DATE");
 const GIT_HASH: &str = env!("GIT_HASH");
 const DEFAULT_SCROLLBACK_LINES: usize = 10000;
+const RECENT_COMMAND_LINES: usize = 5; // Recent commands offered by the history search and AI dialogs
 
-/// Resize all terminals in the active tab to match their pane dimensions
+/// Resize all terminals in the active tab to match their pane dimensions.
+/// Returns false if the resize was skipped because the GUI lock was busy.
 fn resize_terminals_to_panes(
     tab_bar_gui: &Arc<Mutex<TabBarGui>>,
     char_width: f32,
@@ -37,7 +39,7 @@ fn resize_terminals_to_panes(
     tab_bar_height: u32,
     window_width: u32,
     window_height: u32,
-) {
+) -> bool {
     // Non-blocking lock - skip resize if lock is busy (will retry on next event)
     if let Ok(gui) = tab_bar_gui.try_lock() {
         if let Some(pane_layout) = gui.tab_states.get(gui.active_tab) {
@@ -45,8 +47,10 @@ fn resize_terminals_to_panes(
             let pane_area_height = window_height - tab_bar_height;
             let pane_rects = pane_layout.pane_layout.get_pane_rects(0, pane_area_y, window_width, pane_area_height);
 
-            for (_pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
-                let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), char_width, char_height);
+            for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
+                // Zoomed panes fit a different number of cells
+                let (pane_char_width, pane_char_height) = pane_layout.pane_layout.pane_char_size(pane_id, char_width, char_height);
+                let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), pane_char_width, pane_char_height);
 
                 if let Ok(mut t) = terminal.lock() {
                     // Only resize if dimensions have changed
@@ -56,8 +60,10 @@ fn resize_terminals_to_panes(
                 }
             }
         }
+        true
     } else {
         eprintln!("[PERF] Skipped terminal resize - lock busy");
+        false
     }
 }
 
@@ -88,7 +94,8 @@ fn resize_terminals_after_split(
         eprintln!("[RESIZE] Resizing {} terminals after split", pane_rects.len());
 
         for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
-            let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), char_width, char_height);
+            let (pane_char_width, pane_char_height) = pane_layout.pane_layout.pane_char_size(pane_id, char_width, char_height);
+            let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), pane_char_width, pane_char_height);
 
             match terminal.lock() {
                 Ok(mut t) => {
@@ -114,6 +121,18 @@ fn resize_terminals_after_split(
     }
 }
 
+/// Minimize the window, or restore and focus it if it is minimized. PTYs keep running either way.
+fn toggle_window(window: &mut sdl3::video::Window) {
+    if window.is_minimized() {
+        eprintln!("[MAIN] Restoring window");
+        window.restore();
+        window.raise();
+    } else {
+        eprintln!("[MAIN] Hiding window");
+        window.minimize();
+    }
+}
+
 fn main() -> Result<(), String> {
     // eprintln!("[MAIN] Nisdos Terminal starting (built: {})", BUILD_DATE);
 
@@ -124,6 +143,19 @@ fn main() -> Result<(), String> {
     // Parse command-line arguments (exits if --help or --version)
     let cli_args = system::cli::parse_args(BUILD_DATE, GIT_HASH);
 
+    // Headless mode prints the resulting screen and exits before SDL is touched
+    if let Some(headless) = &cli_args.headless {
+        for line in system::cli::run_headless(headless)? {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    // Terminals record their raw output before it is parsed, for reproducing rendering bugs
+    if let Some(path) = &cli_args.record_output {
+        terminal::recorder::set_record_path(path.into());
+    }
+
     // Initialize TTF context (must outlive fonts)
     let ttf_context = sdl3::ttf::init().map_err(|e| e.to_string())?;
 
@@ -140,6 +172,8 @@ fn main() -> Result<(), String> {
     let cpu_font = app.fonts.cpu_font;
     let context_menu_font = app.fonts.context_menu_font;
     let emoji_font = app.fonts.emoji_font;
+    let ui_emoji_font = app.fonts.ui_emoji_font;
+    let context_menu_emoji_font = app.fonts.context_menu_emoji_font;
     let unicode_fallback_font = app.fonts.unicode_fallback_font;
     let cjk_font = app.fonts.cjk_font;
     let mut char_width = app.char_dims.width;
@@ -153,12 +187,12 @@ fn main() -> Result<(), String> {
     let mut sys = app.sys;
     let ctrl_keys = app.ctrl_keys;
     let mut mouse_state = app.mouse_state;
-    let mut glyph_cache = app.glyph_cache;
+    let mut window_geometry = app.window_geometry;
+    let mut glyph_atlas = app.glyph_atlas;
+    let mut frame_stats = render::FrameStats::new(Instant::now());
+    let mut focus_tracker = input::focus::FocusTracker::new();
 
-    #[cfg(target_os = "linux")]
-    let clipboard_tx = app.clipboard_tx;
-    #[cfg(target_os = "linux")]
-    let clipboard_rx = app.clipboard_rx;
+    let mut clipboard = system::clipboard::SystemClipboard::default();
 
     #[cfg(not(target_os = "windows"))]
     let signal_rx = app.signal_rx;
@@ -168,6 +202,7 @@ fn main() -> Result<(), String> {
 
     // CPU monitoring state
     let mut cpu_usage = 0.0_f32;
+    let mut memory_text: Option<String> = None;
     let mut last_cpu_update = Instant::now();
     let cpu_update_interval = std::time::Duration::from_secs(1);
 
@@ -180,6 +215,12 @@ fn main() -> Result<(), String> {
     let mut last_keyboard_input = Instant::now();
     let cursor_debounce_duration = std::time::Duration::from_millis(500);
 
+    // Theme cursor colors, resolved once so invalid values are only reported at startup
+    let theme_cursor_color = settings.theme.cursor_color();
+    let theme_cursor_text_color = settings.theme.cursor_text_color();
+    let missing_glyph = render::MissingGlyph::from_settings_string(&settings.terminal.missing_glyph);
+    let pane_colors = render::PaneColors::from_theme(&settings.theme);
+
     // Get terminal library with hardcoded knowledge
     let term_library = TerminalLibrary::new();
     let shell_config = term_library.get_default_shell().clone();
@@ -187,14 +228,13 @@ fn main() -> Result<(), String> {
     // Pending operations
     let mut pending_pane_split: Option<crate::pane_layout::SplitDirection> = None;
     let mut pending_new_tab = false;
-    let mut last_cache_clear = Instant::now();
+    let mut pending_duplicate_tab = false;
+
+    // Window resizes are applied to terminals only after the drag settles
+    let mut resize_debouncer = system::resize::ResizeDebouncer::new(system::resize::RESIZE_DEBOUNCE);
 
     // Store font path for reloading when font size changes
-    let font_path = if settings.terminal.font_family == "auto" {
-        font_discovery::find_best_monospace_font().unwrap_or_default()
-    } else {
-        settings.terminal.font_family.clone()
-    };
+    let font_path = system::init::get_monospace_font_path(&settings.terminal).unwrap_or_default();
 
     let mut needs_render = true;
     let mut skip_render_count = 0;
@@ -203,28 +243,19 @@ fn main() -> Result<(), String> {
         // Check for termination signals (SIGTERM, SIGINT, SIGHUP from OS)
         #[cfg(not(target_os = "windows"))]
         if let Ok(sig) = signal_rx.try_recv() {
+            if sig == signal_hook::consts::SIGUSR1 {
+                toggle_window(canvas.window_mut());
+                continue;
+            }
             eprintln!("[MAIN] Termination signal {} received, saving state and exiting...", sig);
             if let Ok(gui) = tab_bar_gui.try_lock() {
-                if let Err(e) = state::save_state(&gui) {
+                if let Err(e) = state::save_state(&gui, &settings.state) {
                     eprintln!("[MAIN] Failed to save state: {}", e);
                 }
             }
             break 'running;
         }
 
-        // Receive clipboard objects from background threads and store in PaneLayout
-        #[cfg(target_os = "linux")]
-        {
-            if let Ok(clipboard) = clipboard_rx.try_recv() {
-                if let Ok(mut gui) = tab_bar_gui.try_lock() {
-                    if let Some(pane_layout) = gui.get_active_pane_layout() {
-                        pane_layout.primary_clipboard = Some(clipboard);
-                        eprintln!("[PRIMARY] Clipboard object stored in PaneLayout");
-                    }
-                }
-            }
-        }
-
         // Check for dirty terminals that need rendering
         let has_dirty_content = {
             match tab_bar_gui.try_lock() {
@@ -254,6 +285,15 @@ fn main() -> Result<(), String> {
             needs_render = true;
         }
 
+        // Flag background tabs with new output or a bell
+        if settings.ui.tab_activity_indicators {
+            if let Ok(mut gui) = tab_bar_gui.try_lock() {
+                if gui.update_tab_activity() {
+                    needs_render = true;
+                }
+            }
+        }
+
         // Check for completed animations and clean them up
         {
             if let Ok(mut gui) = tab_bar_gui.try_lock() {
@@ -267,45 +307,98 @@ fn main() -> Result<(), String> {
                             needs_render = true;
                         }
                     }
+                    if pane_layout.toast.as_ref().is_some_and(|toast| toast.is_expired()) {
+                        pane_layout.toast = None;
+                        needs_render = true;
+                    }
+                    // The overlay stays up while a divider is held and fades out once it stops moving
+                    if settings.ui.show_resize_overlay && pane_layout.dragging_divider.is_some() {
+                        pane_layout.resize_overlay.get_or_insert_with(ui::animations::ResizeOverlay::new).touch();
+                    }
+                    if let Some(ref overlay) = pane_layout.resize_overlay {
+                        if overlay.is_expired() {
+                            pane_layout.resize_overlay = None;
+                        }
+                        needs_render = true;
+                    }
                 }
             }
         }
 
         // Update CPU usage periodically
         if last_cpu_update.elapsed() >= cpu_update_interval {
-            sys.refresh_cpu();
-            cpu_usage = sys.global_cpu_info().cpu_usage();
+            let active_pid = if settings.general.active_pane_cpu || settings.ui.show_memory {
+                let terminal = tab_bar_gui.try_lock().ok().and_then(|gui| gui.get_active_terminal());
+                terminal.and_then(|t| t.lock().ok().and_then(|t| t.get_pid()))
+            } else {
+                None
+            };
+            let usage = system::usage::sample_usage(&mut sys, active_pid, settings.general.active_pane_cpu, settings.ui.show_memory);
+            cpu_usage = usage.cpu;
+            if settings.ui.show_memory {
+                memory_text = Some(usage.memory.map(system::usage::format_memory).unwrap_or_else(|| "--".to_string()));
+            }
             last_cpu_update = Instant::now();
+
+            // Tab labels follow the foreground process of each tab, and output logs are flushed,
+            // on the same cadence
+            if let Ok(mut gui) = tab_bar_gui.try_lock() {
+                if gui.refresh_process_names() {
+                    needs_render = true;
+                }
+                for terminal in gui.get_all_terminals() {
+                    if let Ok(t) = terminal.try_lock() {
+                        t.flush_log();
+                    }
+                }
+            }
+        }
+
+        // Apply a debounced window resize once resize events have stopped
+        if let Some((w, h)) = resize_debouncer.take_ready(Instant::now()) {
+            eprintln!("[MAIN] Applying window resize {}x{}", w, h);
+            if !resize_terminals_to_panes(&tab_bar_gui, char_width, char_height, tab_bar_height, w, h) {
+                // Retry on the next iteration rather than losing the final size
+                resize_debouncer.request(w, h, Instant::now());
+            }
+            needs_render = true;
         }
 
-        // Calculate adaptive timeout based on cursor blink and dirty state
+        // Calculate adaptive timeout based on cursor blink and dirty state. New PTY output and
+        // signals wake the loop with a `Wakeup` event, so when idle it only has to wake for timers.
         let timeout_ms = if needs_render || has_dirty_content {
             // If we need to render or have dirty content, wake up soon for responsive updates
             16 // ~60 FPS for active rendering
         } else {
-            // Calculate time until next cursor blink
-            let time_until_blink = cursor_blink_interval.saturating_sub(last_cursor_blink.elapsed());
             let in_debounce_period = last_keyboard_input.elapsed() < cursor_debounce_duration;
-
-            if in_debounce_period {
-                // During debounce, check more frequently to reset blink
-                100
+            let until_blink = if in_debounce_period {
+                // The cursor stays visible until the debounce ends, then starts blinking again
+                cursor_debounce_duration.saturating_sub(last_keyboard_input.elapsed())
             } else {
-                // Use time until blink, capped at 500ms for general responsiveness
-                time_until_blink.as_millis().min(500) as u32
-            }
+                cursor_blink_interval.saturating_sub(last_cursor_blink.elapsed())
+            };
+            let until_cpu_update = cpu_update_interval.saturating_sub(last_cpu_update.elapsed());
+            until_blink.min(until_cpu_update).as_millis() as u32 + 1
+        };
+        // The test server accepts connections without blocking, so it has to be polled
+        #[cfg(feature = "test-server")]
+        let timeout_ms = if test_server.is_some() { timeout_ms.min(100) } else { timeout_ms };
+        // Wake up in time to apply a pending resize
+        let timeout_ms = match resize_debouncer.time_until_ready(Instant::now()) {
+            Some(remaining) => timeout_ms.min(remaining.as_millis() as u32 + 1),
+            None => timeout_ms,
         };
 
         // Collect all events with adaptive timeout
         let mut events = Vec::new();
         let first_event = event_pump.wait_event_timeout(timeout_ms);
-        if let Some(event) = first_event {
-            events.push(event);
-        }
-
-        for event in event_pump.poll_iter() {
-            events.push(event);
+        for event in first_event.into_iter().chain(event_pump.poll_iter()) {
+            // Wakeups only interrupt the wait, the dirty checks below decide whether to render
+            if !event.is_user_event() {
+                events.push(event);
+            }
         }
+        system::wakeup::clear();
 
         // Update cursor blink state
         // If we're within the debounce period after keyboard input, keep cursor visible
@@ -365,9 +458,26 @@ fn main() -> Result<(), String> {
             for event in &events {
                 // Reset cursor debounce timer on keyboard input
                 match event {
-                    Event::KeyDown { .. } | Event::TextInput { .. } => {
+                    Event::KeyDown { .. } | Event::TextInput { .. } | Event::TextEditing { .. } => {
                         last_keyboard_input = Instant::now();
                     }
+                    Event::Window {
+                        win_event: sdl3::event::WindowEvent::FocusGained,
+                        ..
+                    } => focus_tracker.set_window_focused(true),
+                    Event::Window {
+                        win_event: sdl3::event::WindowEvent::FocusLost,
+                        ..
+                    } => focus_tracker.set_window_focused(false),
+                    Event::Window {
+                        win_event:
+                            sdl3::event::WindowEvent::Moved(..)
+                            | sdl3::event::WindowEvent::Resized(..)
+                            | sdl3::event::WindowEvent::Maximized
+                            | sdl3::event::WindowEvent::Minimized
+                            | sdl3::event::WindowEvent::Restored,
+                        ..
+                    } => window_geometry.track(canvas.window()),
                     _ => {}
                 }
 
@@ -385,18 +495,18 @@ fn main() -> Result<(), String> {
                     canvas.window(),
                     &event_pump,
                     &settings,
-                    #[cfg(target_os = "linux")]
-                    &clipboard_tx,
+                    &mut clipboard,
                 );
 
                 // Handle actions requested by event handler
                 match result.action {
                     input::events::EventAction::RequestQuitConfirmation => {
-                        // Show confirmation dialog
-                        if ui::dialogs::confirm_quit(&mut canvas, &mut event_pump, &tab_font, scale_factor) {
+                        // Show confirmation dialog unless settings say otherwise
+                        let terminals = tab_bar_gui.lock().unwrap().get_all_terminals();
+                        if ui::dialogs::should_quit(&mut canvas, &mut event_pump, &tab_font, scale_factor, &settings.general, &terminals) {
                             // User confirmed quit
                             if let Ok(gui) = tab_bar_gui.try_lock() {
-                                if let Err(e) = state::save_state(&gui) {
+                                if let Err(e) = state::save_state(&gui, &settings.state) {
                                     eprintln!("[MAIN] Failed to save state: {}", e);
                                 }
                             }
@@ -407,7 +517,7 @@ fn main() -> Result<(), String> {
                     }
                     input::events::EventAction::Quit => {
                         if let Ok(gui) = tab_bar_gui.try_lock() {
-                            if let Err(e) = state::save_state(&gui) {
+                            if let Err(e) = state::save_state(&gui, &settings.state) {
                                 eprintln!("[MAIN] Failed to save state: {}", e);
                             }
                         }
@@ -425,23 +535,35 @@ fn main() -> Result<(), String> {
 
                             if is_last_tab_with_one_pane {
                                 // Ask for confirmation before closing
+                                let terminals = gui.get_all_terminals();
                                 drop(gui);
-                                if !ui::dialogs::confirm_quit(&mut canvas, &mut event_pump, &tab_font, scale_factor) {
+                                if !ui::dialogs::should_quit(&mut canvas, &mut event_pump, &tab_font, scale_factor, &settings.general, &terminals) {
                                     // User cancelled, don't close
                                     needs_render = true;
                                     continue;
                                 }
                                 // User confirmed, quit
                                 if let Ok(gui) = tab_bar_gui.try_lock() {
-                                    if let Err(e) = state::save_state(&gui) {
+                                    if let Err(e) = state::save_state(&gui, &settings.state) {
                                         eprintln!("[MAIN] Failed to save state: {}", e);
                                     }
                                 }
                                 break 'running;
                             }
 
+                            if settings.general.confirm_close_with_jobs {
+                                let terminals = gui.tab_states.get(close_idx).map(|tab| tab.pane_layout.get_all_terminals()).unwrap_or_default();
+                                drop(gui);
+                                if !ui::dialogs::should_close(&mut canvas, &mut event_pump, &tab_font, scale_factor, &settings.general, &terminals) {
+                                    needs_render = true;
+                                    continue;
+                                }
+                                // The user confirmed, so wait for the lock instead of dropping the close
+                                gui = tab_bar_gui.lock().unwrap();
+                            }
+
                             if gui.remove_tab(close_idx) {
-                                if let Err(e) = state::save_state(&gui) {
+                                if let Err(e) = state::save_state(&gui, &settings.state) {
                                     eprintln!("[MAIN] Failed to save state: {}", e);
                                 }
                                 break 'running; // Last tab closed
@@ -452,12 +574,49 @@ fn main() -> Result<(), String> {
                             }
                         }
                     }
+                    input::events::EventAction::CloseTabs(indices, keep) => {
+                        if let Ok(mut gui) = tab_bar_gui.try_lock() {
+                            // The clicked tab is always kept, so this never empties the tab bar
+                            debug_assert!(!indices.contains(&keep) && indices.len() < gui.tab_states.len());
+
+                            if settings.general.confirm_close_with_jobs {
+                                let terminals: Vec<_> = indices
+                                    .iter()
+                                    .filter_map(|&idx| gui.tab_states.get(idx))
+                                    .flat_map(|tab| tab.pane_layout.get_all_terminals())
+                                    .collect();
+                                drop(gui);
+                                if !ui::dialogs::should_close(&mut canvas, &mut event_pump, &tab_font, scale_factor, &settings.general, &terminals) {
+                                    needs_render = true;
+                                    continue;
+                                }
+                                // The user confirmed, so wait for the lock instead of dropping the close
+                                gui = tab_bar_gui.lock().unwrap();
+                            }
+
+                            gui.close_tabs(&indices, keep);
+                            #[cfg(feature = "test-server")]
+                            if let Some(ref server) = test_server {
+                                server.update_tabs(gui.get_all_terminals());
+                            }
+                        }
+                        // The active tab may have changed, make sure its terminals match their panes
+                        let (window_width, window_height) = canvas.window().size_in_pixels();
+                        resize_terminals_to_panes(&tab_bar_gui, char_width, char_height, tab_bar_height, window_width, window_height);
+                    }
                     input::events::EventAction::NewTab => {
                         pending_new_tab = true;
                     }
                     input::events::EventAction::SplitPane(direction) => {
                         pending_pane_split = Some(direction);
                     }
+                    input::events::EventAction::DuplicatePane => {
+                        // Splits start in the active pane's directory already
+                        pending_pane_split = Some(crate::pane_layout::SplitDirection::Horizontal);
+                    }
+                    input::events::EventAction::DuplicateTab => {
+                        pending_duplicate_tab = true;
+                    }
 
                     input::events::EventAction::SwitchTab(tab_idx) => {
                         if let Ok(mut gui) = tab_bar_gui.try_lock() {
@@ -472,13 +631,24 @@ fn main() -> Result<(), String> {
                     input::events::EventAction::MinimizeWindow => {
                         canvas.window_mut().minimize();
                     }
+                    input::events::EventAction::ToggleWindow => {
+                        toggle_window(canvas.window_mut());
+                    }
                     input::events::EventAction::Resize => {
                         let (new_width, new_height) = canvas.window().size_in_pixels();
                         eprintln!("[MAIN] Window resized to {}x{}", new_width, new_height);
-                        // Resize all terminals to match their pane dimensions
-                        resize_terminals_to_panes(&tab_bar_gui, char_width, char_height, tab_bar_height, new_width, new_height);
+                        // Terminals are resized once the drag settles; the layout follows the window immediately
+                        resize_debouncer.request(new_width, new_height, Instant::now());
+                        if settings.ui.show_resize_overlay {
+                            if let Some(pane_layout) = tab_bar_gui.lock().unwrap().get_active_pane_layout() {
+                                pane_layout.resize_overlay.get_or_insert_with(ui::animations::ResizeOverlay::new).touch();
+                            }
+                        }
+                        needs_render = true;
                     }
                     input::events::EventAction::StartTextInput => {
+                        // Text now goes to the tab being renamed, not the terminal's composition
+                        tab_bar_gui.lock().unwrap().composition = None;
                         canvas.window().subsystem().text_input().start(canvas.window());
                     }
                     input::events::EventAction::StopTextInput => {
@@ -488,6 +658,8 @@ fn main() -> Result<(), String> {
                         // Special keys (Enter, Backspace, arrows) work via KeyDown events, but regular text
                         // requires text input to be enabled.
                         // Solution: Stop text input briefly, then immediately restart it for the terminal.
+                        // Stopping cancels any composition in progress, so drop what is displayed
+                        tab_bar_gui.lock().unwrap().composition = None;
                         canvas.window().subsystem().text_input().stop(canvas.window());
                         canvas.window().subsystem().text_input().start(canvas.window());
                     }
@@ -616,9 +788,20 @@ fn main() -> Result<(), String> {
                                     char_height = h as f32;
                                     eprintln!("[MAIN] New character dimensions: {:.2}x{:.2} pixels", char_width, char_height);
 
-                                    // Clear glyph cache - old glyphs are wrong size
-                                    glyph_cache.clear();
-                                    eprintln!("[MAIN] Glyph cache cleared");
+                                    // Clear glyph atlas - old glyphs are wrong size
+                                    glyph_atlas.clear();
+                                    eprintln!("[MAIN] Glyph atlas cleared");
+
+                                    // Pane title headers are one text row tall
+                                    if settings.ui.show_pane_titles {
+                                        tab_bar_gui.lock().unwrap().set_pane_header_height(char_height.ceil() as u32);
+                                    }
+
+                                    // The same panes now fit a different number of cells; resize the PTYs
+                                    // so running apps get SIGWINCH right away
+                                    let (window_width, window_height) = canvas.window().size_in_pixels();
+                                    resize_terminals_to_panes(&tab_bar_gui, char_width, char_height, tab_bar_height, window_width, window_height);
+                                    needs_render = true;
                                 } else {
                                     eprintln!("[MAIN] Failed to measure character dimensions after font reload");
                                 }
@@ -637,7 +820,7 @@ fn main() -> Result<(), String> {
                                 // Get terminal history for active terminal
                                 let terminal_history = if let Some(terminal) = pane_layout.get_active_terminal() {
                                     if let Ok(t) = terminal.lock() {
-                                        let hist = t.get_command_history();
+                                        let hist = t.get_command_history(RECENT_COMMAND_LINES);
                                         eprintln!("[MAIN] Terminal history (from shell): {:?}", hist);
                                         hist
                                     } else {
@@ -677,7 +860,7 @@ fn main() -> Result<(), String> {
                                 // Get terminal history for active terminal
                                 let terminal_history = if let Some(terminal) = pane_layout.get_active_terminal() {
                                     if let Ok(t) = terminal.lock() {
-                                        let hist = t.get_command_history();
+                                        let hist = t.get_command_history(RECENT_COMMAND_LINES);
                                         eprintln!("[MAIN] Terminal history for AI (from shell): {:?}", hist);
                                         hist
                                     } else {
@@ -704,6 +887,34 @@ fn main() -> Result<(), String> {
                             }
                         }
                     }
+                    input::events::EventAction::RequestPasteConfirmation(text) => {
+                        if ui::dialogs::confirm_paste(&mut canvas, &mut event_pump, &tab_font, scale_factor, &text) {
+                            input::keyboard::paste_to_group(&text, &tab_bar_gui);
+                        } else {
+                            eprintln!("[MAIN] Paste cancelled");
+                        }
+                        needs_render = true;
+                    }
+
+                    input::events::EventAction::RequestClosePaneConfirmation => {
+                        let terminals: Vec<_> = tab_bar_gui.lock().unwrap().get_active_terminal().into_iter().collect();
+                        if ui::dialogs::should_close(&mut canvas, &mut event_pump, &tab_font, scale_factor, &settings.general, &terminals) {
+                            let closed = input::keyboard::close_active_pane(&tab_bar_gui);
+                            if matches!(closed.action, input::keyboard::KeyboardAction::Quit) {
+                                if let Ok(gui) = tab_bar_gui.try_lock() {
+                                    if let Err(e) = state::save_state(&gui, &settings.state) {
+                                        eprintln!("[MAIN] Failed to save state: {}", e);
+                                    }
+                                }
+                                break 'running;
+                            }
+                            if closed.needs_resize {
+                                let (w, h) = canvas.window().size_in_pixels();
+                                resize_terminals_to_panes(&tab_bar_gui, char_width, char_height, tab_bar_height, w, h);
+                            }
+                        }
+                        needs_render = true;
+                    }
 
                     input::events::EventAction::None => {}
                 }
@@ -764,7 +975,8 @@ fn main() -> Result<(), String> {
                     eprintln!("[MAIN] All tabs closing (processes exited)");
                     drop(gui);
 
-                    if !ui::dialogs::confirm_quit(&mut canvas, &mut event_pump, &tab_font, scale_factor) {
+                    // Every shell has exited, so no processes are left running
+                    if !ui::dialogs::should_quit(&mut canvas, &mut event_pump, &tab_font, scale_factor, &settings.general, &[]) {
                         // User cancelled quit - spawn a new terminal to replace the dead one
                         eprintln!("[MAIN] User cancelled quit, spawning new terminal");
 
@@ -778,8 +990,9 @@ fn main() -> Result<(), String> {
                             term_height,
                             shell_config.clone(),
                             DEFAULT_SCROLLBACK_LINES,
-                            std::env::current_dir().ok(),
+                            settings.general.startup_dir(),
                             cursor_style,
+                            crate::terminal::main::TerminalOptions::from_settings(&settings.terminal),
                         )));
 
                         let mut gui = tab_bar_gui.lock().unwrap();
@@ -795,7 +1008,7 @@ fn main() -> Result<(), String> {
                         }
                     } else {
                         // User confirmed quit
-                        if let Err(e) = state::save_state(&tab_bar_gui.lock().unwrap()) {
+                        if let Err(e) = state::save_state(&tab_bar_gui.lock().unwrap(), &settings.state) {
                             eprintln!("[MAIN] Failed to save state: {}", e);
                         }
                         break 'running;
@@ -833,12 +1046,28 @@ fn main() -> Result<(), String> {
                                 pane_layout.set_active_pane(pane_id);
                                 pending_pane_split = Some(crate::pane_layout::SplitDirection::Horizontal);
                             }
+                            "duplicate_pane" => {
+                                pane_layout.set_active_pane(pane_id);
+                                pending_pane_split = Some(crate::pane_layout::SplitDirection::Horizontal);
+                            }
+                            "duplicate_tab" => {
+                                pane_layout.set_active_pane(pane_id);
+                                pending_duplicate_tab = true;
+                            }
                             "to_tab" => {
                                 if let Some(terminal) = pane_layout.extract_pane(pane_id) {
                                     let new_tab_index = gui.tab_states.len() + 1;
                                     gui.add_tab(terminal, format!("Tab {}", new_tab_index));
                                 }
                             }
+                            "restart_shell" => {
+                                if let Some(terminal_arc) = pane_layout.root.find_terminal(pane_id) {
+                                    if let Ok(mut terminal) = terminal_arc.lock() {
+                                        terminal.restart();
+                                    }
+                                }
+                                needs_render = true;
+                            }
                             "kill_shell" => {
                                 if let Some(terminal_arc) = pane_layout.root.find_terminal(pane_id) {
                                     if let Ok(mut terminal) = terminal_arc.lock() {
@@ -846,7 +1075,15 @@ fn main() -> Result<(), String> {
                                     }
                                 }
                             }
-                            _ => {}
+                            other => {
+                                if let Some(sig) = other.strip_prefix("signal:").and_then(|sig| sig.parse::<i32>().ok()) {
+                                    if let Some(terminal_arc) = pane_layout.root.find_terminal(pane_id) {
+                                        if let Ok(mut terminal) = terminal_arc.lock() {
+                                            terminal.send_signal(sig);
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -873,6 +1110,7 @@ fn main() -> Result<(), String> {
                     DEFAULT_SCROLLBACK_LINES,
                     start_dir,
                     cursor_style,
+                    crate::terminal::main::TerminalOptions::from_settings(&settings.terminal),
                 )));
 
                 let mut gui = tab_bar_gui.lock().unwrap();
@@ -885,52 +1123,63 @@ fn main() -> Result<(), String> {
                 }
             }
 
+            if pending_duplicate_tab {
+                pending_duplicate_tab = false;
+                let source = tab_bar_gui.lock().unwrap().get_active_terminal();
+                if let Some(source) = source {
+                    let duplicate = source.lock().unwrap().duplicate();
+
+                    let mut gui = tab_bar_gui.lock().unwrap();
+                    let new_tab_index = gui.tab_states.len() + 1;
+                    gui.add_tab(Arc::new(Mutex::new(duplicate)), format!("Tab {}", new_tab_index));
+
+                    #[cfg(feature = "test-server")]
+                    if let Some(ref server) = test_server {
+                        server.update_tabs(gui.get_all_terminals());
+                    }
+                    drop(gui);
+
+                    // The duplicate has the source pane's size, fit it to the whole tab
+                    let (w, h) = canvas.window().size_in_pixels();
+                    resize_terminals_to_panes(&tab_bar_gui, char_width, char_height, tab_bar_height, w, h);
+                }
+            }
+
             if let Some(direction) = pending_pane_split.take() {
                 let (w, h) = canvas.window().size_in_pixels();
 
                 // Check if the current active pane is large enough to split
-                let mut can_split = false;
-                {
-                    let gui = tab_bar_gui.lock().unwrap();
-                    if let Some(pane_layout_state) = gui.tab_states.get(gui.active_tab) {
-                        let pane_area_y = tab_bar_height as i32;
-                        let pane_area_height = h - tab_bar_height;
-                        let pane_rects = pane_layout_state.pane_layout.get_pane_rects(0, pane_area_y, w, pane_area_height);
-
-                        // Find the active pane's dimensions
-                        if let Some((_, rect, _, _, _)) = pane_rects.iter().find(|(id, _, _, _, _)| *id == pane_layout_state.pane_layout.active_pane) {
-                            let (current_cols, current_rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), char_width, char_height);
-
-                            // Calculate dimensions after split (accounting for 2-pixel divider)
-                            let divider_chars_h = (2.0 / char_width).ceil() as u32;
-                            let divider_chars_v = (2.0 / char_height).ceil() as u32;
-
-                            match direction {
-                                crate::pane_layout::SplitDirection::Horizontal => {
-                                    // Each pane will be roughly half width
-                                    let split_width = (current_cols.saturating_sub(divider_chars_h)) / 2;
-                                    if split_width >= 10 && current_rows >= 5 {
-                                        can_split = true;
-                                    } else {
-                                        eprintln!("[SPLIT] Cannot split horizontally: resulting width {} would be less than 10 chars", split_width);
-                                    }
-                                }
-                                crate::pane_layout::SplitDirection::Vertical => {
-                                    // Each pane will be roughly half height
-                                    let split_height = (current_rows.saturating_sub(divider_chars_v)) / 2;
-                                    if split_height >= 5 && current_cols >= 10 {
-                                        can_split = true;
-                                    } else {
-                                        eprintln!("[SPLIT] Cannot split vertically: resulting height {} would be less than 5 chars", split_height);
-                                    }
+                let can_split = {
+                    let mut gui = tab_bar_gui.lock().unwrap();
+                    let pane_area_y = tab_bar_height as i32;
+                    let pane_area_height = h - tab_bar_height;
+                    match gui.get_active_pane_layout() {
+                        Some(pane_layout) => {
+                            let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, w, pane_area_height);
+                            let active_rect = pane_rects
+                                .iter()
+                                .find(|(id, _, _, _, _)| *id == pane_layout.active_pane)
+                                .map(|(_, rect, _, _, _)| *rect);
+                            // A zoomed pane is split at its own cell size
+                            let (pane_char_width, pane_char_height) = pane_layout.pane_char_size(pane_layout.active_pane, char_width, char_height);
+                            match active_rect.map(|rect| {
+                                crate::pane_layout::PaneLayout::can_split(direction, pane_char_width, pane_char_height, rect, pane_layout.divider_width)
+                            }) {
+                                Some(Ok(())) => true,
+                                Some(Err(reason)) => {
+                                    eprintln!("[SPLIT] {}", reason);
+                                    pane_layout.toast = Some(ui::animations::Toast::new(reason.to_string()));
+                                    needs_render = true;
+                                    false
                                 }
+                                None => false,
                             }
                         }
+                        None => false,
                     }
-                }
+                };
 
                 if !can_split {
-                    eprintln!("[SPLIT] Pane too small to split (minimum: 10 chars wide, 5 chars tall)");
                     // Skip the split operation
                 } else {
                     let term_height = ((h - tab_bar_height) as f32 / char_height).floor() as u32;
@@ -950,6 +1199,7 @@ fn main() -> Result<(), String> {
                         DEFAULT_SCROLLBACK_LINES,
                         start_dir,
                         cursor_style,
+                        crate::terminal::main::TerminalOptions::from_settings(&settings.terminal),
                     )));
 
                     let mut gui = tab_bar_gui.lock().unwrap();
@@ -973,6 +1223,10 @@ fn main() -> Result<(), String> {
                 }
             }
 
+            // Focus events go to the active pane, which the events above may have changed
+            let active_terminal = tab_bar_gui.lock().unwrap().get_active_terminal();
+            input::focus::FocusTracker::report(focus_tracker.update(active_terminal));
+
             // Render everything using optimized render module
             // This only renders the active tab and visible content
             let any_dirty = render::render_frame(
@@ -983,29 +1237,32 @@ fn main() -> Result<(), String> {
                 &tab_font,
                 &button_font,
                 &cpu_font,
+                &ui_emoji_font,
                 &font,
                 &emoji_font,
                 &unicode_fallback_font,
                 &cjk_font,
                 &context_menu_font,
+                &context_menu_emoji_font,
                 cpu_usage,
+                memory_text.as_deref(),
                 tab_bar_height,
                 scale_factor,
                 char_width,
                 char_height,
                 cursor_visible,
-                &mut glyph_cache,
+                theme_cursor_color,
+                theme_cursor_text_color,
+                mouse_state.hovered_divider.map(|(split_id, _direction)| split_id),
+                &mut glyph_atlas,
+                missing_glyph,
+                &pane_colors,
+                &mut frame_stats,
             )?;
 
             if any_dirty {
                 needs_render = true;
             }
-
-            // Periodically clear glyph cache to prevent unlimited memory growth
-            if last_cache_clear.elapsed().as_secs() > 60 {
-                glyph_cache.clear();
-                last_cache_clear = Instant::now();
-            }
         } else {
             skip_render_count += 1;
             // Print skip message every 100 iterations or on first skip
@@ -1018,7 +1275,7 @@ fn main() -> Result<(), String> {
                 Ok(true) => {
                     eprintln!("[MAIN] Shutdown requested by test server");
                     if let Ok(gui) = tab_bar_gui.try_lock() {
-                        if let Err(e) = state::save_state(&gui) {
+                        if let Err(e) = state::save_state(&gui, &settings.state) {
                             eprintln!("[MAIN] Failed to save state: {}", e);
                         }
                     }
@@ -1034,6 +1291,11 @@ fn main() -> Result<(), String> {
 
     // Note: State is already saved by all exit paths before breaking the 'running loop
     // (signal handling, quit actions, last tab closed, test server shutdown, etc.)
+    // The window geometry is the same for all of them, so it is saved once here
+    window_geometry.track(canvas.window());
+    if let Err(e) = state::save_window_geometry(&window_geometry) {
+        eprintln!("[MAIN] Failed to save window geometry: {}", e);
+    }
 
     Ok(())
 }
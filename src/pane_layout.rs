@@ -1,18 +1,64 @@
 use crate::terminal::Terminal;
-use crate::ui::animations::CopyAnimation;
+use crate::ui::animations::{CopyAnimation, ResizeOverlay, Toast};
 use sdl3::rect::Rect;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
-#[cfg(target_os = "linux")]
-use arboard::Clipboard;
-
 /// Unique identifier for a pane
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PaneId(pub usize);
 
 pub static NEXT_PANE_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
 
+/// Thickness of the dividers between panes in pixels until `theme.dividerWidth` is applied
+pub const DEFAULT_DIVIDER_WIDTH: u32 = 2;
+
+/// Pixels on either side of a divider that still grab it
+const DIVIDER_HIT_MARGIN: u32 = 3;
+/// Thinnest grab area across a divider, so a 1-pixel divider is still easy to hit
+const DIVIDER_MIN_HIT_SIZE: u32 = 8;
+
+/// The two sides of a split of `rect`, with a divider `divider_size` pixels thick between them.
+/// `ratio` is the first side's share of the space left after the divider.
+fn split_rect(rect: Rect, direction: SplitDirection, ratio: f32, divider_size: u32) -> (Rect, Rect) {
+    match direction {
+        SplitDirection::Horizontal => {
+            let first_width = (rect.width().saturating_sub(divider_size) as f32 * ratio) as u32;
+            let second_width = rect.width().saturating_sub(first_width + divider_size);
+            (
+                Rect::new(rect.x(), rect.y(), first_width, rect.height()),
+                Rect::new(rect.x() + (first_width + divider_size) as i32, rect.y(), second_width, rect.height()),
+            )
+        }
+        SplitDirection::Vertical => {
+            let first_height = (rect.height().saturating_sub(divider_size) as f32 * ratio) as u32;
+            let second_height = rect.height().saturating_sub(first_height + divider_size);
+            (
+                Rect::new(rect.x(), rect.y(), rect.width(), first_height),
+                Rect::new(rect.x(), rect.y() + (first_height + divider_size) as i32, rect.width(), second_height),
+            )
+        }
+    }
+}
+
+/// The area around a divider that grabs it, widened across the divider only
+fn divider_hit_rect(rect: Rect, direction: SplitDirection) -> Rect {
+    let widen = |thickness: u32| {
+        let size = (thickness + 2 * DIVIDER_HIT_MARGIN).max(DIVIDER_MIN_HIT_SIZE);
+        (size, ((size - thickness) / 2) as i32)
+    };
+    match direction {
+        SplitDirection::Horizontal => {
+            let (width, offset) = widen(rect.width());
+            Rect::new(rect.x() - offset, rect.y(), width, rect.height())
+        }
+        SplitDirection::Vertical => {
+            let (height, offset) = widen(rect.height());
+            Rect::new(rect.x(), rect.y() - offset, rect.width(), height)
+        }
+    }
+}
+
 impl PaneId {
     fn new() -> Self {
         PaneId(NEXT_PANE_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
@@ -189,6 +235,46 @@ impl PaneNode {
         }
     }
 
+    /// Reset every split ratio to an even division. Returns true if any ratio changed.
+    pub fn balance_ratios(&mut self) -> bool {
+        match self {
+            PaneNode::Leaf { .. } => false,
+            PaneNode::Split { ratio, first, second, .. } => {
+                let changed = *ratio != 0.5;
+                *ratio = 0.5;
+                // Both sides must be visited, so don't short-circuit
+                let first_changed = first.balance_ratios();
+                let second_changed = second.balance_ratios();
+                changed || first_changed || second_changed
+            }
+        }
+    }
+
+    /// Find a leaf node by pane ID
+    fn find_leaf(&self, pane_id: PaneId) -> Option<&PaneNode> {
+        match self {
+            PaneNode::Leaf { id, .. } => (*id == pane_id).then_some(self),
+            PaneNode::Split { first, second, .. } => first.find_leaf(pane_id).or_else(|| second.find_leaf(pane_id)),
+        }
+    }
+
+    /// Put leaf `b` where leaf `a` is and vice versa, leaving the splits untouched
+    fn swap_leaves(&mut self, a: &PaneNode, b: &PaneNode) {
+        match self {
+            PaneNode::Leaf { id, .. } => {
+                if *id == a.id() {
+                    *self = b.clone();
+                } else if *id == b.id() {
+                    *self = a.clone();
+                }
+            }
+            PaneNode::Split { first, second, .. } => {
+                first.swap_leaves(a, b);
+                second.swap_leaves(a, b);
+            }
+        }
+    }
+
     /// Update split ratio for a specific split node
     pub fn update_ratio(&mut self, pane_id: PaneId, new_ratio: f32) -> bool {
         match self {
@@ -205,6 +291,31 @@ impl PaneNode {
     }
 }
 
+/// Smallest pane size, in characters, that a split or a divider drag may produce
+pub const MIN_SPLIT_COLS: u32 = 10;
+pub const MIN_SPLIT_ROWS: u32 = 5;
+
+/// Range and step of a pane's font size multiplier
+pub const MIN_FONT_SCALE: f32 = 0.5;
+pub const MAX_FONT_SCALE: f32 = 3.0;
+pub const FONT_SCALE_STEP: f32 = 0.1;
+
+/// Why a pane can't be split
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitError {
+    TooNarrow,
+    TooShort,
+}
+
+impl std::fmt::Display for SplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitError::TooNarrow => write!(f, "Pane too narrow to split (minimum: {} chars wide)", MIN_SPLIT_COLS),
+            SplitError::TooShort => write!(f, "Pane too short to split (minimum: {} chars tall)", MIN_SPLIT_ROWS),
+        }
+    }
+}
+
 pub enum CloseResult {
     NotFound,
     RemoveThis,
@@ -239,9 +350,6 @@ pub struct PaneLayout {
     pub dragging_divider: Option<PaneId>,
     /// Track preview ratio during dragging (split_id, preview_ratio)
     pub drag_preview: Option<(PaneId, f32)>,
-    /// Keep clipboard context alive to maintain PRIMARY selection on Linux
-    #[cfg(target_os = "linux")]
-    pub primary_clipboard: Option<Clipboard>,
     /// Context menu images
     pub context_menu_images: Option<ContextMenuImages>,
     /// Context menu state: (pane_id, x, y)
@@ -252,8 +360,18 @@ pub struct PaneLayout {
     pub pending_context_action: Option<(PaneId, String)>,
     /// Copy animation (expanding and fading rectangle after Ctrl+Shift+C)
     pub copy_animation: Option<CopyAnimation>,
+    /// Short message shown over the active pane
+    pub toast: Option<Toast>,
+    /// Grid size of the active pane, shown while the window or a divider is being resized
+    pub resize_overlay: Option<ResizeOverlay>,
     /// Panes selected for group input (Ctrl+click to toggle)
     pub selected_panes: HashSet<PaneId>,
+    /// Font size multipliers of panes zoomed away from the global font size
+    pub font_scales: HashMap<PaneId, f32>,
+    /// Height of the title header drawn above each pane when the tab is split (0 = no headers)
+    pub header_height: u32,
+    /// Thickness of the dividers between panes in pixels (`theme.dividerWidth`)
+    pub divider_width: u32,
 }
 
 impl PaneLayout {
@@ -266,14 +384,17 @@ impl PaneLayout {
             active_pane,
             dragging_divider: None,
             drag_preview: None,
-            #[cfg(target_os = "linux")]
-            primary_clipboard: None,
             context_menu_images: None,
             context_menu_open: None,
             context_menu: None,
             pending_context_action: None,
             copy_animation: None,
+            toast: None,
+            resize_overlay: None,
             selected_panes: HashSet::new(),
+            font_scales: HashMap::new(),
+            header_height: 0,
+            divider_width: DEFAULT_DIVIDER_WIDTH,
         }
     }
 
@@ -295,6 +416,69 @@ impl PaneLayout {
         self.root.find_terminal(self.active_pane)
     }
 
+    /// Font size multiplier of a pane (1.0 unless it was zoomed)
+    pub fn font_scale(&self, pane_id: PaneId) -> f32 {
+        self.font_scales.get(&pane_id).copied().unwrap_or(1.0)
+    }
+
+    /// Cell size of a pane, given the cell size at the global font size
+    pub fn pane_char_size(&self, pane_id: PaneId, char_width: f32, char_height: f32) -> (f32, f32) {
+        let scale = self.font_scale(pane_id);
+        (char_width * scale, char_height * scale)
+    }
+
+    /// Change the active pane's font size multiplier by `step`, within MIN_FONT_SCALE..=MAX_FONT_SCALE.
+    /// Returns true if it changed (caller should resize).
+    pub fn zoom_active_pane(&mut self, step: f32) -> bool {
+        let old_scale = self.font_scale(self.active_pane);
+        // Round to the step so repeated zooming doesn't accumulate float error
+        let new_scale = ((old_scale + step) / FONT_SCALE_STEP).round() * FONT_SCALE_STEP;
+        let new_scale = new_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+        if (new_scale - old_scale).abs() < f32::EPSILON {
+            return false;
+        }
+
+        if (new_scale - 1.0).abs() < f32::EPSILON {
+            self.font_scales.remove(&self.active_pane);
+        } else {
+            self.font_scales.insert(self.active_pane, new_scale);
+        }
+        true
+    }
+
+    /// Check whether a pane occupying `rect` is large enough to be split in the given direction.
+    /// Both halves must keep at least MIN_SPLIT_COLS x MIN_SPLIT_ROWS after a divider `divider_width` pixels thick.
+    pub fn can_split(direction: SplitDirection, char_width: f32, char_height: f32, rect: Rect, divider_width: u32) -> Result<(), SplitError> {
+        let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), char_width, char_height);
+
+        match direction {
+            SplitDirection::Horizontal => {
+                // Each pane will be roughly half width
+                let divider_chars = (divider_width as f32 / char_width).ceil() as u32;
+                let split_width = cols.saturating_sub(divider_chars) / 2;
+                if split_width < MIN_SPLIT_COLS {
+                    Err(SplitError::TooNarrow)
+                } else if rows < MIN_SPLIT_ROWS {
+                    Err(SplitError::TooShort)
+                } else {
+                    Ok(())
+                }
+            }
+            SplitDirection::Vertical => {
+                // Each pane will be roughly half height
+                let divider_chars = (divider_width as f32 / char_height).ceil() as u32;
+                let split_height = rows.saturating_sub(divider_chars) / 2;
+                if split_height < MIN_SPLIT_ROWS {
+                    Err(SplitError::TooShort)
+                } else if cols < MIN_SPLIT_COLS {
+                    Err(SplitError::TooNarrow)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
     /// Split the active pane in the given direction
     pub fn split_active_pane(&mut self, direction: SplitDirection, new_terminal: Arc<Mutex<Terminal>>) {
         let active_pane = self.active_pane;
@@ -307,6 +491,7 @@ impl PaneLayout {
     /// Close a pane by ID
     pub fn close_pane(&mut self, pane_id: PaneId) -> bool {
         let result = self.root.close_pane(pane_id);
+        self.font_scales.remove(&pane_id);
         match result {
             CloseResult::RemoveThis => {
                 // This was the only pane, signal to close tab
@@ -343,39 +528,48 @@ impl PaneLayout {
         self.root.collect_terminals_with_ids()
     }
 
-    /// Cycle to the next pane in the layout
+    /// Cycle to the next pane in the layout, wrapping around
     pub fn cycle_to_next_pane(&mut self) {
-        let pane_ids = self.root.collect_leaf_ids();
-        if pane_ids.len() <= 1 {
-            return; // Nothing to cycle
+        let pane_ids: Vec<PaneId> = self.get_terminals_with_pane_ids().into_iter().map(|(id, _)| id).collect();
+        if let Some(next) = cycle_pane_id(&pane_ids, self.active_pane, true) {
+            self.set_active_pane(next);
         }
+    }
 
-        if let Some(current_idx) = pane_ids.iter().position(|&id| id == self.active_pane) {
-            let next_idx = (current_idx + 1) % pane_ids.len();
-            self.active_pane = pane_ids[next_idx];
-        } else {
-            // Current pane not found, set to first pane
-            if let Some(&first_id) = pane_ids.first() {
-                self.active_pane = first_id;
-            }
+    /// Cycle to the previous pane in the layout, wrapping around
+    pub fn cycle_to_previous_pane(&mut self) {
+        let pane_ids: Vec<PaneId> = self.get_terminals_with_pane_ids().into_iter().map(|(id, _)| id).collect();
+        if let Some(previous) = cycle_pane_id(&pane_ids, self.active_pane, false) {
+            self.set_active_pane(previous);
         }
     }
 
-    /// Cycle to the previous pane in the layout
-    pub fn cycle_to_previous_pane(&mut self) {
-        let pane_ids = self.root.collect_leaf_ids();
-        if pane_ids.len() <= 1 {
-            return; // Nothing to cycle
+    /// Swap two panes in the tree. Pane IDs travel with their terminals, so the active pane and
+    /// group selection follow the terminal. Returns true if anything moved (caller should resize).
+    pub fn swap_panes(&mut self, a: PaneId, b: PaneId) -> bool {
+        if a == b {
+            return false;
         }
+        let (Some(leaf_a), Some(leaf_b)) = (self.root.find_leaf(a).cloned(), self.root.find_leaf(b).cloned()) else {
+            return false;
+        };
+        self.root.swap_leaves(&leaf_a, &leaf_b);
+        true
+    }
 
-        if let Some(current_idx) = pane_ids.iter().position(|&id| id == self.active_pane) {
-            let prev_idx = if current_idx == 0 { pane_ids.len() - 1 } else { current_idx - 1 };
-            self.active_pane = pane_ids[prev_idx];
-        } else {
-            // Current pane not found, set to first pane
-            if let Some(&first_id) = pane_ids.first() {
-                self.active_pane = first_id;
-            }
+    /// Give every split an even division of its space. The active pane is left alone.
+    /// Returns true if any ratio changed (caller should resize).
+    pub fn balance(&mut self) -> bool {
+        self.drag_preview = None;
+        self.root.balance_ratios()
+    }
+
+    /// Swap the active pane with the next one in layout order (wrapping around)
+    pub fn swap_active_with_next_pane(&mut self) -> bool {
+        let pane_ids = self.root.collect_leaf_ids();
+        match pane_ids.iter().position(|&id| id == self.active_pane) {
+            Some(idx) => self.swap_panes(self.active_pane, pane_ids[(idx + 1) % pane_ids.len()]),
+            None => false,
         }
     }
 
@@ -395,17 +589,31 @@ impl PaneLayout {
     /// Returns: Vec<(PaneId, Rect, Arc<Mutex<Terminal>>, is_active)>
     pub fn get_pane_rects(&self, x: i32, y: i32, width: u32, height: u32) -> Vec<(PaneId, Rect, Arc<Mutex<Terminal>>, bool, bool)> {
         let mut panes = Vec::new();
-        self.collect_pane_rects(&self.root, x, y, width, height, &mut panes);
+        self.collect_pane_rects(&self.root, Rect::new(x, y, width, height), &mut panes);
         panes
     }
 
-    fn collect_pane_rects(&self, node: &PaneNode, x: i32, y: i32, width: u32, height: u32, panes: &mut Vec<(PaneId, Rect, Arc<Mutex<Terminal>>, bool, bool)>) {
+    /// A split's ratio, or the preview ratio while its divider is being dragged
+    fn effective_ratio(&self, split_id: PaneId, ratio: f32) -> f32 {
+        match self.drag_preview {
+            Some((preview_id, preview_ratio)) if preview_id == split_id => preview_ratio,
+            _ => ratio,
+        }
+    }
+
+    fn collect_pane_rects(&self, node: &PaneNode, rect: Rect, panes: &mut Vec<(PaneId, Rect, Arc<Mutex<Terminal>>, bool, bool)>) {
         match node {
             PaneNode::Leaf { id, terminal } => {
                 let is_active = *id == self.active_pane;
                 let is_selected = self.selected_panes.contains(id);
-                let rect = Rect::new(x, y, width, height);
-                panes.push((*id, rect, terminal.clone(), is_active, is_selected));
+                // The header sits above the content, so the pane gets the rect below it.
+                // A single pane has nothing to tell apart and stays headerless.
+                let header = match self.root {
+                    PaneNode::Split { .. } => self.header_height.min(rect.height()),
+                    PaneNode::Leaf { .. } => 0,
+                };
+                let content_rect = Rect::new(rect.x(), rect.y() + header as i32, rect.width(), rect.height() - header);
+                panes.push((*id, content_rect, terminal.clone(), is_active, is_selected));
             }
             PaneNode::Split {
                 id,
@@ -414,36 +622,9 @@ impl PaneLayout {
                 first,
                 second,
             } => {
-                let split_id = *id;
-                let divider_size = 2;
-
-                // Use preview ratio if this divider is being dragged
-                let effective_ratio = if let Some((preview_id, preview_ratio)) = self.drag_preview {
-                    if preview_id == split_id {
-                        preview_ratio
-                    } else {
-                        *ratio
-                    }
-                } else {
-                    *ratio
-                };
-
-                match direction {
-                    SplitDirection::Horizontal => {
-                        let first_width = ((width as i32 - divider_size) as f32 * effective_ratio) as u32;
-                        let second_width = width - first_width - divider_size as u32;
-
-                        self.collect_pane_rects(first, x, y, first_width, height, panes);
-                        self.collect_pane_rects(second, x + first_width as i32 + divider_size, y, second_width, height, panes);
-                    }
-                    SplitDirection::Vertical => {
-                        let first_height = ((height as i32 - divider_size) as f32 * effective_ratio) as u32;
-                        let second_height = height - first_height - divider_size as u32;
-
-                        self.collect_pane_rects(first, x, y, width, first_height, panes);
-                        self.collect_pane_rects(second, x, y + first_height as i32 + divider_size, width, second_height, panes);
-                    }
-                }
+                let (first_rect, second_rect) = split_rect(rect, *direction, self.effective_ratio(*id, *ratio), self.divider_width);
+                self.collect_pane_rects(first, first_rect, panes);
+                self.collect_pane_rects(second, second_rect, panes);
             }
         }
     }
@@ -452,11 +633,11 @@ impl PaneLayout {
     /// Returns: Vec<(PaneId, Rect, SplitDirection)>
     pub fn get_divider_rects(&self, x: i32, y: i32, width: u32, height: u32) -> Vec<(PaneId, Rect, SplitDirection)> {
         let mut dividers = Vec::new();
-        self.collect_divider_rects(&self.root, x, y, width, height, &mut dividers);
+        self.collect_divider_rects(&self.root, Rect::new(x, y, width, height), &mut dividers);
         dividers
     }
 
-    fn collect_divider_rects(&self, node: &PaneNode, x: i32, y: i32, width: u32, height: u32, dividers: &mut Vec<(PaneId, Rect, SplitDirection)>) {
+    fn collect_divider_rects(&self, node: &PaneNode, rect: Rect, dividers: &mut Vec<(PaneId, Rect, SplitDirection)>) {
         match node {
             PaneNode::Leaf { .. } => {}
             PaneNode::Split {
@@ -466,44 +647,17 @@ impl PaneLayout {
                 first,
                 second,
             } => {
-                let split_id = *id;
-                let divider_size = 2;
-
-                // Use preview ratio if this divider is being dragged
-                let effective_ratio = if let Some((preview_id, preview_ratio)) = self.drag_preview {
-                    if preview_id == split_id {
-                        preview_ratio
-                    } else {
-                        *ratio
-                    }
-                } else {
-                    *ratio
-                };
-
-                match direction {
-                    SplitDirection::Horizontal => {
-                        let first_width = ((width as i32 - divider_size) as f32 * effective_ratio) as u32;
-                        let second_width = width - first_width - divider_size as u32;
-
-                        let divider_x = x + first_width as i32;
-                        let divider_rect = Rect::new(divider_x, y, divider_size as u32, height);
-                        dividers.push((split_id, divider_rect, *direction));
-
-                        self.collect_divider_rects(first, x, y, first_width, height, dividers);
-                        self.collect_divider_rects(second, x + first_width as i32 + divider_size, y, second_width, height, dividers);
-                    }
-                    SplitDirection::Vertical => {
-                        let first_height = ((height as i32 - divider_size) as f32 * effective_ratio) as u32;
-                        let second_height = height - first_height - divider_size as u32;
+                let (first_rect, second_rect) = split_rect(rect, *direction, self.effective_ratio(*id, *ratio), self.divider_width);
 
-                        let divider_y = y + first_height as i32;
-                        let divider_rect = Rect::new(x, divider_y, width, divider_size as u32);
-                        dividers.push((split_id, divider_rect, *direction));
+                // The divider fills the gap between the two sides
+                let divider_rect = match direction {
+                    SplitDirection::Horizontal => Rect::new(first_rect.right(), rect.y(), self.divider_width, rect.height()),
+                    SplitDirection::Vertical => Rect::new(rect.x(), first_rect.bottom(), rect.width(), self.divider_width),
+                };
+                dividers.push((*id, divider_rect, *direction));
 
-                        self.collect_divider_rects(first, x, y, width, first_height, dividers);
-                        self.collect_divider_rects(second, x, y + first_height as i32 + divider_size, width, second_height, dividers);
-                    }
-                }
+                self.collect_divider_rects(first, first_rect, dividers);
+                self.collect_divider_rects(second, second_rect, dividers);
             }
         }
     }
@@ -522,24 +676,58 @@ impl PaneLayout {
 
     /// Start dragging a divider (returns true if a divider was grabbed)
     pub fn start_drag_divider(&mut self, mouse_x: i32, mouse_y: i32, area_x: i32, area_y: i32, area_width: u32, area_height: u32) -> bool {
-        let dividers = self.get_divider_rects(area_x, area_y, area_width, area_height);
-        for (split_id, rect, _direction) in dividers {
-            // Expand hit area for easier dragging
-            let hit_rect = Rect::new(rect.x() - 3, rect.y() - 3, rect.width() + 6, rect.height() + 6);
-            if hit_rect.contains_point((mouse_x, mouse_y)) {
+        match self.divider_at(mouse_x, mouse_y, area_x, area_y, area_width, area_height) {
+            Some(split_id) => {
                 self.dragging_divider = Some(split_id);
                 // Get current ratio
                 if let Some(ratio) = self.get_split_ratio(split_id) {
                     self.drag_preview = Some((split_id, ratio));
                 }
-                return true;
+                true
             }
+            None => false,
         }
-        false
     }
 
-    /// Update divider drag
-    pub fn update_drag_divider(&mut self, delta_x: i32, delta_y: i32, area_x: i32, area_y: i32, area_width: u32, area_height: u32) {
+    /// Find the split node whose divider is under the mouse
+    pub fn divider_at(&self, mouse_x: i32, mouse_y: i32, area_x: i32, area_y: i32, area_width: u32, area_height: u32) -> Option<PaneId> {
+        self.divider_hit(mouse_x, mouse_y, area_x, area_y, area_width, area_height)
+            .map(|(split_id, _direction)| split_id)
+    }
+
+    /// Find the divider under the mouse along with the direction it drags in
+    pub fn divider_hit(&self, mouse_x: i32, mouse_y: i32, area_x: i32, area_y: i32, area_width: u32, area_height: u32) -> Option<(PaneId, SplitDirection)> {
+        self.get_divider_rects(area_x, area_y, area_width, area_height)
+            .into_iter()
+            .find(|(_split_id, rect, direction)| divider_hit_rect(*rect, *direction).contains_point((mouse_x, mouse_y)))
+            .map(|(split_id, _rect, direction)| (split_id, direction))
+    }
+
+    /// Give one split an even division of its space, including a drag in progress on it.
+    /// Returns true if the split exists (caller should resize).
+    pub fn reset_split_ratio(&mut self, split_id: PaneId) -> bool {
+        if let Some((preview_id, preview_ratio)) = &mut self.drag_preview {
+            if *preview_id == split_id {
+                *preview_ratio = 0.5;
+            }
+        }
+        self.root.update_ratio(split_id, 0.5)
+    }
+
+    /// Update divider drag. The divider stops where a pane on either side would drop below
+    /// MIN_SPLIT_COLS x MIN_SPLIT_ROWS.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_drag_divider(
+        &mut self,
+        delta_x: i32,
+        delta_y: i32,
+        area_x: i32,
+        area_y: i32,
+        area_width: u32,
+        area_height: u32,
+        char_width: f32,
+        char_height: f32,
+    ) {
         if let Some(split_id) = self.dragging_divider {
             let dividers = self.get_divider_rects(area_x, area_y, area_width, area_height);
             for (div_id, _rect, direction) in dividers {
@@ -556,9 +744,19 @@ impl PaneLayout {
 
                     let ratio_delta = delta as f32 / parent_size;
 
-                    if let Some((preview_id, preview_ratio)) = &mut self.drag_preview {
-                        if *preview_id == split_id {
-                            *preview_ratio = (*preview_ratio + ratio_delta).clamp(0.1, 0.9);
+                    if let Some((preview_id, preview_ratio)) = self.drag_preview {
+                        if preview_id == split_id {
+                            let ratio = self.clamp_split_ratio(
+                                split_id,
+                                preview_ratio + ratio_delta,
+                                area_x,
+                                area_y,
+                                area_width,
+                                area_height,
+                                char_width,
+                                char_height,
+                            );
+                            self.drag_preview = Some((split_id, ratio));
                         }
                     }
                     break;
@@ -567,6 +765,94 @@ impl PaneLayout {
         }
     }
 
+    /// Limit a split's ratio so every pane on both sides keeps at least MIN_SPLIT_COLS x MIN_SPLIT_ROWS
+    /// cells, given the pane area and the cell size at the global font size
+    #[allow(clippy::too_many_arguments)]
+    pub fn clamp_split_ratio(
+        &self,
+        split_id: PaneId,
+        ratio: f32,
+        area_x: i32,
+        area_y: i32,
+        area_width: u32,
+        area_height: u32,
+        char_width: f32,
+        char_height: f32,
+    ) -> f32 {
+        let ratio = ratio.clamp(0.1, 0.9);
+        let Some((rect, direction, first, second)) =
+            Self::find_split(&self.root, split_id, Rect::new(area_x, area_y, area_width, area_height), self.divider_width)
+        else {
+            return ratio;
+        };
+
+        let extent = match direction {
+            SplitDirection::Horizontal => rect.width(),
+            SplitDirection::Vertical => rect.height(),
+        };
+        let available = extent.saturating_sub(self.divider_width) as f32;
+        let first_min = self.min_extent(first, direction, char_width, char_height) as f32;
+        let second_min = self.min_extent(second, direction, char_width, char_height) as f32;
+        if first_min + second_min > available {
+            // Too small for both minimums; share the space in proportion to them
+            return first_min / (first_min + second_min);
+        }
+
+        // Half a pixel of slack so the truncation to whole pixels doesn't undercut the minimum
+        let min_ratio = (first_min + 0.5) / available;
+        let max_ratio = (available - second_min) / available;
+        ratio.clamp(min_ratio.min(max_ratio), max_ratio)
+    }
+
+    /// Find a split node and the rect it occupies within `rect`
+    fn find_split(node: &PaneNode, split_id: PaneId, rect: Rect, divider_width: u32) -> Option<(Rect, SplitDirection, &PaneNode, &PaneNode)> {
+        let PaneNode::Split {
+            id,
+            direction,
+            ratio,
+            first,
+            second,
+        } = node
+        else {
+            return None;
+        };
+        if *id == split_id {
+            return Some((rect, *direction, first, second));
+        }
+
+        let (first_rect, second_rect) = split_rect(rect, *direction, *ratio, divider_width);
+        Self::find_split(first, split_id, first_rect, divider_width).or_else(|| Self::find_split(second, split_id, second_rect, divider_width))
+    }
+
+    /// Smallest size in pixels, along `direction`, that a subtree fits into with every pane
+    /// keeping MIN_SPLIT_COLS x MIN_SPLIT_ROWS cells at its own font scale
+    fn min_extent(&self, node: &PaneNode, direction: SplitDirection, char_width: f32, char_height: f32) -> u32 {
+        match node {
+            PaneNode::Leaf { id, .. } => {
+                let (pane_char_width, pane_char_height) = self.pane_char_size(*id, char_width, char_height);
+                let padding = crate::ui::render::get_pane_padding() * 2;
+                match direction {
+                    SplitDirection::Horizontal => (MIN_SPLIT_COLS as f32 * pane_char_width).ceil() as u32 + padding,
+                    SplitDirection::Vertical => (MIN_SPLIT_ROWS as f32 * pane_char_height).ceil() as u32 + padding + self.header_height,
+                }
+            }
+            PaneNode::Split {
+                direction: split_direction,
+                first,
+                second,
+                ..
+            } => {
+                let first_min = self.min_extent(first, direction, char_width, char_height);
+                let second_min = self.min_extent(second, direction, char_width, char_height);
+                if *split_direction == direction {
+                    first_min + self.divider_width + second_min
+                } else {
+                    first_min.max(second_min)
+                }
+            }
+        }
+    }
+
     /// Stop dragging divider and apply changes
     pub fn stop_drag_divider(&mut self) {
         if let Some((split_id, preview_ratio)) = self.drag_preview {
@@ -606,7 +892,11 @@ impl PaneLayout {
             let items = vec![
                 ContextMenuItem::new(menu_images.vertical_split, "Split vertically", "split_vertical".to_string()),
                 ContextMenuItem::new(menu_images.horizontal_split, "Split horizontally", "split_horizontal".to_string()),
+                ContextMenuItem::new(menu_images.horizontal_split, "Duplicate pane", "duplicate_pane".to_string()),
+                ContextMenuItem::new(menu_images.expand_into_tab, "Duplicate as a tab", "duplicate_tab".to_string()),
                 ContextMenuItem::with_enabled(menu_images.expand_into_tab, "Turn into a tab", "to_tab".to_string(), pane_count > 1),
+                ContextMenuItem::new(menu_images.kill_shell, "Restart shell", "restart_shell".to_string()),
+                ContextMenuItem::new(menu_images.kill_shell, "Send signal ▸", "signal_menu".to_string()),
                 ContextMenuItem::new(menu_images.kill_shell, "Kill terminal", "kill_shell".to_string()),
             ];
             self.context_menu = Some(ContextMenu::new(items, (x, y)));
@@ -615,14 +905,32 @@ impl PaneLayout {
         eprintln!("[PANE_LAYOUT] Context menu opened for pane {:?} at ({}, {})", pane_id, x, y);
     }
 
+    /// Replace the open context menu with the signals that can be sent to the pane's process.
+    /// Their actions are "signal:<number>".
+    fn open_signal_menu(&mut self, x: i32, y: i32) {
+        use crate::ui::context_menu::{ContextMenu, ContextMenuItem};
+
+        if let Some(ref menu_images) = self.context_menu_images {
+            let items = crate::terminal::utils::MENU_SIGNALS
+                .iter()
+                .map(|(name, sig)| ContextMenuItem::new(menu_images.kill_shell, *name, format!("signal:{}", sig)))
+                .collect();
+            self.context_menu = Some(ContextMenu::new(items, (x, y)));
+        }
+    }
+
     /// Handle a click on the context menu. Returns true if the click was handled.
     /// Sets pending_context_action if a menu item was clicked.
     pub fn handle_context_menu_click(&mut self, mouse_x: i32, mouse_y: i32) -> bool {
-        if let Some((menu_pane_id, _, _)) = self.context_menu_open {
-            if let Some(ref menu) = self.context_menu {
-                if let Some(action) = menu.handle_click(mouse_x, mouse_y) {
-                    self.pending_context_action = Some((menu_pane_id, action));
+        if let Some((menu_pane_id, x, y)) = self.context_menu_open {
+            match self.context_menu.as_ref().and_then(|menu| menu.handle_click(mouse_x, mouse_y)) {
+                // The submenu takes the menu's place and stays open
+                Some(action) if action == "signal_menu" => {
+                    self.open_signal_menu(x, y);
+                    return true;
                 }
+                Some(action) => self.pending_context_action = Some((menu_pane_id, action)),
+                None => {}
             }
 
             // Close menu on any click
@@ -641,6 +949,49 @@ impl PaneLayout {
         }
     }
 
+    /// Screen rectangle of the active pane's selection, with its rows clamped to those in view.
+    /// `terminal` is the active pane's terminal, locked by the caller.
+    #[allow(clippy::too_many_arguments)]
+    pub fn active_selection_rect(
+        &self,
+        terminal: &Terminal,
+        area_x: i32,
+        area_y: i32,
+        area_width: u32,
+        area_height: u32,
+        char_width: f32,
+        char_height: f32,
+    ) -> Option<Rect> {
+        let selection = (*terminal.selection.lock().unwrap())?;
+        let pane_rects = self.get_pane_rects(area_x, area_y, area_width, area_height);
+        let (pane_id, rect, _, _, _) = pane_rects.iter().find(|(_, _, _, is_active, _)| *is_active)?;
+
+        let (char_width, char_height) = self.pane_char_size(*pane_id, char_width, char_height);
+        let pane_padding = crate::ui::render::get_pane_padding() as i32;
+        let (start_col, start_row, end_col, end_row) = selection.normalized();
+        // Selection rows are absolute, clamp them to the rows in view
+        let (view_top_row, view_height) = {
+            let sb = terminal.screen_buffer.lock().unwrap();
+            (sb.view_row_to_absolute(0), sb.height())
+        };
+        let start_row = start_row.saturating_sub(view_top_row).min(view_height - 1);
+        let end_row = end_row.saturating_sub(view_top_row).min(view_height - 1);
+
+        let x = rect.x() + pane_padding + (start_col as f32 * char_width) as i32;
+        let y = rect.y() + pane_padding + (start_row as f32 * char_height) as i32;
+        let width = ((end_col - start_col + 1) as f32 * char_width) as u32;
+        let height = ((end_row - start_row + 1) as f32 * char_height) as u32;
+        Some(Rect::new(x, y, width, height))
+    }
+
+    /// Play the copy animation over `selection_rect`, unless `copy_animation` (the setting) is
+    /// off. Every way of copying a selection calls this once the text is on the clipboard.
+    pub fn trigger_copy_feedback(&mut self, selection_rect: Rect, copy_animation: bool) {
+        if copy_animation {
+            self.copy_animation = Some(CopyAnimation::new(selection_rect));
+        }
+    }
+
     /// Toggle pane selection for group input (Ctrl+click)
     pub fn toggle_pane_selection(&mut self, pane_id: PaneId) {
         if self.selected_panes.contains(&pane_id) {
@@ -669,3 +1020,296 @@ impl PaneLayout {
         }
     }
 }
+
+/// The pane after (or before) `current` in layout order, wrapping around. None with a single
+/// pane, as there is nothing to cycle to; an unknown `current` gives the first pane.
+pub fn cycle_pane_id(pane_ids: &[PaneId], current: PaneId, forward: bool) -> Option<PaneId> {
+    if pane_ids.len() <= 1 {
+        return None;
+    }
+    let Some(index) = pane_ids.iter().position(|&id| id == current) else {
+        return pane_ids.first().copied();
+    };
+    let next = if forward {
+        (index + 1) % pane_ids.len()
+    } else {
+        (index + pane_ids.len() - 1) % pane_ids.len()
+    };
+    Some(pane_ids[next])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::test_support::test_terminal;
+
+    fn split_ratios(node: &PaneNode) -> Vec<f32> {
+        match node {
+            PaneNode::Leaf { .. } => Vec::new(),
+            PaneNode::Split { ratio, first, second, .. } => {
+                let mut ratios = vec![*ratio];
+                ratios.extend(split_ratios(first));
+                ratios.extend(split_ratios(second));
+                ratios
+            }
+        }
+    }
+
+    /// A pane rect holding exactly `cols` x `rows` cells of 10x20 pixels
+    fn pane_rect(cols: u32, rows: u32) -> Rect {
+        let padding = crate::ui::render::get_pane_padding() * 2;
+        Rect::new(0, 0, cols * 10 + padding, rows * 20 + padding)
+    }
+
+    #[test]
+    fn test_can_split_reports_reason() {
+        let check = |direction, cols, rows| PaneLayout::can_split(direction, 10.0, 20.0, pane_rect(cols, rows), DEFAULT_DIVIDER_WIDTH);
+
+        // Side by side: one column goes to the divider, each half needs 10
+        assert_eq!(check(SplitDirection::Horizontal, 21, 24), Ok(()));
+        assert_eq!(check(SplitDirection::Horizontal, 20, 24), Err(SplitError::TooNarrow));
+        assert_eq!(check(SplitDirection::Horizontal, 80, 4), Err(SplitError::TooShort));
+
+        // Stacked: one row goes to the divider, each half needs 5
+        assert_eq!(check(SplitDirection::Vertical, 80, 11), Ok(()));
+        assert_eq!(check(SplitDirection::Vertical, 80, 10), Err(SplitError::TooShort));
+        assert_eq!(check(SplitDirection::Vertical, 9, 24), Err(SplitError::TooNarrow));
+    }
+
+    #[test]
+    fn test_balance_resets_nested_ratios() {
+        let mut layout = PaneLayout::new(test_terminal());
+        assert!(!layout.balance(), "a single pane has nothing to balance");
+
+        // Three levels of splits, each left lopsided
+        layout.split_active_pane(SplitDirection::Vertical, test_terminal());
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        layout.split_active_pane(SplitDirection::Vertical, test_terminal());
+        let active = layout.active_pane();
+        fn skew(node: &mut PaneNode, ratio: f32) {
+            if let PaneNode::Split { ratio: r, first, second, .. } = node {
+                *r = ratio;
+                skew(first, ratio);
+                skew(second, ratio);
+            }
+        }
+        skew(&mut layout.root, 0.8);
+        assert_eq!(split_ratios(&layout.root), vec![0.8; 3]);
+
+        assert!(layout.balance());
+        assert_eq!(split_ratios(&layout.root), vec![0.5; 3]);
+        assert_eq!(layout.active_pane(), active);
+        assert!(!layout.balance(), "balancing twice changes nothing");
+    }
+
+    #[test]
+    fn test_copy_feedback_starts_animation() {
+        let mut layout = PaneLayout::new(test_terminal());
+        assert!(layout.copy_animation.is_none());
+
+        layout.trigger_copy_feedback(Rect::new(10, 20, 300, 40), false);
+        assert!(layout.copy_animation.is_none(), "the animation is off");
+
+        layout.trigger_copy_feedback(Rect::new(10, 20, 300, 40), true);
+        let animation = layout.copy_animation.as_ref().expect("copying should start the animation");
+        assert_eq!(animation.original_rect, Rect::new(10, 20, 300, 40));
+    }
+
+    #[test]
+    fn test_divider_double_click_resets_its_split() {
+        let mut layout = PaneLayout::new(test_terminal());
+        layout.split_active_pane(SplitDirection::Vertical, test_terminal());
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        let root_id = layout.root.id();
+        layout.root.update_ratio(root_id, 0.8);
+        let dividers = layout.get_divider_rects(0, 0, 800, 600);
+        let (inner_id, _, _) = dividers.iter().find(|(_, _, direction)| *direction == SplitDirection::Horizontal).unwrap();
+        layout.root.update_ratio(*inner_id, 0.3);
+        assert_eq!(split_ratios(&layout.root), vec![0.8, 0.3]);
+
+        // Each divider rect maps back to its own split node, anywhere else hits none
+        let dividers = layout.get_divider_rects(0, 0, 800, 600);
+        assert_eq!(dividers.len(), 2);
+        for (split_id, rect, _direction) in &dividers {
+            let center = rect.center();
+            assert_eq!(layout.divider_at(center.x(), center.y(), 0, 0, 800, 600), Some(*split_id));
+        }
+        assert_eq!(layout.divider_at(5, 5, 0, 0, 800, 600), None);
+
+        // Only the clicked split goes back to 50/50, also while it is being dragged
+        let (inner_id, rect, _) = dividers.iter().find(|(_, _, direction)| *direction == SplitDirection::Horizontal).unwrap();
+        assert!(layout.start_drag_divider(rect.center().x(), rect.center().y(), 0, 0, 800, 600));
+        assert!(layout.reset_split_ratio(*inner_id));
+        assert_eq!(layout.drag_preview, Some((*inner_id, 0.5)));
+        layout.stop_drag_divider();
+        assert_eq!(split_ratios(&layout.root), vec![0.8, 0.5]);
+        assert!(!layout.reset_split_ratio(PaneId(9999)));
+    }
+
+    #[test]
+    fn test_divider_hit_testing() {
+        let mut layout = PaneLayout::new(test_terminal());
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        let (split_id, rect, _) = layout.get_divider_rects(0, 40, 800, 600)[0];
+        assert_eq!(rect, Rect::new(399, 40, 2, 600));
+
+        // The 2-pixel divider grabs from 3 pixels on either side, along its whole length
+        let hit = |x, y| layout.divider_hit(x, y, 0, 40, 800, 600);
+        assert_eq!(hit(399, 300), Some((split_id, SplitDirection::Horizontal)));
+        assert_eq!(hit(396, 40), Some((split_id, SplitDirection::Horizontal)));
+        assert_eq!(hit(403, 639), Some((split_id, SplitDirection::Horizontal)));
+        assert_eq!(hit(395, 300), None);
+        assert_eq!(hit(404, 300), None);
+        assert_eq!(hit(399, 39), None, "the tab bar above is not part of the divider");
+        assert_eq!(hit(399, 640), None);
+
+        // Thin dividers keep a grab area wide enough to hit, thick ones get the margin around them
+        let thin = Rect::new(100, 0, 50, 1);
+        assert_eq!(divider_hit_rect(thin, SplitDirection::Vertical), Rect::new(100, -3, 50, 8));
+        let thick = Rect::new(100, 0, 6, 50);
+        assert_eq!(divider_hit_rect(thick, SplitDirection::Horizontal), Rect::new(97, 0, 12, 50));
+    }
+
+    #[test]
+    fn test_divider_drag_keeps_minimum_pane_size() {
+        use crate::ui::render::calculate_terminal_size;
+
+        // Side by side panes in a 500x600 area with 10x20 cells; the right one split again
+        let mut layout = PaneLayout::new(test_terminal());
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        let root_id = layout.root.id();
+        let clamp = |layout: &PaneLayout, ratio: f32| layout.clamp_split_ratio(root_id, ratio, 0, 0, 500, 600, 10.0, 20.0);
+
+        // Room to spare leaves the ratio alone
+        assert_eq!(clamp(&layout, 0.4), 0.4);
+
+        for ratio in [0.0, 0.1, 0.9, 1.0] {
+            layout.drag_preview = Some((root_id, clamp(&layout, ratio)));
+            for (_, rect, _, _, _) in layout.get_pane_rects(0, 0, 500, 600) {
+                let (cols, rows) = calculate_terminal_size(rect.width(), rect.height(), 10.0, 20.0);
+                assert!(
+                    cols >= MIN_SPLIT_COLS && rows >= MIN_SPLIT_ROWS,
+                    "ratio {} gave a {}x{} pane",
+                    ratio,
+                    cols,
+                    rows
+                );
+            }
+        }
+
+        // The divider stops at the boundary rather than short of it
+        let max_ratio = clamp(&layout, 1.0);
+        layout.drag_preview = Some((root_id, max_ratio + 0.01));
+        let narrowest = layout
+            .get_pane_rects(0, 0, 500, 600)
+            .iter()
+            .map(|(_, rect, _, _, _)| calculate_terminal_size(rect.width(), rect.height(), 10.0, 20.0).0)
+            .min();
+        assert_eq!(narrowest, Some(MIN_SPLIT_COLS - 1));
+    }
+
+    #[test]
+    fn test_swap_panes_across_orientations() {
+        // [a | [b / c]] with a lopsided outer split
+        let (term_a, term_b, term_c) = (test_terminal(), test_terminal(), test_terminal());
+        let mut layout = PaneLayout::new(term_a.clone());
+        let a = layout.active_pane();
+        layout.split_active_pane(SplitDirection::Vertical, term_b.clone());
+        let b = layout.active_pane();
+        layout.split_active_pane(SplitDirection::Horizontal, term_c.clone());
+        let c = layout.active_pane();
+        let outer = layout.root.id();
+        layout.root.update_ratio(outer, 0.3);
+        let ratios = split_ratios(&layout.root);
+
+        // Swapping a pane with itself changes nothing
+        assert!(!layout.swap_panes(b, b));
+        assert_eq!(layout.root.collect_leaf_ids(), vec![a, b, c]);
+
+        assert!(layout.swap_panes(a, c));
+        assert_eq!(layout.root.collect_leaf_ids(), vec![c, b, a]);
+        assert_eq!(split_ratios(&layout.root), ratios);
+        assert!(Arc::ptr_eq(&layout.root.find_terminal(a).unwrap(), &term_a));
+        assert!(Arc::ptr_eq(&layout.root.find_terminal(c).unwrap(), &term_c));
+        // The active pane follows its terminal
+        assert_eq!(layout.active_pane(), c);
+        assert!(Arc::ptr_eq(&layout.get_active_terminal().unwrap(), &term_c));
+
+        // c is now first in layout order, so the next pane is b
+        assert!(layout.swap_active_with_next_pane());
+        assert_eq!(layout.root.collect_leaf_ids(), vec![b, c, a]);
+        assert_eq!(layout.root.count_leaf_panes(), 3);
+    }
+
+    #[test]
+    fn test_zoom_only_changes_active_pane() {
+        let mut layout = PaneLayout::new(test_terminal());
+        let first = layout.active_pane();
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        let second = layout.active_pane();
+
+        assert!(layout.zoom_active_pane(FONT_SCALE_STEP));
+        assert!(layout.zoom_active_pane(FONT_SCALE_STEP));
+        assert!((layout.font_scale(second) - 1.2).abs() < 1e-4);
+        assert_eq!(layout.font_scale(first), 1.0);
+
+        let (w, h) = layout.pane_char_size(second, 10.0, 20.0);
+        assert!((w - 12.0).abs() < 1e-3 && (h - 24.0).abs() < 1e-3);
+        assert_eq!(layout.pane_char_size(first, 10.0, 20.0), (10.0, 20.0));
+
+        // Back at the global size the pane no longer has an override
+        assert!(layout.zoom_active_pane(-2.0 * FONT_SCALE_STEP));
+        assert!(layout.font_scales.is_empty());
+
+        // Zooming stops at the limits
+        for _ in 0..10 {
+            layout.zoom_active_pane(-FONT_SCALE_STEP);
+        }
+        assert_eq!(layout.font_scale(second), MIN_FONT_SCALE);
+        assert!(!layout.zoom_active_pane(-FONT_SCALE_STEP));
+    }
+
+    #[test]
+    fn test_cycle_pane_id_wraps() {
+        let ids = [PaneId(4), PaneId(7), PaneId(9)];
+        assert_eq!(cycle_pane_id(&ids, PaneId(4), true), Some(PaneId(7)));
+        assert_eq!(cycle_pane_id(&ids, PaneId(9), true), Some(PaneId(4)));
+        assert_eq!(cycle_pane_id(&ids, PaneId(4), false), Some(PaneId(9)));
+        assert_eq!(cycle_pane_id(&ids, PaneId(7), false), Some(PaneId(4)));
+        assert_eq!(cycle_pane_id(&ids, PaneId(1), true), Some(PaneId(4)));
+
+        // A single pane stays where it is
+        assert_eq!(cycle_pane_id(&[PaneId(4)], PaneId(4), true), None);
+        assert_eq!(cycle_pane_id(&[PaneId(4)], PaneId(4), false), None);
+    }
+
+    #[test]
+    fn test_pane_headers_take_one_row() {
+        use crate::ui::render::calculate_terminal_size;
+
+        let mut layout = PaneLayout::new(test_terminal());
+        layout.header_height = 20;
+        let full = pane_rect(80, 24);
+        let area_width = full.width() * 2 + 2;
+
+        // A single pane stays headerless
+        let rects = layout.get_pane_rects(0, 0, area_width, full.height());
+        assert_eq!(rects[0].1, Rect::new(0, 0, area_width, full.height()));
+
+        // Side by side panes each lose one row to their header
+        layout.split_active_pane(SplitDirection::Horizontal, test_terminal());
+        let rects = layout.get_pane_rects(0, 0, area_width, full.height());
+        assert_eq!(rects.len(), 2);
+        for (_, rect, _, _, _) in &rects {
+            assert_eq!(rect.y(), 20);
+            assert_eq!(rect.height(), full.height() - 20);
+            assert_eq!(calculate_terminal_size(rect.width(), rect.height(), 10.0, 20.0), (80, 23));
+        }
+
+        // Without headers the panes get the full height back
+        layout.header_height = 0;
+        let rects = layout.get_pane_rects(0, 0, area_width, full.height());
+        assert_eq!(calculate_terminal_size(rects[1].1.width(), rects[1].1.height(), 10.0, 20.0), (80, 24));
+    }
+}
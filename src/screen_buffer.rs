@@ -1,4 +1,4 @@
-use crate::ansi::{DEFAULT_BG_COLOR, DEFAULT_FG_COLOR};
+use crate::ansi::{TextAttributes, DEFAULT_BG_COLOR, DEFAULT_FG_COLOR};
 use sdl3::pixels::Color;
 use unicode_width::UnicodeWidthChar;
 
@@ -57,6 +57,31 @@ impl Default for CharSet {
     }
 }
 
+/// Rows of one finished shell command, from its prompt to the end of its output, in absolute rows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommandRegion {
+    pub start_row: usize,
+    pub end_row: usize,
+    pub exit_code: i32,
+}
+
+/// Per-row line size set by the DEC line attribute escapes (ESC # 3/4/5/6)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineAttribute {
+    #[default]
+    Normal, // ESC # 5 - DECSWL
+    DoubleWidth,        // ESC # 6 - DECDWL
+    DoubleHeightTop,    // ESC # 3 - DECDHL top half
+    DoubleHeightBottom, // ESC # 4 - DECDHL bottom half
+}
+
+impl LineAttribute {
+    /// Whether glyphs on this row are drawn twice as wide
+    pub fn is_double_width(self) -> bool {
+        self != LineAttribute::Normal
+    }
+}
+
 /// Cursor style as set by DECSCUSR escape sequences
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CursorStyle {
@@ -87,6 +112,19 @@ impl CursorStyle {
             _ => CursorStyle::SteadyBar, // Default to pipe/bar for backwards compatibility
         }
     }
+
+    /// Convert from a DECSCUSR parameter (CSI Ps SP q), None if it isn't a known style
+    pub fn from_decscusr(param: u32) -> Option<Self> {
+        match param {
+            0 | 1 => Some(CursorStyle::BlinkingBlock), // 0 is the terminal default, a blinking block
+            2 => Some(CursorStyle::SteadyBlock),
+            3 => Some(CursorStyle::BlinkingUnderline),
+            4 => Some(CursorStyle::SteadyUnderline),
+            5 => Some(CursorStyle::BlinkingBar),
+            6 => Some(CursorStyle::SteadyBar),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -105,25 +143,48 @@ pub struct Cell {
     pub invisible: bool,
 }
 
+/// An empty cell, also lent out for the missing columns of narrower scrollback rows
+static BLANK_CELL: Cell = Cell {
+    ch: ' ',
+    extended: None,
+    fg_color: DEFAULT_FG_COLOR,
+    bg_color: DEFAULT_BG_COLOR,
+    width: 1,
+    bold: false,
+    italic: false,
+    underline: false,
+    strikethrough: false,
+    blink: false,
+    reverse: false,
+    invisible: false,
+};
+
 impl Default for Cell {
     fn default() -> Self {
-        Cell {
-            ch: ' ',
-            extended: None,
-            fg_color: DEFAULT_FG_COLOR,
-            bg_color: DEFAULT_BG_COLOR,
-            width: 1,
-            bold: false,
-            italic: false,
-            underline: false,
-            strikethrough: false,
-            blink: false,
-            reverse: false,
-            invisible: false,
-        }
+        BLANK_CELL.clone()
     }
 }
 
+impl Cell {
+    /// Nothing visible: a plain space on the default background. Spaces with a background
+    /// color are content (e.g. a colored block) and are kept when trimming text.
+    pub fn is_blank(&self) -> bool {
+        self.ch == ' ' && self.extended.is_none() && self.bg_color == DEFAULT_BG_COLOR && !self.reverse
+    }
+
+    /// Exactly like the padding of an empty row, so it can be dropped from the end of a
+    /// stored row and restored by padding without losing any styling
+    pub fn is_trailing_trimmable(&self) -> bool {
+        self.is_blank()
+            && self.width == 1
+            && self.fg_color == DEFAULT_FG_COLOR
+            && !(self.bold || self.italic || self.underline || self.strikethrough || self.blink || self.invisible)
+    }
+}
+
+/// Spacing of the default tab stops when the `tabWidth` setting doesn't say otherwise
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
 /// Check if a character is a special symbol that needs scaling in rendering
 #[inline]
 pub fn is_special_symbol(ch: char) -> bool {
@@ -146,6 +207,18 @@ pub fn is_special_symbol(ch: char) -> bool {
         0x2A00..=0x2AFF |  // Supplemental Mathematical Operators
         0x2B00..=0x2BFF |  // Miscellaneous Symbols and Arrows
         0xFF00..=0xFFEF    // Halfwidth and Fullwidth Forms (includes ･)
+    ) || is_private_use_symbol(ch)
+}
+
+/// Check if a character is in a Private Use Area, where Powerline separators
+/// (U+E0A0–U+E0D7) and Nerd Font icons live. These glyphs only exist in patched
+/// terminal fonts, so they must be rendered with the main font, not the fallback font.
+#[inline]
+pub fn is_private_use_symbol(ch: char) -> bool {
+    let codepoint = ch as u32;
+    matches!(codepoint,
+        0xE000..=0xF8FF |    // Private Use Area (Powerline, Nerd Font icons)
+        0xF0000..=0xFFFFD    // Supplementary Private Use Area-A (Nerd Font Material Design icons)
     )
 }
 
@@ -197,6 +270,17 @@ pub fn is_emoji_grapheme(s: &str) -> bool {
     s.chars().any(is_emoji_char)
 }
 
+/// Number of cells a grapheme cluster occupies. Emoji clusters (including flags, which are a
+/// pair of regional indicators) are always double-width; anything else follows the East Asian
+/// Width of its first character.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    if is_emoji_grapheme(grapheme) {
+        2
+    } else {
+        grapheme.chars().next().and_then(|c| c.width()).unwrap_or(1)
+    }
+}
+
 /// Check if a character is a CJK (Chinese, Japanese, Korean) character
 #[inline]
 pub fn is_cjk_char(ch: char) -> bool {
@@ -264,9 +348,11 @@ pub struct ScreenBuffer {
     pub invisible: bool,
     // Last character printed (for REP - Repeat command)
     last_char: Option<char>,
-    // Tab stops (by default every 8 columns, but can be customized)
+    // Tab stops (by default every tab_width columns, but can be customized)
     // None means use default tab stops, Some(set) means custom tab stops
     tab_stops: Option<std::collections::HashSet<usize>>,
+    // Spacing of the default tab stops, never 0
+    tab_width: usize,
     // Reverse video mode (DECSCNM) - swap all foreground/background colors globally
     pub reverse_video_mode: bool,
     // Scrolling region (top and bottom margins, 0-based, inclusive)
@@ -283,14 +369,32 @@ pub struct ScreenBuffer {
     scrollback_limit: usize,
     // Current scroll offset (0 means viewing the live terminal, positive means scrolled back)
     pub scroll_offset: usize,
+    // Lines dropped from the front of the scrollback so far, keeps absolute row numbers stable
+    scrollback_trimmed: usize,
+    // Output arrived while the view was scrolled back (cleared when returning to the live view)
+    new_output_while_scrolled: bool,
+    // A BEL was received and not yet picked up by the tab bar
+    bell_rung: bool,
+    // The reader thread processed output that the tab bar hasn't picked up yet
+    output_arrived: bool,
+    // Absolute row where the running command's prompt started
+    command_start_row: Option<usize>,
+    // Finished commands with their exit codes, oldest first and not overlapping
+    command_regions: Vec<CommandRegion>,
     // Origin mode (DECOM) - when enabled, cursor positioning is relative to scroll region
     origin_mode: bool,
     // Auto-wrap mode (DECAWM) - when enabled, cursor wraps at right margin
     auto_wrap_mode: bool,
+    // Reverse-wraparound mode (xterm ?45) - backspace at the left margin goes to the end of the previous line
+    reverse_wraparound: bool,
     // Pending wrap state - cursor is past last column, wrap on next character
     pub(crate) pending_wrap: bool,
     // Cursor style (DECSCUSR)
     pub cursor_style: CursorStyle,
+    // Cursor color set by the application (OSC 12), overrides the theme
+    pub cursor_color: Option<Color>,
+    // Title set by the application (OSC 0 / OSC 2)
+    pub title: Option<String>,
     // Character set designation - G0, G1, G2, G3
     g0_charset: CharSet,
     g1_charset: CharSet,
@@ -304,11 +408,13 @@ pub struct ScreenBuffer {
     insert_mode: bool,
     // Automatic newline mode (LNM) - when enabled, CR (Ctrl-M) acts as CR+LF
     automatic_newline: bool,
+    // Line size of each screen row (DECDWL/DECDHL), moves together with the rows
+    line_attributes: Vec<LineAttribute>,
 }
 
 impl ScreenBuffer {
-    pub fn new_with_scrollback(width: usize, height: usize, scrollback_limit: usize, cursor_style: CursorStyle) -> Self {
-        Self {
+    pub fn new_with_scrollback(width: usize, height: usize, scrollback_limit: usize, cursor_style: CursorStyle, tab_width: usize) -> Self {
+        let mut buffer = Self {
             cells: vec![vec![Cell::default(); width]; height],
             width,
             height,
@@ -325,6 +431,7 @@ impl ScreenBuffer {
             invisible: false,
             last_char: None,
             tab_stops: None,
+            tab_width: DEFAULT_TAB_WIDTH,
             reverse_video_mode: false,
             g0_charset: CharSet::Ascii,
             g1_charset: CharSet::Ascii,
@@ -339,13 +446,25 @@ impl ScreenBuffer {
             scrollback_buffer: Vec::new(),
             scrollback_limit,
             scroll_offset: 0,
+            scrollback_trimmed: 0,
+            new_output_while_scrolled: false,
+            bell_rung: false,
+            output_arrived: false,
+            command_start_row: None,
+            command_regions: Vec::new(),
             origin_mode: false,
             auto_wrap_mode: true,
+            reverse_wraparound: false,
             pending_wrap: false,
             cursor_style,
+            cursor_color: None,
+            title: None,
             insert_mode: false,
             automatic_newline: false,
-        }
+            line_attributes: vec![LineAttribute::Normal; height],
+        };
+        buffer.set_tab_width(tab_width);
+        buffer
     }
 
     pub fn resize(&mut self, width: usize, height: usize) {
@@ -361,17 +480,15 @@ impl ScreenBuffer {
         eprintln!("[SCREEN_BUFFER] Resize: {}x{} -> {}x{}", old_width, old_height, width, height);
         eprintln!("[SCREEN_BUFFER] Old cursor: ({}, {})", old_cursor_x, old_cursor_y);
 
-        // Create new buffer
-        let mut new_cells = vec![vec![Cell::default(); width]; height];
-
         // Check if we need to rewrap content due to width change
         let needs_rewrap = old_width != width && width < old_width;
 
-        // If width decreased, rewrap all content before handling height changes
-        let (working_cells, rewrap_cursor_x, rewrap_cursor_y) = if needs_rewrap {
+        // If width decreased, rewrap all content before handling height changes.
+        // The old rows are moved, not copied: the buffer is rebuilt from them below.
+        let (mut working_cells, rewrap_cursor_x, rewrap_cursor_y) = if needs_rewrap {
             self.rewrap_content(width, old_height)
         } else {
-            (self.cells.clone(), old_cursor_x, old_cursor_y)
+            (std::mem::take(&mut self.cells), old_cursor_x, old_cursor_y)
         };
 
         // Update old_height and cursor position if rewrapping changed them
@@ -386,7 +503,7 @@ impl ScreenBuffer {
             for row in &working_cells {
                 for cell in row {
                     total_cells += 1;
-                    if cell.ch != ' ' {
+                    if !cell.is_blank() {
                         non_space_count += 1;
                     }
                 }
@@ -429,35 +546,18 @@ impl ScreenBuffer {
                 0
             };
 
-            // Save lines to scrollback if we're keeping bottom content
-            if lines_to_scrollback > 0 && self.scrollback_limit > 0 {
-                for i in 0..lines_to_scrollback {
-                    if i < working_cells.len() {
-                        self.scrollback_buffer.push(working_cells[i].clone());
-                    }
-                }
-
-                // Trim scrollback buffer if it exceeds the limit
-                if self.scrollback_buffer.len() > self.scrollback_limit {
-                    let excess = self.scrollback_buffer.len() - self.scrollback_limit;
-                    self.scrollback_buffer.drain(0..excess);
-                }
+            if lines_to_scrollback > 0 {
+                // Keep bottom content, the rows above it go to scrollback
+                let kept = working_cells.split_off(lines_to_scrollback);
+                if self.scrollback_limit > 0 {
+                    self.scrollback_buffer.append(&mut working_cells);
 
-                eprintln!("[SCREEN_BUFFER] Moved {} lines to scrollback (cursor in bottom half)", lines_to_scrollback);
-            }
+                    // Trim scrollback buffer if it exceeds the limit
+                    self.trim_scrollback();
 
-            // Copy content to new buffer
-            if lines_to_scrollback > 0 {
-                // Keep bottom content - copy from (lines_to_scrollback) onwards
-                for (new_y, old_y) in (lines_to_scrollback..old_height).enumerate() {
-                    if old_y < working_cells.len() && new_y < height {
-                        for x in 0..width {
-                            if x < working_cells[old_y].len() {
-                                new_cells[new_y][x] = working_cells[old_y][x].clone();
-                            }
-                        }
-                    }
+                    eprintln!("[SCREEN_BUFFER] Moved {} lines to scrollback (cursor in bottom half)", lines_to_scrollback);
                 }
+                working_cells = kept;
 
                 // Adjust cursor position
                 if old_cursor_y >= lines_to_scrollback {
@@ -466,36 +566,23 @@ impl ScreenBuffer {
                     self.cursor_y = 0;
                 }
             } else {
-                // Keep top content - copy what fits
-                for y in 0..height.min(old_height) {
-                    if y < working_cells.len() {
-                        for x in 0..width {
-                            if x < working_cells[y].len() {
-                                new_cells[y][x] = working_cells[y][x].clone();
-                            }
-                        }
-                    }
-                }
-
+                // Keep top content - what fits is kept below
                 // Keep cursor at same position
                 self.cursor_y = old_cursor_y;
             }
         } else {
-            // Terminal is same size or growing - simple copy from working_cells
-            let copy_height = old_height.min(height);
-            for (y, row) in new_cells.iter_mut().enumerate().take(copy_height) {
-                if y < working_cells.len() {
-                    for x in 0..width {
-                        if x < working_cells[y].len() {
-                            row[x] = working_cells[y][x].clone();
-                        }
-                    }
-                }
-            }
-
+            // Terminal is same size or growing - the rows are kept as they are
             self.cursor_y = old_cursor_y;
         }
 
+        // Fit the kept rows to the new size
+        working_cells.truncate(height);
+        working_cells.resize_with(height, Vec::new);
+        for row in &mut working_cells {
+            row.resize(width, Cell::default());
+        }
+        let new_cells = working_cells;
+
         // Keep cursor in bounds (use updated position from rewrap if it happened)
         self.cursor_x = old_cursor_x.min(width.saturating_sub(1));
         self.cursor_y = self.cursor_y.min(height.saturating_sub(1));
@@ -503,120 +590,71 @@ impl ScreenBuffer {
         self.cells = new_cells;
         self.width = width;
         self.height = height;
+        // Rows may have been rewrapped, so line sizes no longer apply
+        self.line_attributes = vec![LineAttribute::Normal; height];
+        // A scrolling region or saved cursor from the old size may point past the new screen
+        self.scroll_region = self.scroll_region.filter(|&(top, bottom)| top < bottom && bottom < height);
+        self.saved_cursor_x = self.saved_cursor_x.min(width - 1);
+        self.saved_cursor_y = self.saved_cursor_y.min(height - 1);
         self.dirty = true;
     }
 
     /// Rewrap content to fit a new width, preserving all text
     /// Returns (rewrapped_lines, new_cursor_x, new_cursor_y)
+    ///
+    /// Rows are streamed cell by cell into the new rows, so no flattened copy of the whole
+    /// screen is built. Cells keep their attributes; a double-width character that would be
+    /// split wraps to the next row whole. Blank rows are dropped, as content is joined up.
     fn rewrap_content(&self, new_width: usize, _old_height: usize) -> (Vec<Vec<Cell>>, usize, usize) {
         eprintln!("[SCREEN_BUFFER] Rewrapping content to width {}", new_width);
 
-        // Collect all text content from all lines, trimming trailing spaces
-        // Also track the cursor position in the flattened text
-        let mut all_text: Vec<(char, Color, Color)> = Vec::new();
-        let mut cursor_char_index: Option<usize> = None;
-        let mut current_char_index = 0;
+        let mut new_rows: Vec<Vec<Cell>> = Vec::new();
+        let mut current_row = vec![Cell::default(); new_width];
+        let mut x = 0;
+        let mut cursor = None;
+        // The cursor sits on whatever is placed next (a cell or a line break)
+        let mut cursor_pending = false;
 
         for (row_idx, row) in self.cells.iter().enumerate() {
-            // Find the last non-space character in this row
-            let mut last_content_idx = 0;
-            for (i, cell) in row.iter().enumerate() {
-                if cell.ch != ' ' || cell.extended.is_some() {
-                    last_content_idx = i + 1;
-                }
-            }
-
-            // Collect content up to last non-space character
-            for i in 0..last_content_idx {
-                if i < row.len() {
-                    let cell = &row[i];
-
-                    // Track cursor position in flattened text
-                    if row_idx == self.cursor_y && i == self.cursor_x {
-                        cursor_char_index = Some(current_char_index);
-                    }
+            // Find the last non-blank cell in this row, keeping trailing spaces that have a background
+            let last_content_idx = row.iter().rposition(|cell| !cell.is_blank()).map_or(0, |i| i + 1);
 
-                    all_text.push((cell.ch, cell.fg_color, cell.bg_color));
-                    current_char_index += 1;
+            for (i, cell) in row[..last_content_idx].iter().enumerate() {
+                if row_idx == self.cursor_y && i == self.cursor_x && cursor.is_none() {
+                    cursor_pending = true;
+                }
 
-                    // Handle extended graphemes
-                    if let Some(ref ext) = cell.extended {
-                        for ch in ext.chars() {
-                            all_text.push((ch, cell.fg_color, cell.bg_color));
-                            current_char_index += 1;
-                        }
-                    }
+                // Wrap to the next row, early for a double-width character that would not fit
+                if x >= new_width || (cell.width == 2 && x + 1 >= new_width && x > 0) {
+                    new_rows.push(std::mem::replace(&mut current_row, vec![Cell::default(); new_width]));
+                    x = 0;
+                }
+                if cursor_pending {
+                    cursor = Some((x, new_rows.len()));
+                    cursor_pending = false;
                 }
+
+                current_row[x] = cell.clone();
+                x += 1;
             }
 
             // If cursor is on this line but past the content (in trailing spaces),
             // mark it at the end of the line's content
-            if row_idx == self.cursor_y && self.cursor_x >= last_content_idx && cursor_char_index.is_none() {
-                cursor_char_index = Some(current_char_index);
+            if row_idx == self.cursor_y && self.cursor_x >= last_content_idx && cursor.is_none() {
+                cursor_pending = true;
             }
 
-            // Add a newline marker to preserve line breaks
-            // (represented as space with special flag - we'll handle this during rewrap)
+            // A line break ends the row being filled
             if last_content_idx > 0 {
-                all_text.push(('\n', Color::default(), Color::default()));
-                current_char_index += 1;
-            }
-        }
-
-        // If cursor wasn't found (e.g., on empty line), set it to end
-        let cursor_char_index = cursor_char_index.unwrap_or(current_char_index);
-
-        // Now rewrap the text to fit new_width, tracking cursor position
-        let mut new_rows: Vec<Vec<Cell>> = Vec::new();
-        let mut current_row = vec![Cell::default(); new_width];
-        let mut x = 0;
-        let mut char_index = 0;
-        let mut new_cursor_x = 0;
-        let mut new_cursor_y = 0;
-        let mut cursor_found = false;
-
-        for (ch, fg, bg) in all_text {
-            // Check if this is where the cursor should be
-            if !cursor_found && char_index == cursor_char_index {
-                new_cursor_x = x;
-                new_cursor_y = new_rows.len();
-                cursor_found = true;
-            }
-
-            char_index += 1;
-            if ch == '\n' {
-                // Line break - finish current row and start new one
+                if cursor_pending {
+                    cursor = Some((x, new_rows.len()));
+                    cursor_pending = false;
+                }
                 if x > 0 || new_rows.is_empty() {
-                    new_rows.push(current_row);
-                    current_row = vec![Cell::default(); new_width];
+                    new_rows.push(std::mem::replace(&mut current_row, vec![Cell::default(); new_width]));
                     x = 0;
                 }
-                continue;
-            }
-
-            // Check if we need to wrap to next line
-            if x >= new_width {
-                new_rows.push(current_row);
-                current_row = vec![Cell::default(); new_width];
-                x = 0;
             }
-
-            // Add character to current row
-            current_row[x] = Cell {
-                ch,
-                fg_color: fg,
-                bg_color: bg,
-                extended: None,
-                width: 1, // Default to 1 for normal characters
-                bold: false,
-                italic: false,
-                underline: false,
-                strikethrough: false,
-                blink: false,
-                reverse: false,
-                invisible: false,
-            };
-            x += 1;
         }
 
         // Add the last row if it has content
@@ -625,10 +663,7 @@ impl ScreenBuffer {
         }
 
         // If cursor wasn't placed yet (was at or after end), place it at the end
-        if !cursor_found {
-            new_cursor_x = x;
-            new_cursor_y = new_rows.len().saturating_sub(1);
-        }
+        let (new_cursor_x, new_cursor_y) = cursor.unwrap_or((x, new_rows.len().saturating_sub(1)));
 
         eprintln!("[SCREEN_BUFFER] Rewrapped {} old lines into {} new lines", self.cells.len(), new_rows.len());
         eprintln!(
@@ -641,6 +676,23 @@ impl ScreenBuffer {
 
     /// Put a grapheme cluster (potentially multi-character emoji with modifiers)
     pub fn put_grapheme(&mut self, grapheme: &str) {
+        if self.scroll_offset > 0 {
+            self.new_output_while_scrolled = true;
+        }
+
+        // Combining marks arriving on their own (e.g. split across reads) belong to the previous character
+        if !grapheme.is_empty() && grapheme.chars().all(|c| c.width() == Some(0)) {
+            self.attach_to_previous_cell(grapheme);
+            return;
+        }
+
+        let char_width = grapheme_width(grapheme);
+
+        // A double-width character does not fit in the last column, so it wraps early like in xterm
+        if char_width == 2 && self.auto_wrap_mode && self.width > 1 && self.cursor_x == self.width - 1 {
+            self.pending_wrap = true;
+        }
+
         // Handle pending wrap from previous character
         if self.pending_wrap && self.auto_wrap_mode {
             self.cursor_x = 0;
@@ -658,13 +710,6 @@ impl ScreenBuffer {
             // If insert mode is enabled, shift characters to the right before writing
             if self.insert_mode {
                 // Shift all characters from cursor position to the right by char_width
-                // First, determine the width of the character we're about to insert
-                let is_emoji = is_emoji_grapheme(grapheme);
-                let first_char = grapheme.chars().next().unwrap_or(' ');
-                let unicode_width = first_char.width().unwrap_or(1);
-                let char_width = if is_emoji { 2 } else { unicode_width };
-
-                // Shift characters to the right
                 if self.cursor_x + char_width < self.width {
                     // Shift characters within the line
                     let y = self.cursor_y;
@@ -677,18 +722,14 @@ impl ScreenBuffer {
                             self.cells[y][self.cursor_x + i] = Cell::default();
                         }
                     }
+                    // A wide character pushed into the last column lost its continuation cell
+                    let last = self.width - 1;
+                    if self.cells[y][last].width == 2 {
+                        self.cells[y][last] = Cell::default();
+                    }
                 }
             }
-            // Determine character width
-            // First check if this grapheme contains an emoji (including combined emojis)
-            let is_emoji = is_emoji_grapheme(grapheme);
-
-            // For non-emoji characters, use Unicode East Asian Width property
             let first_char = grapheme.chars().next().unwrap_or(' ');
-            let unicode_width = first_char.width().unwrap_or(1);
-
-            // Use the larger of emoji detection or Unicode width
-            let char_width = if is_emoji { 2 } else { unicode_width };
 
             // Write the grapheme cluster
             // For simple single-char graphemes, use just the char field
@@ -752,28 +793,62 @@ impl ScreenBuffer {
         }
     }
 
+    /// Append zero-width characters to the cell before the cursor without advancing it
+    fn attach_to_previous_cell(&mut self, grapheme: &str) {
+        if self.cursor_y >= self.height {
+            return;
+        }
+        // With a pending wrap the cursor still sits on the character just written
+        let mut x = if self.pending_wrap {
+            self.cursor_x
+        } else {
+            match self.cursor_x.checked_sub(1) {
+                Some(x) => x,
+                None => return, // Nothing to combine with at the start of a line
+            }
+        };
+        // Step back from the continuation half of a wide character
+        if x > 0 && self.cells[self.cursor_y][x].width == 0 {
+            x -= 1;
+        }
+
+        let cell = &mut self.cells[self.cursor_y][x];
+        let mut combined = match cell.extended.take() {
+            Some(extended) => extended.into_string(),
+            None => cell.ch.to_string(),
+        };
+        combined.push_str(grapheme);
+        cell.extended = Some(combined.into());
+        self.dirty = true;
+    }
+
+    /// CR - return to the first column, cancelling a wrap pending at the right margin
+    pub fn carriage_return(&mut self) {
+        self.pending_wrap = false;
+        self.cursor_x = 0;
+        self.dirty = true;
+    }
+
     pub fn newline(&mut self) {
         self.pending_wrap = false;
-        self.cursor_y += 1;
 
         // Get the scrolling region bounds
         let (_scroll_top, scroll_bottom) = self.scroll_region.unwrap_or((0, self.height - 1));
 
-        // Only scroll if we're past the bottom of the scrolling region
-        if self.cursor_y > scroll_bottom {
-            self.cursor_y = scroll_bottom;
-            self.scroll_up(1);
-        } else if self.cursor_y >= self.height {
-            self.cursor_y = self.height - 1;
+        if self.cursor_y == scroll_bottom {
+            // At the bottom margin: scroll only the region, the cursor stays
             self.scroll_up(1);
+        } else if self.cursor_y < self.height - 1 {
+            self.cursor_y += 1;
         }
+        // Below the region on the last screen row: LF does nothing
         self.dirty = true;
     }
 
     pub fn tab(&mut self) {
         self.pending_wrap = false;
 
-        // Use custom tab stops if defined, otherwise use default (every 8 columns)
+        // Use custom tab stops if defined, otherwise use default (every tab_width columns)
         if let Some(ref stops) = self.tab_stops {
             // Find next tab stop after current position
             let mut next_tab = self.width - 1; // Default to end of line
@@ -784,8 +859,7 @@ impl ScreenBuffer {
             }
             self.cursor_x = next_tab;
         } else {
-            // Default tab stops every 8 columns
-            let next_tab = ((self.cursor_x / 8) + 1) * 8;
+            let next_tab = ((self.cursor_x / self.tab_width) + 1) * self.tab_width;
             self.cursor_x = next_tab.min(self.width - 1);
         }
         self.dirty = true;
@@ -850,36 +924,48 @@ impl ScreenBuffer {
         self.dirty = true;
     }
 
+    /// BS - move left one column; with reverse-wraparound and auto-wrap on, backspace at the
+    /// left margin continues from the last column of the previous line
+    pub fn backspace(&mut self) {
+        if self.cursor_x == 0 && self.cursor_y > 0 && self.reverse_wraparound && self.auto_wrap_mode {
+            self.pending_wrap = false;
+            self.cursor_x = self.width - 1;
+            self.cursor_y -= 1;
+            self.dirty = true;
+        } else {
+            self.move_cursor_left(1);
+        }
+    }
+
     /// CHT - Cursor Horizontal Forward Tabulation
-    /// Move cursor forward n tab stops (default tab stops at multiples of 8)
+    /// Move cursor forward n tab stops (custom stops if set, otherwise multiples of tab_width)
     pub fn forward_tab(&mut self, n: usize) {
-        self.pending_wrap = false;
         for _ in 0..n {
-            // Move to next tab stop (multiple of 8)
-            let next_tab = ((self.cursor_x / 8) + 1) * 8;
-            self.cursor_x = next_tab.min(self.width - 1);
-            // If we hit the right edge, stop
+            // tab() stops at the right edge
+            self.tab();
             if self.cursor_x == self.width - 1 {
                 break;
             }
         }
+        self.pending_wrap = false;
         self.dirty = true;
     }
 
     /// CBT - Cursor Backward Tabulation
-    /// Move cursor backward n tab stops (default tab stops at multiples of 8)
+    /// Move cursor backward n tab stops (custom stops if set, otherwise multiples of tab_width)
     pub fn back_tab(&mut self, n: usize) {
         self.pending_wrap = false;
         for _ in 0..n {
             if self.cursor_x == 0 {
                 break;
             }
-            // Move to previous tab stop (multiple of 8)
             // If already at a tab stop, move to the previous one
-            let prev_tab = if self.cursor_x % 8 == 0 {
-                self.cursor_x.saturating_sub(8)
+            let prev_tab = if let Some(ref stops) = self.tab_stops {
+                stops.iter().copied().filter(|&stop| stop < self.cursor_x).max().unwrap_or(0)
+            } else if self.cursor_x.is_multiple_of(self.tab_width) {
+                self.cursor_x.saturating_sub(self.tab_width)
             } else {
-                (self.cursor_x / 8) * 8
+                (self.cursor_x / self.tab_width) * self.tab_width
             };
             self.cursor_x = prev_tab;
         }
@@ -892,21 +978,20 @@ impl ScreenBuffer {
     }
 
     pub fn restore_cursor(&mut self) {
+        self.pending_wrap = false;
         self.cursor_x = self.saved_cursor_x.min(self.width.saturating_sub(1));
         self.cursor_y = self.saved_cursor_y.min(self.height.saturating_sub(1));
         self.dirty = true;
     }
 
     pub fn clear_screen(&mut self) {
-        self.pending_wrap = false;
-
         // Save current screen content to scrollback buffer before clearing
         // Only save lines up to the cursor position (or last non-empty line)
         if self.scrollback_limit > 0 {
             // Find the last line with actual content
             let mut last_content_line = self.cursor_y;
             for y in (0..self.cells.len()).rev() {
-                let has_content = self.cells[y].iter().any(|cell| cell.ch != ' ' || cell.extended.is_some());
+                let has_content = self.cells[y].iter().any(|cell| !cell.is_blank());
                 if has_content {
                     last_content_line = y;
                     break;
@@ -919,12 +1004,36 @@ impl ScreenBuffer {
             }
 
             // Trim scrollback buffer if it exceeds the limit
-            if self.scrollback_buffer.len() > self.scrollback_limit {
-                let excess = self.scrollback_buffer.len() - self.scrollback_limit;
-                self.scrollback_buffer.drain(0..excess);
-            }
+            self.trim_scrollback();
         }
 
+        self.erase_screen();
+    }
+
+    /// Clear the screen and the scrollback, leaving nothing to scroll back to
+    pub fn clear_all(&mut self) {
+        self.clear_scrollback();
+        self.erase_screen();
+    }
+
+    /// Drop every scrollback line and return to the live view (ED 3). Absolute rows of the
+    /// screen keep their numbers, as if the lines had been trimmed.
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback_trimmed += self.scrollback_buffer.len();
+        self.scrollback_buffer.clear();
+
+        let first_row = self.scrollback_trimmed;
+        self.command_regions.retain(|region| region.end_row >= first_row);
+
+        self.scroll_offset = 0;
+        self.new_output_while_scrolled = false;
+        self.dirty = true;
+    }
+
+    /// Blank the visible screen without saving it, home the cursor and reset the scrolling region
+    fn erase_screen(&mut self) {
+        self.pending_wrap = false;
+
         // Clear all cells
         for row in &mut self.cells {
             for cell in row {
@@ -943,6 +1052,8 @@ impl ScreenBuffer {
             }
         }
 
+        self.line_attributes.fill(LineAttribute::Normal);
+
         // Move cursor to home position
         self.cursor_x = 0;
         self.cursor_y = 0;
@@ -1097,25 +1208,23 @@ impl ScreenBuffer {
         self.dirty = true;
     }
 
+    /// A blank cell for ECH/ICH/DCH: a space in the current colors, without text attributes
+    fn blank_cell(&self) -> Cell {
+        Cell {
+            fg_color: self.fg_color,
+            bg_color: self.bg_color,
+            ..Cell::default()
+        }
+    }
+
     pub fn erase_chars(&mut self, n: usize) {
         // Erase n characters starting at cursor position (ECH - Erase Character)
         // Characters are replaced with spaces, cursor doesn't move
         if self.cursor_y < self.height {
+            let blank = self.blank_cell();
             let end_x = (self.cursor_x + n).min(self.width);
-            for x in self.cursor_x..end_x {
-                let cell = &mut self.cells[self.cursor_y][x];
-                cell.ch = ' ';
-                cell.extended = None;
-                cell.fg_color = crate::ansi::DEFAULT_FG_COLOR;
-                cell.bg_color = crate::ansi::DEFAULT_BG_COLOR;
-                cell.width = 1;
-                cell.bold = false;
-                cell.italic = false;
-                cell.underline = false;
-                cell.strikethrough = false;
-                cell.blink = false;
-                cell.reverse = false;
-                cell.invisible = false;
+            for cell in &mut self.cells[self.cursor_y][self.cursor_x.min(end_x)..end_x] {
+                *cell = blank.clone();
             }
         }
         self.dirty = true;
@@ -1124,6 +1233,7 @@ impl ScreenBuffer {
     pub fn clear_region(&mut self, top: usize, bottom: usize) {
         // Clear rows from top to bottom (inclusive, 0-based)
         for y in top..=bottom.min(self.height - 1) {
+            self.line_attributes[y] = LineAttribute::Normal;
             for x in 0..self.width {
                 self.cells[y][x] = Cell {
                     ch: ' ',
@@ -1162,6 +1272,7 @@ impl ScreenBuffer {
         }
 
         // Shift existing characters to the right
+        let blank = self.blank_cell();
         let row = &mut self.cells[self.cursor_y];
 
         // Move characters from right to left to avoid overwriting
@@ -1176,20 +1287,7 @@ impl ScreenBuffer {
         // Fill inserted positions with blank characters
         let end = (cursor_x + n).min(self.width);
         for cell in row.iter_mut().take(end).skip(cursor_x) {
-            *cell = Cell {
-                ch: ' ',
-                extended: None,
-                fg_color: crate::ansi::DEFAULT_FG_COLOR,
-                bg_color: crate::ansi::DEFAULT_BG_COLOR,
-                width: 1,
-                bold: false,
-                italic: false,
-                underline: false,
-                strikethrough: false,
-                blink: false,
-                reverse: false,
-                invisible: false,
-            };
+            *cell = blank.clone();
         }
 
         self.dirty = true;
@@ -1212,6 +1310,7 @@ impl ScreenBuffer {
             return;
         }
 
+        let blank = self.blank_cell();
         let row = &mut self.cells[self.cursor_y];
 
         // Shift characters from right side to the left
@@ -1221,20 +1320,7 @@ impl ScreenBuffer {
                 row[x] = row[source_pos].clone();
             } else {
                 // Fill with blank at the end
-                row[x] = Cell {
-                    ch: ' ',
-                    extended: None,
-                    fg_color: crate::ansi::DEFAULT_FG_COLOR,
-                    bg_color: crate::ansi::DEFAULT_BG_COLOR,
-                    width: 1,
-                    bold: false,
-                    italic: false,
-                    underline: false,
-                    strikethrough: false,
-                    blink: false,
-                    reverse: false,
-                    invisible: false,
-                };
+                row[x] = blank.clone();
             }
         }
 
@@ -1252,28 +1338,29 @@ impl ScreenBuffer {
         }
 
         // If scrollback is enabled and we're scrolling the full screen, save to scrollback
+        let mut lines_added = 0;
         if self.scrollback_limit > 0 && scroll_top == 0 && scroll_bottom == self.height - 1 {
             // Save the top n lines to scrollback buffer
             for i in 0..n {
                 if scroll_top + i < self.cells.len() {
                     self.scrollback_buffer.push(self.cells[scroll_top + i].clone());
+                    lines_added += 1;
                 }
             }
 
             // Trim scrollback buffer if it exceeds the limit
-            if self.scrollback_buffer.len() > self.scrollback_limit {
-                let excess = self.scrollback_buffer.len() - self.scrollback_limit;
-                self.scrollback_buffer.drain(0..excess);
-            }
+            self.trim_scrollback();
         }
 
         // Move lines up within the scrolling region
         for y in scroll_top..=(scroll_bottom - n) {
             self.cells[y] = self.cells[y + n].clone();
+            self.line_attributes[y] = self.line_attributes[y + n];
         }
 
         // Clear bottom lines of the scrolling region
         for y in (scroll_bottom - n + 1)..=scroll_bottom {
+            self.line_attributes[y] = LineAttribute::Normal;
             for x in 0..self.width {
                 let cell = &mut self.cells[y][x];
                 cell.ch = ' ';
@@ -1291,8 +1378,12 @@ impl ScreenBuffer {
             }
         }
 
-        // When terminal scrolls (app writes), reset to live view
-        self.scroll_offset = 0;
+        if self.scroll_offset > 0 {
+            self.new_output_while_scrolled = true;
+            // Keep a scrolled-back view anchored to the same content as lines move into
+            // scrollback. Only lines trimmed off the front of the scrollback can move it.
+            self.scroll_offset = (self.scroll_offset + lines_added).min(self.scrollback_buffer.len());
+        }
         self.dirty = true;
     }
 
@@ -1309,10 +1400,12 @@ impl ScreenBuffer {
         // Move lines down within the scrolling region (iterate in reverse to avoid overwriting)
         for y in (scroll_top + n..=scroll_bottom).rev() {
             self.cells[y] = self.cells[y - n].clone();
+            self.line_attributes[y] = self.line_attributes[y - n];
         }
 
         // Clear top lines of the scrolling region
         for y in scroll_top..(scroll_top + n) {
+            self.line_attributes[y] = LineAttribute::Normal;
             for x in 0..self.width {
                 let cell = &mut self.cells[y][x];
                 cell.ch = ' ';
@@ -1351,12 +1444,15 @@ impl ScreenBuffer {
         let n = n.min(scroll_bottom - self.cursor_y + 1);
 
         // Move lines down from cursor position to bottom of scrolling region
-        for y in (self.cursor_y..=(scroll_bottom - n)).rev() {
-            self.cells[y + n] = self.cells[y].clone();
+        // (written relative to the destination row so nothing underflows when n fills the region)
+        for y in ((self.cursor_y + n)..=scroll_bottom).rev() {
+            self.cells[y] = self.cells[y - n].clone();
+            self.line_attributes[y] = self.line_attributes[y - n];
         }
 
         // Clear the newly inserted lines at cursor position
         for y in self.cursor_y..(self.cursor_y + n) {
+            self.line_attributes[y] = LineAttribute::Normal;
             for x in 0..self.width {
                 self.cells[y][x] = Cell {
                     ch: ' ',
@@ -1396,12 +1492,15 @@ impl ScreenBuffer {
         let n = n.min(scroll_bottom - self.cursor_y + 1);
 
         // Move lines up from below cursor within scrolling region
-        for y in self.cursor_y..=(scroll_bottom - n) {
+        // (scroll_bottom + 1 - n never underflows since n is at most the rows left in the region)
+        for y in self.cursor_y..(scroll_bottom + 1 - n) {
             self.cells[y] = self.cells[y + n].clone();
+            self.line_attributes[y] = self.line_attributes[y + n];
         }
 
         // Clear the lines at the bottom of scrolling region
-        for y in (scroll_bottom - n + 1)..=scroll_bottom {
+        for y in (scroll_bottom + 1 - n)..=scroll_bottom {
+            self.line_attributes[y] = LineAttribute::Normal;
             for x in 0..self.width {
                 self.cells[y][x] = Cell {
                     ch: ' ',
@@ -1430,7 +1529,8 @@ impl ScreenBuffer {
         } else {
             let top = top.min(self.height - 1);
             let bottom = bottom.min(self.height - 1);
-            if top <= bottom {
+            // A region must span at least two lines, otherwise DECSTBM is ignored
+            if top < bottom {
                 self.scroll_region = Some((top, bottom));
             }
         }
@@ -1444,10 +1544,23 @@ impl ScreenBuffer {
         self.origin_mode = enabled;
     }
 
+    /// Cursor position for CPR as 1-based (row, col); in origin mode the row is relative to the region top
+    pub fn cursor_report_position(&self) -> (usize, usize) {
+        let top = match (self.origin_mode, self.scroll_region) {
+            (true, Some((top, _))) => top,
+            _ => 0,
+        };
+        (self.cursor_y.saturating_sub(top) + 1, self.cursor_x + 1)
+    }
+
     pub fn set_auto_wrap_mode(&mut self, enabled: bool) {
         self.auto_wrap_mode = enabled;
     }
 
+    pub fn set_reverse_wraparound(&mut self, enabled: bool) {
+        self.reverse_wraparound = enabled;
+    }
+
     pub fn set_insert_mode(&mut self, enabled: bool) {
         self.insert_mode = enabled;
     }
@@ -1480,6 +1593,15 @@ impl ScreenBuffer {
         self.scrollback_limit
     }
 
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Set the spacing of the default tab stops, at least one column
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width.max(1);
+    }
+
     pub fn get_scrollback_buffer(&self) -> &Vec<Vec<Cell>> {
         &self.scrollback_buffer
     }
@@ -1489,46 +1611,48 @@ impl ScreenBuffer {
     }
 
     /// Restore output lines to scrollback buffer (for loading from saved state)
+    ///
+    /// Plain lines carry no styling, so they are restored with default colors and attributes.
     pub fn restore_to_scrollback(&mut self, lines: Vec<String>) {
-        for line in lines {
-            let mut row = Vec::with_capacity(self.width);
-
-            // Convert string to cells
-            for ch in line.chars() {
-                let cell = Cell {
-                    ch,
-                    extended: None,
-                    fg_color: DEFAULT_FG_COLOR,
-                    bg_color: DEFAULT_BG_COLOR,
-                    width: 1,
-                    bold: false,
-                    italic: false,
-                    underline: false,
-                    strikethrough: false,
-                    blink: false,
-                    reverse: false,
-                    invisible: false,
-                };
-                row.push(cell);
+        let rows = lines
+            .into_iter()
+            .map(|line| line.chars().map(|ch| Cell { ch, ..Cell::default() }).collect())
+            .collect();
+        self.restore_styled_to_scrollback(rows);
+    }
+
+    /// Restore styled rows to scrollback buffer (for loading from saved state)
+    ///
+    /// Rows saved from a wider terminal wrap onto as many rows as they need, so no output is cut off.
+    pub fn restore_styled_to_scrollback(&mut self, rows: Vec<Vec<Cell>>) {
+        for mut row in rows {
+            while row.len() > self.width && row.last().is_some_and(Cell::is_trailing_trimmable) {
+                row.pop();
             }
 
-            // Pad with empty cells to match width
-            while row.len() < self.width {
-                row.push(Cell::default());
-            }
+            let mut rest = row.as_slice();
+            loop {
+                // A wide character that would straddle the edge moves to the next row whole
+                let mut take = rest.len().min(self.width);
+                if take < rest.len() && take > 1 && rest[take].width == 0 {
+                    take -= 1;
+                }
+                let mut wrapped = rest[..take].to_vec();
+                rest = &rest[take..];
 
-            // Truncate if too long
-            row.truncate(self.width);
+                // Pad with empty cells to match width
+                wrapped.resize(self.width, Cell::default());
+                self.scrollback_buffer.push(wrapped);
 
-            // Add to scrollback buffer
-            self.scrollback_buffer.push(row);
+                if rest.is_empty() {
+                    break;
+                }
+            }
         }
 
         // Enforce scrollback limit
         if self.scrollback_limit > 0 {
-            while self.scrollback_buffer.len() > self.scrollback_limit {
-                self.scrollback_buffer.remove(0);
-            }
+            self.trim_scrollback();
         }
 
         self.dirty = true;
@@ -1542,6 +1666,63 @@ impl ScreenBuffer {
         self.dirty = false;
     }
 
+    /// Record a BEL from the application
+    pub fn ring_bell(&mut self) {
+        self.bell_rung = true;
+    }
+
+    /// Whether a BEL arrived since the last call
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell_rung)
+    }
+
+    /// Record that the application wrote output. Only the PTY reader calls this, so redraws
+    /// for selection, scrolling or resizing don't count as activity.
+    pub fn mark_output(&mut self) {
+        self.output_arrived = true;
+    }
+
+    /// Whether output arrived since the last call
+    pub fn take_output(&mut self) -> bool {
+        std::mem::take(&mut self.output_arrived)
+    }
+
+    /// Absolute row of the cursor (see `view_row_to_absolute`)
+    fn cursor_absolute_row(&self) -> usize {
+        self.scrollback_trimmed + self.scrollback_buffer.len() + self.cursor_y
+    }
+
+    /// A prompt starts at the cursor (OSC 133;A), opening the next command region
+    pub fn mark_prompt_start(&mut self) {
+        self.command_start_row = Some(self.cursor_absolute_row());
+    }
+
+    /// The running command finished (OSC 133;D or the shell integration's exit report).
+    /// Its region ends on the line before the cursor, where the next prompt is about to start.
+    pub fn finish_command(&mut self, exit_code: Option<i32>) {
+        let row = self.cursor_absolute_row();
+        let end_row = if self.cursor_x == 0 { row.checked_sub(1) } else { Some(row) };
+
+        if let (Some(start_row), Some(end_row), Some(exit_code)) = (self.command_start_row, end_row, exit_code) {
+            // A cursor above the prompt means the screen was cleared, there is nothing to mark
+            if end_row >= start_row {
+                self.command_regions.retain(|region| region.end_row < start_row);
+                self.command_regions.push(CommandRegion { start_row, end_row, exit_code });
+                self.dirty = true;
+            }
+        }
+        self.command_start_row = Some(row);
+    }
+
+    /// Exit code of the finished command covering an absolute row
+    pub fn command_exit_code_at(&self, row: usize) -> Option<i32> {
+        let index = self.command_regions.partition_point(|region| region.end_row < row);
+        self.command_regions
+            .get(index)
+            .filter(|region| region.start_row <= row)
+            .map(|region| region.exit_code)
+    }
+
     // Scrollback control methods
 
     /// Check if we're viewing live content (not scrolled back)
@@ -1549,8 +1730,17 @@ impl ScreenBuffer {
         self.scroll_offset == 0
     }
 
+    /// Whether the live view has new output the user hasn't seen while scrolled back
+    pub fn has_new_output_below(&self) -> bool {
+        self.new_output_while_scrolled && !self.is_at_bottom()
+    }
+
     /// Scroll the view up (backward in time) by n lines
     pub fn scroll_view_up(&mut self, n: usize) {
+        if self.scroll_offset == 0 {
+            // Scrolling begins; only output from now on counts as new
+            self.new_output_while_scrolled = false;
+        }
         // Limit scroll to show scrollback but never hide ALL current screen content
         // Allow scrolling back through the entire scrollback buffer
         let max_scroll = self.scrollback_buffer.len();
@@ -1561,12 +1751,21 @@ impl ScreenBuffer {
     /// Scroll the view down (forward in time) by n lines
     pub fn scroll_view_down(&mut self, n: usize) {
         self.scroll_offset = self.scroll_offset.saturating_sub(n);
+        if self.scroll_offset == 0 {
+            self.new_output_while_scrolled = false;
+        }
         self.dirty = true;
     }
 
+    /// Jump to the oldest line of scrollback
+    pub fn scroll_view_to_top(&mut self) {
+        self.scroll_view_up(self.scrollback_buffer.len());
+    }
+
     /// Jump to the bottom (live view)
     pub fn reset_view_offset(&mut self) {
         self.scroll_offset = 0;
+        self.new_output_while_scrolled = false;
         self.dirty = true;
     }
 
@@ -1580,18 +1779,18 @@ impl ScreenBuffer {
         }
     }
 
+    /// The default tab stops made explicit, so single stops can be added or removed
+    fn default_tab_stops(&self) -> std::collections::HashSet<usize> {
+        (self.tab_width..self.width).step_by(self.tab_width).collect()
+    }
+
     /// Clear tab stops (TBC - CSI Ps g)
     pub fn clear_tab_stop(&mut self, mode: usize) {
         match mode {
             0 => {
                 // Clear tab stop at current column
                 if self.tab_stops.is_none() {
-                    // Initialize with default tab stops (every 8 columns)
-                    let mut stops = std::collections::HashSet::new();
-                    for i in (8..self.width).step_by(8) {
-                        stops.insert(i);
-                    }
-                    self.tab_stops = Some(stops);
+                    self.tab_stops = Some(self.default_tab_stops());
                 }
                 if let Some(ref mut stops) = self.tab_stops {
                     stops.remove(&self.cursor_x);
@@ -1610,36 +1809,61 @@ impl ScreenBuffer {
     /// Set a tab stop at the current column (HTS - ESC H)
     pub fn set_tab_stop(&mut self) {
         if self.tab_stops.is_none() {
-            // Initialize with default tab stops (every 8 columns)
-            let mut stops = std::collections::HashSet::new();
-            for i in (8..self.width).step_by(8) {
-                stops.insert(i);
-            }
-            self.tab_stops = Some(stops);
+            self.tab_stops = Some(self.default_tab_stops());
         }
         if let Some(ref mut stops) = self.tab_stops {
             stops.insert(self.cursor_x);
         }
     }
 
-    /// Perform a soft terminal reset (DECSTR)
-    pub fn soft_reset(&mut self) {
-        // Reset text attributes
-        self.bold = false;
-        self.italic = false;
-        self.underline = false;
-        self.strikethrough = false;
-        self.blink = false;
-        self.reverse = false;
-        self.invisible = false;
-
-        // Reset colors
+    /// Attributes new characters are written with
+    pub fn text_attributes(&self) -> TextAttributes {
+        TextAttributes {
+            bold: self.bold,
+            italic: self.italic,
+            underline: self.underline,
+            strikethrough: self.strikethrough,
+            blink: self.blink,
+            reverse: self.reverse,
+            invisible: self.invisible,
+        }
+    }
+
+    pub fn set_text_attributes(&mut self, attributes: TextAttributes) {
+        let TextAttributes {
+            bold,
+            italic,
+            underline,
+            strikethrough,
+            blink,
+            reverse,
+            invisible,
+        } = attributes;
+        self.bold = bold;
+        self.italic = italic;
+        self.underline = underline;
+        self.strikethrough = strikethrough;
+        self.blink = blink;
+        self.reverse = reverse;
+        self.invisible = invisible;
+    }
+
+    /// SGR 0 - clear every text attribute and return to the default colors
+    pub fn reset_graphic_rendition(&mut self) {
+        self.set_text_attributes(TextAttributes::default());
         self.fg_color = DEFAULT_FG_COLOR;
         self.bg_color = DEFAULT_BG_COLOR;
+    }
+
+    /// Perform a soft terminal reset (DECSTR)
+    pub fn soft_reset(&mut self) {
+        // Reset text attributes and colors
+        self.reset_graphic_rendition();
 
         // Reset modes
         self.origin_mode = false;
         self.auto_wrap_mode = true;
+        self.reverse_wraparound = false;
         self.reverse_video_mode = false;
         self.insert_mode = false;
         self.automatic_newline = false;
@@ -1740,8 +1964,10 @@ impl ScreenBuffer {
         if y < lines_from_scrollback {
             // This row should come from the scrollback buffer
             let scrollback_y = self.scrollback_buffer.len().saturating_sub(self.scroll_offset) + y;
-            if scrollback_y < self.scrollback_buffer.len() && x < self.scrollback_buffer[scrollback_y].len() {
-                return Some(&self.scrollback_buffer[scrollback_y][x]);
+            if let Some(row) = self.scrollback_buffer.get(scrollback_y) {
+                // Rows saved before the screen got wider end early, the rest of them is blank.
+                // Rows saved while it was wider are cut at the screen edge.
+                return row.get(x).or((x < self.width).then_some(&BLANK_CELL));
             }
         } else {
             // This row should come from the current screen buffer
@@ -1753,16 +1979,545 @@ impl ScreenBuffer {
 
         None
     }
+
+    /// Drop the oldest scrollback lines beyond the limit
+    fn trim_scrollback(&mut self) {
+        if self.scrollback_buffer.len() > self.scrollback_limit {
+            let excess = self.scrollback_buffer.len() - self.scrollback_limit;
+            self.scrollback_buffer.drain(0..excess);
+            self.scrollback_trimmed += excess;
+
+            let first_row = self.scrollback_trimmed;
+            self.command_regions.retain(|region| region.end_row >= first_row);
+        }
+    }
+
+    /// Absolute row shown at view row `y`. Absolute rows number the scrollback followed by the
+    /// screen, counting from the first line ever written, so a row keeps its number while the
+    /// view scrolls, output pushes it into scrollback, or older lines are trimmed.
+    pub fn view_row_to_absolute(&self, y: usize) -> usize {
+        let scrollback_len = self.scrollback_buffer.len();
+        // An offset past the scrollback shows the live screen, like get_cell_with_scrollback
+        let offset = if self.scroll_offset > scrollback_len { 0 } else { self.scroll_offset };
+        self.scrollback_trimmed + scrollback_len - offset + y
+    }
+
+    /// Cell at an absolute row (see `view_row_to_absolute`), None once the row was trimmed
+    pub fn get_cell_absolute(&self, x: usize, row: usize) -> Option<&Cell> {
+        let index = row.checked_sub(self.scrollback_trimmed)?;
+        match self.scrollback_buffer.get(index) {
+            Some(line) => line.get(x),
+            None => self.get_cell(x, index - self.scrollback_buffer.len()),
+        }
+    }
+
+    /// Text from (start_col, start_row) to (end_col, end_row) inclusive, in absolute rows, with
+    /// trailing blanks of each line trimmed
+    pub fn text_in_range(&self, start_col: usize, start_row: usize, end_col: usize, end_row: usize) -> String {
+        let last_row = self.scrollback_trimmed + self.scrollback_buffer.len() + self.height - 1;
+        let mut text = String::new();
+
+        for row in start_row..=end_row.min(last_row) {
+            let line_start = if row == start_row { start_col } else { 0 };
+            let line_end = if row == end_row { end_col.min(self.width - 1) } else { self.width - 1 };
+
+            let mut line = String::new();
+            // Trailing blank cells are dropped, but not spaces with a background color
+            let mut content_len = 0;
+            for col in line_start..=line_end {
+                if let Some(cell) = self.get_cell_absolute(col, row) {
+                    if cell.width == 0 || cell.ch == '\0' {
+                        continue;
+                    }
+
+                    if let Some(ref extended) = cell.extended {
+                        line.push_str(extended);
+                    } else {
+                        line.push(cell.ch);
+                    }
+                    if !cell.is_blank() {
+                        content_len = line.len();
+                    }
+                }
+            }
+
+            text.push_str(&line[..content_len]);
+
+            if row < end_row.min(last_row) {
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    /// Set the line size of the cursor row (DECDWL/DECDHL/DECSWL)
+    pub fn set_line_attribute(&mut self, attribute: LineAttribute) {
+        if self.cursor_y < self.height {
+            self.line_attributes[self.cursor_y] = attribute;
+            self.dirty = true;
+        }
+    }
+
+    /// Line size of a row relative to the current view; rows shown from scrollback are normal size
+    pub fn line_attribute(&self, y: usize) -> LineAttribute {
+        let lines_from_scrollback = if self.scroll_offset <= self.scrollback_buffer.len() {
+            self.scroll_offset.min(self.height)
+        } else {
+            0
+        };
+        if y < lines_from_scrollback {
+            return LineAttribute::Normal;
+        }
+        self.line_attributes.get(y - lines_from_scrollback).copied().unwrap_or_default()
+    }
+
+    /// Get the visible rows as text (accounting for scroll offset), with trailing blanks trimmed
+    pub fn get_visible_text(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                let mut line = String::new();
+                for x in 0..self.width {
+                    match self.get_cell_with_scrollback(x, y) {
+                        // Skip continuation cells of double-width characters
+                        Some(cell) if cell.width == 0 => {}
+                        Some(cell) => match cell.extended {
+                            Some(ref extended) => line.push_str(extended),
+                            None => line.push(cell.ch),
+                        },
+                        None => line.push(' '),
+                    }
+                }
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_restore_styled_scrollback_keeps_colors() {
+        let red = Color::RGB(205, 49, 49);
+        let mut source = ScreenBuffer::new_with_scrollback(10, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        source.fg_color = red;
+        source.bold = true;
+        source.put_grapheme("E");
+        source.fg_color = DEFAULT_FG_COLOR;
+        source.bold = false;
+        source.put_grapheme("r");
+        source.scroll_up(1);
+
+        // A second row, longer than the narrow buffer below, ends in green
+        let green = Color::RGB(13, 188, 121);
+        source.cursor_x = 0;
+        for grapheme in ["w", "r", "a", "p"] {
+            source.put_grapheme(grapheme);
+        }
+        source.fg_color = green;
+        for grapheme in ["p", "e", "d"] {
+            source.put_grapheme(grapheme);
+        }
+        source.fg_color = DEFAULT_FG_COLOR;
+        source.scroll_up(1);
+        let rows = source.get_scrollback_buffer().clone();
+
+        let row_text = |row: &[Cell]| row.iter().map(|cell| cell.ch).collect::<String>();
+        let assert_first_row = |row: &[Cell], width: usize| {
+            assert_eq!(row.len(), width);
+            assert_eq!(row[0].ch, 'E');
+            assert_eq!(row[0].fg_color, red);
+            assert!(row[0].bold);
+            assert_eq!(row[1].ch, 'r');
+            assert_eq!(row[1].fg_color, DEFAULT_FG_COLOR);
+            assert!(!row[1].bold);
+        };
+
+        // Restoring into a wider buffer pads rows
+        let mut restored = ScreenBuffer::new_with_scrollback(20, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        restored.restore_styled_to_scrollback(rows.clone());
+        let scrollback = restored.get_scrollback_buffer();
+        assert_eq!(scrollback.len(), 2);
+        assert_first_row(&scrollback[0], 20);
+        assert_eq!(row_text(&scrollback[1]).trim_end(), "wrapped");
+        assert!(scrollback[1][4..7].iter().all(|cell| cell.fg_color == green));
+
+        // A narrower one rewraps the rows that don't fit, keeping their colors
+        let mut restored = ScreenBuffer::new_with_scrollback(4, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        restored.restore_styled_to_scrollback(rows);
+        let scrollback = restored.get_scrollback_buffer();
+        assert_eq!(scrollback.len(), 3);
+        assert_first_row(&scrollback[0], 4);
+        assert_eq!(row_text(&scrollback[1]), "wrap");
+        assert!(scrollback[1].iter().all(|cell| cell.fg_color == DEFAULT_FG_COLOR));
+        assert_eq!(row_text(&scrollback[2]), "ped ");
+        assert!(scrollback[2][..3].iter().all(|cell| cell.fg_color == green));
+    }
+
+    #[test]
+    fn test_get_visible_text() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 3, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for grapheme in ["h", "i", "世"] {
+            buffer.put_grapheme(grapheme);
+        }
+        buffer.newline();
+        buffer.cursor_x = 0;
+        buffer.put_grapheme("x");
+
+        assert_eq!(buffer.get_visible_text(), vec!["hi世".to_string(), "x".to_string(), String::new()]);
+    }
+
+    #[test]
+    fn test_powerline_and_nerd_symbols_are_special() {
+        // Powerline separators and a Nerd Font icon
+        assert!(is_special_symbol('\u{E0B0}'));
+        assert!(is_special_symbol('\u{E0B2}'));
+        assert!(is_special_symbol('\u{F0001}'));
+        assert!(is_private_use_symbol('\u{E0A0}'));
+
+        // Regular text and box drawing must not be scaled
+        assert!(!is_special_symbol('A'));
+        assert!(!is_special_symbol('─'));
+        assert!(!is_private_use_symbol('→'));
+    }
+
+    #[test]
+    fn test_origin_mode_clamps_cursor_to_region() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(20, 24, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        buffer.set_scroll_region(5, 10);
+        buffer.set_origin_mode(true);
+
+        // Home goes to the region top
+        buffer.move_cursor_to(0, 0);
+        assert_eq!((buffer.cursor_x, buffer.cursor_y), (0, 5));
+        assert_eq!(buffer.cursor_report_position(), (1, 1));
+
+        // Rows are offset by the region top and clamped to its bottom
+        buffer.move_cursor_to(3, 2);
+        assert_eq!(buffer.cursor_y, 7);
+        buffer.move_cursor_to(3, 50);
+        assert_eq!(buffer.cursor_y, 10);
+        assert_eq!(buffer.cursor_report_position(), (6, 4));
+
+        // Without origin mode positions are absolute again
+        buffer.set_origin_mode(false);
+        buffer.move_cursor_to(0, 2);
+        assert_eq!(buffer.cursor_y, 2);
+        assert_eq!(buffer.cursor_report_position(), (3, 1));
+    }
+
+    fn row_text(buffer: &ScreenBuffer, y: usize) -> String {
+        (0..buffer.width())
+            .map(|x| buffer.get_cell(x, y).unwrap().ch)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Buffer with the row number written on every row and rows 2-5 as the scroll region
+    fn numbered_region_buffer() -> ScreenBuffer {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 8, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for y in 0..8 {
+            buffer.move_cursor_to(0, y);
+            buffer.put_grapheme(&y.to_string());
+        }
+        buffer.set_scroll_region(2, 5);
+        buffer
+    }
+
+    fn all_rows(buffer: &ScreenBuffer) -> Vec<String> {
+        (0..buffer.height()).map(|y| row_text(buffer, y)).collect()
+    }
+
+    #[test]
+    fn test_insert_lines_at_region_bounds() {
+        // Top of the region, n equal to and larger than the region height
+        for n in [4, 100] {
+            let mut buffer = numbered_region_buffer();
+            buffer.move_cursor_to(0, 2);
+            buffer.insert_lines(n);
+            assert_eq!(all_rows(&buffer), vec!["0", "1", "", "", "", "", "6", "7"]);
+        }
+
+        // Bottom row of the region
+        let mut buffer = numbered_region_buffer();
+        buffer.move_cursor_to(0, 5);
+        buffer.insert_lines(3);
+        assert_eq!(all_rows(&buffer), vec!["0", "1", "2", "3", "4", "", "6", "7"]);
+
+        // Partial insert inside the region
+        let mut buffer = numbered_region_buffer();
+        buffer.move_cursor_to(0, 3);
+        buffer.insert_lines(1);
+        assert_eq!(all_rows(&buffer), vec!["0", "1", "2", "", "3", "4", "6", "7"]);
+
+        // Outside the region nothing changes
+        let mut buffer = numbered_region_buffer();
+        buffer.move_cursor_to(0, 7);
+        buffer.insert_lines(2);
+        assert_eq!(all_rows(&buffer), vec!["0", "1", "2", "3", "4", "5", "6", "7"]);
+
+        // Whole screen from the first row used to underflow
+        let mut buffer = numbered_region_buffer();
+        buffer.reset_scroll_region();
+        buffer.move_cursor_to(0, 0);
+        buffer.insert_lines(8);
+        assert!(all_rows(&buffer).iter().all(|row| row.is_empty()));
+    }
+
+    #[test]
+    fn test_delete_lines_at_region_bounds() {
+        for n in [4, 100] {
+            let mut buffer = numbered_region_buffer();
+            buffer.move_cursor_to(0, 2);
+            buffer.delete_lines(n);
+            assert_eq!(all_rows(&buffer), vec!["0", "1", "", "", "", "", "6", "7"]);
+        }
+
+        let mut buffer = numbered_region_buffer();
+        buffer.move_cursor_to(0, 5);
+        buffer.delete_lines(3);
+        assert_eq!(all_rows(&buffer), vec!["0", "1", "2", "3", "4", "", "6", "7"]);
+
+        let mut buffer = numbered_region_buffer();
+        buffer.move_cursor_to(0, 3);
+        buffer.delete_lines(1);
+        assert_eq!(all_rows(&buffer), vec!["0", "1", "2", "4", "5", "", "6", "7"]);
+
+        let mut buffer = numbered_region_buffer();
+        buffer.move_cursor_to(0, 0);
+        buffer.delete_lines(2);
+        assert_eq!(all_rows(&buffer), vec!["0", "1", "2", "3", "4", "5", "6", "7"]);
+
+        let mut buffer = numbered_region_buffer();
+        buffer.reset_scroll_region();
+        buffer.move_cursor_to(0, 0);
+        buffer.delete_lines(8);
+        assert!(all_rows(&buffer).iter().all(|row| row.is_empty()));
+    }
+
+    #[test]
+    fn test_newline_scrolls_only_the_region() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 8, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for y in 0..8 {
+            buffer.move_cursor_to(0, y);
+            buffer.put_grapheme(&y.to_string());
+        }
+        buffer.set_scroll_region(2, 4);
+
+        // LF at the bottom margin scrolls rows 2-4 only
+        buffer.move_cursor_to(0, 4);
+        for _ in 0..2 {
+            buffer.newline();
+        }
+        assert_eq!(buffer.cursor_y, 4);
+        let rows: Vec<String> = (0..8).map(|y| row_text(&buffer, y)).collect();
+        assert_eq!(rows, vec!["0", "1", "4", "", "", "5", "6", "7"]);
+        assert!(buffer.get_scrollback_buffer().is_empty(), "region scrolls must not feed scrollback");
+
+        // LF below the region moves down to the last row without scrolling anything
+        buffer.move_cursor_to(0, 5);
+        for _ in 0..5 {
+            buffer.newline();
+        }
+        assert_eq!(buffer.cursor_y, 7);
+        let rows: Vec<String> = (0..8).map(|y| row_text(&buffer, y)).collect();
+        assert_eq!(rows, vec!["0", "1", "4", "", "", "5", "6", "7"]);
+    }
+
+    #[test]
+    fn test_insert_mode_shifts_right() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(6, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for grapheme in ["a", "b", "c"] {
+            buffer.put_grapheme(grapheme);
+        }
+        buffer.set_insert_mode(true);
+        buffer.move_cursor_to(1, 0);
+        buffer.put_grapheme("X");
+        assert_eq!(row_text(&buffer, 0), "aXbc");
+        assert_eq!(buffer.cursor_x, 2);
+
+        // A wide insert shifts by two cells
+        buffer.put_grapheme("世");
+        assert_eq!(buffer.get_visible_text()[0], "aX世bc");
+    }
+
+    #[test]
+    fn test_combining_mark_joins_previous_cell() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(6, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        // The combining acute arrives as its own grapheme, as when a read ends after the "e"
+        buffer.put_grapheme("e");
+        buffer.put_grapheme("\u{0301}");
+        buffer.put_grapheme("x");
+
+        let cell = buffer.get_cell(0, 0).unwrap();
+        assert_eq!(cell.extended.as_deref(), Some("e\u{0301}"));
+        assert_eq!(cell.width, 1);
+        assert_eq!(buffer.get_cell(1, 0).unwrap().ch, 'x');
+        assert_eq!(buffer.cursor_x, 2);
+        assert_eq!(buffer.get_visible_text()[0], "e\u{0301}x");
+
+        // Also at the right margin, where the cursor has not moved past the base character
+        buffer.move_cursor_to(5, 1);
+        buffer.put_grapheme("a");
+        buffer.put_grapheme("\u{0308}");
+        assert_eq!(buffer.get_cell(5, 1).unwrap().extended.as_deref(), Some("a\u{0308}"));
+        assert_eq!((buffer.cursor_x, buffer.cursor_y), (5, 1));
+    }
+
+    #[test]
+    fn test_clear_all_drops_scrollback() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for line in ["one", "two", "three", "four"] {
+            for ch in line.chars() {
+                buffer.put_grapheme(&ch.to_string());
+            }
+            buffer.carriage_return();
+            buffer.newline();
+        }
+        assert!(!buffer.get_scrollback_buffer().is_empty());
+        buffer.scroll_view_up(1);
+
+        // clear_screen keeps the screen reachable in scrollback, clear_all does not
+        buffer.clear_all();
+        assert!(buffer.get_scrollback_buffer().is_empty());
+        assert!(buffer.is_at_bottom());
+        assert_eq!((buffer.cursor_x, buffer.cursor_y), (0, 0));
+        for y in 0..2 {
+            assert!((0..10).all(|x| buffer.get_cell(x, y).unwrap().is_blank()));
+        }
+    }
+
+    #[test]
+    fn test_flag_emoji_is_one_wide_cell() {
+        let flag = "\u{1F1FA}\u{1F1F8}";
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        buffer.put_grapheme(flag);
+
+        let cell = buffer.get_cell(0, 0).unwrap();
+        assert_eq!(cell.width, 2);
+        assert_eq!(cell.extended.as_deref(), Some(flag));
+        assert_eq!(buffer.get_cell(1, 0).unwrap().width, 0);
+        assert_eq!(buffer.cursor_x, 2);
+
+        // A flag that does not fit in the last column moves to the next line whole
+        buffer.cursor_x = 9;
+        buffer.put_grapheme(flag);
+        assert_eq!(buffer.get_cell(9, 0).unwrap().extended, None);
+        assert_eq!(buffer.get_cell(0, 1).unwrap().extended.as_deref(), Some(flag));
+        assert_eq!(buffer.get_cell(1, 1).unwrap().width, 0);
+    }
+
+    #[test]
+    fn test_colored_trailing_spaces_are_kept() {
+        let plain = Cell::default();
+        let colored = Cell {
+            bg_color: Color::RGB(0, 0, 128),
+            ..Cell::default()
+        };
+        let underlined = Cell {
+            underline: true,
+            ..Cell::default()
+        };
+        assert!(plain.is_blank() && plain.is_trailing_trimmable());
+        assert!(!colored.is_blank() && !colored.is_trailing_trimmable());
+        assert!(underlined.is_blank() && !underlined.is_trailing_trimmable());
+
+        // "ab" followed by two spaces on a colored background, then plain spaces
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        buffer.put_grapheme("a");
+        buffer.put_grapheme("b");
+        buffer.bg_color = Color::RGB(0, 0, 128);
+        buffer.put_grapheme(" ");
+        buffer.put_grapheme(" ");
+        buffer.bg_color = DEFAULT_BG_COLOR;
+        buffer.newline();
+        buffer.carriage_return();
+        buffer.put_grapheme("c");
+        buffer.put_grapheme(" ");
+
+        let top = buffer.view_row_to_absolute(0);
+        assert_eq!(buffer.text_in_range(0, top, 9, top + 1), "ab  \nc");
+    }
+
+    #[test]
+    fn test_erased_cells_drop_text_attributes() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(6, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        buffer.bold = true;
+        buffer.underline = true;
+        buffer.put_grapheme("a");
+        buffer.put_grapheme("b");
+        buffer.put_grapheme("c");
+        buffer.bold = false;
+        buffer.underline = false;
+        buffer.bg_color = Color::RGB(0, 0, 128);
+        buffer.move_cursor_to(0, 0);
+
+        let is_plain_blank = |cell: &Cell| cell.ch == ' ' && !cell.bold && !cell.underline && cell.bg_color == Color::RGB(0, 0, 128);
+
+        buffer.erase_chars(1);
+        assert!(is_plain_blank(buffer.get_cell(0, 0).unwrap()));
+        assert!(buffer.get_cell(1, 0).unwrap().bold, "cells past the erased ones keep their style");
+
+        buffer.insert_chars(1);
+        assert!(is_plain_blank(buffer.get_cell(0, 0).unwrap()));
+
+        buffer.delete_chars(2);
+        assert_eq!(buffer.get_cell(0, 0).unwrap().ch, 'b');
+        assert!(is_plain_blank(buffer.get_cell(5, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_insert_mode_drops_wide_char_at_right_margin() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(6, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for grapheme in ["a", "b", "c", "d", "世"] {
+            buffer.put_grapheme(grapheme);
+        }
+        buffer.set_insert_mode(true);
+        buffer.move_cursor_to(0, 0);
+        buffer.put_grapheme("X");
+
+        // The wide character would straddle the margin, so it is dropped rather than left half-drawn
+        assert_eq!(buffer.get_visible_text()[0], "Xabcd");
+        assert_eq!(buffer.get_cell(5, 0).unwrap().width, 1);
+    }
+
+    #[test]
+    fn test_line_attributes_follow_rows() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        buffer.move_cursor_to(0, 1);
+        buffer.set_line_attribute(LineAttribute::DoubleWidth);
+        assert_eq!(buffer.line_attribute(1), LineAttribute::DoubleWidth);
+        assert_eq!(buffer.line_attribute(0), LineAttribute::Normal);
+
+        // Scrolling moves the attribute with its row
+        buffer.scroll_up(1);
+        assert_eq!(buffer.line_attribute(0), LineAttribute::DoubleWidth);
+        assert_eq!(buffer.line_attribute(1), LineAttribute::Normal);
+
+        buffer.clear_screen();
+        assert_eq!(buffer.line_attribute(0), LineAttribute::Normal);
+    }
+
+    #[test]
+    fn test_single_line_scroll_region_is_ignored() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(20, 24, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        buffer.set_scroll_region(4, 4);
+        assert_eq!(buffer.get_scroll_region(), None);
+
+        // Full-screen region disables the region
+        buffer.set_scroll_region(2, 8);
+        assert_eq!(buffer.get_scroll_region(), Some((2, 8)));
+        buffer.set_scroll_region(0, 23);
+        assert_eq!(buffer.get_scroll_region(), None);
+    }
+
     #[test]
     fn test_resize_minimum_size_enforcement() {
         // Test that resize enforces minimum size of 2x2
-        let mut buffer = ScreenBuffer::new_with_scrollback(10, 10, 1000, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 10, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Try to resize to 0x0 - should be clamped to 2x2
         buffer.resize(0, 0);
@@ -1781,7 +2536,7 @@ mod tests {
 
         #[test]
         fn test_forward_tab() {
-            let mut sb = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default());
+            let mut sb = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
             // Test moving forward 1 tab stop from column 0
             sb.cursor_x = 0;
@@ -1811,7 +2566,7 @@ mod tests {
 
         #[test]
         fn test_back_tab() {
-            let mut sb = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default());
+            let mut sb = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
             // Test moving back 1 tab stop from column 16
             sb.cursor_x = 16;
@@ -1841,7 +2596,7 @@ mod tests {
 
         #[test]
         fn test_forward_back_tab_combination() {
-            let mut sb = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default());
+            let mut sb = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
             // Start at 0, go forward 3 tabs, then back 1 tab
             sb.cursor_x = 0;
@@ -1856,12 +2611,63 @@ mod tests {
             sb.back_tab(2);
             assert_eq!(sb.cursor_x, 8);
         }
+
+        #[test]
+        fn test_tab_width_of_four() {
+            let mut sb = ScreenBuffer::new_with_scrollback(20, 2, 100, CursorStyle::default(), 4);
+
+            sb.tab();
+            assert_eq!(sb.cursor_x, 4);
+            sb.cursor_x = 5;
+            sb.tab();
+            assert_eq!(sb.cursor_x, 8);
+            sb.forward_tab(2);
+            assert_eq!(sb.cursor_x, 16);
+            sb.forward_tab(5);
+            assert_eq!(sb.cursor_x, 19, "Should stop at the right edge");
+            sb.cursor_x = 10;
+            sb.back_tab(1);
+            assert_eq!(sb.cursor_x, 8);
+            sb.back_tab(1);
+            assert_eq!(sb.cursor_x, 4);
+
+            // A stop set with HTS is added to the 4-column defaults and wins over them
+            sb.cursor_x = 6;
+            sb.set_tab_stop();
+            sb.cursor_x = 4;
+            sb.tab();
+            assert_eq!(sb.cursor_x, 6);
+            sb.tab();
+            assert_eq!(sb.cursor_x, 8);
+            sb.back_tab(1);
+            assert_eq!(sb.cursor_x, 6);
+
+            // With all stops cleared, only the stop set afterwards is used
+            sb.clear_tab_stop(3);
+            sb.cursor_x = 3;
+            sb.set_tab_stop();
+            sb.cursor_x = 0;
+            sb.forward_tab(1);
+            assert_eq!(sb.cursor_x, 3);
+            sb.forward_tab(1);
+            assert_eq!(sb.cursor_x, 19);
+        }
+
+        #[test]
+        fn test_tab_width_is_at_least_one() {
+            let mut sb = ScreenBuffer::new_with_scrollback(20, 2, 100, CursorStyle::default(), 0);
+            assert_eq!(sb.tab_width(), 1);
+            sb.set_tab_width(0);
+            assert_eq!(sb.tab_width(), 1);
+            sb.tab();
+            assert_eq!(sb.cursor_x, 1);
+        }
     }
 
     #[test]
     fn test_resize_preserves_content() {
         // Test that resize preserves content when growing/shrinking
-        let mut buffer = ScreenBuffer::new_with_scrollback(10, 10, 1000, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 10, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Put some content in the buffer
         buffer.move_cursor_to(0, 0);
@@ -1904,7 +2710,7 @@ mod tests {
     fn test_resize_height_decrease_preserves_recent_content() {
         // Test that when terminal gets shorter, recent content stays visible
         // and older content moves to scrollback
-        let mut buffer = ScreenBuffer::new_with_scrollback(80, 20, 1000, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(80, 20, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Fill the buffer with identifiable content (20 lines)
         for y in 0..20 {
@@ -1954,7 +2760,7 @@ mod tests {
     #[test]
     fn test_resize_width_decrease_rewraps_content() {
         // Test that when width decreases, content is rewrapped to preserve text
-        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 100, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Fill first line with a long string of identifiable characters
         buffer.move_cursor_to(0, 0);
@@ -2015,7 +2821,7 @@ mod tests {
     fn test_resize_width_decrease_with_scrollback_and_cursor() {
         // Test that when rewrapping creates more lines than fit, excess goes to scrollback
         // and cursor position is correctly tracked
-        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 20, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 20, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Fill buffer with multiple long lines (more than will fit after rewrap)
         for i in 0..5 {
@@ -2110,11 +2916,80 @@ mod tests {
         assert!(found_line0_in_scrollback, "Older content (LINE0) should be in scrollback");
     }
 
+    #[test]
+    fn test_rewrap_large_buffer_line_count() {
+        // A full screen of long lines rewrapped to a very narrow width in one step. This used to
+        // flatten every cell first; it should now stream through and keep every row of text.
+        let (cols, rows) = (400, 100);
+        let mut buffer = ScreenBuffer::new_with_scrollback(cols, rows, 10_000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for y in 0..rows {
+            buffer.move_cursor_to(0, y);
+            for x in 0..cols {
+                buffer.put_grapheme(if x % 2 == 0 { "a" } else { "b" });
+            }
+        }
+
+        buffer.resize(8, 10);
+
+        // Every 400-cell line becomes 50 rows of 8, the bottom 10 stay on screen
+        assert_eq!(buffer.get_scrollback_buffer().len() + buffer.height(), rows * cols / 8);
+        assert_eq!(buffer.get_cell(0, 9).unwrap().ch, 'a');
+        assert_eq!(buffer.get_cell(7, 9).unwrap().ch, 'b');
+
+        // Shrinking again rewraps the screen only, the scrollback is left as it is
+        let scrollback_before = buffer.get_scrollback_buffer().len();
+        buffer.resize(4, 10);
+        assert_eq!(buffer.get_scrollback_buffer().len(), scrollback_before + 10);
+    }
+
+    #[test]
+    fn test_scrollback_rows_of_another_width_have_no_gaps() {
+        for (saved_width, new_width) in [(10, 20), (20, 10)] {
+            let mut buffer = ScreenBuffer::new_with_scrollback(saved_width, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+            for i in 0..12 {
+                for ch in format!("line{:02}", i).chars() {
+                    buffer.put_grapheme(&ch.to_string());
+                }
+                buffer.cursor_x = 0;
+                buffer.newline();
+            }
+
+            buffer.resize(new_width, 4);
+            buffer.scroll_view_up(100);
+            assert!(!buffer.is_at_bottom());
+
+            for y in 0..4 {
+                let row: Vec<&Cell> = (0..new_width).filter_map(|x| buffer.get_cell_with_scrollback(x, y)).collect();
+                assert_eq!(row.len(), new_width, "every column of view row {} has a cell", y);
+                let text: String = row.iter().map(|cell| cell.ch).collect();
+                assert_eq!(text.trim_end(), format!("line{:02}", y), "{} -> {} columns", saved_width, new_width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rewrap_keeps_wide_characters_whole() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for grapheme in ["a", "b", "c", "世", "d"] {
+            buffer.put_grapheme(grapheme);
+        }
+
+        buffer.resize(4, 4);
+
+        // "abc" leaves one column, too few for the wide character, which moves down whole
+        let row: String = (0..4).map(|x| buffer.get_cell(x, 0).unwrap().ch).collect();
+        assert_eq!(row, "abc ");
+        assert_eq!(buffer.get_cell(0, 1).unwrap().ch, '世');
+        assert_eq!(buffer.get_cell(0, 1).unwrap().width, 2);
+        assert_eq!(buffer.get_cell(1, 1).unwrap().width, 0);
+        assert_eq!(buffer.get_cell(2, 1).unwrap().ch, 'd');
+    }
+
     #[test]
     fn test_resize_with_very_small_font() {
         // Simulate what happens when font is too large for window
         // This would result in cols=0 or rows=0 without minimum enforcement
-        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Fill with some content
         for y in 0..24 {
@@ -2139,7 +3014,7 @@ mod tests {
 
     #[test]
     fn test_cursor_stays_in_bounds_after_resize() {
-        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 100, CursorStyle::default());
+        let mut buffer = ScreenBuffer::new_with_scrollback(80, 24, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
 
         // Move cursor to bottom right
         buffer.move_cursor_to(79, 23);
@@ -2156,4 +3031,135 @@ mod tests {
         assert_eq!(buffer.cursor_x, 1, "Cursor X should be clamped to width-1");
         assert_eq!(buffer.cursor_y, 1, "Cursor Y should be clamped to height-1");
     }
+
+    #[test]
+    fn test_output_while_scrolled_sets_new_output_flag() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 3, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for _ in 0..5 {
+            buffer.put_grapheme("a");
+            buffer.scroll_up(1);
+        }
+
+        // Output while watching the live view is not "new"
+        assert!(!buffer.has_new_output_below());
+
+        buffer.scroll_view_up(2);
+        assert!(!buffer.has_new_output_below(), "scrolling alone is not new output");
+
+        buffer.put_grapheme("b");
+        assert!(buffer.has_new_output_below());
+        assert_eq!(buffer.scroll_offset, 2, "the view stays where it was");
+
+        buffer.reset_view_offset();
+        assert!(!buffer.has_new_output_below());
+
+        // Scrolling back down to the bottom also counts as having seen it
+        buffer.scroll_view_up(2);
+        buffer.put_grapheme("c");
+        buffer.scroll_view_down(2);
+        assert!(!buffer.has_new_output_below());
+        buffer.scroll_view_up(1);
+        assert!(!buffer.has_new_output_below());
+    }
+
+    #[test]
+    fn test_paging_through_scrollback_is_clamped() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for _ in 0..10 {
+            buffer.scroll_up(1);
+        }
+        assert_eq!(buffer.scrollback_buffer.len(), 10);
+
+        // A page up moves the view by a screen's worth of rows
+        buffer.scroll_view_up(4);
+        assert_eq!(buffer.scroll_offset, 4);
+        buffer.scroll_view_up(2);
+        assert_eq!(buffer.scroll_offset, 6);
+
+        // Paging past the oldest line stops there, and so does jumping to the top
+        buffer.scroll_view_up(4);
+        buffer.scroll_view_up(4);
+        assert_eq!(buffer.scroll_offset, 10);
+        buffer.reset_view_offset();
+        buffer.scroll_view_to_top();
+        assert_eq!(buffer.scroll_offset, 10);
+
+        buffer.scroll_view_down(4);
+        assert_eq!(buffer.scroll_offset, 6);
+        buffer.scroll_view_down(100);
+        assert!(buffer.is_at_bottom());
+    }
+
+    /// Write a line of text and move to the start of the next row
+    fn write_line(buffer: &mut ScreenBuffer, text: &str) {
+        for ch in text.chars() {
+            buffer.put_grapheme(&ch.to_string());
+        }
+        buffer.newline();
+        buffer.cursor_x = 0;
+    }
+
+    #[test]
+    fn test_scrolled_view_stays_anchored_to_content() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 3, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        let top_line = |buffer: &ScreenBuffer| -> String {
+            (0..10)
+                .filter_map(|x| buffer.get_cell_with_scrollback(x, 0))
+                .map(|cell| cell.ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+
+        // 20 numbered lines, most of them in scrollback
+        for i in 0..20 {
+            write_line(&mut buffer, &format!("line{}", i));
+        }
+
+        buffer.scroll_view_up(10);
+        let anchored = top_line(&buffer);
+        assert_eq!(anchored, "line8");
+
+        for i in 20..23 {
+            write_line(&mut buffer, &format!("line{}", i));
+        }
+        assert_eq!(buffer.scroll_offset, 13);
+        assert_eq!(top_line(&buffer), anchored);
+
+        // At the bottom, output keeps following the live view
+        buffer.reset_view_offset();
+        buffer.newline();
+        assert!(buffer.is_at_bottom());
+    }
+
+    #[test]
+    fn test_absolute_rows_follow_content_while_scrolling() {
+        let mut buffer = ScreenBuffer::new_with_scrollback(10, 3, 5, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for i in 0..6 {
+            write_line(&mut buffer, &format!("line{}", i));
+        }
+
+        // Select the top two rows of a scrolled-back view
+        buffer.scroll_view_up(3);
+        let (start_row, end_row) = (buffer.view_row_to_absolute(0), buffer.view_row_to_absolute(1));
+        assert_eq!(buffer.text_in_range(0, start_row, 9, end_row), "line1\nline2");
+
+        // Scrolling the view moves the rows on screen, not the selected content
+        buffer.scroll_view_down(1);
+        assert_eq!(buffer.view_row_to_absolute(0), start_row + 1);
+        assert_eq!(buffer.text_in_range(0, start_row, 9, end_row), "line1\nline2");
+
+        // Neither does output pushing lines into scrollback and trimming the oldest one
+        write_line(&mut buffer, "line6");
+        write_line(&mut buffer, "line7");
+        assert_eq!(buffer.text_in_range(0, start_row, 9, end_row), "line1\nline2");
+        assert_eq!(
+            buffer.text_in_range(0, buffer.view_row_to_absolute(0), 9, buffer.view_row_to_absolute(0)),
+            "line2"
+        );
+
+        // Once a selected line is trimmed it reads as empty
+        write_line(&mut buffer, "line8");
+        assert_eq!(buffer.text_in_range(0, start_row, 9, end_row), "\nline2");
+    }
 }
@@ -2,11 +2,16 @@
 //! Provides font rendering, drawing primitives, and UI elements
 
 use crate::input::hotkeys::SequentialHotkeyState;
-use sdl3::pixels::Color;
+use crate::screen_buffer::is_emoji_grapheme;
+use crate::tab_gui::TabActivity;
+use sdl3::pixels::{Color, PixelFormat};
 use sdl3::rect::Rect;
-use sdl3::render::{Canvas, TextureCreator};
+use sdl3::render::{BlendMode, Canvas, TextureCreator};
+use sdl3::surface::Surface;
 use sdl3::ttf::Font;
 use sdl3::video::Window;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Color constants for UI elements
 pub const BG_DARK: Color = Color::RGB(30, 30, 30);
@@ -14,6 +19,17 @@ pub const BG_MEDIUM: Color = Color::RGB(40, 40, 40);
 pub const BG_LIGHT: Color = Color::RGB(50, 50, 50);
 pub const TEXT_WHITE: Color = Color::RGB(255, 255, 255);
 pub const TEXT_GRAY: Color = Color::RGB(200, 200, 200);
+const BELL_COLOR: Color = Color::RGB(255, 190, 60);
+
+/// Tab width bounds; between them a tab is as wide as its measured label
+const MIN_TAB_WIDTH: i32 = 140;
+const MAX_TAB_WIDTH: i32 = 500;
+/// Space left of a tab label and right of its close button
+const TAB_LEFT_PADDING: i32 = 24;
+const TAB_RIGHT_PADDING: i32 = 6;
+const TAB_ELLIPSIS: &str = "...";
+#[cfg(not(production))]
+const DEV_MODE_TEXT: &str = "[DEV MODE]";
 
 /// Represents a clickable rectangle area
 #[derive(Debug, Clone, Copy)]
@@ -34,12 +50,16 @@ impl ClickableRect {
 /// Tab bar state and rendering
 pub struct TabBar {
     pub tabs: Vec<String>,
+    /// Output/bell flags of each tab, indexed like `tabs`
+    pub tab_activity: Vec<TabActivity>,
     pub active_tab: usize,
     pub tab_rects: Vec<ClickableRect>,
     pub close_button_rects: Vec<ClickableRect>,
     pub add_button_rect: ClickableRect,
     pub minimize_button_rect: ClickableRect,
     pub close_button_rect: ClickableRect,
+    /// Draw minimize/close buttons; off when the OS decorates the window
+    pub show_window_controls: bool,
     pub cpu_indicator_rect: ClickableRect,
     pub height: u32,
     pub editing_tab: Option<usize>,
@@ -51,7 +71,13 @@ pub struct TabBar {
     pub drag_start_x: i32,
     pub drag_offset_x: i32,
     pub sequential_hotkey_state: SequentialHotkeyState,
+    /// Drop the next text input event, set when an Alt+key press was already sent as ESC + key
+    pub suppress_next_text_input: bool,
     pub first_visible_tab_index: usize,
+    /// Tabs drawn in the strip on the last render; the rest are scrolled out of view
+    pub visible_tabs: Range<usize>,
+    /// Scroll the strip to the active tab on the next render, set when the active tab changes
+    reveal_active_tab: bool,
     pub left_scroll_button_rect: ClickableRect,
     pub right_scroll_button_rect: ClickableRect,
 }
@@ -60,13 +86,16 @@ impl TabBar {
     pub fn new(height: u32) -> Self {
         Self {
             tabs: Vec::new(),
+            tab_activity: Vec::new(),
             active_tab: 0,
             tab_rects: Vec::new(),
             close_button_rects: Vec::new(),
             add_button_rect: ClickableRect::new(Rect::new(0, 0, 0, 0)),
             sequential_hotkey_state: SequentialHotkeyState::new(),
+            suppress_next_text_input: false,
             minimize_button_rect: ClickableRect::new(Rect::new(0, 0, 0, 0)),
             close_button_rect: ClickableRect::new(Rect::new(0, 0, 0, 0)),
+            show_window_controls: true,
             cpu_indicator_rect: ClickableRect::new(Rect::new(0, 0, 0, 0)),
             height,
             editing_tab: None,
@@ -78,6 +107,8 @@ impl TabBar {
             drag_start_x: 0,
             drag_offset_x: 0,
             first_visible_tab_index: 0,
+            visible_tabs: 0..0,
+            reveal_active_tab: true,
             left_scroll_button_rect: ClickableRect::new(Rect::new(0, 0, 0, 0)),
             right_scroll_button_rect: ClickableRect::new(Rect::new(0, 0, 0, 0)),
         }
@@ -89,6 +120,9 @@ impl TabBar {
 
     pub fn set_active_tab(&mut self, index: usize) {
         if index < self.tabs.len() {
+            if index != self.active_tab {
+                self.reveal_active_tab = true;
+            }
             self.active_tab = index;
         }
     }
@@ -171,7 +205,8 @@ impl TabBar {
 
     pub fn get_clicked_tab(&self, mouse_x: i32, mouse_y: i32) -> Option<usize> {
         for (idx, tab_rect) in self.tab_rects.iter().enumerate() {
-            if tab_rect.contains_point(mouse_x, mouse_y) {
+            // Scrolled-out tabs keep their positions for drag and drop but can't be clicked
+            if self.visible_tabs.contains(&idx) && tab_rect.contains_point(mouse_x, mouse_y) {
                 return Some(idx);
             }
         }
@@ -180,7 +215,7 @@ impl TabBar {
 
     pub fn get_clicked_close_button(&self, mouse_x: i32, mouse_y: i32) -> Option<usize> {
         for (idx, close_rect) in self.close_button_rects.iter().enumerate() {
-            if close_rect.contains_point(mouse_x, mouse_y) {
+            if self.visible_tabs.contains(&idx) && close_rect.contains_point(mouse_x, mouse_y) {
                 return Some(idx);
             }
         }
@@ -201,15 +236,18 @@ impl TabBar {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn render<T>(
         &mut self,
         canvas: &mut Canvas<Window>,
         font: &Font,
         _button_font: &Font,
         cpu_font: &Font,
+        emoji_font: &Font,
         texture_creator: &TextureCreator<T>,
         window_width: u32,
         cpu_usage: f32,
+        memory_text: Option<&str>,
     ) -> Result<(), String> {
         // Clear tab bar area
         canvas.set_draw_color(BG_DARK);
@@ -238,46 +276,59 @@ impl TabBar {
 
         x += cpu_width as i32 + 12;
 
+        // Memory indicator of the active pane's processes, styled like the CPU one
+        if let Some(memory_text) = memory_text {
+            let surface = cpu_font.render(memory_text).blended(TEXT_WHITE).map_err(|e| e.to_string())?;
+            let texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string())?;
+            let memory_width = 100; // Fits "999.9 GB" without jumping
+            let memory_rect = Rect::new(x, y, memory_width, self.height - 6);
+
+            canvas.set_draw_color(BG_MEDIUM);
+            canvas.fill_rect(memory_rect).map_err(|e| e.to_string())?;
+
+            let text_y = y + ((self.height - 6 - surface.height()) / 2) as i32;
+            let text_rect = Rect::new(x + 12, text_y, surface.width(), surface.height());
+            canvas.copy(&texture, None, text_rect).map_err(|e| e.to_string())?;
+
+            x += memory_width as i32 + 12;
+        }
+
         // Calculate available space for tabs
-        // Reserve space for: add button + dev mode indicator + window controls
+        // Tabs end where the right-hand items begin: the window controls and the dev mode
+        // indicator are measured, and the add button always follows the last tab
         let button_size = (self.height - 12) as i32;
-        let window_controls_width = (button_size + 6) * 2 + 6; // Two buttons + spacing
+        let full_controls_width = (button_size + 6) * 2 + 6; // Two buttons + spacing
+        let window_controls_width = if self.show_window_controls { full_controls_width } else { 0 };
+        // The dev mode indicator keeps its gap to the window controls, or to the edge without them
         #[cfg(not(production))]
-        let dev_mode_width = 150; // Approximate width for "[DEV MODE]"
-        #[cfg(production)]
-        let dev_mode_width = 0;
-        // Adjust reserved space based on whether dev mode indicator is present
+        let dev_mode_margin = 225 - (full_controls_width - window_controls_width);
+        #[allow(unused_mut)]
+        let mut right_items_x = window_width as i32 - window_controls_width;
         #[cfg(not(production))]
-        let right_reserved_space = window_controls_width + dev_mode_width + 180; // More padding when dev mode present
-        #[cfg(production)]
-        let right_reserved_space = window_controls_width + 80; // Less padding in production
+        {
+            // Same position the indicator is drawn at below
+            right_items_x = right_items_x.min(window_width as i32 - text_width(font, emoji_font, DEV_MODE_TEXT) as i32 - dev_mode_margin);
+        }
         let add_button_width = button_size + 24;
-
-        // Calculate uniform tab width to maximize space usage
-        let available_width_for_tabs = window_width as i32 - x - add_button_width - right_reserved_space;
+        let available_width_for_tabs = right_items_x - 12 - x - add_button_width;
         let num_tabs = self.tabs.len();
-
-        // Calculate uniform tab width, distributing available space equally among all tabs
-        let min_tab_width = 200i32; // Minimum width to prevent tabs from being too narrow
-        let max_tab_width = 500i32; // Maximum width to prevent tabs from being too wide
         let tab_spacing = 1i32; // Space between tabs
 
-        let uniform_tab_width = if num_tabs > 0 {
-            let total_spacing = (num_tabs.saturating_sub(1) as i32) * tab_spacing;
-            let available_for_tabs = available_width_for_tabs - total_spacing;
-            let calculated_width = available_for_tabs / num_tabs as i32;
-            // Clamp between min and max
-            calculated_width.max(min_tab_width).min(max_tab_width)
-        } else {
-            min_tab_width
-        };
-
-        // Calculate total tabs width using uniform width
-        let total_tabs_width = if num_tabs > 0 {
-            (uniform_tab_width * num_tabs as i32) + ((num_tabs - 1) as i32 * tab_spacing)
-        } else {
-            0
-        };
+        // Size every tab to its measured label (the edit text while renaming), clamped so
+        // short titles stay clickable and long ones get truncated with an ellipsis
+        let close_size = self.height - 12;
+        let label_chrome_width = TAB_LEFT_PADDING + close_size as i32 + TAB_RIGHT_PADDING;
+        let tab_widths: Vec<i32> = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(idx, tab_name)| {
+                let label = if Some(idx) == self.editing_tab { &self.edit_text } else { tab_name };
+                (text_width(font, emoji_font, label) as i32 + label_chrome_width).clamp(MIN_TAB_WIDTH, MAX_TAB_WIDTH)
+            })
+            .collect();
+
+        let total_tabs_width = tab_widths.iter().sum::<i32>() + (num_tabs.saturating_sub(1) as i32 * tab_spacing);
 
         // Determine if we need scroll buttons
         let scroll_button_size = 40u32; // Medium size for scroll buttons
@@ -288,23 +339,29 @@ impl TabBar {
             self.first_visible_tab_index = self.tabs.len().saturating_sub(1);
         }
 
+        let available_for_visible = available_width_for_tabs - (scroll_button_size as i32 * 2) - 12;
+
+        // Keep the active tab in view after switching to it (including newly opened tabs)
+        if needs_scrolling && self.reveal_active_tab {
+            self.first_visible_tab_index =
+                first_visible_tab_for(&tab_widths, tab_spacing, available_for_visible, self.first_visible_tab_index, self.active_tab);
+        }
+        self.reveal_active_tab = false;
+
         // Prevent overscrolling: ensure we don't scroll past the point where all remaining tabs fit
         if needs_scrolling {
             // Calculate width of tabs starting from first_visible_tab_index
             let mut visible_width = 0i32;
-            let available_for_visible = available_width_for_tabs - (scroll_button_size as i32 * 2) - 12;
 
-            for _idx in self.first_visible_tab_index..self.tabs.len() {
-                // Use uniform tab width
-                visible_width += uniform_tab_width + 1;
+            for tab_width in &tab_widths[self.first_visible_tab_index..] {
+                visible_width += tab_width + tab_spacing;
             }
 
             // If all remaining tabs fit, move first_visible_tab_index back
             while self.first_visible_tab_index > 0 && visible_width <= available_for_visible {
                 // Try including the previous tab
                 self.first_visible_tab_index -= 1;
-                // Use uniform tab width
-                visible_width += uniform_tab_width + 1;
+                visible_width += tab_widths[self.first_visible_tab_index] + tab_spacing;
 
                 // If it doesn't fit, undo
                 if visible_width > available_for_visible {
@@ -314,7 +371,12 @@ impl TabBar {
             }
         }
 
-        let mut tabs_start_x = x;
+        self.visible_tabs = if needs_scrolling {
+            visible_tab_range(&tab_widths, tab_spacing, available_for_visible, self.first_visible_tab_index)
+        } else {
+            0..num_tabs
+        };
+
         let mut tabs_end_x = x + available_width_for_tabs;
 
         // Draw left scroll button if needed
@@ -343,7 +405,6 @@ impl TabBar {
             }
 
             x += scroll_button_size as i32 + 6;
-            tabs_start_x = x; // Update tabs_start_x to prevent overlap with scroll button
             tabs_end_x = x + available_width_for_tabs - scroll_button_size as i32 - 6 - scroll_button_size as i32 - 6;
         }
 
@@ -364,10 +425,8 @@ impl TabBar {
                 continue;
             }
 
-            let close_size = self.height - 12;
             let display_text = if Some(idx) == self.editing_tab { &self.edit_text } else { tab_name };
-            // Use uniform tab width for all tabs
-            let tab_width = uniform_tab_width as u32;
+            let tab_width = tab_widths[idx] as u32;
 
             // If this tab is being dragged, save it for later rendering
             if Some(idx) == self.dragging_tab {
@@ -376,28 +435,17 @@ impl TabBar {
                 // Still need to advance x and store rect for drop position calculation
                 let tab_rect = Rect::new(x, y, tab_width, self.height - 6);
                 self.tab_rects.push(ClickableRect::new(tab_rect));
-                let close_x = x + tab_width as i32 - (close_size as i32) - 6;
-                let close_y = y + 6;
-                let close_rect = Rect::new(close_x, close_y, close_size, close_size);
-                self.close_button_rects.push(ClickableRect::new(close_rect));
+                self.close_button_rects.push(ClickableRect::new(tab_close_button_rect(tab_rect)));
                 x += tab_width as i32 + 1;
                 continue;
             }
 
-            // Check if tab is completely out of view or partially clipped
-            let tab_right = x + tab_width as i32;
-            let tab_out_of_view = (tab_right < tabs_start_x) || (x > tabs_end_x);
-            let tab_clipped_left = x < tabs_start_x && tab_right > tabs_start_x;
-            let tab_clipped_right = x < tabs_end_x && tab_right > tabs_end_x;
-
-            if tab_out_of_view || (needs_scrolling && (tab_clipped_left || tab_clipped_right)) {
+            // Tabs past the right end of the strip are not drawn
+            if !self.visible_tabs.contains(&idx) {
                 // Still need to store rect and advance x for proper positioning
                 let tab_rect = Rect::new(x, y, tab_width, self.height - 6);
                 self.tab_rects.push(ClickableRect::new(tab_rect));
-                let close_x = x + tab_width as i32 - (close_size as i32) - 6;
-                let close_y = y + 6;
-                let close_rect = Rect::new(close_x, close_y, close_size, close_size);
-                self.close_button_rects.push(ClickableRect::new(close_rect));
+                self.close_button_rects.push(ClickableRect::new(tab_close_button_rect(tab_rect)));
                 x += tab_width as i32 + 1;
                 continue;
             }
@@ -413,41 +461,13 @@ impl TabBar {
             };
 
             // Calculate available space for text (tab_width - left padding - close button - right padding)
-            let left_padding = 24;
-            let right_padding = 6;
-            let max_text_width = (tab_width as i32 - left_padding - close_size as i32 - right_padding).max(20) as u32;
+            let max_text_width = (tab_width as i32 - label_chrome_width).max(20) as u32;
 
             // Truncate text if necessary with ellipsis
-            let mut display_text_truncated = display_text.to_string();
-            let mut needs_truncation = false;
-
-            // Check if full text fits
-            if let Some(surface) = safe_render_text(font, display_text, TEXT_GRAY) {
-                if surface.width() > max_text_width {
-                    needs_truncation = true;
-                    // Binary search for the right length
-                    let chars: Vec<char> = display_text.chars().collect();
-                    let mut len = chars.len();
-                    while len > 0 {
-                        let truncated: String = chars.iter().take(len).collect();
-                        let with_ellipsis = format!("{}...", truncated);
-                        if let Some(test_surface) = safe_render_text(font, &with_ellipsis, TEXT_GRAY) {
-                            if test_surface.width() <= max_text_width {
-                                display_text_truncated = with_ellipsis;
-                                break;
-                            }
-                        }
-                        len = len * 3 / 4; // Reduce by 25% each iteration
-                    }
-                    if len == 0 {
-                        display_text_truncated = "...".to_string();
-                    }
-                }
-            }
+            let final_display = truncate_with_ellipsis(display_text, max_text_width, |text| text_width(font, emoji_font, text));
 
             // Try to render text, with fallback for unsupported characters
-            let final_display = if needs_truncation { &display_text_truncated } else { display_text };
-            let (text_width, text_height, text_texture) = if let Some(surface) = safe_render_text(font, final_display, TEXT_GRAY) {
+            let (label_width, text_height, text_texture) = if let Some(surface) = render_mixed_text(font, emoji_font, &final_display, TEXT_GRAY) {
                 let width = surface.width().min(max_text_width);
                 let height = surface.height();
                 match texture_creator.create_texture_from_surface(&surface) {
@@ -475,11 +495,25 @@ impl TabBar {
             canvas.set_draw_color(bg_color);
             canvas.fill_rect(tab_rect).map_err(|e| e.to_string())?;
 
+            // Activity indicator of a background tab, in the label's left padding
+            if !is_active {
+                let activity = self.tab_activity.get(idx).copied().unwrap_or_default();
+                let indicator_center = (x + TAB_LEFT_PADDING / 2, y + (self.height as i32 - 6) / 2);
+                let indicator_size = (self.height as i32 - 6) / 3;
+                if activity.has_bell {
+                    canvas.set_draw_color(BELL_COLOR);
+                    draw_bell(canvas, indicator_center, indicator_size);
+                } else if activity.has_activity {
+                    canvas.set_draw_color(TEXT_GRAY);
+                    draw_activity_dot(canvas, indicator_center, (indicator_size / 5).max(2));
+                }
+            }
+
             // Draw text (if available) with increased left padding, clipped to available space
             if let Some(texture) = text_texture {
-                let text_x = x + left_padding;
+                let text_x = x + TAB_LEFT_PADDING;
                 let text_y = y + ((self.height - 6 - text_height) / 2) as i32;
-                let clipped_width = text_width.min(max_text_width);
+                let clipped_width = label_width.min(max_text_width);
                 let text_rect = Rect::new(text_x, text_y, clipped_width, text_height);
                 let _ = canvas.copy(&texture, None, text_rect);
             }
@@ -490,12 +524,12 @@ impl TabBar {
                 let text_before_cursor = self.edit_text.chars().take(self.edit_cursor_pos).collect::<String>();
                 let cursor_text_width = if text_before_cursor.is_empty() {
                     0
-                } else if let Some(surface) = safe_render_text(font, &text_before_cursor, TEXT_GRAY) {
+                } else if let Some(surface) = render_mixed_text(font, emoji_font, &text_before_cursor, TEXT_GRAY) {
                     surface.width()
                 } else {
                     0
                 };
-                let cursor_x = x + 24 + cursor_text_width as i32; // Updated for new left padding
+                let cursor_x = x + TAB_LEFT_PADDING + cursor_text_width as i32;
                 let cursor_y = y + 6;
                 let cursor_height = self.height - 12;
                 canvas.set_draw_color(Color::RGB(255, 255, 255));
@@ -507,17 +541,15 @@ impl TabBar {
             self.tab_rects.push(tab_clickable);
 
             // Draw close button (only visible on hover, but always reserve space)
-            let close_x = x + tab_width as i32 - (close_size as i32) - 6;
-            let close_y = y + 6;
-            let close_rect = Rect::new(close_x, close_y, close_size, close_size);
+            let close_rect = tab_close_button_rect(tab_rect);
 
             // Check if this tab is currently hovered (recalculate based on current mouse position)
             let is_tab_hovered = tab_rect.contains_point((self.mouse_x, self.mouse_y));
             if is_tab_hovered {
                 // Draw close button "×" manually with SDL primitives
                 canvas.set_draw_color(TEXT_WHITE);
-                let center_x = close_x + (close_size as i32 / 2);
-                let center_y = close_y + (close_size as i32 / 2);
+                let center_x = close_rect.x() + (close_size as i32 / 2);
+                let center_y = close_rect.y() + (close_size as i32 / 2);
                 let half_size = close_size as i32 * 4 / 10; // 40% of button size
 
                 // Draw X as two diagonal lines
@@ -561,42 +593,13 @@ impl TabBar {
             let display_text = if Some(idx) == self.editing_tab { &self.edit_text } else { &tab_name };
 
             // Calculate available space for text (same as non-dragged tabs)
-            let close_size = self.height - 12;
-            let left_padding = 24;
-            let right_padding = 6;
-            let max_text_width = (tab_width as i32 - left_padding - close_size as i32 - right_padding).max(20) as u32;
+            let max_text_width = (tab_width as i32 - label_chrome_width).max(20) as u32;
 
             // Truncate text if necessary with ellipsis
-            let mut display_text_truncated = display_text.to_string();
-            let mut needs_truncation = false;
-
-            // Check if full text fits
-            if let Some(surface) = safe_render_text(font, display_text, TEXT_GRAY) {
-                if surface.width() > max_text_width {
-                    needs_truncation = true;
-                    // Binary search for the right length
-                    let chars: Vec<char> = display_text.chars().collect();
-                    let mut len = chars.len();
-                    while len > 0 {
-                        let truncated: String = chars.iter().take(len).collect();
-                        let with_ellipsis = format!("{}...", truncated);
-                        if let Some(test_surface) = safe_render_text(font, &with_ellipsis, TEXT_GRAY) {
-                            if test_surface.width() <= max_text_width {
-                                display_text_truncated = with_ellipsis;
-                                break;
-                            }
-                        }
-                        len = len * 3 / 4; // Reduce by 25% each iteration
-                    }
-                    if len == 0 {
-                        display_text_truncated = "...".to_string();
-                    }
-                }
-            }
+            let final_display = truncate_with_ellipsis(display_text, max_text_width, |text| text_width(font, emoji_font, text));
 
             // Render text
-            let final_display = if needs_truncation { &display_text_truncated } else { display_text };
-            let (text_width, text_height, text_texture) = if let Some(surface) = safe_render_text(font, final_display, TEXT_GRAY) {
+            let (label_width, text_height, text_texture) = if let Some(surface) = render_mixed_text(font, emoji_font, &final_display, TEXT_GRAY) {
                 let width = surface.width().min(max_text_width);
                 let height = surface.height();
                 match texture_creator.create_texture_from_surface(&surface) {
@@ -631,9 +634,9 @@ impl TabBar {
 
             // Draw text (clipped to available space)
             if let Some(texture) = text_texture {
-                let text_x = dragged_x + left_padding;
+                let text_x = dragged_x + TAB_LEFT_PADDING;
                 let text_y = y + ((self.height - 6 - text_height) / 2) as i32;
-                let clipped_width = text_width.min(max_text_width);
+                let clipped_width = label_width.min(max_text_width);
                 let text_rect = Rect::new(text_x, text_y, clipped_width, text_height);
                 let _ = canvas.copy(&texture, None, text_rect);
             }
@@ -644,12 +647,12 @@ impl TabBar {
                 let text_before_cursor = self.edit_text.chars().take(self.edit_cursor_pos).collect::<String>();
                 let cursor_text_width = if text_before_cursor.is_empty() {
                     0
-                } else if let Some(surface) = safe_render_text(font, &text_before_cursor, TEXT_GRAY) {
+                } else if let Some(surface) = render_mixed_text(font, emoji_font, &text_before_cursor, TEXT_GRAY) {
                     surface.width()
                 } else {
                     0
                 };
-                let cursor_x = dragged_x + 24 + cursor_text_width as i32;
+                let cursor_x = dragged_x + TAB_LEFT_PADDING + cursor_text_width as i32;
                 let cursor_y = y + 6;
                 let cursor_height = self.height - 12;
                 canvas.set_draw_color(Color::RGB(255, 255, 255));
@@ -722,13 +725,12 @@ impl TabBar {
         // [DEV MODE] indicator (only in non-production builds)
         #[cfg(not(production))]
         let _dev_mode_width = {
-            let dev_text = "[DEV MODE]";
-            if let Some(dev_surface) = safe_render_text(font, dev_text, Color::RGB(255, 150, 50)) {
+            if let Some(dev_surface) = safe_render_text(font, DEV_MODE_TEXT, Color::RGB(255, 150, 50)) {
                 if let Ok(dev_texture) = texture_creator.create_texture_from_surface(&dev_surface) {
                     let dev_width = dev_surface.width();
                     let dev_height = dev_surface.height();
                     // Position to the left of window controls
-                    let dev_x = window_width as i32 - dev_width as i32 - 225; // 225px from right for window controls
+                    let dev_x = window_width as i32 - dev_width as i32 - dev_mode_margin;
                     let dev_y = y + ((self.height - 6 - dev_height) / 2) as i32;
                     let dev_rect = Rect::new(dev_x, dev_y, dev_width, dev_height);
                     let _ = canvas.copy(&dev_texture, None, dev_rect);
@@ -744,6 +746,13 @@ impl TabBar {
         #[cfg(production)]
         let _dev_mode_width = 0;
 
+        if !self.show_window_controls {
+            // Off-screen, so clicks never hit them
+            self.close_button_rect = ClickableRect::new(Rect::new(-1000, -1000, 0, 0));
+            self.minimize_button_rect = ClickableRect::new(Rect::new(-1000, -1000, 0, 0));
+            return Ok(());
+        }
+
         // Window controls (right side) - larger and vertically centered
         let button_size = (self.height - 12) as i32;
         let button_y = y + 6;
@@ -790,6 +799,170 @@ impl TabBar {
     }
 }
 
+/// Filled dot marking output in a background tab, in the current draw color
+fn draw_activity_dot(canvas: &mut Canvas<Window>, (center_x, center_y): (i32, i32), radius: i32) {
+    for dy in -radius..=radius {
+        let half_width = ((radius * radius - dy * dy) as f32).sqrt() as i32;
+        let _ = canvas.draw_line((center_x - half_width, center_y + dy), (center_x + half_width, center_y + dy));
+    }
+}
+
+/// Bell marking a BEL in a background tab, in the current draw color: a dome widening down to
+/// a rim, with the clapper below it
+fn draw_bell(canvas: &mut Canvas<Window>, (center_x, center_y): (i32, i32), size: i32) {
+    let top = center_y - size / 2;
+    let rim = center_y + size / 3;
+    for row in top..=rim {
+        let t = (row - top) as f32 / (rim - top).max(1) as f32;
+        let half_width = (size as f32 * (0.2 + 0.25 * t.sqrt())) as i32;
+        let _ = canvas.draw_line((center_x - half_width, row), (center_x + half_width, row));
+    }
+    let _ = canvas.draw_line((center_x - size / 2, rim + 1), (center_x + size / 2, rim + 1));
+    let clapper_size = (size / 5).max(2);
+    let _ = canvas.fill_rect(Rect::new(center_x - clapper_size / 2, rim + 2, clapper_size as u32, clapper_size as u32));
+}
+
+/// The × button of a tab: a square at the right end of the tab, as tall as the tab minus its
+/// top inset. Clicks inside it close the tab; the rest of the tab switches to it.
+pub fn tab_close_button_rect(tab_rect: Rect) -> Rect {
+    let close_size = tab_rect.height().saturating_sub(6);
+    let close_x = tab_rect.right() - close_size as i32 - TAB_RIGHT_PADDING;
+    Rect::new(close_x, tab_rect.y() + 6, close_size, close_size)
+}
+
+/// A tab bar with one tab per rect, all visible and each with its × button, for hit-testing in tests
+#[cfg(test)]
+pub(crate) fn tab_bar_with_tab_rects(rects: &[Rect]) -> TabBar {
+    let mut tab_bar = TabBar::new(40);
+    tab_bar.set_tabs((1..=rects.len()).map(|i| format!("tab {}", i)).collect());
+    tab_bar.tab_rects = rects.iter().map(|&rect| ClickableRect::new(rect)).collect();
+    tab_bar.close_button_rects = rects.iter().map(|&rect| ClickableRect::new(tab_close_button_rect(rect))).collect();
+    tab_bar.visible_tabs = 0..rects.len();
+    tab_bar
+}
+
+/// Tabs that fit in `available` pixels when the strip starts at tab `first`. The first tab is
+/// always included, even when it is wider than the strip on its own.
+fn visible_tab_range(tab_widths: &[i32], spacing: i32, available: i32, first: usize) -> Range<usize> {
+    if first >= tab_widths.len() {
+        return first..first;
+    }
+
+    let mut used = tab_widths[first];
+    let mut end = first + 1;
+    while end < tab_widths.len() && used + spacing + tab_widths[end] <= available {
+        used += spacing + tab_widths[end];
+        end += 1;
+    }
+    first..end
+}
+
+/// The first tab to show so that `active` is within the visible range, moving the strip as
+/// little as possible from `first`
+fn first_visible_tab_for(tab_widths: &[i32], spacing: i32, available: i32, first: usize, active: usize) -> usize {
+    if active >= tab_widths.len() || active < first {
+        return active.min(tab_widths.len().saturating_sub(1));
+    }
+
+    let mut first = first;
+    while !visible_tab_range(tab_widths, spacing, available, first).contains(&active) {
+        first += 1;
+    }
+    first
+}
+
+/// Rendered width of `text` as `render_mixed_text` draws it, measured without rasterizing it
+fn text_width(font: &Font, emoji_font: &Font, text: &str) -> u32 {
+    split_emoji_segments(text)
+        .into_iter()
+        .map(|(segment, is_emoji)| {
+            let segment_font = if is_emoji { emoji_font } else { font };
+            segment_font.size_of(segment).map(|(width, _)| width).unwrap_or(0)
+        })
+        .sum()
+}
+
+/// Split `text` into runs of emoji and of everything else, keeping grapheme clusters
+/// (ZWJ sequences, flags, skin tones) whole. Each run is flagged `true` if it is emoji.
+pub fn split_emoji_segments(text: &str) -> Vec<(&str, bool)> {
+    let mut segments: Vec<(&str, bool)> = Vec::new();
+    let mut start = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        let is_emoji = is_emoji_grapheme(grapheme);
+        match segments.last_mut() {
+            Some((segment, segment_is_emoji)) if *segment_is_emoji == is_emoji => *segment = &text[start..offset + grapheme.len()],
+            _ => {
+                start = offset;
+                segments.push((&text[offset..offset + grapheme.len()], is_emoji));
+            }
+        }
+    }
+    segments
+}
+
+/// Render UI text in `font` with its emoji taken from `emoji_font`, so a UI font without emoji
+/// glyphs doesn't draw boxes for them. The runs are rendered separately and composited side by
+/// side into one surface, vertically centered, which callers use like any rendered text.
+pub fn render_mixed_text(font: &Font, emoji_font: &Font, text: &str, color: Color) -> Option<Surface<'static>> {
+    let segments = split_emoji_segments(text);
+    if !segments.iter().any(|(_, is_emoji)| *is_emoji) {
+        return safe_render_text(font, text, color);
+    }
+
+    let surfaces: Vec<Surface<'static>> = segments
+        .iter()
+        .filter_map(|(segment, is_emoji)| {
+            if *is_emoji {
+                emoji_font
+                    .render(segment)
+                    .blended(color)
+                    .ok()
+                    .filter(|surface| surface.width() > 0 && surface.height() > 0)
+            } else {
+                safe_render_text(font, segment, color)
+            }
+        })
+        .collect();
+
+    let width = surfaces.iter().map(|surface| surface.width()).sum::<u32>();
+    let height = surfaces.iter().map(|surface| surface.height()).max()?;
+    let mut mixed = Surface::new(width, height, PixelFormat::RGBA32).ok()?;
+
+    let mut x = 0;
+    for mut surface in surfaces {
+        // Runs don't overlap, so their pixels are copied as they are instead of blended
+        let _ = surface.set_blend_mode(BlendMode::None);
+        let y = (height - surface.height()) / 2;
+        let _ = surface.blit(None, &mut mixed, Rect::new(x, y as i32, surface.width(), surface.height()));
+        x += surface.width() as i32;
+    }
+    Some(mixed)
+}
+
+/// Shorten `text` to the longest prefix that fits `max_width` together with an ellipsis,
+/// as measured by `measure`. Text that already fits is returned unchanged.
+fn truncate_with_ellipsis(text: &str, max_width: u32, measure: impl Fn(&str) -> u32) -> String {
+    if measure(text) <= max_width {
+        return text.to_string();
+    }
+
+    // Binary search for the longest prefix that still fits
+    let chars: Vec<char> = text.chars().collect();
+    let (mut low, mut high) = (0, chars.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        let candidate: String = chars[..mid].iter().collect::<String>() + TAB_ELLIPSIS;
+        if measure(&candidate) <= max_width {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    let prefix: String = chars[..low].iter().collect();
+    format!("{}{}", prefix.trim_end(), TAB_ELLIPSIS)
+}
+
 /// Safe text rendering that filters out characters the font can't render
 /// Returns None if the text can't be rendered at all
 fn safe_render_text(font: &Font, text: &str, color: Color) -> Option<sdl3::surface::Surface<'static>> {
@@ -830,3 +1003,85 @@ fn safe_render_text(font: &Font, text: &str, color: Color) -> Option<sdl3::surfa
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_emoji_segments() {
+        assert_eq!(split_emoji_segments("build 🚀 done"), vec![("build ", false), ("🚀", true), (" done", false)]);
+        assert_eq!(split_emoji_segments("🔥🔥 hot"), vec![("🔥🔥", true), (" hot", false)]);
+        // ZWJ sequences and flags stay in one run
+        assert_eq!(split_emoji_segments("👩‍💻🇸🇪"), vec![("👩‍💻🇸🇪", true)]);
+        assert_eq!(split_emoji_segments("plain"), vec![("plain", false)]);
+        assert!(split_emoji_segments("").is_empty());
+    }
+
+    #[test]
+    fn test_close_button_hit_region() {
+        let tab_rect = Rect::new(100, 3, 200, 34);
+        let close_rect = tab_close_button_rect(tab_rect);
+
+        // A square inside the tab, flush with the right padding
+        assert_eq!(close_rect, Rect::new(266, 9, 28, 28));
+        assert!(close_rect.left() >= tab_rect.left() && close_rect.right() <= tab_rect.right());
+        assert!(close_rect.top() >= tab_rect.top() && close_rect.bottom() <= tab_rect.bottom());
+
+        let mut tab_bar = tab_bar_with_tab_rects(&[Rect::new(0, 3, 99, 34), tab_rect]);
+
+        // The × closes, the label area only switches
+        assert_eq!(tab_bar.get_clicked_close_button(280, 20), Some(1));
+        assert_eq!(tab_bar.get_clicked_close_button(150, 20), None);
+        assert_eq!(tab_bar.get_clicked_tab(150, 20), Some(1));
+        // Right of the × is still tab body
+        assert_eq!(tab_bar.get_clicked_close_button(297, 20), None);
+        assert_eq!(tab_bar.get_clicked_tab(297, 20), Some(1));
+
+        // Scrolled-out tabs can't be hit at all
+        tab_bar.visible_tabs = 0..1;
+        assert_eq!(tab_bar.get_clicked_close_button(280, 20), None);
+        assert_eq!(tab_bar.get_clicked_tab(150, 20), None);
+    }
+
+    #[test]
+    fn test_visible_tabs_for_width() {
+        let widths = [100, 150, 100, 200, 100];
+
+        // Everything fits
+        assert_eq!(visible_tab_range(&widths, 1, 1000, 0), 0..5);
+        // Only whole tabs are shown: 100 + 1 + 150 + 1 + 100 = 352
+        assert_eq!(visible_tab_range(&widths, 1, 352, 0), 0..3);
+        assert_eq!(visible_tab_range(&widths, 1, 351, 0), 0..2);
+        assert_eq!(visible_tab_range(&widths, 1, 352, 2), 2..4);
+        // A single tab wider than the strip is still shown
+        assert_eq!(visible_tab_range(&widths, 1, 50, 3), 3..4);
+        assert_eq!(visible_tab_range(&widths, 1, 50, 5), 5..5);
+
+        // Scrolling to the active tab moves the strip as little as possible
+        assert_eq!(first_visible_tab_for(&widths, 1, 352, 0, 1), 0);
+        assert_eq!(first_visible_tab_for(&widths, 1, 352, 0, 3), 2);
+        assert_eq!(first_visible_tab_for(&widths, 1, 352, 0, 4), 3);
+        assert_eq!(first_visible_tab_for(&widths, 1, 352, 3, 1), 1);
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis_fits_width_budget() {
+        // Monospace stand-in for the font: 10px per character
+        let measure = |text: &str| text.chars().count() as u32 * 10;
+
+        assert_eq!(truncate_with_ellipsis("short", 100, measure), "short");
+        assert_eq!(truncate_with_ellipsis("exactly10!", 100, measure), "exactly10!");
+
+        let truncated = truncate_with_ellipsis("a very long tab title", 100, measure);
+        assert_eq!(truncated, "a very...");
+        assert!(measure(&truncated) <= 100);
+
+        // Multi-byte characters are never split
+        assert_eq!(truncate_with_ellipsis("ñandú ñandú ñandú", 100, measure), "ñandú ñ...");
+
+        // Nothing but the ellipsis fits
+        assert_eq!(truncate_with_ellipsis("title", 35, measure), "...");
+        assert_eq!(truncate_with_ellipsis("title", 10, measure), "...");
+    }
+}
@@ -7,9 +7,13 @@
 //! - Windows Production: %APPDATA%\nist\settings.json
 //! - Windows Test/Debug: %APPDATA%\nist-test\settings.json
 
+use crate::ansi::parse_color_spec;
 use directories::ProjectDirs;
 use sdl3::keyboard::Keycode;
+use sdl3::pixels::Color;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs;
 use std::path::PathBuf;
 
@@ -159,17 +163,17 @@ impl Key {
             Key::Y => Some(Keycode::Y),
             Key::Z => Some(Keycode::Z),
 
-            // Numbers - TODO: Find correct SDL3 Keycode names
-            Key::Num0 => None,
-            Key::Num1 => None,
-            Key::Num2 => None,
-            Key::Num3 => None,
-            Key::Num4 => None,
-            Key::Num5 => None,
-            Key::Num6 => None,
-            Key::Num7 => None,
-            Key::Num8 => None,
-            Key::Num9 => None,
+            // Numbers
+            Key::Num0 => Some(Keycode::_0),
+            Key::Num1 => Some(Keycode::_1),
+            Key::Num2 => Some(Keycode::_2),
+            Key::Num3 => Some(Keycode::_3),
+            Key::Num4 => Some(Keycode::_4),
+            Key::Num5 => Some(Keycode::_5),
+            Key::Num6 => Some(Keycode::_6),
+            Key::Num7 => Some(Keycode::_7),
+            Key::Num8 => Some(Keycode::_8),
+            Key::Num9 => Some(Keycode::_9),
 
             // Function keys
             Key::F1 => Some(Keycode::F1),
@@ -218,6 +222,23 @@ impl Key {
             Key::Backtick => None, // TODO: Find correct SDL3 Keycode name
         }
     }
+
+    /// The digit of a number key
+    pub fn digit(&self) -> Option<usize> {
+        let digits = [
+            Key::Num0,
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+            Key::Num9,
+        ];
+        digits.iter().position(|digit| digit == self)
+    }
 }
 
 impl KeyBinding {
@@ -292,18 +313,50 @@ pub struct NavigationHotkeys {
     pub next_pane: Vec<KeyBinding>,
     #[serde(rename = "previousPane", default = "default_previous_pane")]
     pub previous_pane: Vec<KeyBinding>,
+    /// Move to the next pane of the tab, wrapping around instead of moving on to the next tab
+    #[serde(rename = "cyclePane", default = "default_cycle_pane")]
+    pub cycle_pane: Vec<KeyBinding>,
+    #[serde(rename = "cyclePaneBack", default = "default_cycle_pane_back")]
+    pub cycle_pane_back: Vec<KeyBinding>,
+    #[serde(rename = "swapPane", default = "default_swap_pane")]
+    pub swap_pane: Vec<KeyBinding>,
+    #[serde(rename = "balancePanes", default = "default_balance_panes")]
+    pub balance_panes: Vec<KeyBinding>,
+    /// Split the active pane with a fresh shell in the same directory
+    #[serde(rename = "duplicatePane", default = "default_duplicate_pane")]
+    pub duplicate_pane: Vec<KeyBinding>,
+    /// Open a tab with a fresh shell in the active pane's directory
+    #[serde(rename = "duplicateTab", default = "default_duplicate_tab")]
+    pub duplicate_tab: Vec<KeyBinding>,
+    /// Change the font size of the focused pane only
+    #[serde(rename = "zoomPaneIn", default = "default_zoom_pane_in")]
+    pub zoom_pane_in: Vec<KeyBinding>,
+    #[serde(rename = "zoomPaneOut", default = "default_zoom_pane_out")]
+    pub zoom_pane_out: Vec<KeyBinding>,
     #[serde(rename = "newTab", default = "default_new_tab")]
     pub new_tab: Vec<KeyBinding>,
     #[serde(rename = "nextTab", default = "default_next_tab")]
     pub next_tab: Vec<KeyBinding>,
     #[serde(rename = "previousTab", default = "default_previous_tab")]
     pub previous_tab: Vec<KeyBinding>,
+    /// Number keys jumping to a tab: 1-9 go to that tab (or the last one if there are fewer), 0 to the last tab
+    #[serde(rename = "goToTab", default = "default_go_to_tab")]
+    pub go_to_tab: Vec<KeyBinding>,
     #[serde(rename = "goToPrompt", default = "default_go_to_prompt")]
     pub go_to_prompt: Vec<KeyBinding>,
     #[serde(rename = "terminalHistorySearch", default = "default_terminal_history_search")]
     pub terminal_history_search: Vec<KeyBinding>,
     #[serde(rename = "aiCommandGeneration", default = "default_ai_command_generation")]
     pub ai_command_generation: Vec<KeyBinding>,
+    /// Minimize or restore the window; works while the window has focus, see README for an OS-wide hotkey
+    #[serde(rename = "toggleWindow", default = "default_toggle_window")]
+    pub toggle_window: Vec<KeyBinding>,
+    /// Clear the active pane's screen and scrollback
+    #[serde(rename = "clearScrollback", default = "default_clear_scrollback")]
+    pub clear_scrollback: Vec<KeyBinding>,
+    /// Start or stop logging the active pane's output to a file in `terminal.logDirectory`
+    #[serde(rename = "toggleOutputLog", default = "default_toggle_output_log")]
+    pub toggle_output_log: Vec<KeyBinding>,
 }
 
 // Default functions for NavigationHotkeys fields
@@ -357,6 +410,86 @@ fn default_previous_pane() -> Vec<KeyBinding> {
     }]
 }
 
+fn default_cycle_pane() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::RightBracket,
+        key2: None,
+    }]
+}
+
+fn default_cycle_pane_back() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::LeftBracket,
+        key2: None,
+    }]
+}
+
+fn default_swap_pane() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::S,
+        key2: None,
+    }]
+}
+
+fn default_balance_panes() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::B,
+        key2: None,
+    }]
+}
+
+fn default_duplicate_pane() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::D,
+        key2: None,
+    }]
+}
+
+fn default_duplicate_tab() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::N,
+        key2: None,
+    }]
+}
+
+fn default_zoom_pane_in() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: false,
+        alt: false,
+        key: Key::Equals,
+        key2: None,
+    }]
+}
+
+fn default_zoom_pane_out() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: false,
+        alt: false,
+        key: Key::Minus,
+        key2: None,
+    }]
+}
+
 fn default_new_tab() -> Vec<KeyBinding> {
     vec![KeyBinding {
         ctrl: true,
@@ -390,6 +523,30 @@ fn default_previous_tab() -> Vec<KeyBinding> {
     vec![]
 }
 
+fn default_go_to_tab() -> Vec<KeyBinding> {
+    [
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+        Key::Num0,
+    ]
+    .into_iter()
+    .map(|key| KeyBinding {
+        ctrl: false,
+        shift: false,
+        alt: true,
+        key,
+        key2: None,
+    })
+    .collect()
+}
+
 fn default_go_to_prompt() -> Vec<KeyBinding> {
     vec![KeyBinding {
         ctrl: false,
@@ -420,6 +577,36 @@ fn default_ai_command_generation() -> Vec<KeyBinding> {
     }]
 }
 
+fn default_toggle_window() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::F12,
+        key2: None,
+    }]
+}
+
+fn default_clear_scrollback() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::K,
+        key2: None,
+    }]
+}
+
+fn default_toggle_output_log() -> Vec<KeyBinding> {
+    vec![KeyBinding {
+        ctrl: true,
+        shift: true,
+        alt: false,
+        key: Key::L,
+        key2: None,
+    }]
+}
+
 impl Default for NavigationHotkeys {
     fn default() -> Self {
         Self {
@@ -428,12 +615,24 @@ impl Default for NavigationHotkeys {
             close_pane: default_close_pane(),
             next_pane: default_next_pane(),
             previous_pane: default_previous_pane(),
+            cycle_pane: default_cycle_pane(),
+            cycle_pane_back: default_cycle_pane_back(),
+            swap_pane: default_swap_pane(),
+            balance_panes: default_balance_panes(),
+            duplicate_pane: default_duplicate_pane(),
+            duplicate_tab: default_duplicate_tab(),
+            zoom_pane_in: default_zoom_pane_in(),
+            zoom_pane_out: default_zoom_pane_out(),
             new_tab: default_new_tab(),
             next_tab: default_next_tab(),
             previous_tab: default_previous_tab(),
+            go_to_tab: default_go_to_tab(),
             go_to_prompt: default_go_to_prompt(),
             terminal_history_search: default_terminal_history_search(),
             ai_command_generation: default_ai_command_generation(),
+            toggle_window: default_toggle_window(),
+            clear_scrollback: default_clear_scrollback(),
+            toggle_output_log: default_toggle_output_log(),
         }
     }
 }
@@ -448,20 +647,471 @@ pub struct Hotkeys {
 /// Terminal-specific settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalSettings {
-    #[serde(rename = "fontSize")]
+    #[serde(rename = "fontSize", default = "default_font_size")]
     pub font_size: f32,
-    #[serde(rename = "fontFamily")]
+    /// "auto", a font file, or a family name such as "JetBrains Mono Bold"
+    #[serde(rename = "fontFamily", default = "default_font_family")]
     pub font_family: String,
+    #[serde(default = "default_cursor")]
     pub cursor: String,
+    /// Send ESC before keys pressed with Alt, as shell bindings like Alt+b expect
+    #[serde(rename = "altSendsEsc", default = "default_alt_sends_esc")]
+    pub alt_sends_esc: bool,
+    /// Ask before pasting text with newlines unless the app has enabled bracketed paste
+    #[serde(rename = "confirmMultilinePaste", default = "default_confirm_multiline_paste")]
+    pub confirm_multiline_paste: bool,
+    /// Columns between the default tab stops, i.e. where a received tab moves the cursor
+    #[serde(rename = "tabWidth", default = "default_tab_width")]
+    pub tab_width: usize,
+    /// Let programs read the clipboard with OSC 52 queries. This hands the clipboard to anything
+    /// that can print to the terminal, e.g. a remote host over ssh, so it is off by default.
+    #[serde(rename = "allowOsc52Read", default)]
+    pub allow_osc52_read: bool,
+    /// Ctrl+C copies the selection when there is one; off leaves copying to Ctrl+Shift+C and
+    /// always sends the interrupt
+    #[serde(rename = "ctrlCCopiesSelection", default = "default_ctrl_c_copies_selection")]
+    pub ctrl_c_copies_selection: bool,
+    /// Set NO_COLOR=1 for new shells, asking programs for output without colors
+    #[serde(rename = "forceNoColor", default)]
+    pub force_no_color: bool,
+    /// How characters no font has are drawn: "box" (□), "blank", or "hex" for their codepoint
+    #[serde(rename = "missingGlyph", default = "default_missing_glyph")]
+    pub missing_glyph: String,
+    /// Directory for output logs started with the toggleOutputLog hotkey, the home directory if
+    /// unset; `~` and environment variables are expanded
+    #[serde(rename = "logDirectory", default, skip_serializing_if = "Option::is_none")]
+    pub log_directory: Option<String>,
+    /// Strip escape sequences from output logs, leaving plain text
+    #[serde(rename = "logStripEscapes", default = "default_log_strip_escapes")]
+    pub log_strip_escapes: bool,
+}
+
+fn default_font_size() -> f32 {
+    12.0
+}
+
+fn default_font_family() -> String {
+    "auto".to_string()
+}
+
+fn default_cursor() -> String {
+    "pipe".to_string()
+}
+
+fn default_alt_sends_esc() -> bool {
+    true
+}
+
+fn default_confirm_multiline_paste() -> bool {
+    true
+}
+
+fn default_tab_width() -> usize {
+    8
+}
+
+fn default_ctrl_c_copies_selection() -> bool {
+    true
+}
+
+fn default_missing_glyph() -> String {
+    "box".to_string()
+}
+
+fn default_log_strip_escapes() -> bool {
+    true
 }
 
 impl Default for TerminalSettings {
     fn default() -> Self {
         Self {
-            font_size: 12.0,
-            font_family: "auto".to_string(),
-            cursor: "pipe".to_string(),
+            font_size: default_font_size(),
+            font_family: default_font_family(),
+            cursor: default_cursor(),
+            alt_sends_esc: default_alt_sends_esc(),
+            confirm_multiline_paste: default_confirm_multiline_paste(),
+            tab_width: default_tab_width(),
+            allow_osc52_read: false,
+            ctrl_c_copies_selection: default_ctrl_c_copies_selection(),
+            force_no_color: false,
+            missing_glyph: default_missing_glyph(),
+            log_directory: None,
+            log_strip_escapes: default_log_strip_escapes(),
+        }
+    }
+}
+
+impl TerminalSettings {
+    /// The configured directory for output logs with `~` and environment variables expanded
+    pub fn log_directory(&self) -> Option<PathBuf> {
+        let directory = self.log_directory.as_deref().map(str::trim).filter(|dir| !dir.is_empty())?;
+        Some(expand_path(directory, |name| std::env::var(name).ok()))
+    }
+
+    /// The configured font file with `~` and environment variables expanded, or `None` for "auto"
+    pub fn font_path(&self) -> Option<PathBuf> {
+        let family = self.font_family.trim();
+        if family == "auto" {
+            return None;
+        }
+        Some(expand_path(family, |name| std::env::var(name).ok()))
+    }
+}
+
+/// General application behavior
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneralSettings {
+    /// Ask before quitting when the last tab or pane is closed
+    #[serde(rename = "confirmQuit", default = "default_confirm_quit")]
+    pub confirm_quit: bool,
+    /// Only ask when a program other than an idle shell is running in some terminal
+    #[serde(rename = "confirmQuitOnlyIfProcessesRunning", default)]
+    pub confirm_quit_only_if_processes_running: bool,
+    /// Show the CPU usage of the active pane's processes in the tab bar instead of the whole system
+    #[serde(rename = "activePaneCpu", default)]
+    pub active_pane_cpu: bool,
+    /// Ask before closing a tab or pane in which a program other than the shell is running
+    #[serde(rename = "confirmCloseWithJobs", default = "default_confirm_close_with_jobs")]
+    pub confirm_close_with_jobs: bool,
+    /// Directory the first terminal starts in; `~` and environment variables are expanded
+    #[serde(rename = "defaultCwd", default, skip_serializing_if = "Option::is_none")]
+    pub default_cwd: Option<String>,
+}
+
+fn default_confirm_quit() -> bool {
+    true
+}
+
+fn default_confirm_close_with_jobs() -> bool {
+    true
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            confirm_quit: default_confirm_quit(),
+            confirm_quit_only_if_processes_running: false,
+            active_pane_cpu: false,
+            confirm_close_with_jobs: default_confirm_close_with_jobs(),
+            default_cwd: None,
+        }
+    }
+}
+
+impl GeneralSettings {
+    /// Whether quitting needs confirmation; `processes_running` is only called when it matters
+    pub fn needs_quit_confirmation(&self, processes_running: impl FnOnce() -> bool) -> bool {
+        self.confirm_quit && (!self.confirm_quit_only_if_processes_running || processes_running())
+    }
+
+    /// Directory for terminals that have nothing to inherit: `defaultCwd` if it names an
+    /// existing directory, otherwise the directory nist was started from
+    pub fn startup_dir(&self) -> Option<PathBuf> {
+        if let Some(configured) = self.default_cwd.as_deref().filter(|dir| !dir.trim().is_empty()) {
+            let dir = expand_path(configured.trim(), |name| std::env::var(name).ok());
+            if dir.is_dir() {
+                return Some(dir);
+            }
+            eprintln!("[SETTINGS] defaultCwd {:?} is not a directory, using the current directory", dir);
         }
+        std::env::current_dir().ok()
+    }
+}
+
+/// Expand a leading `~` to the home directory and `$VAR`, `${VAR}` or `%VAR%` to the variable's
+/// value, looked up through `var`. Unknown variables are left as written.
+fn expand_path(path: &str, var: impl Fn(&str) -> Option<String>) -> PathBuf {
+    let home_var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    let mut expanded = String::new();
+    let mut rest = path;
+
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        if let Some(home) = var(home_var) {
+            expanded.push_str(&home);
+            rest = &rest[1..];
+        }
+    }
+
+    while let Some(start) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, written_len) = if rest[start..].starts_with("${") {
+            match after[1..].find('}') {
+                Some(end) => (&after[1..end + 1], end + 3),
+                None => ("", 1),
+            }
+        } else if rest[start..].starts_with('%') {
+            match after.find('%') {
+                Some(end) => (&after[..end], end + 2),
+                None => ("", 1),
+            }
+        } else {
+            let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            (&after[..end], end + 1)
+        };
+
+        let written = &rest[start..start + written_len];
+        match var(name).filter(|_| !name.is_empty()) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(written),
+        }
+        rest = &rest[start + written_len..];
+    }
+    expanded.push_str(rest);
+
+    PathBuf::from(expanded)
+}
+
+/// Optional parts of the user interface
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiSettings {
+    /// Show the resident memory of the active pane's processes next to the CPU readout
+    #[serde(rename = "showMemory", default)]
+    pub show_memory: bool,
+    /// Mark background tabs that produced output (a dot) or rang the bell (a bell)
+    #[serde(rename = "tabActivityIndicators", default = "default_tab_activity_indicators")]
+    pub tab_activity_indicators: bool,
+    /// Show a header with each pane's title (or foreground process) when a tab is split
+    #[serde(rename = "showPaneTitles", default)]
+    pub show_pane_titles: bool,
+    /// Show the active pane's columns and rows while the window or a pane divider is being resized
+    #[serde(rename = "showResizeOverlay", default = "default_show_resize_overlay")]
+    pub show_resize_overlay: bool,
+    /// Flash the selection when it is copied
+    #[serde(rename = "copyAnimation", default = "default_copy_animation")]
+    pub copy_animation: bool,
+}
+
+fn default_tab_activity_indicators() -> bool {
+    true
+}
+
+fn default_show_resize_overlay() -> bool {
+    true
+}
+
+fn default_copy_animation() -> bool {
+    true
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            show_memory: false,
+            tab_activity_indicators: default_tab_activity_indicators(),
+            show_pane_titles: false,
+            show_resize_overlay: default_show_resize_overlay(),
+            copy_animation: default_copy_animation(),
+        }
+    }
+}
+
+/// Main window creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSettings {
+    /// Draw the window without OS decorations, with nist's own window controls in the tab bar
+    #[serde(default = "default_window_borderless")]
+    pub borderless: bool,
+    /// Start maximized. Unset, the window reopens the way it was left (maximized on first launch).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximized: Option<bool>,
+    /// Initial size in logical pixels, used when the window is not maximized. Unset, the window
+    /// reopens at the size it was left at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+fn default_window_borderless() -> bool {
+    true
+}
+
+// Window state on first launch, when neither the settings nor the last run give one
+const DEFAULT_WINDOW_MAXIMIZED: bool = true;
+const DEFAULT_WINDOW_WIDTH: u32 = 2376;
+const DEFAULT_WINDOW_HEIGHT: u32 = 1593;
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            borderless: default_window_borderless(),
+            maximized: None,
+            width: None,
+            height: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    /// Whether the size or maximized state is set, which then wins over the geometry saved on exit
+    pub fn sets_geometry(&self) -> bool {
+        self.maximized.is_some() || self.width.is_some() || self.height.is_some()
+    }
+
+    /// Initial size, falling back to the first-launch default for unset dimensions
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.width.unwrap_or(DEFAULT_WINDOW_WIDTH).max(1),
+            self.height.unwrap_or(DEFAULT_WINDOW_HEIGHT).max(1),
+        )
+    }
+
+    pub fn starts_maximized(&self) -> bool {
+        self.maximized.unwrap_or(DEFAULT_WINDOW_MAXIMIZED)
+    }
+}
+
+// Session restore limits
+pub const DEFAULT_RESTORE_OUTPUT_LINES: usize = 100; // Output lines saved per pane by default
+pub const MAX_RESTORE_OUTPUT_LINES: usize = 10_000; // Upper clamp for restoreOutputLines
+pub const DEFAULT_RESTORE_COMMAND_LINES: usize = 5; // Commands saved per pane by default
+pub const MAX_RESTORE_COMMAND_LINES: usize = 1000; // Upper clamp for restoreCommandLines
+
+/// Session state settings: how much history is saved and restored across restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSettings {
+    /// Output lines saved per pane and restored on the next start (default 100, at most 10000)
+    #[serde(rename = "restoreOutputLines", default = "default_restore_output_lines")]
+    pub restore_output_lines: usize,
+    /// Commands kept per pane for the session history (default 5, at most 1000)
+    #[serde(rename = "restoreCommandLines", default = "default_restore_command_lines")]
+    pub restore_command_lines: usize,
+    /// Keep blank lines between output lines in the saved history; off collapses them
+    #[serde(rename = "preserveBlankLines", default = "default_preserve_blank_lines")]
+    pub preserve_blank_lines: bool,
+}
+
+fn default_restore_output_lines() -> usize {
+    DEFAULT_RESTORE_OUTPUT_LINES
+}
+
+fn default_restore_command_lines() -> usize {
+    DEFAULT_RESTORE_COMMAND_LINES
+}
+
+fn default_preserve_blank_lines() -> bool {
+    true
+}
+
+impl Default for StateSettings {
+    fn default() -> Self {
+        Self {
+            restore_output_lines: default_restore_output_lines(),
+            restore_command_lines: default_restore_command_lines(),
+            preserve_blank_lines: default_preserve_blank_lines(),
+        }
+    }
+}
+
+impl StateSettings {
+    /// Clamp the limits to their maximums, returning true if anything changed
+    pub fn clamp_limits(&mut self) -> bool {
+        let original = (self.restore_output_lines, self.restore_command_lines);
+        self.restore_output_lines = self.restore_output_lines.min(MAX_RESTORE_OUTPUT_LINES);
+        self.restore_command_lines = self.restore_command_lines.min(MAX_RESTORE_COMMAND_LINES);
+        original != (self.restore_output_lines, self.restore_command_lines)
+    }
+}
+
+/// Color theme overrides. Colors use `#RRGGBB`, `#RGB` or `rgb:RR/GG/BB` notation.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ThemeSettings {
+    /// Fill color of the cursor; defaults to the foreground color of the cell under it
+    #[serde(rename = "cursorColor", default, skip_serializing_if = "Option::is_none")]
+    pub cursor_color: Option<String>,
+    /// Color of the character under a block cursor; defaults to the cell background
+    #[serde(rename = "cursorTextColor", default, skip_serializing_if = "Option::is_none")]
+    pub cursor_text_color: Option<String>,
+    /// Color of the dividers between panes
+    #[serde(rename = "dividerColor", default, skip_serializing_if = "Option::is_none")]
+    pub divider_color: Option<String>,
+    /// Color of a divider under the pointer, showing it can be dragged
+    #[serde(rename = "dividerHoverColor", default, skip_serializing_if = "Option::is_none")]
+    pub divider_hover_color: Option<String>,
+    /// Color of the border around the active pane when the tab is split
+    #[serde(rename = "activePaneBorderColor", default, skip_serializing_if = "Option::is_none")]
+    pub active_pane_border_color: Option<String>,
+    /// Background of the title header above each pane of a split tab
+    #[serde(rename = "paneHeaderColor", default, skip_serializing_if = "Option::is_none")]
+    pub pane_header_color: Option<String>,
+    /// Background of the active pane's title header
+    #[serde(rename = "paneHeaderActiveColor", default, skip_serializing_if = "Option::is_none")]
+    pub pane_header_active_color: Option<String>,
+    /// Text color of the pane title headers
+    #[serde(rename = "paneHeaderTextColor", default, skip_serializing_if = "Option::is_none")]
+    pub pane_header_text_color: Option<String>,
+    /// Text color of the active pane's title header
+    #[serde(rename = "paneHeaderActiveTextColor", default, skip_serializing_if = "Option::is_none")]
+    pub pane_header_active_text_color: Option<String>,
+    /// Thickness of the dividers between panes in pixels
+    #[serde(rename = "dividerWidth", default, skip_serializing_if = "Option::is_none")]
+    pub divider_width: Option<u32>,
+}
+
+/// Divider thickness limits, so a divider stays visible and doesn't eat into the panes
+const MIN_DIVIDER_WIDTH: u32 = 1;
+const MAX_DIVIDER_WIDTH: u32 = 16;
+const DEFAULT_DIVIDER_WIDTH: u32 = 2;
+
+impl ThemeSettings {
+    /// Resolve the configured cursor color, ignoring values that cannot be parsed
+    pub fn cursor_color(&self) -> Option<Color> {
+        Self::resolve("cursorColor", self.cursor_color.as_deref())
+    }
+
+    /// Resolve the configured block cursor text color, ignoring values that cannot be parsed
+    pub fn cursor_text_color(&self) -> Option<Color> {
+        Self::resolve("cursorTextColor", self.cursor_text_color.as_deref())
+    }
+
+    /// Resolve the configured divider color, ignoring values that cannot be parsed
+    pub fn divider_color(&self) -> Option<Color> {
+        Self::resolve("dividerColor", self.divider_color.as_deref())
+    }
+
+    /// Resolve the configured color of a hovered divider, ignoring values that cannot be parsed
+    pub fn divider_hover_color(&self) -> Option<Color> {
+        Self::resolve("dividerHoverColor", self.divider_hover_color.as_deref())
+    }
+
+    /// Resolve the configured active pane border color, ignoring values that cannot be parsed
+    pub fn active_pane_border_color(&self) -> Option<Color> {
+        Self::resolve("activePaneBorderColor", self.active_pane_border_color.as_deref())
+    }
+
+    /// Resolve the configured pane header background, ignoring values that cannot be parsed
+    pub fn pane_header_color(&self) -> Option<Color> {
+        Self::resolve("paneHeaderColor", self.pane_header_color.as_deref())
+    }
+
+    /// Resolve the configured active pane header background, ignoring values that cannot be parsed
+    pub fn pane_header_active_color(&self) -> Option<Color> {
+        Self::resolve("paneHeaderActiveColor", self.pane_header_active_color.as_deref())
+    }
+
+    /// Resolve the configured pane header text color, ignoring values that cannot be parsed
+    pub fn pane_header_text_color(&self) -> Option<Color> {
+        Self::resolve("paneHeaderTextColor", self.pane_header_text_color.as_deref())
+    }
+
+    /// Resolve the configured active pane header text color, ignoring values that cannot be parsed
+    pub fn pane_header_active_text_color(&self) -> Option<Color> {
+        Self::resolve("paneHeaderActiveTextColor", self.pane_header_active_text_color.as_deref())
+    }
+
+    /// The divider thickness in pixels, clamped to a usable range
+    pub fn divider_width(&self) -> u32 {
+        self.divider_width.unwrap_or(DEFAULT_DIVIDER_WIDTH).clamp(MIN_DIVIDER_WIDTH, MAX_DIVIDER_WIDTH)
+    }
+
+    fn resolve(name: &str, value: Option<&str>) -> Option<Color> {
+        let value = value?;
+        let color = parse_color_spec(value);
+        if color.is_none() {
+            eprintln!("[SETTINGS] Invalid color '{}' for theme.{}, using default", value, name);
+        }
+        color
     }
 }
 
@@ -480,9 +1130,20 @@ pub struct ExternalVendor {
 pub struct Settings {
     #[serde(default)]
     pub external: Vec<ExternalVendor>,
+    #[serde(default)]
     pub terminal: TerminalSettings,
     #[serde(default)]
     pub hotkeys: Hotkeys,
+    #[serde(default)]
+    pub theme: ThemeSettings,
+    #[serde(default)]
+    pub state: StateSettings,
+    #[serde(default)]
+    pub general: GeneralSettings,
+    #[serde(default)]
+    pub ui: UiSettings,
+    #[serde(default)]
+    pub window: WindowSettings,
 }
 
 /// Get the path to the settings file based on build profile
@@ -520,21 +1181,109 @@ pub fn get_settings_path() -> Result<PathBuf, String> {
     get_settings_file_path()
 }
 
-/// Load settings from the settings file
+/// Deserialize one section of the settings file, keeping its valid fields. Fields of the wrong
+/// type and fields the section doesn't have (usually typos) are left out and described in `problems`.
+fn parse_section<T: DeserializeOwned + Serialize + Default>(name: &str, value: Option<&Value>, problems: &mut Vec<String>) -> T {
+    let fields = match value {
+        None => return T::default(),
+        Some(Value::Object(fields)) => fields,
+        Some(other) => {
+            problems.push(format!("{}: expected an object, found {}", name, other));
+            return T::default();
+        }
+    };
+
+    // Each field is tried on its own, so one bad field doesn't take the others down with it
+    let mut valid = Map::new();
+    for (key, field) in fields {
+        let single = Value::Object(Map::from_iter([(key.clone(), field.clone())]));
+        match T::deserialize(&single) {
+            Ok(_) => {
+                valid.insert(key.clone(), field.clone());
+            }
+            Err(e) => problems.push(format!("{}.{}: {}", name, key, e)),
+        }
+    }
+
+    let section = T::deserialize(&Value::Object(valid.clone())).unwrap_or_else(|e| {
+        problems.push(format!("{}: {}", name, e));
+        T::default()
+    });
+
+    // Serde skips fields it doesn't know; they are the ones missing when the section is written back.
+    // Nulls are left alone, as unset optional fields aren't written back either.
+    if let Ok(Value::Object(known)) = serde_json::to_value(&section) {
+        for (key, _) in valid.iter().filter(|(key, field)| !field.is_null() && !known.contains_key(*key)) {
+            problems.push(format!("{}.{}: unknown field", name, key));
+        }
+    }
+
+    section
+}
+
+/// Parse the contents of a settings file. Anything that is valid JSON loads: fields that are
+/// wrong or unknown fall back to their defaults and are returned as problems, one line each.
+/// Only malformed JSON is an error, with the line and column where parsing stopped.
+pub fn parse_settings(contents: &str) -> Result<(Settings, Vec<String>), String> {
+    let value: Value = serde_json::from_str(contents).map_err(|e| format!("Failed to parse settings file: {}", e))?;
+    let Value::Object(root) = value else {
+        return Err("Failed to parse settings file: expected an object".to_string());
+    };
+
+    let mut problems = Vec::new();
+    let external = match root.get("external") {
+        Some(external) => serde_json::from_value(external.clone()).unwrap_or_else(|e| {
+            problems.push(format!("external: {}", e));
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let navigation = parse_section(
+        "hotkeys.navigation",
+        root.get("hotkeys").and_then(|hotkeys| hotkeys.get("navigation")),
+        &mut problems,
+    );
+
+    let settings = Settings {
+        external,
+        terminal: parse_section("terminal", root.get("terminal"), &mut problems),
+        hotkeys: Hotkeys { navigation },
+        theme: parse_section("theme", root.get("theme"), &mut problems),
+        state: parse_section("state", root.get("state"), &mut problems),
+        general: parse_section("general", root.get("general"), &mut problems),
+        ui: parse_section("ui", root.get("ui"), &mut problems),
+        window: parse_section("window", root.get("window"), &mut problems),
+    };
+
+    if let Ok(Value::Object(known)) = serde_json::to_value(Settings::default()) {
+        for key in root.keys().filter(|key| !known.contains_key(*key)) {
+            problems.push(format!("{}: unknown section", key));
+        }
+    }
+
+    Ok((settings, problems))
+}
+
+/// Load settings from the settings file, along with the problems found in it (see `parse_settings`)
 /// If the file doesn't exist, creates it with default settings
-pub fn load_settings() -> Result<Settings, String> {
+pub fn load_settings() -> Result<(Settings, Vec<String>), String> {
     let settings_path = get_settings_file_path()?;
 
     if !settings_path.exists() {
         // Create default settings file
         let default_settings = Settings::default();
         save_settings(&default_settings)?;
-        return Ok(default_settings);
+        return Ok((default_settings, Vec::new()));
     }
 
     let contents = fs::read_to_string(&settings_path).map_err(|e| format!("Failed to read settings file: {}", e))?;
 
-    let mut settings: Settings = serde_json::from_str(&contents).map_err(|e| format!("Failed to parse settings file: {}", e))?;
+    let (mut settings, problems) = parse_settings(&contents)?;
+    for problem in &problems {
+        eprintln!("[SETTINGS] Ignored {}", problem);
+    }
+    // Writing corrections back would also drop whatever the user got wrong; leave the file to them
+    let can_save = problems.is_empty();
 
     // Validate and fix font size (minimum 8.0, maximum 48.0)
     let original_font_size = settings.terminal.font_size;
@@ -546,10 +1295,23 @@ pub fn load_settings() -> Result<Settings, String> {
             "[SETTINGS] Font size {} was out of bounds, corrected to {}",
             original_font_size, settings.terminal.font_size
         );
-        save_settings(&settings)?;
+        if can_save {
+            save_settings(&settings)?;
+        }
+    }
+
+    // Cap the restored history so the state file stays small
+    if settings.state.clamp_limits() {
+        eprintln!(
+            "[SETTINGS] Restore limits were too large, capped to {} output and {} command lines",
+            settings.state.restore_output_lines, settings.state.restore_command_lines
+        );
+        if can_save {
+            save_settings(&settings)?;
+        }
     }
 
-    Ok(settings)
+    Ok((settings, problems))
 }
 
 /// Save settings to the settings file
@@ -574,6 +1336,8 @@ mod tests {
         assert_eq!(settings.terminal.font_size, 12.0);
         assert_eq!(settings.terminal.font_family, "auto");
         assert_eq!(settings.terminal.cursor, "pipe");
+        assert_eq!(settings.state.restore_output_lines, DEFAULT_RESTORE_OUTPUT_LINES);
+        assert_eq!(settings.state.restore_command_lines, DEFAULT_RESTORE_COMMAND_LINES);
         // Verify default hotkeys are present
         assert_eq!(settings.hotkeys.navigation.split_right.len(), 1);
         assert_eq!(settings.hotkeys.navigation.split_down.len(), 1);
@@ -596,6 +1360,156 @@ mod tests {
         assert_eq!(settings.terminal.cursor, deserialized.terminal.cursor);
     }
 
+    #[test]
+    fn test_theme_cursor_colors() {
+        let settings = Settings::default();
+        assert_eq!(settings.theme.cursor_color(), None);
+        assert_eq!(settings.theme.cursor_text_color(), None);
+
+        let json = r##"
+        {
+            "terminal": { "fontSize": 12.0, "fontFamily": "auto", "cursor": "block" },
+            "theme": { "cursorColor": "#ff8000", "cursorTextColor": "rgb:00/00/00" }
+        }
+        "##;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.theme.cursor_color(), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(settings.theme.cursor_text_color(), Some(Color::RGB(0, 0, 0)));
+
+        // Unparseable colors fall back to the default behavior
+        let theme = ThemeSettings {
+            cursor_color: Some("not-a-color".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(theme.cursor_color(), None);
+    }
+
+    #[test]
+    fn test_theme_divider_settings() {
+        let settings = Settings::default();
+        assert_eq!(settings.theme.divider_color(), None);
+        assert_eq!(settings.theme.divider_width(), 2);
+
+        let json = r##"
+        {
+            "terminal": { "fontSize": 12.0, "fontFamily": "auto", "cursor": "block" },
+            "theme": { "dividerColor": "#202020", "dividerHoverColor": "#8ab", "activePaneBorderColor": "rgb:ff/80/00", "dividerWidth": 1 }
+        }
+        "##;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.theme.divider_color(), Some(Color::RGB(32, 32, 32)));
+        assert_eq!(settings.theme.divider_hover_color(), Some(Color::RGB(136, 170, 187)));
+        assert_eq!(settings.theme.active_pane_border_color(), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(settings.theme.divider_width(), 1);
+
+        // A divider can't vanish or swallow the panes
+        let theme = |width| ThemeSettings {
+            divider_width: Some(width),
+            ..Default::default()
+        };
+        assert_eq!(theme(0).divider_width(), 1);
+        assert_eq!(theme(500).divider_width(), 16);
+    }
+
+    #[test]
+    fn test_state_limits_are_clamped() {
+        let json = r#"
+        {
+            "terminal": { "fontSize": 12.0, "fontFamily": "auto", "cursor": "pipe" },
+            "state": { "restoreOutputLines": 1000000 }
+        }
+        "#;
+        let mut settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.state.restore_command_lines, DEFAULT_RESTORE_COMMAND_LINES);
+        assert!(settings.state.clamp_limits());
+        assert_eq!(settings.state.restore_output_lines, MAX_RESTORE_OUTPUT_LINES);
+        assert!(!settings.state.clamp_limits());
+    }
+
+    #[test]
+    fn test_quit_confirmation() {
+        // Default: always ask, without looking at processes
+        let general = GeneralSettings::default();
+        assert!(general.needs_quit_confirmation(|| panic!("processes should not be checked")));
+
+        let general = GeneralSettings {
+            confirm_quit: false,
+            confirm_quit_only_if_processes_running: true,
+            ..GeneralSettings::default()
+        };
+        assert!(!general.needs_quit_confirmation(|| true));
+
+        let general = GeneralSettings {
+            confirm_quit: true,
+            confirm_quit_only_if_processes_running: true,
+            ..GeneralSettings::default()
+        };
+        assert!(general.needs_quit_confirmation(|| true));
+        assert!(!general.needs_quit_confirmation(|| false));
+
+        let settings: Settings =
+            serde_json::from_str(r#"{"terminal": {"fontSize": 12.0, "fontFamily": "auto", "cursor": "pipe"}, "general": {"confirmQuit": false}}"#).unwrap();
+        assert!(!settings.general.confirm_quit);
+        assert!(!settings.general.confirm_quit_only_if_processes_running);
+        assert!(settings.general.confirm_close_with_jobs);
+    }
+
+    #[test]
+    fn test_window_settings_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"terminal": {"fontSize": 12.0, "fontFamily": "auto", "cursor": "pipe"}, "window": {"borderless": false}}"#).unwrap();
+        assert!(!settings.window.borderless);
+        assert!(!settings.window.sets_geometry());
+        assert!(settings.window.starts_maximized());
+        assert_eq!(settings.window.size(), (2376, 1593));
+
+        let default_window = Settings::default().window;
+        assert!(default_window.borderless && !default_window.sets_geometry());
+
+        // Setting only the width still pins the geometry
+        let settings: Settings =
+            serde_json::from_str(r#"{"terminal": {"fontSize": 12.0, "fontFamily": "auto", "cursor": "pipe"}, "window": {"width": 1200}}"#).unwrap();
+        assert!(settings.window.sets_geometry());
+        assert_eq!(settings.window.size(), (1200, 1593));
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let var = |name: &str| match name {
+            "HOME" | "USERPROFILE" => Some("/home/ada".to_string()),
+            "PROJECTS" => Some("/srv/projects".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(expand_path("~", var), PathBuf::from("/home/ada"));
+        assert_eq!(expand_path("~/code", var), PathBuf::from("/home/ada/code"));
+        assert_eq!(expand_path("$PROJECTS/nist", var), PathBuf::from("/srv/projects/nist"));
+        assert_eq!(expand_path("${PROJECTS}_old", var), PathBuf::from("/srv/projects_old"));
+        assert_eq!(expand_path("%PROJECTS%\\nist", var), PathBuf::from("/srv/projects\\nist"));
+        assert_eq!(expand_path("~/$PROJECTS", var), PathBuf::from("/home/ada//srv/projects"));
+
+        // Only a leading tilde means home, unknown or unfinished variables stay as written
+        assert_eq!(expand_path("/tmp/~x", var), PathBuf::from("/tmp/~x"));
+        assert_eq!(expand_path("~ada/code", var), PathBuf::from("~ada/code"));
+        assert_eq!(expand_path("$MISSING/a", var), PathBuf::from("$MISSING/a"));
+        assert_eq!(expand_path("${PROJECTS", var), PathBuf::from("${PROJECTS"));
+        assert_eq!(expand_path("100% $", var), PathBuf::from("100% $"));
+    }
+
+    #[test]
+    fn test_font_path_expansion() {
+        let mut terminal = TerminalSettings::default();
+        assert_eq!(terminal.font_path(), None);
+
+        terminal.font_family = "/usr/share/fonts/Hack.ttf".to_string();
+        assert_eq!(terminal.font_path(), Some(PathBuf::from("/usr/share/fonts/Hack.ttf")));
+
+        if let Ok(home) = std::env::var(if cfg!(windows) { "USERPROFILE" } else { "HOME" }) {
+            terminal.font_family = "~/.fonts/Hack.ttf".to_string();
+            assert_eq!(terminal.font_path(), Some(PathBuf::from(format!("{}/.fonts/Hack.ttf", home))));
+        }
+    }
+
     #[test]
     fn test_config_directory_path() {
         // Test that we can get a config directory path
@@ -712,8 +1626,7 @@ mod tests {
         assert_eq!(Key::ArrowDown.to_keycode(), Some(Keycode::Down));
         assert_eq!(Key::F1.to_keycode(), Some(Keycode::F1));
         assert_eq!(Key::Enter.to_keycode(), Some(Keycode::Return));
-        // Num0 keycode name unknown in SDL3, skipping test
-        // assert_eq!(Key::Num0.to_keycode(), Some(Keycode::Num0));
+        assert_eq!(Key::Num0.to_keycode(), Some(Keycode::_0));
     }
 
     #[test]
@@ -1093,4 +2006,44 @@ mod tests {
             assert_eq!(corrected, size, "Valid font size {} should not be changed", size);
         }
     }
+
+    #[test]
+    fn test_parse_settings_keeps_valid_fields() {
+        let json = r#"{
+            "terminal": { "fontSize": "big", "cursor": "block" },
+            "ui": { "showMemory": true, "showPaneTitles": 1 }
+        }"#;
+        let (settings, problems) = parse_settings(json).unwrap();
+
+        assert_eq!(settings.terminal.font_size, 12.0);
+        assert_eq!(settings.terminal.cursor, "block");
+        assert!(settings.ui.show_memory);
+        assert!(!settings.ui.show_pane_titles);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("terminal.fontSize:"), "{}", problems[0]);
+        assert!(problems[1].starts_with("ui.showPaneTitles:"), "{}", problems[1]);
+    }
+
+    #[test]
+    fn test_parse_settings_reports_unknown_keys() {
+        let json = r#"{
+            "terminal": { "fontSzie": 14, "fontSize": 16 },
+            "theme": { "cursorColor": null },
+            "colours": {}
+        }"#;
+        let (settings, problems) = parse_settings(json).unwrap();
+
+        assert_eq!(settings.terminal.font_size, 16.0);
+        assert_eq!(
+            problems,
+            vec!["terminal.fontSzie: unknown field".to_string(), "colours: unknown section".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_settings_syntax_error_names_line() {
+        let json = "{\n  \"terminal\": {\n    \"fontSize\": 14,\n  }\n}";
+        let error = parse_settings(json).unwrap_err();
+        assert!(error.contains("line 4"), "{}", error);
+    }
 }
@@ -1,7 +1,13 @@
 use crate::pane_layout::{PaneNode, SplitDirection};
+use crate::screen_buffer::Cell;
+use crate::settings::StateSettings;
 use crate::tab_gui::{TabBarGui, TabState};
 use crate::terminal::Terminal;
 use directories::ProjectDirs;
+use sdl3::pixels::Color;
+use sdl3::rect::Rect;
+use sdl3::video::Window;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
@@ -28,13 +34,111 @@ enum SerializablePaneNode {
 
 #[derive(Clone, Debug)]
 struct TerminalHistory {
-    input: Vec<String>,  // Last MAX_COMMAND_HISTORY commands
-    output: Vec<String>, // Last MAX_OUTPUT_HISTORY output lines
+    input: Vec<String>,  // Last restoreCommandLines commands
+    output: Vec<String>, // Last restoreOutputLines output lines
+    // Output rows with colors and attributes; when present, restored instead of the plain lines
+    styled_output: Vec<Vec<Cell>>,
+}
+
+/// Compact serialized form of a screen cell, stored as `[text, fg, bg, flags]`
+/// with colors packed as 0xRRGGBB and the attributes as bitflags
+#[derive(Debug, Clone, PartialEq)]
+struct SerializedCell {
+    text: String,
+    fg: u32,
+    bg: u32,
+    flags: u32,
+}
+
+impl SerializedCell {
+    const BOLD: u32 = 1 << 0;
+    const ITALIC: u32 = 1 << 1;
+    const UNDERLINE: u32 = 1 << 2;
+    const STRIKETHROUGH: u32 = 1 << 3;
+    const BLINK: u32 = 1 << 4;
+    const REVERSE: u32 = 1 << 5;
+    const INVISIBLE: u32 = 1 << 6;
+    // Cell width: set for the first half of a wide character and for its continuation cell
+    const WIDE: u32 = 1 << 7;
+    const CONTINUATION: u32 = 1 << 8;
+
+    fn from_cell(cell: &Cell) -> Self {
+        let pack = |c: Color| ((c.r as u32) << 16) | ((c.g as u32) << 8) | c.b as u32;
+        let flags = [
+            (cell.bold, Self::BOLD),
+            (cell.italic, Self::ITALIC),
+            (cell.underline, Self::UNDERLINE),
+            (cell.strikethrough, Self::STRIKETHROUGH),
+            (cell.blink, Self::BLINK),
+            (cell.reverse, Self::REVERSE),
+            (cell.invisible, Self::INVISIBLE),
+            (cell.width == 2, Self::WIDE),
+            (cell.width == 0, Self::CONTINUATION),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+
+        SerializedCell {
+            text: cell.extended.as_deref().map(|s| s.to_string()).unwrap_or_else(|| cell.ch.to_string()),
+            fg: pack(cell.fg_color),
+            bg: pack(cell.bg_color),
+            flags,
+        }
+    }
+
+    fn to_cell(&self) -> Cell {
+        let unpack = |c: u32| Color::RGB((c >> 16) as u8, (c >> 8) as u8, c as u8);
+        let mut chars = self.text.chars();
+        let ch = chars.next().unwrap_or(' ');
+
+        Cell {
+            ch,
+            extended: if chars.next().is_some() { Some(self.text.as_str().into()) } else { None },
+            fg_color: unpack(self.fg),
+            bg_color: unpack(self.bg),
+            width: if self.flags & Self::WIDE != 0 {
+                2
+            } else if self.flags & Self::CONTINUATION != 0 {
+                0
+            } else {
+                1
+            },
+            bold: self.flags & Self::BOLD != 0,
+            italic: self.flags & Self::ITALIC != 0,
+            underline: self.flags & Self::UNDERLINE != 0,
+            strikethrough: self.flags & Self::STRIKETHROUGH != 0,
+            blink: self.flags & Self::BLINK != 0,
+            reverse: self.flags & Self::REVERSE != 0,
+            invisible: self.flags & Self::INVISIBLE != 0,
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Array(vec![
+            JsonValue::String(self.text.clone()),
+            JsonValue::Number(self.fg as f64),
+            JsonValue::Number(self.bg as f64),
+            JsonValue::Number(self.flags as f64),
+        ])
+    }
+
+    fn from_json(json: &JsonValue) -> Option<Self> {
+        let parts = json.get::<Vec<JsonValue>>()?;
+        let number = |i: usize| parts.get(i).and_then(|v| v.get::<f64>()).map(|v| *v as u32);
+        Some(SerializedCell {
+            text: parts.first()?.get::<String>()?.clone(),
+            fg: number(1)?,
+            bg: number(2)?,
+            flags: number(3)?,
+        })
+    }
 }
 
 impl SerializablePaneNode {
     /// Convert a PaneNode to a serializable structure (without terminals)
-    fn from_pane_node(node: &PaneNode) -> Self {
+    /// keeping as much history per terminal as the state settings allow
+    fn from_pane_node(node: &PaneNode, limits: &StateSettings) -> Self {
         match node {
             PaneNode::Leaf { terminal, .. } => {
                 // Extract current working directory from terminal
@@ -46,14 +150,18 @@ impl SerializablePaneNode {
 
                 // Capture output history before extracting
                 if let Ok(t) = terminal.lock() {
-                    t.capture_output_history();
+                    t.capture_output_history(limits.restore_output_lines, limits.preserve_blank_lines);
                 }
 
                 // Extract command and output history
                 let history = terminal.lock().ok().map(|t| {
-                    let input = t.get_command_history();
+                    let input = t.get_command_history(limits.restore_command_lines);
                     let output = t.get_output_history();
-                    TerminalHistory { input, output }
+                    TerminalHistory {
+                        input,
+                        output,
+                        styled_output: t.get_styled_output_history(),
+                    }
                 });
 
                 SerializablePaneNode::Leaf { working_directory, history }
@@ -70,8 +178,8 @@ impl SerializablePaneNode {
                     SplitDirection::Vertical => "vertical".to_string(),
                 },
                 ratio: *ratio as f64,
-                first: Box::new(SerializablePaneNode::from_pane_node(first)),
-                second: Box::new(SerializablePaneNode::from_pane_node(second)),
+                first: Box::new(SerializablePaneNode::from_pane_node(first, limits)),
+                second: Box::new(SerializablePaneNode::from_pane_node(second, limits)),
             },
         }
     }
@@ -96,6 +204,17 @@ impl SerializablePaneNode {
                         "output".to_string(),
                         JsonValue::Array(hist.output.iter().map(|s| JsonValue::String(s.clone())).collect()),
                     );
+                    if !hist.styled_output.is_empty() {
+                        history_map.insert(
+                            "styled_output".to_string(),
+                            JsonValue::Array(
+                                hist.styled_output
+                                    .iter()
+                                    .map(|row| JsonValue::Array(row.iter().map(|cell| SerializedCell::from_cell(cell).to_json()).collect()))
+                                    .collect(),
+                            ),
+                        );
+                    }
                     map.insert("history".to_string(), JsonValue::Object(history_map));
                 }
                 JsonValue::Object(map)
@@ -139,7 +258,23 @@ impl SerializablePaneNode {
                         .and_then(|v| v.get::<Vec<JsonValue>>())
                         .map(|arr| arr.iter().filter_map(|v| v.get::<String>().cloned()).collect::<Vec<String>>())
                         .unwrap_or_default();
-                    Some(TerminalHistory { input, output })
+                    // Older state files only have the plain output lines
+                    let styled_output = hist_map
+                        .get("styled_output")
+                        .and_then(|v| v.get::<Vec<JsonValue>>())
+                        .map(|rows| {
+                            rows.iter()
+                                .filter_map(|row| row.get::<Vec<JsonValue>>())
+                                .map(|row| {
+                                    row.iter()
+                                        .filter_map(SerializedCell::from_json)
+                                        .map(|cell| cell.to_cell())
+                                        .collect::<Vec<Cell>>()
+                                })
+                                .collect::<Vec<Vec<Cell>>>()
+                        })
+                        .unwrap_or_default();
+                    Some(TerminalHistory { input, output, styled_output })
                 });
 
                 Some(SerializablePaneNode::Leaf { working_directory, history })
@@ -175,7 +310,11 @@ impl SerializablePaneNode {
                 if let Some(hist) = history {
                     if let Ok(term) = terminal.lock() {
                         term.set_command_history(hist.input.clone());
-                        term.set_output_history(hist.output.clone());
+                        if hist.styled_output.is_empty() {
+                            term.set_output_history(hist.output.clone());
+                        } else {
+                            term.set_styled_output_history(hist.styled_output.clone());
+                        }
                     }
                 }
 
@@ -238,8 +377,97 @@ fn get_state_file_path() -> Result<PathBuf, String> {
     Ok(config_dir.join("state.json"))
 }
 
-/// Save the current tab-pane layout state
-pub fn save_state(tab_bar: &TabBarGui) -> Result<(), String> {
+/// Window size and position from the last run, stored in window.json next to state.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    /// Minimized, e.g. with the toggle hotkey, so the next launch starts minimized as well
+    #[serde(default)]
+    pub minimized: bool,
+}
+
+impl WindowGeometry {
+    pub fn from_window(window: &Window) -> Self {
+        let (x, y) = window.position();
+        let (width, height) = window.size();
+        Self {
+            x,
+            y,
+            width,
+            height,
+            maximized: window.is_maximized(),
+            minimized: window.is_minimized(),
+        }
+    }
+
+    /// Follow the window's current state. Position and size are only taken while the window is
+    /// neither maximized nor minimized, so the size saved is the one to restore to.
+    pub fn track(&mut self, window: &Window) {
+        self.follow(Self::from_window(window));
+    }
+
+    fn follow(&mut self, current: WindowGeometry) {
+        if current.minimized {
+            self.minimized = true;
+        } else if current.maximized {
+            self.maximized = true;
+            self.minimized = false;
+        } else {
+            *self = current;
+        }
+    }
+
+    /// Fit the geometry onto the display showing its center, or onto `primary` when that
+    /// display is gone, shrinking it to the display and moving it fully inside
+    pub fn clamp_to_displays(&self, displays: &[Rect], primary: Rect) -> Self {
+        let center = (self.x + self.width as i32 / 2, self.y + self.height as i32 / 2);
+        let display = displays.iter().copied().find(|bounds| bounds.contains_point(center)).unwrap_or(primary);
+
+        let width = self.width.clamp(1, display.width());
+        let height = self.height.clamp(1, display.height());
+        Self {
+            x: self.x.clamp(display.x(), display.x() + (display.width() - width) as i32),
+            y: self.y.clamp(display.y(), display.y() + (display.height() - height) as i32),
+            width,
+            height,
+            maximized: self.maximized,
+            minimized: self.minimized,
+        }
+    }
+}
+
+fn get_window_geometry_path() -> Result<PathBuf, String> {
+    Ok(get_state_file_path()?.with_file_name("window.json"))
+}
+
+/// Remember the window geometry for the next launch
+pub fn save_window_geometry(geometry: &WindowGeometry) -> Result<(), String> {
+    let path = get_window_geometry_path()?;
+    let json = serde_json::to_string_pretty(geometry).map_err(|e| format!("Failed to serialize window geometry: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write window geometry: {}", e))?;
+    eprintln!("[STATE] Saved window geometry to: {:?}", path);
+    Ok(())
+}
+
+/// Window geometry from the last run, None on first launch or if the file is unreadable
+pub fn load_window_geometry() -> Option<WindowGeometry> {
+    let path = get_window_geometry_path().ok()?;
+    let json = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&json) {
+        Ok(geometry) => Some(geometry),
+        Err(e) => {
+            eprintln!("[STATE] Ignoring invalid window geometry: {}", e);
+            None
+        }
+    }
+}
+
+/// Save the current tab-pane layout state, including the history allowed by `limits` for each pane
+pub fn save_state(tab_bar: &TabBarGui, limits: &StateSettings) -> Result<(), String> {
     let state_path = get_state_file_path()?;
 
     // Build state structure
@@ -257,9 +485,10 @@ pub fn save_state(tab_bar: &TabBarGui) -> Result<(), String> {
     for tab_state in &tab_bar.tab_states {
         let mut tab_map = HashMap::new();
         tab_map.insert("name".to_string(), JsonValue::String(tab_state.name.clone()));
+        tab_map.insert("custom_name".to_string(), JsonValue::Boolean(tab_state.custom_name));
 
         // Serialize pane layout
-        let serializable_layout = SerializablePaneNode::from_pane_node(&tab_state.pane_layout.root);
+        let serializable_layout = SerializablePaneNode::from_pane_node(&tab_state.pane_layout.root, limits);
         tab_map.insert("pane_layout".to_string(), serializable_layout.to_json());
 
         // Save active pane index (we'll just save 0 for now since we can't easily serialize the PaneId)
@@ -390,14 +619,17 @@ where
             active_pane: crate::pane_layout::PaneId(0), // Will be set to first leaf
             dragging_divider: None,
             drag_preview: None,
-            #[cfg(target_os = "linux")]
-            primary_clipboard: None,
             context_menu_images: None,
             context_menu_open: None,
             context_menu: None,
             pending_context_action: None,
             copy_animation: None,
+            toast: None,
+            resize_overlay: None,
             selected_panes: std::collections::HashSet::new(),
+            font_scales: std::collections::HashMap::new(),
+            header_height: 0,
+            divider_width: crate::pane_layout::DEFAULT_DIVIDER_WIDTH,
         };
 
         // Set active pane to the first leaf
@@ -407,12 +639,22 @@ where
             pane_layout.active_pane = *first_id;
         }
 
+        // Older state files don't record this, so treat anything but a generated name as custom
+        let custom_name = tab_obj
+            .get("custom_name")
+            .and_then(|v| v.get::<bool>())
+            .copied()
+            .unwrap_or_else(|| !crate::tab_gui::is_default_tab_name(tab_name));
+
         let tab_state = TabState {
             pane_layout,
             name: tab_name.clone(),
+            custom_name,
+            process_name: None,
             is_editing: false,
             temp_name: tab_name.clone(),
             cursor_pos: 0,
+            activity: crate::tab_gui::TabActivity::default(),
         };
 
         tab_bar.tab_states.push(tab_state);
@@ -513,6 +755,233 @@ fn escape_json_string(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::screen_buffer::{CursorStyle, ScreenBuffer, DEFAULT_TAB_WIDTH};
+
+    #[test]
+    fn test_styled_history_round_trip() {
+        let red = Color::RGB(205, 49, 49);
+        let mut row = vec![Cell::default(); 3];
+        row[0] = Cell {
+            ch: 'E',
+            fg_color: red,
+            bold: true,
+            ..Cell::default()
+        };
+        row[1].ch = 'r';
+
+        let node = SerializablePaneNode::Leaf {
+            working_directory: None,
+            history: Some(TerminalHistory {
+                input: Vec::new(),
+                output: vec!["Er".to_string()],
+                styled_output: vec![row],
+            }),
+        };
+        let json: JsonValue = format_json(&node.to_json()).parse().unwrap();
+
+        let Some(SerializablePaneNode::Leaf { history: Some(history), .. }) = SerializablePaneNode::from_json(&json) else {
+            panic!("expected a leaf with history");
+        };
+        let mut sb = ScreenBuffer::new_with_scrollback(10, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        sb.restore_styled_to_scrollback(history.styled_output);
+
+        let restored = &sb.get_scrollback_buffer()[0];
+        assert_eq!(restored[0].ch, 'E');
+        assert_eq!(restored[0].fg_color, red);
+        assert!(restored[0].bold);
+        assert_eq!(restored[1].ch, 'r');
+        assert!(!restored[1].bold);
+        assert_eq!(restored[1].fg_color, crate::ansi::DEFAULT_FG_COLOR);
+    }
+
+    #[test]
+    fn test_scrollback_round_trip_into_narrower_terminal() {
+        use crate::terminal::main::collect_output_history;
+
+        let red = Color::RGB(205, 49, 49);
+        let mut sb = ScreenBuffer::new_with_scrollback(30, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for line in ["one", "", "abcdefghijklmnopqrstuvwxyz", "$ "] {
+            if line.starts_with('a') {
+                sb.fg_color = red;
+            }
+            for ch in line.chars() {
+                sb.put_grapheme(&ch.to_string());
+            }
+            sb.fg_color = crate::ansi::DEFAULT_FG_COLOR;
+            sb.cursor_x = 0;
+            sb.newline();
+        }
+        sb.put_grapheme("中");
+
+        let (output, styled_output) = collect_output_history(&sb, StateSettings::default().restore_output_lines, true);
+        assert_eq!(output, vec!["one", "", "abcdefghijklmnopqrstuvwxyz", "$", "中"]);
+
+        let node = SerializablePaneNode::Leaf {
+            working_directory: None,
+            history: Some(TerminalHistory {
+                input: Vec::new(),
+                output,
+                styled_output,
+            }),
+        };
+        let json: JsonValue = format_json(&node.to_json()).parse().unwrap();
+        let Some(SerializablePaneNode::Leaf { history: Some(history), .. }) = SerializablePaneNode::from_json(&json) else {
+            panic!("expected a leaf with history");
+        };
+
+        // The blank line survives, the long line wraps instead of being cut off
+        let mut restored = ScreenBuffer::new_with_scrollback(12, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        restored.restore_styled_to_scrollback(history.styled_output);
+        let text: Vec<String> = restored
+            .get_scrollback_buffer()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .filter(|cell| cell.width > 0)
+                    .map(|cell| cell.ch)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(text, vec!["one", "", "abcdefghijkl", "mnopqrstuvwx", "yz", "$", "中"]);
+        assert_eq!(restored.get_scrollback_buffer()[4][1].fg_color, red);
+        assert!(restored.get_scrollback_buffer().iter().all(|row| row.len() == 12));
+    }
+
+    #[test]
+    fn test_serialized_cells_round_trip() {
+        let mut sb = ScreenBuffer::new_with_scrollback(10, 2, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        sb.fg_color = Color::RGB(205, 49, 49);
+        sb.bg_color = Color::RGB(0, 0, 128);
+        sb.bold = true;
+        sb.put_grapheme("E");
+        sb.bold = false;
+        sb.italic = true;
+        sb.underline = true;
+        sb.put_grapheme("中");
+        sb.italic = false;
+        sb.underline = false;
+        sb.reverse = true;
+        sb.put_grapheme("👍🏽");
+        let row: Vec<Cell> = (0..sb.width()).filter_map(|x| sb.get_cell(x, 0).cloned()).collect();
+
+        let serialized = SerializedCell::from_cell(&row[0]);
+        assert_eq!(
+            serialized,
+            SerializedCell {
+                text: "E".to_string(),
+                fg: 0xCD3131,
+                bg: 0x000080,
+                flags: SerializedCell::BOLD,
+            }
+        );
+
+        let json = JsonValue::Array(row.iter().map(|cell| SerializedCell::from_cell(cell).to_json()).collect());
+        let parsed: JsonValue = format_json(&json).parse().unwrap();
+        let restored: Vec<Cell> = parsed
+            .get::<Vec<JsonValue>>()
+            .unwrap()
+            .iter()
+            .map(|v| SerializedCell::from_json(v).unwrap().to_cell())
+            .collect();
+
+        assert_eq!(restored.len(), row.len());
+        for (original, restored) in row.iter().zip(&restored) {
+            assert_eq!(restored.ch, original.ch);
+            assert_eq!(restored.extended, original.extended);
+            assert_eq!(restored.fg_color, original.fg_color);
+            assert_eq!(restored.bg_color, original.bg_color);
+            assert_eq!(restored.width, original.width);
+            assert_eq!(
+                (restored.bold, restored.italic, restored.underline, restored.reverse),
+                (original.bold, original.italic, original.underline, original.reverse)
+            );
+        }
+        assert_eq!(restored[1].width, 2);
+        assert_eq!(restored[2].width, 0);
+        assert_eq!(restored[3].extended.as_deref(), Some("👍🏽"));
+    }
+
+    #[test]
+    fn test_window_geometry_round_trip() {
+        let geometry = WindowGeometry {
+            x: 1980,
+            y: 40,
+            width: 1600,
+            height: 900,
+            maximized: false,
+            minimized: false,
+        };
+        let json = serde_json::to_string(&geometry).unwrap();
+        assert_eq!(serde_json::from_str::<WindowGeometry>(&json).unwrap(), geometry);
+
+        // Still on the second monitor: kept as is
+        let primary = Rect::new(0, 0, 1920, 1080);
+        let displays = [primary, Rect::new(1920, 0, 2560, 1440)];
+        assert_eq!(geometry.clamp_to_displays(&displays, primary), geometry);
+
+        // Second monitor unplugged: moved and shrunk onto the primary display
+        let clamped = geometry.clamp_to_displays(&[primary], primary);
+        assert_eq!(clamped, WindowGeometry { x: 320, y: 40, ..geometry });
+
+        let oversized = WindowGeometry {
+            x: -50,
+            y: -50,
+            width: 4000,
+            height: 3000,
+            maximized: true,
+            minimized: true,
+        };
+        assert_eq!(
+            oversized.clamp_to_displays(&[primary], primary),
+            WindowGeometry {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+                maximized: true,
+                minimized: true,
+            }
+        );
+
+        // Maximizing and minimizing keep the bounds to restore to
+        let mut tracked = geometry;
+        tracked.follow(WindowGeometry {
+            x: 0,
+            y: 0,
+            width: 2560,
+            height: 1440,
+            maximized: true,
+            minimized: false,
+        });
+        assert_eq!(tracked, WindowGeometry { maximized: true, ..geometry });
+        tracked.follow(WindowGeometry { minimized: true, ..tracked });
+        assert_eq!(
+            tracked,
+            WindowGeometry {
+                maximized: true,
+                minimized: true,
+                ..geometry
+            }
+        );
+        tracked.follow(WindowGeometry { x: 10, ..geometry });
+        assert_eq!(tracked, WindowGeometry { x: 10, ..geometry });
+
+        // Files from before the toggle hotkey existed
+        let old: WindowGeometry = serde_json::from_str(r#"{"x": 0, "y": 0, "width": 800, "height": 600, "maximized": false}"#).unwrap();
+        assert!(!old.minimized);
+    }
+
+    #[test]
+    fn test_plain_history_still_loads() {
+        let json: JsonValue = r#"{"type": "leaf", "history": {"input": ["ls"], "output": ["a", "b"]}}"#.parse().unwrap();
+        let Some(SerializablePaneNode::Leaf { history: Some(history), .. }) = SerializablePaneNode::from_json(&json) else {
+            panic!("expected a leaf with history");
+        };
+        assert_eq!(history.output, vec!["a", "b"]);
+        assert!(history.styled_output.is_empty());
+    }
 
     #[test]
     fn test_config_directory_path() {
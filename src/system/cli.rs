@@ -4,12 +4,35 @@
 //! - Help and version information display
 //! - Test server port configuration
 //! - Early exit for non-GUI modes
+//! - Headless mode that runs a script through the VT parser without SDL
+//! - Recording of raw terminal output for debugging
+
+use crate::screen_buffer::{CursorStyle, ScreenBuffer, DEFAULT_TAB_WIDTH};
+use crate::terminal::sequences::process_output;
+use std::sync::{Arc, Mutex};
+
+/// Screen size used by headless mode when --cols / --rows are not given
+const DEFAULT_HEADLESS_COLS: usize = 80;
+const DEFAULT_HEADLESS_ROWS: usize = 24;
 
 /// CLI arguments parsed from command line
 #[derive(Debug)]
 pub struct CliArgs {
     /// Port number for test server (if enabled)
     pub test_port: Option<u16>,
+    /// Script to run without a window (if --headless was given)
+    pub headless: Option<HeadlessArgs>,
+    /// File to record raw terminal output to (if --record-output was given)
+    pub record_output: Option<String>,
+}
+
+/// Options for running a script headless
+#[derive(Debug)]
+pub struct HeadlessArgs {
+    /// File whose bytes are fed to the terminal as program output
+    pub script: String,
+    pub cols: usize,
+    pub rows: usize,
 }
 
 /// Parse command line arguments and handle help/version flags.
@@ -25,6 +48,10 @@ pub struct CliArgs {
 pub fn parse_args(build_date: &str, git_hash: &str) -> CliArgs {
     let args: Vec<String> = std::env::args().collect();
     let mut test_port: Option<u16> = None;
+    let mut headless_script: Option<String> = None;
+    let mut record_output: Option<String> = None;
+    let mut cols = DEFAULT_HEADLESS_COLS;
+    let mut rows = DEFAULT_HEADLESS_ROWS;
 
     // Handle --help and --version before initializing SDL
     for arg in args.iter().skip(1) {
@@ -47,7 +74,80 @@ pub fn parse_args(build_date: &str, git_hash: &str) -> CliArgs {
         }
     }
 
-    CliArgs { test_port }
+    // Parse headless mode arguments
+    for (i, arg) in args.iter().enumerate() {
+        if i + 1 >= args.len() {
+            break;
+        }
+        match arg.as_str() {
+            "--headless" => headless_script = Some(args[i + 1].clone()),
+            "--cols" => cols = parse_dimension(&args[i + 1], "--cols"),
+            "--rows" => rows = parse_dimension(&args[i + 1], "--rows"),
+            _ => {}
+        }
+    }
+
+    // Parse --record-output argument
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--record-output" && i + 1 < args.len() {
+            record_output = Some(args[i + 1].clone());
+        }
+    }
+
+    CliArgs {
+        test_port,
+        headless: headless_script.map(|script| HeadlessArgs { script, cols, rows }),
+        record_output,
+    }
+}
+
+/// Parse a positive screen dimension, exiting with an error message if it is invalid
+fn parse_dimension(value: &str, flag: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            eprintln!("[CLI] Invalid value for {}: {}", flag, value);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Feed a script through the VT parser on a screen of the given size and return the
+/// visible lines. No window, PTY or shell is involved, so this runs on CI machines.
+pub fn run_headless(args: &HeadlessArgs) -> Result<Vec<String>, String> {
+    let script = std::fs::read(&args.script).map_err(|e| format!("Failed to read script {}: {}", args.script, e))?;
+    let text = String::from_utf8_lossy(&script);
+
+    let screen_buffer = Arc::new(Mutex::new(ScreenBuffer::new_with_scrollback(
+        args.cols,
+        args.rows,
+        0,
+        CursorStyle::default(),
+        DEFAULT_TAB_WIDTH,
+    )));
+    let saved_screen_buffer = Arc::new(Mutex::new(Vec::new()));
+    // Replies to queries (e.g. cursor position reports) have no program to go to
+    let writer: Arc<Mutex<Box<dyn std::io::Write + Send>>> = Arc::new(Mutex::new(Box::new(std::io::sink())));
+    let last_command_exit_code = Arc::new(Mutex::new(None));
+    let default_cursor_style = Arc::new(Mutex::new(CursorStyle::default()));
+    let keypad_application_mode = Arc::new(Mutex::new(false));
+
+    let incomplete = process_output(
+        &text,
+        &screen_buffer,
+        &saved_screen_buffer,
+        &writer,
+        &last_command_exit_code,
+        &default_cursor_style,
+        &keypad_application_mode,
+        None,
+    );
+    if !incomplete.is_empty() {
+        eprintln!("[CLI] Script ends inside an escape sequence: {:?}", incomplete);
+    }
+
+    let lines = screen_buffer.lock().unwrap().get_visible_text();
+    Ok(lines)
 }
 
 /// Print help information and usage
@@ -61,6 +161,11 @@ fn print_help(build_date: &str, git_hash: &str) {
     println!("    -h, --help          Print help information");
     println!("    -v, --version       Print version information");
     println!("    --test-port <PORT>  Enable test server on specified port");
+    println!("    --headless <FILE>   Feed FILE to the terminal without a window and print the screen");
+    println!("    --cols <N>          Headless screen width (default {})", DEFAULT_HEADLESS_COLS);
+    println!("    --rows <N>          Headless screen height (default {})", DEFAULT_HEADLESS_ROWS);
+    println!("    --record-output <FILE>");
+    println!("                        Write the raw output of each terminal to FILE, FILE.2, ... for debugging");
 }
 
 /// Print version information
@@ -0,0 +1,110 @@
+//! System clipboard access.
+//!
+//! Copy, paste and the escape sequence parser go through `ClipboardProvider`, so their logic
+//! can be tested with `MockClipboard` and other backends can be slotted in without touching
+//! the callers. `SystemClipboard` is the arboard implementation used by the app.
+
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex};
+
+/// Source and destination of copied text
+pub trait ClipboardProvider {
+    fn get(&mut self) -> Option<String>;
+    fn set(&mut self, text: &str);
+
+    /// The primary selection (select to copy, middle click to paste). Platforms without one
+    /// have nothing to read and ignore writes.
+    fn get_primary(&mut self) -> Option<String> {
+        None
+    }
+
+    fn set_primary(&mut self, _text: &str) {}
+}
+
+/// The system clipboard, opened through arboard on each access. Clones share the primary
+/// selection they set.
+#[derive(Clone, Default)]
+pub struct SystemClipboard {
+    /// Owner of the primary selection. X11 selections are served by the client that set them,
+    /// so the clipboard that set it is kept alive until the next selection replaces it.
+    #[cfg(target_os = "linux")]
+    primary_owner: Arc<Mutex<Option<arboard::Clipboard>>>,
+}
+
+impl ClipboardProvider for SystemClipboard {
+    fn get(&mut self) -> Option<String> {
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                eprintln!("[CLIPBOARD] Failed to read clipboard: {}", e);
+                None
+            }
+        }
+    }
+
+    fn set(&mut self, text: &str) {
+        if let Err(e) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            eprintln!("[CLIPBOARD] Failed to copy: {}", e);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn get_primary(&mut self) -> Option<String> {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get().clipboard(LinuxClipboardKind::Primary).text()) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                eprintln!("[PRIMARY] Failed to get PRIMARY clipboard text: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_primary(&mut self, text: &str) {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+        let text = text.to_string();
+        let primary_owner = Arc::clone(&self.primary_owner);
+
+        // Connecting to the display can be slow, so the selection is taken in the background
+        std::thread::spawn(move || match arboard::Clipboard::new() {
+            Ok(mut clipboard) => {
+                if let Err(e) = clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text) {
+                    eprintln!("[PRIMARY] Failed to copy to primary selection: {}", e);
+                } else if let Ok(mut owner) = primary_owner.lock() {
+                    *owner = Some(clipboard);
+                }
+            }
+            Err(e) => {
+                eprintln!("[PRIMARY] Failed to create clipboard: {}", e);
+            }
+        });
+    }
+}
+
+/// In-memory clipboard for tests
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockClipboard {
+    pub text: Option<String>,
+    pub primary: Option<String>,
+}
+
+#[cfg(test)]
+impl ClipboardProvider for MockClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set(&mut self, text: &str) {
+        self.text = Some(text.to_string());
+    }
+
+    fn get_primary(&mut self) -> Option<String> {
+        self.primary.clone()
+    }
+
+    fn set_primary(&mut self, text: &str) {
+        self.primary = Some(text.to_string());
+    }
+}
@@ -11,15 +11,11 @@ use crate::settings;
 use crate::state;
 use crate::tab_gui::TabBarGui;
 use crate::terminal::{Terminal, TerminalLibrary};
-use arboard::Clipboard;
 use sdl3::render::{Canvas, TextureCreator};
 use sdl3::ttf::Sdl3TtfContext;
 use sdl3::video::{Window, WindowContext};
-use std::collections::HashMap;
 #[cfg(not(target_os = "windows"))]
 use std::sync::mpsc::channel;
-#[cfg(target_os = "linux")]
-use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use sysinfo::System;
 
@@ -39,6 +35,10 @@ pub struct Fonts<'a> {
     pub context_menu_font: sdl3::ttf::Font<'a>,
     /// Emoji font for emoji rendering
     pub emoji_font: sdl3::ttf::Font<'a>,
+    /// Emoji font at the tab font size, for emoji in tab titles
+    pub ui_emoji_font: sdl3::ttf::Font<'a>,
+    /// Emoji font at the context menu font size, for emoji in menu captions
+    pub context_menu_emoji_font: sdl3::ttf::Font<'a>,
     /// Unicode fallback font for symbols
     pub unicode_fallback_font: sdl3::ttf::Font<'a>,
     /// CJK font for Chinese, Japanese, Korean characters
@@ -74,11 +74,9 @@ pub struct InitializedApp<'a> {
     pub sys: System,
     pub ctrl_keys: std::collections::HashMap<sdl3::keyboard::Scancode, u8>,
     pub mouse_state: crate::input::mouse::MouseState,
-    pub glyph_cache: HashMap<String, sdl3::render::Texture<'a>>,
-    #[cfg(target_os = "linux")]
-    pub clipboard_tx: Sender<Clipboard>,
-    #[cfg(target_os = "linux")]
-    pub clipboard_rx: Receiver<Clipboard>,
+    /// Window bounds to save on exit, see `WindowGeometry::track`
+    pub window_geometry: state::WindowGeometry,
+    pub glyph_atlas: crate::ui::glyph_atlas::GlyphAtlas<'a>,
     #[cfg(not(target_os = "windows"))]
     pub signal_rx: std::sync::mpsc::Receiver<i32>,
     #[cfg(feature = "test-server")]
@@ -99,7 +97,11 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
     #[cfg(not(target_os = "windows"))]
     let signal_rx = setup_signal_handlers()?;
 
-    let (window_width, window_height) = (2376_u32, 1593_u32);
+    // Load settings
+    let (settings, settings_problems) = settings::load_settings().unwrap_or_else(|e| {
+        eprintln!("[INIT] Failed to load settings, using defaults: {}", e);
+        (settings::Settings::default(), vec![e])
+    });
 
     let sdl_context = sdl3::init().unwrap();
 
@@ -109,7 +111,7 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
     let video_subsystem = sdl_context.video().unwrap();
 
     // Create window with high DPI awareness
-    let mut window = create_window(&video_subsystem, window_width, window_height)?;
+    let (mut window, window_geometry) = create_window(&video_subsystem, &settings.window)?;
 
     // Set window icon
     set_window_icon(&mut window);
@@ -123,12 +125,6 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
     // Get window dimensions
     let (drawable_width, drawable_height) = canvas.window().size_in_pixels();
 
-    // Load settings
-    let settings = settings::load_settings().unwrap_or_else(|e| {
-        eprintln!("[INIT] Failed to load settings, using defaults: {}", e);
-        settings::Settings::default()
-    });
-
     // Load all fonts
     let fonts = load_fonts(ttf_context, &settings, scale_info.scale_factor)?;
 
@@ -138,14 +134,11 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
     // Set up rendering components
     let texture_creator = canvas.texture_creator();
     let event_pump = sdl_context.event_pump().map_err(|e| e.to_string())?;
+    install_waker(&sdl_context)?;
 
     // Enable text input for terminal typing
     canvas.window().subsystem().text_input().start(canvas.window());
 
-    // Set up clipboard channel (Linux only)
-    #[cfg(target_os = "linux")]
-    let (clipboard_tx, clipboard_rx) = channel();
-
     // Initialize system monitor
     let sys = System::new_all();
 
@@ -155,7 +148,9 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
 
     // Calculate tab bar height with scaling
     let tab_bar_height = (36.0 * scale_info.scale_factor) as u32;
-    let tab_bar = crate::sdl_renderer::TabBar::new(tab_bar_height);
+    let mut tab_bar = crate::sdl_renderer::TabBar::new(tab_bar_height);
+    // Decorated windows get their controls from the OS
+    tab_bar.show_window_controls = settings.window.borderless;
 
     // Calculate terminal dimensions
     let terminal_height = ((drawable_height - tab_bar_height) as f32 / char_dims.height).floor() as u32;
@@ -163,11 +158,40 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
 
     // Initialize tab bar GUI with state loading
     let cursor_style = crate::screen_buffer::CursorStyle::from_settings_string(&settings.terminal.cursor);
-    let tab_bar_gui = initialize_tab_bar_gui(terminal_width, terminal_height, shell_config, default_scrollback_lines, cursor_style);
+    let tab_bar_gui = initialize_tab_bar_gui(
+        terminal_width,
+        terminal_height,
+        shell_config,
+        default_scrollback_lines,
+        cursor_style,
+        crate::terminal::main::TerminalOptions::from_settings(&settings.terminal),
+        settings.general.startup_dir(),
+    );
 
     // Set context menu images
     load_and_set_context_menu_images(&tab_bar_gui);
 
+    // Point out settings that were ignored, the full list is in the log
+    if let Some(first) = settings_problems.first() {
+        let message = match settings_problems.len() {
+            1 => format!("Settings: {}", first),
+            n => format!("Settings: {} (+{} more, see log)", first, n - 1),
+        };
+        if let Some(pane_layout) = tab_bar_gui.lock().unwrap().get_active_pane_layout() {
+            pane_layout.toast = Some(crate::ui::animations::Toast {
+                duration: std::time::Duration::from_secs(5),
+                ..crate::ui::animations::Toast::new(message)
+            });
+        }
+    }
+
+    tab_bar_gui.lock().unwrap().set_pane_divider_width(settings.theme.divider_width());
+
+    // Pane title headers take one text row above each pane of a split tab
+    if settings.ui.show_pane_titles {
+        tab_bar_gui.lock().unwrap().set_pane_header_height(char_dims.height.ceil() as u32);
+    }
+
     // Initialize test server if requested
     #[cfg(feature = "test-server")]
     let test_server = initialize_test_server(
@@ -187,7 +211,7 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
     let mouse_state = crate::input::mouse::MouseState::new();
 
     // Initialize glyph cache
-    let glyph_cache = HashMap::new();
+    let glyph_atlas = crate::ui::glyph_atlas::GlyphAtlas::new();
 
     Ok(InitializedApp {
         canvas,
@@ -203,11 +227,8 @@ pub fn initialize<'a>(ttf_context: &'a Sdl3TtfContext, test_port: Option<u16>, d
         sys,
         ctrl_keys,
         mouse_state,
-        glyph_cache,
-        #[cfg(target_os = "linux")]
-        clipboard_tx,
-        #[cfg(target_os = "linux")]
-        clipboard_rx,
+        window_geometry,
+        glyph_atlas,
         #[cfg(not(target_os = "windows"))]
         signal_rx,
         #[cfg(feature = "test-server")]
@@ -221,19 +242,36 @@ fn setup_signal_handlers() -> Result<std::sync::mpsc::Receiver<i32>, String> {
     use signal_hook::consts::signal::*;
     use signal_hook::iterator::Signals;
 
-    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP]).map_err(|e| format!("Failed to register signal handlers: {}", e))?;
+    // SIGUSR1 toggles the window, so a system-wide shortcut can run `pkill -USR1 nist`
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP, SIGUSR1]).map_err(|e| format!("Failed to register signal handlers: {}", e))?;
 
     let (signal_tx, signal_rx) = channel::<i32>();
     std::thread::spawn(move || {
         for sig in signals.forever() {
             eprintln!("[SIGNAL] Received signal: {}", sig);
             let _ = signal_tx.send(sig);
+            crate::system::wakeup::wake();
         }
     });
 
     Ok(signal_rx)
 }
 
+/// Let background threads wake the main loop by pushing a `Wakeup` event
+fn install_waker(sdl_context: &sdl3::Sdl) -> Result<(), String> {
+    let event_subsystem = sdl_context.event().map_err(|e| e.to_string())?;
+    event_subsystem
+        .register_custom_event::<crate::system::wakeup::Wakeup>()
+        .map_err(|e| format!("Failed to register wakeup event: {}", e))?;
+    let sender = event_subsystem.event_sender();
+    crate::system::wakeup::set_waker(move || {
+        if let Err(e) = sender.push_custom_event(crate::system::wakeup::Wakeup) {
+            eprintln!("[MAIN] Failed to push wakeup event: {}", e);
+        }
+    });
+    Ok(())
+}
+
 /// Configure SDL hints for proper window management
 fn configure_sdl_hints() {
     sdl3::hint::set("SDL_VIDEO_X11_WMCLASS", "nist");
@@ -243,17 +281,56 @@ fn configure_sdl_hints() {
     sdl3::hint::set("SDL_APP_NAME", "Nisdos Terminal");
 }
 
-/// Create the main window
-fn create_window(video_subsystem: &sdl3::VideoSubsystem, width: u32, height: u32) -> Result<Window, String> {
-    video_subsystem
-        .window("Nisdos Terminal", width, height)
-        .position_centered()
-        .resizable()
-        .maximized()
-        .borderless()
-        .high_pixel_density()
-        .build()
-        .map_err(|e| e.to_string())
+/// Create the main window. Also returns its unmaximized bounds, which are tracked from then on
+/// and saved on exit.
+fn create_window(video_subsystem: &sdl3::VideoSubsystem, window_settings: &settings::WindowSettings) -> Result<(Window, state::WindowGeometry), String> {
+    let saved_geometry = state::load_window_geometry();
+    // Reopen where the window was last time, on a display that still exists, unless the
+    // settings give a size or maximized state of their own
+    let restored_geometry = saved_geometry.filter(|_| !window_settings.sets_geometry()).and_then(|geometry| {
+        let primary = video_subsystem.get_primary_display().ok()?.get_usable_bounds().ok()?;
+        let displays: Vec<_> = video_subsystem
+            .displays()
+            .map(|displays| displays.iter().filter_map(|display| display.get_usable_bounds().ok()).collect())
+            .unwrap_or_default();
+        Some(geometry.clamp_to_displays(&displays, primary))
+    });
+    eprintln!("[INIT] Restoring window geometry: {:?}", restored_geometry);
+
+    let (width, height) = match restored_geometry {
+        Some(geometry) => (geometry.width, geometry.height),
+        None => window_settings.size(),
+    };
+    let mut builder = video_subsystem.window("Nisdos Terminal", width, height);
+    match restored_geometry {
+        Some(geometry) => builder.position(geometry.x, geometry.y),
+        None => builder.position_centered(),
+    };
+    builder.resizable().high_pixel_density();
+    if restored_geometry
+        .map(|geometry| geometry.maximized)
+        .unwrap_or(window_settings.starts_maximized())
+    {
+        builder.maximized();
+    }
+    if window_settings.borderless {
+        builder.borderless();
+    }
+    let mut window = builder.build().map_err(|e| e.to_string())?;
+
+    // A maximized window reports its maximized size, the one to come back to is the requested one
+    let mut normal_bounds = state::WindowGeometry::from_window(&window);
+    normal_bounds.width = width;
+    normal_bounds.height = height;
+    if let Some(geometry) = restored_geometry {
+        (normal_bounds.x, normal_bounds.y) = (geometry.x, geometry.y);
+    }
+
+    // Minimized last time, e.g. with the toggle hotkey: stays minimized whatever the settings say about its size
+    if saved_geometry.map(|geometry| geometry.minimized).unwrap_or(false) {
+        window.minimize();
+    }
+    Ok((window, normal_bounds))
 }
 
 /// Set the window icon from embedded PNG data
@@ -385,7 +462,7 @@ fn load_fonts<'a>(ttf_context: &'a Sdl3TtfContext, settings: &settings::Settings
     let font_size = settings.terminal.font_size * scale_factor;
 
     // Load monospace font
-    let font_path = get_monospace_font_path(&settings.terminal.font_family)?;
+    let font_path = get_monospace_font_path(&settings.terminal)?;
     let font = ttf_context.load_font(&font_path, font_size).map_err(|e| {
         eprintln!("[INIT] Failed to load font from {}: {}", font_path, e);
         format!("Font loading failed from {}: {}", font_path, e)
@@ -436,6 +513,12 @@ fn load_fonts<'a>(ttf_context: &'a Sdl3TtfContext, settings: &settings::Settings
     let emoji_font = ttf_context
         .load_font(&emoji_font_path, font_size)
         .map_err(|e| format!("Emoji font loading failed: {}", e))?;
+    let ui_emoji_font = ttf_context
+        .load_font(&emoji_font_path, tab_font_size)
+        .map_err(|e| format!("Emoji font loading failed: {}", e))?;
+    let context_menu_emoji_font = ttf_context
+        .load_font(&emoji_font_path, context_menu_font_size)
+        .map_err(|e| format!("Emoji font loading failed: {}", e))?;
     eprintln!("[INIT] Loaded emoji font: {}", emoji_font_path);
 
     // Load Unicode fallback font - use FreeMono for specific missing symbols (U+23BF, U+276F, U+2588)
@@ -466,22 +549,25 @@ fn load_fonts<'a>(ttf_context: &'a Sdl3TtfContext, settings: &settings::Settings
         cpu_font,
         context_menu_font,
         emoji_font,
+        ui_emoji_font,
+        context_menu_emoji_font,
         unicode_fallback_font,
         cjk_font,
     })
 }
 
-/// Get the monospace font path from settings or auto-discovery
-fn get_monospace_font_path(font_family: &str) -> Result<String, String> {
-    if font_family == "auto" {
-        font_discovery::find_best_monospace_font().ok_or_else(|| "[ERROR] No suitable monospace font found on your system!".to_string())
-    } else {
-        let path = font_family.to_string();
-        if !std::path::Path::new(&path).exists() {
-            eprintln!("[INIT] Font file not found: {}, falling back to auto-discovery", path);
+/// Get the monospace font path from settings (a file or a family name) or auto-discovery
+pub fn get_monospace_font_path(terminal_settings: &settings::TerminalSettings) -> Result<String, String> {
+    match terminal_settings.font_path() {
+        Some(path) if path.exists() => Ok(path.to_string_lossy().into_owned()),
+        configured => {
+            if let Some(path) = configured {
+                if let Some(found) = font_discovery::find_font_by_family(terminal_settings.font_family.trim()) {
+                    return Ok(found);
+                }
+                eprintln!("[INIT] Font file or family not found: {}, falling back to auto-discovery", path.display());
+            }
             font_discovery::find_best_monospace_font().ok_or_else(|| "[ERROR] No suitable monospace font found on your system!".to_string())
-        } else {
-            Ok(path)
         }
     }
 }
@@ -507,16 +593,22 @@ fn initialize_tab_bar_gui(
     shell_config: crate::terminal::ShellConfig,
     default_scrollback_lines: usize,
     cursor_style: crate::screen_buffer::CursorStyle,
+    terminal_options: crate::terminal::main::TerminalOptions,
+    startup_dir: Option<std::path::PathBuf>,
 ) -> Arc<Mutex<TabBarGui>> {
     let shell_config_clone = shell_config.clone();
+    let startup_dir_clone = startup_dir.clone();
+    let terminal_options_clone = terminal_options.clone();
     let terminal_factory = move |start_dir: Option<std::path::PathBuf>| {
+        // Panes whose saved directory is gone start in the configured default
         Arc::new(Mutex::new(Terminal::new_with_scrollback(
             terminal_width,
             terminal_height,
             shell_config_clone.clone(),
             default_scrollback_lines,
-            start_dir,
+            start_dir.or_else(|| startup_dir_clone.clone()),
             cursor_style,
+            terminal_options_clone.clone(),
         )))
     };
 
@@ -533,8 +625,9 @@ fn initialize_tab_bar_gui(
                 terminal_height,
                 shell_config,
                 default_scrollback_lines,
-                std::env::current_dir().ok(),
+                startup_dir,
                 cursor_style,
+                terminal_options,
             )));
             tab_bar_new.add_tab(first_terminal, "Tab 1".to_string());
             Arc::new(Mutex::new(tab_bar_new))
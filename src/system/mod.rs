@@ -3,6 +3,14 @@
 //! This module contains functionality for:
 //! - Command-line argument parsing and non-GUI behavior
 //! - System initialization (SDL, fonts, terminals, etc.)
+//! - Coalescing of window resize events
+//! - CPU and memory usage for the tab bar indicators
+//! - Clipboard access for escape sequences
+//! - Waking the main loop from background threads
 
 pub mod cli;
+pub mod clipboard;
 pub mod init;
+pub mod resize;
+pub mod usage;
+pub mod wakeup;
@@ -0,0 +1,82 @@
+//! Window resize coalescing.
+//!
+//! A drag-resize produces a resize event for every mouse motion. Resizing the PTYs and
+//! rewrapping every screen buffer on each of them causes flicker, so the main loop records
+//! the latest window size here and only applies it once events have stopped for a short delay.
+
+use std::time::{Duration, Instant};
+
+/// How long resize events must stop before terminals are actually resized
+pub const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+pub struct ResizeDebouncer {
+    delay: Duration,
+    pending: Option<(u32, u32)>,
+    last_request: Instant,
+}
+
+impl ResizeDebouncer {
+    pub fn new(delay: Duration) -> Self {
+        ResizeDebouncer {
+            delay,
+            pending: None,
+            last_request: Instant::now(),
+        }
+    }
+
+    /// Record the latest window size, restarting the quiet period
+    pub fn request(&mut self, width: u32, height: u32, now: Instant) {
+        self.pending = Some((width, height));
+        self.last_request = now;
+    }
+
+    /// Take the pending size once no resize has been requested for the debounce delay
+    pub fn take_ready(&mut self, now: Instant) -> Option<(u32, u32)> {
+        if self.pending.is_some() && now.duration_since(self.last_request) >= self.delay {
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+
+    /// Time left until a pending resize becomes ready, used to bound the event wait
+    pub fn time_until_ready(&self, now: Instant) -> Option<Duration> {
+        self.pending.map(|_| self.delay.saturating_sub(now.duration_since(self.last_request)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_resizes_apply_once() {
+        let start = Instant::now();
+        let mut debouncer = ResizeDebouncer::new(RESIZE_DEBOUNCE);
+        let mut applied = Vec::new();
+
+        // A drag-resize: one event every 10ms for 200ms
+        for i in 0..20u32 {
+            let now = start + Duration::from_millis(i as u64 * 10);
+            debouncer.request(800 + i, 600 + i, now);
+            if let Some(size) = debouncer.take_ready(now) {
+                applied.push(size);
+            }
+        }
+        assert!(applied.is_empty(), "no resize should be applied while events keep coming");
+
+        let last = start + Duration::from_millis(190);
+        assert_eq!(debouncer.time_until_ready(last), Some(RESIZE_DEBOUNCE));
+        assert_eq!(debouncer.take_ready(last + Duration::from_millis(49)), None);
+
+        let settled = last + RESIZE_DEBOUNCE;
+        if let Some(size) = debouncer.take_ready(settled) {
+            applied.push(size);
+        }
+        assert_eq!(applied, vec![(819, 619)]);
+
+        // Nothing left afterwards
+        assert_eq!(debouncer.take_ready(settled + Duration::from_secs(1)), None);
+        assert_eq!(debouncer.time_until_ready(settled), None);
+    }
+}
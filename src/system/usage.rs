@@ -0,0 +1,119 @@
+//! CPU and memory usage for the tab bar indicators.
+//!
+//! CPU defaults to the usage of the whole system. With `activePaneCpu` enabled it is the
+//! usage of the active pane's shell and everything it started, which shows which terminal is
+//! keeping the machine busy. The optional memory readout always covers that process tree.
+
+use std::collections::HashMap;
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+/// One reading of the tab bar indicators
+pub struct UsageSample {
+    /// CPU usage in percent of the whole machine
+    pub cpu: f32,
+    /// Resident memory of the active pane's process tree in bytes, if it could be determined
+    pub memory: Option<u64>,
+}
+
+/// Take a usage sample. `pid` is the active terminal's shell; processes are only refreshed when
+/// per-process CPU or memory is wanted. Meant to be called on a ~1s cadence. Falls back to the
+/// global CPU usage when the process tree can't be found.
+pub fn sample_usage(sys: &mut System, pid: Option<u32>, active_pane_cpu: bool, show_memory: bool) -> UsageSample {
+    sys.refresh_cpu();
+    let global_cpu = sys.global_cpu_info().cpu_usage();
+
+    let tree = match pid {
+        Some(pid) if active_pane_cpu || show_memory => {
+            sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
+            let processes: Vec<(u32, Option<u32>)> = sys
+                .processes()
+                .iter()
+                .map(|(pid, process)| (pid.as_u32(), process.parent().map(|parent| parent.as_u32())))
+                .collect();
+            Some(process_tree(pid, &processes)).filter(|pids| !pids.is_empty())
+        }
+        _ => None,
+    };
+    let tree_processes = || tree.iter().flatten().filter_map(|pid| sys.process(Pid::from_u32(*pid)));
+
+    let cpu = if active_pane_cpu && tree.is_some() {
+        // Per-process usage is relative to one core, scale it to the whole machine like the global value
+        tree_processes().map(|process| process.cpu_usage()).sum::<f32>() / sys.cpus().len().max(1) as f32
+    } else {
+        global_cpu
+    };
+    let memory = if show_memory && tree.is_some() {
+        Some(tree_processes().map(|process| process.memory()).sum())
+    } else {
+        None
+    };
+
+    UsageSample { cpu, memory }
+}
+
+/// Format a byte count for the memory indicator, e.g. "512 MB" or "1.2 GB"
+pub fn format_memory(bytes: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    const GB: u64 = 1024 * MB;
+
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else {
+        format!("{} MB", bytes / MB)
+    }
+}
+
+/// Collect `root` and all of its descendants from (pid, parent pid) entries.
+/// Returns an empty list if `root` isn't among them.
+fn process_tree(root: u32, processes: &[(u32, Option<u32>)]) -> Vec<u32> {
+    if !processes.iter().any(|(pid, _)| *pid == root) {
+        return Vec::new();
+    }
+
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for &(pid, parent) in processes {
+        if let Some(parent) = parent {
+            children.entry(parent).or_default().push(pid);
+        }
+    }
+
+    let mut tree = vec![root];
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        for &child in children.get(&pid).into_iter().flatten() {
+            tree.push(child);
+            pending.push(child);
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_tree_includes_descendants() {
+        let processes = [
+            (1, None),
+            (100, Some(1)),   // shell
+            (101, Some(100)), // make
+            (102, Some(101)), // compiler started by make
+            (200, Some(1)),   // another pane's shell
+        ];
+
+        let mut tree = process_tree(100, &processes);
+        tree.sort_unstable();
+        assert_eq!(tree, vec![100, 101, 102]);
+        assert_eq!(process_tree(200, &processes), vec![200]);
+        assert!(process_tree(999, &processes).is_empty());
+    }
+
+    #[test]
+    fn test_format_memory() {
+        assert_eq!(format_memory(0), "0 MB");
+        assert_eq!(format_memory(512 * 1024 * 1024), "512 MB");
+        assert_eq!(format_memory(1024 * 1024 * 1024), "1.0 GB");
+        assert_eq!(format_memory(1288 * 1024 * 1024), "1.3 GB");
+    }
+}
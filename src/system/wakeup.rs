@@ -0,0 +1,87 @@
+//! Main loop wakeups from background threads.
+//!
+//! The main loop blocks on the SDL event queue while nothing happens. Threads that change
+//! what is on screen (the PTY readers, the signal handler) call `wake` so the loop notices
+//! promptly instead of polling. Wakeups are coalesced: only one is queued until the main loop
+//! calls `clear` after draining its events.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// The main loop's waker, installed once the event subsystem exists
+static WAKER: OnceLock<Waker> = OnceLock::new();
+
+/// Event pushed onto the SDL queue to wake the main loop
+pub struct Wakeup;
+
+/// Coalesces wakeups: `wake` calls the push function only while no wakeup is pending
+pub struct Waker {
+    push: Box<dyn Fn() + Send + Sync>,
+    /// A wakeup is queued and not yet seen by the main loop
+    pending: AtomicBool,
+}
+
+impl Waker {
+    pub fn new(push: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            push: Box::new(push),
+            pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Push a wakeup, unless one is already pending
+    pub fn wake(&self) {
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            (self.push)();
+        }
+    }
+
+    /// The pending wakeup was seen, so the next `wake` pushes a new one
+    pub fn clear(&self) {
+        self.pending.store(false, Ordering::Release);
+    }
+}
+
+/// Install the function that wakes the main loop. Only the first call has an effect.
+pub fn set_waker(push: impl Fn() + Send + Sync + 'static) {
+    let _ = WAKER.set(Waker::new(push));
+}
+
+/// Wake the main loop, unless a wakeup is already queued
+pub fn wake() {
+    if let Some(waker) = WAKER.get() {
+        waker.wake();
+    }
+}
+
+/// Called by the main loop after draining events, so the next `wake` queues a new wakeup
+pub fn clear() {
+    if let Some(waker) = WAKER.get() {
+        waker.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_wakeups_are_coalesced_until_cleared() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let waker = Waker::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        waker.wake();
+        waker.wake();
+        waker.wake();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        waker.clear();
+        waker.wake();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
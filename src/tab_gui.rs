@@ -1,14 +1,43 @@
 use crate::pane_layout::{ContextMenuImages, PaneLayout};
 use crate::terminal::Terminal;
+use crate::ui::context_menu::{ContextMenu, ContextMenuItem};
 use std::sync::{Arc, Mutex};
 
 /// Manages the state of a single tab
 pub struct TabState {
     pub pane_layout: PaneLayout,
     pub name: String,
+    /// True once the user has named the tab; otherwise the foreground process name is shown
+    pub custom_name: bool,
+    /// Foreground process of the tab's active pane, refreshed periodically
+    pub process_name: Option<String>,
     pub is_editing: bool,
     pub temp_name: String,
     pub cursor_pos: usize,
+    /// What happened in the tab while it was in the background
+    pub activity: TabActivity,
+}
+
+/// Output or a bell seen in a tab since it was last active
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TabActivity {
+    pub has_activity: bool,
+    pub has_bell: bool,
+}
+
+impl TabActivity {
+    /// Record what one of the tab's terminals did. The active tab is being looked at, so its
+    /// flags are cleared instead. Returns true if the indicator changed.
+    pub fn record(&mut self, is_active_tab: bool, output: bool, bell: bool) -> bool {
+        let before = *self;
+        if is_active_tab {
+            *self = TabActivity::default();
+        } else {
+            self.has_activity |= output;
+            self.has_bell |= bell;
+        }
+        *self != before
+    }
 }
 
 impl TabState {
@@ -17,18 +46,49 @@ impl TabState {
         Self {
             pane_layout,
             name: name.clone(),
+            custom_name: false,
+            process_name: None,
             is_editing: false,
             temp_name: name,
             cursor_pos: 0,
+            activity: TabActivity::default(),
         }
     }
 
     pub fn get_name(&self) -> String {
-        self.name.clone()
+        match self.process_name {
+            Some(ref process_name) if !self.custom_name => process_name.clone(),
+            _ => self.name.clone(),
+        }
     }
 
     pub fn set_name(&mut self, name: String) {
         self.name = name;
+        self.custom_name = true;
+    }
+
+    /// Re-read the foreground process of every pane, which the pane headers show, and
+    /// take the tab label from the active pane. Returns true if any name changed.
+    pub fn refresh_process_name(&mut self) -> bool {
+        let mut changed = false;
+        for terminal in self.pane_layout.get_all_terminals() {
+            // Skip this round rather than wait on a busy terminal
+            if let Ok(mut t) = terminal.try_lock() {
+                changed |= t.refresh_process_name();
+            }
+        }
+        let Some(terminal) = self.pane_layout.get_active_terminal() else {
+            return changed;
+        };
+        let Ok(t) = terminal.try_lock() else {
+            return changed;
+        };
+        let process_name = t.process_name().map(str::to_string);
+        if process_name != self.process_name {
+            self.process_name = process_name;
+            changed = true;
+        }
+        changed
     }
 
     pub fn start_editing(&mut self) {
@@ -87,11 +147,27 @@ impl TabState {
     }
 }
 
+/// In-progress input method composition (pre-edit text), drawn at the cursor until committed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composition {
+    pub text: String,
+    /// Cursor position within the text, in characters
+    pub cursor: usize,
+}
+
 /// Manages the GUI state for all tabs
 pub struct TabBarGui {
     pub tab_states: Vec<TabState>,
     pub active_tab: usize,
     pub context_menu_images: Option<ContextMenuImages>,
+    /// Tab bar context menu, along with the index of the tab it was opened on
+    pub tab_context_menu: Option<(usize, ContextMenu<String>)>,
+    /// Composition in progress in the active terminal, not yet sent to the PTY
+    pub composition: Option<Composition>,
+    /// Height of the per-pane title headers in split tabs (0 = headers off)
+    pub pane_header_height: u32,
+    /// Thickness of the dividers between panes in pixels
+    pub pane_divider_width: u32,
 }
 
 impl TabBarGui {
@@ -100,6 +176,10 @@ impl TabBarGui {
             tab_states: Vec::new(),
             active_tab: 0,
             context_menu_images: None,
+            tab_context_menu: None,
+            composition: None,
+            pane_header_height: 0,
+            pane_divider_width: crate::pane_layout::DEFAULT_DIVIDER_WIDTH,
         }
     }
 
@@ -109,6 +189,8 @@ impl TabBarGui {
         if let Some(ref images) = self.context_menu_images {
             tab_state.pane_layout.context_menu_images = Some(images.clone());
         }
+        tab_state.pane_layout.header_height = self.pane_header_height;
+        tab_state.pane_layout.divider_width = self.pane_divider_width;
         self.tab_states.push(tab_state);
         self.active_tab = self.tab_states.len() - 1;
     }
@@ -121,6 +203,22 @@ impl TabBarGui {
         }
     }
 
+    /// Set the pane title header height (0 to hide headers) on all tabs
+    pub fn set_pane_header_height(&mut self, height: u32) {
+        self.pane_header_height = height;
+        for tab_state in &mut self.tab_states {
+            tab_state.pane_layout.header_height = height;
+        }
+    }
+
+    /// Set the thickness of the dividers between panes on all tabs
+    pub fn set_pane_divider_width(&mut self, width: u32) {
+        self.pane_divider_width = width;
+        for tab_state in &mut self.tab_states {
+            tab_state.pane_layout.divider_width = width;
+        }
+    }
+
     pub fn remove_tab(&mut self, index: usize) -> bool {
         if index >= self.tab_states.len() {
             return false;
@@ -139,6 +237,64 @@ impl TabBarGui {
         false
     }
 
+    /// Close several tabs at once. Indices are removed from the highest down so the
+    /// remaining ones stay valid. If the active tab is among them, `keep` becomes active.
+    /// Returns true if no tabs are left.
+    pub fn close_tabs(&mut self, indices: &[usize], keep: usize) -> bool {
+        let mut indices: Vec<usize> = indices.iter().copied().filter(|&i| i < self.tab_states.len()).collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let active_tab = active_tab_after_removal(self.active_tab, keep, &indices);
+        for &index in indices.iter().rev() {
+            if self.remove_tab(index) {
+                return true;
+            }
+        }
+        self.set_active_tab(active_tab);
+        false
+    }
+
+    /// Open the tab bar context menu for the given tab
+    pub fn open_tab_context_menu(&mut self, tab_index: usize, x: i32, y: i32) {
+        if let Some(ref menu_images) = self.context_menu_images {
+            let tab_count = self.tab_states.len();
+            let items = vec![
+                ContextMenuItem::with_enabled(menu_images.kill_shell, "Close other tabs", "close_other_tabs".to_string(), tab_count > 1),
+                ContextMenuItem::with_enabled(
+                    menu_images.kill_shell,
+                    "Close tabs to the right",
+                    "close_tabs_to_right".to_string(),
+                    tab_index + 1 < tab_count,
+                ),
+            ];
+            self.tab_context_menu = Some((tab_index, ContextMenu::new(items, (x, y))));
+        }
+
+        eprintln!("[TAB_GUI] Context menu opened for tab {} at ({}, {})", tab_index, x, y);
+    }
+
+    /// Handle a click while the tab context menu is open. Returns None if the menu wasn't open,
+    /// otherwise the clicked tab and the indices of the tabs to close (empty if nothing was picked).
+    pub fn handle_tab_context_menu_click(&mut self, mouse_x: i32, mouse_y: i32) -> Option<(usize, Vec<usize>)> {
+        // Close menu on any click
+        let (tab_index, menu) = self.tab_context_menu.take()?;
+        let tab_count = self.tab_states.len();
+        let indices = match menu.handle_click(mouse_x, mouse_y).as_deref() {
+            Some("close_other_tabs") => other_tabs(tab_count, tab_index),
+            Some("close_tabs_to_right") => tabs_to_the_right(tab_count, tab_index),
+            _ => Vec::new(),
+        };
+        Some((tab_index, indices))
+    }
+
+    /// Update the tab context menu hover state based on mouse position
+    pub fn update_tab_context_menu_hover(&mut self, mouse_x: i32, mouse_y: i32) {
+        if let Some((_, ref mut menu)) = self.tab_context_menu {
+            menu.update_hover(mouse_x, mouse_y);
+        }
+    }
+
     pub fn set_active_tab(&mut self, index: usize) {
         if index < self.tab_states.len() {
             self.active_tab = index;
@@ -198,6 +354,16 @@ impl TabBarGui {
         self.tab_states.get_mut(self.active_tab).map(|ts| &mut ts.pane_layout)
     }
 
+    /// Refresh the foreground process names shown in tab labels and pane headers.
+    /// Returns true if any of them changed.
+    pub fn refresh_process_names(&mut self) -> bool {
+        let mut changed = false;
+        for tab_state in self.tab_states.iter_mut() {
+            changed |= tab_state.refresh_process_name();
+        }
+        changed
+    }
+
     /// Get all terminals from all tabs (used for test server and management)
     pub fn get_all_terminals(&self) -> Vec<Arc<Mutex<Terminal>>> {
         self.tab_states.iter().flat_map(|ts| ts.pane_layout.get_all_terminals()).collect()
@@ -213,6 +379,32 @@ impl TabBarGui {
             .unwrap_or_default()
     }
 
+    /// Flag background tabs whose terminals printed output or rang the bell, and clear the active
+    /// tab's flags. Returns true if any tab's indicator changed.
+    pub fn update_tab_activity(&mut self) -> bool {
+        let active_tab = self.active_tab;
+        let mut changed = false;
+        for (idx, tab_state) in self.tab_states.iter_mut().enumerate() {
+            let is_active_tab = idx == active_tab;
+            if is_active_tab {
+                changed |= tab_state.activity.record(true, false, false);
+            }
+            for terminal in tab_state.pane_layout.get_all_terminals() {
+                let Ok(t) = terminal.try_lock() else { continue };
+                let Ok(mut sb) = t.screen_buffer.try_lock() else { continue };
+                // Output and bells are always taken so old ones don't show up after switching away
+                let output = sb.take_output();
+                let bell = sb.take_bell();
+                changed |= tab_state.activity.record(is_active_tab, output, bell);
+            }
+        }
+        changed
+    }
+
+    pub fn get_tab_activity(&self) -> Vec<TabActivity> {
+        self.tab_states.iter().map(|ts| ts.activity).collect()
+    }
+
     pub fn get_tab_names(&self) -> Vec<String> {
         self.tab_states.iter().map(|ts| ts.get_name()).collect()
     }
@@ -240,3 +432,95 @@ impl TabBarGui {
         false
     }
 }
+
+/// Check whether a tab name is one of the generated "Tab N" names
+pub fn is_default_tab_name(name: &str) -> bool {
+    name.strip_prefix("Tab ").is_some_and(|n| n.parse::<usize>().is_ok())
+}
+
+/// Names of the jobs running in the foreground of the given terminals, in pane order
+pub fn running_jobs(terminals: &[Arc<Mutex<Terminal>>]) -> Vec<String> {
+    terminals
+        .iter()
+        .filter_map(|terminal| terminal.lock().ok().and_then(|t| t.foreground_job_name()))
+        .collect()
+}
+
+/// Indices of the tabs after `index`
+fn tabs_to_the_right(tab_count: usize, index: usize) -> Vec<usize> {
+    ((index + 1)..tab_count).collect()
+}
+
+/// Indices of every tab except `index`
+fn other_tabs(tab_count: usize, index: usize) -> Vec<usize> {
+    (0..tab_count).filter(|&i| i != index).collect()
+}
+
+/// Where the active tab ends up once the sorted `removed` indices are gone.
+/// If the active tab itself is removed, `keep` takes over.
+fn active_tab_after_removal(active_tab: usize, keep: usize, removed: &[usize]) -> usize {
+    let target = if removed.contains(&active_tab) { keep } else { active_tab };
+    target - removed.iter().filter(|&&i| i < target).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tabs_to_the_right() {
+        assert_eq!(tabs_to_the_right(5, 1), vec![2, 3, 4]);
+        assert_eq!(tabs_to_the_right(5, 0), vec![1, 2, 3, 4]);
+        assert!(tabs_to_the_right(5, 4).is_empty());
+        assert!(tabs_to_the_right(1, 0).is_empty());
+
+        assert_eq!(other_tabs(4, 2), vec![0, 1, 3]);
+        assert!(other_tabs(1, 0).is_empty());
+    }
+
+    #[test]
+    fn test_default_tab_names() {
+        assert!(is_default_tab_name("Tab 1"));
+        assert!(is_default_tab_name("Tab 12"));
+        assert!(!is_default_tab_name("Tab"));
+        assert!(!is_default_tab_name("Tab one"));
+        assert!(!is_default_tab_name("server logs"));
+    }
+
+    #[test]
+    fn test_tab_activity_lifecycle() {
+        let mut activity = TabActivity::default();
+
+        // Output in a background tab shows the dot
+        assert!(activity.record(false, true, false));
+        assert!(activity.has_activity && !activity.has_bell);
+        assert!(!activity.record(false, true, false), "already flagged");
+
+        // A bell is added on top and nothing is lost by quiet terminals
+        assert!(activity.record(false, false, true));
+        assert!(!activity.record(false, false, false));
+        assert_eq!(
+            activity,
+            TabActivity {
+                has_activity: true,
+                has_bell: true
+            }
+        );
+
+        // Activating the tab clears both, and the active tab is never flagged
+        assert!(activity.record(true, false, false));
+        assert_eq!(activity, TabActivity::default());
+        assert!(!activity.record(true, true, true));
+        assert_eq!(activity, TabActivity::default());
+    }
+
+    #[test]
+    fn test_active_tab_after_bulk_close() {
+        // Active tab to the right of the clicked one gets closed: the clicked tab takes over
+        assert_eq!(active_tab_after_removal(3, 1, &tabs_to_the_right(5, 1)), 1);
+        // Active tab survives and shifts left past the closed tabs before it
+        assert_eq!(active_tab_after_removal(2, 2, &other_tabs(5, 2)), 0);
+        // Closing tabs after the active one leaves it in place
+        assert_eq!(active_tab_after_removal(0, 2, &tabs_to_the_right(5, 2)), 0);
+    }
+}
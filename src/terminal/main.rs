@@ -1,14 +1,85 @@
 use crate::history;
-use crate::screen_buffer::ScreenBuffer;
+use crate::screen_buffer::{Cell, ScreenBuffer, DEFAULT_TAB_WIDTH};
+use crate::system::clipboard::{ClipboardProvider, SystemClipboard};
 use crate::terminal::config::ShellConfig;
-use crate::terminal::sequences::process_output;
-use crate::terminal::utils::{create_shell_init_file, MAX_COMMAND_HISTORY, MAX_OUTPUT_HISTORY};
+use crate::terminal::output_log::OutputLog;
+use crate::terminal::recorder;
+use crate::terminal::sequences::{decode_utf8, process_output};
+use crate::terminal::utils::create_shell_init_file;
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
+// Give up on a PTY that keeps failing instead of retrying forever (~1 second of errors)
+const MAX_CONSECUTIVE_READ_ERRORS: u32 = 100;
+
+// Pastes are written to the PTY in pieces of this size
+const PASTE_CHUNK_SIZE: usize = 4096;
+
+/// Behavior of a terminal from the `terminal` settings, kept so a duplicated or restarted
+/// shell behaves the same
+#[derive(Debug, Clone)]
+pub(crate) struct TerminalOptions {
+    /// Spacing of the default tab stops
+    pub tab_width: usize,
+    /// Answer OSC 52 clipboard queries
+    pub allow_osc52_read: bool,
+    /// Ask programs for plain output with NO_COLOR
+    pub force_no_color: bool,
+    /// Strip escape sequences from output logs
+    pub log_strip_escapes: bool,
+    /// Directory output logs are created in, the home directory when None
+    pub log_directory: Option<PathBuf>,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> Self {
+        Self {
+            tab_width: DEFAULT_TAB_WIDTH,
+            allow_osc52_read: false,
+            force_no_color: false,
+            log_strip_escapes: true,
+            log_directory: None,
+        }
+    }
+}
+
+impl TerminalOptions {
+    pub(crate) fn from_settings(settings: &crate::settings::TerminalSettings) -> Self {
+        Self {
+            tab_width: settings.tab_width,
+            allow_osc52_read: settings.allow_osc52_read,
+            force_no_color: settings.force_no_color,
+            log_strip_escapes: settings.log_strip_escapes,
+            log_directory: settings.log_directory(),
+        }
+    }
+}
+
+/// Name programs see in TERM_PROGRAM, matching the "Nisdos Terminal" app name given to SDL
+const TERM_PROGRAM: &str = "Nisdos";
+
+/// Environment variables set for the shell on top of the inherited environment
+fn child_env(cols: u32, rows: u32, force_no_color: bool) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("TERM", "xterm-256color".to_string()),
+        ("TERM_PROGRAM", TERM_PROGRAM.to_string()),
+        ("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION").to_string()),
+        // 24-bit colors are supported, apps only use them when told so
+        ("COLORTERM", "truecolor".to_string()),
+        ("COLUMNS", cols.to_string()),
+        ("LINES", rows.to_string()),
+    ];
+    if force_no_color {
+        env.push(("NO_COLOR", "1".to_string()));
+    }
+    env
+}
+
 pub(crate) struct Terminal {
     master: Box<dyn portable_pty::MasterPty>,
     writer: Arc<Mutex<Box<dyn std::io::Write + Send>>>,
@@ -17,17 +88,31 @@ pub(crate) struct Terminal {
     pub(crate) width: u32,
     pub(crate) height: u32,
     pub(crate) shell_config: ShellConfig,
+    options: TerminalOptions,
     pub(crate) application_cursor_keys: Arc<Mutex<bool>>,
+    pub(crate) keypad_application_mode: Arc<Mutex<bool>>,
     pub(crate) mouse_tracking_mode: Arc<Mutex<MouseTrackingMode>>,
     pub(crate) mouse_sgr_mode: Arc<Mutex<bool>>,
     pub(crate) selection: Arc<Mutex<Option<Selection>>>,
     pub(crate) bracketed_paste_mode: Arc<Mutex<bool>>,
+    /// The application asked to be told when it gains or loses focus (mode 1004)
+    pub(crate) focus_reporting: Arc<Mutex<bool>>,
     pub(crate) cursor_visible: Arc<Mutex<bool>>,
     pub(crate) command_history: Arc<Mutex<Vec<String>>>,
     pub(crate) output_history: Arc<Mutex<Vec<String>>>,
+    pub(crate) styled_output_history: Arc<Mutex<Vec<Vec<Cell>>>>,
     pub(crate) current_command: Arc<Mutex<String>>,
+    /// Foreground process name as of the last `refresh_process_name`, read when drawing
+    process_name: Option<String>,
+    /// Log of this terminal's output, written by the reader thread while logging is on
+    output_log: Arc<Mutex<Option<OutputLog>>>,
+    default_cursor_style: Arc<Mutex<crate::screen_buffer::CursorStyle>>,
+    shutdown: Arc<AtomicBool>,
+    reader_exited: Arc<AtomicBool>,
 }
 
+/// A text selection. Rows are absolute (see `ScreenBuffer::view_row_to_absolute`) so the
+/// selection stays on the same content while the view scrolls.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct Selection {
     pub start_col: usize,
@@ -51,6 +136,24 @@ impl Selection {
         self.end_row = row;
     }
 
+    /// Move whichever end is nearer to (col, row), in reading order on lines `width` cells wide,
+    /// to that cell. The other end becomes the anchor so a following drag keeps extending.
+    pub fn extend_to(&mut self, col: usize, row: usize, width: usize) {
+        let (start_col, start_row, end_col, end_row) = self.normalized();
+        let position = |col: usize, row: usize| row * width + col;
+        let clicked = position(col, row);
+        let to_start = clicked.abs_diff(position(start_col, start_row));
+        let to_end = clicked.abs_diff(position(end_col, end_row));
+
+        let (anchor_col, anchor_row) = if to_start < to_end { (end_col, end_row) } else { (start_col, start_row) };
+        *self = Selection {
+            start_col: anchor_col,
+            start_row: anchor_row,
+            end_col: col,
+            end_row: row,
+        };
+    }
+
     pub fn normalized(&self) -> (usize, usize, usize, usize) {
         if self.start_row < self.end_row || (self.start_row == self.end_row && self.start_col <= self.end_col) {
             (self.start_col, self.start_row, self.end_col, self.end_row)
@@ -96,6 +199,7 @@ impl Terminal {
         scrollback_limit: usize,
         start_directory: Option<std::path::PathBuf>,
         cursor_style: crate::screen_buffer::CursorStyle,
+        options: TerminalOptions,
     ) -> Self {
         let pty_system = native_pty_system();
 
@@ -141,9 +245,9 @@ impl Terminal {
             }
         }
 
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("COLUMNS", initial_width.to_string());
-        cmd.env("LINES", initial_height.to_string());
+        for (key, value) in child_env(initial_width, initial_height, options.force_no_color) {
+            cmd.env(key, value);
+        }
 
         if let Some(dir) = start_directory {
             cmd.cwd(dir);
@@ -158,6 +262,7 @@ impl Terminal {
             initial_height as usize,
             scrollback_limit,
             cursor_style,
+            options.tab_width,
         )));
 
         let screen_buffer_clone = Arc::clone(&screen_buffer);
@@ -165,15 +270,19 @@ impl Terminal {
         let saved_screen_buffer_clone = Arc::clone(&saved_screen_buffer);
 
         let application_cursor_keys = Arc::new(Mutex::new(false));
+        let keypad_application_mode = Arc::new(Mutex::new(false));
         let mouse_tracking_mode = Arc::new(Mutex::new(MouseTrackingMode::Disabled));
         let mouse_sgr_mode = Arc::new(Mutex::new(false));
         let bracketed_paste_mode = Arc::new(Mutex::new(false));
+        let focus_reporting = Arc::new(Mutex::new(false));
         let cursor_visible = Arc::new(Mutex::new(true));
 
         let application_cursor_keys_clone = Arc::clone(&application_cursor_keys);
+        let keypad_application_mode_clone = Arc::clone(&keypad_application_mode);
         let mouse_tracking_mode_clone = Arc::clone(&mouse_tracking_mode);
         let mouse_sgr_mode_clone = Arc::clone(&mouse_sgr_mode);
         let bracketed_paste_mode_clone = Arc::clone(&bracketed_paste_mode);
+        let focus_reporting_clone = Arc::clone(&focus_reporting);
         let cursor_visible_clone = Arc::clone(&cursor_visible);
 
         let last_command_exit_code = Arc::new(Mutex::new(None));
@@ -190,26 +299,51 @@ impl Terminal {
 
         let master = pty_pair.master;
 
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_clone = Arc::clone(&shutdown);
+        let reader_exited = Arc::new(AtomicBool::new(false));
+        let reader_exited_clone = Arc::clone(&reader_exited);
+
+        let mut recorder = recorder::open_recorder();
+        let output_log: Arc<Mutex<Option<OutputLog>>> = Arc::new(Mutex::new(None));
+        let output_log_clone = Arc::clone(&output_log);
+        let allow_osc52_read = options.allow_osc52_read;
+
         thread::spawn(move || {
             let mut buffer = [0; 20000];
             let mut incomplete_sequence = String::new();
+            let mut incomplete_utf8 = Vec::new();
+            let mut consecutive_errors = 0;
+            let mut clipboard = SystemClipboard::default();
 
-            loop {
+            while !shutdown_clone.load(Ordering::Relaxed) {
                 match reader.read(&mut buffer) {
                     Ok(bytes_read) if bytes_read > 0 => {
-                        let mut text = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
+                        consecutive_errors = 0;
+                        if let Some(recorder) = recorder.as_mut() {
+                            recorder.record(&buffer[..bytes_read]);
+                        }
+                        let mut text = decode_utf8(&mut incomplete_utf8, &buffer[..bytes_read]);
+                        if let Ok(mut output_log) = output_log_clone.lock() {
+                            if let Some(output_log) = output_log.as_mut() {
+                                output_log.write(&text);
+                            }
+                        }
 
                         if !incomplete_sequence.is_empty() {
                             text = incomplete_sequence.clone() + &text;
                             incomplete_sequence.clear();
                         }
 
+                        let osc52_clipboard: Option<&mut dyn ClipboardProvider> = if allow_osc52_read { Some(&mut clipboard) } else { None };
+
                         Self::parse_mode_sequences(
                             &text,
                             &application_cursor_keys_clone,
                             &mouse_tracking_mode_clone,
                             &mouse_sgr_mode_clone,
                             &bracketed_paste_mode_clone,
+                            &focus_reporting_clone,
                             &cursor_visible_clone,
                         );
 
@@ -220,7 +354,14 @@ impl Terminal {
                             &thread_writer,
                             &last_command_exit_code_clone,
                             &default_cursor_style_clone,
+                            &keypad_application_mode_clone,
+                            osc52_clipboard,
                         );
+                        if let Ok(mut sb) = screen_buffer_clone.lock() {
+                            sb.mark_output();
+                        }
+
+                        crate::system::wakeup::wake();
 
                         if !incomplete_sequence.is_empty() {
                             eprintln!(
@@ -235,11 +376,23 @@ impl Terminal {
                         break;
                     }
                     Err(err) => {
+                        if shutdown_clone.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        consecutive_errors += 1;
+                        if consecutive_errors >= MAX_CONSECUTIVE_READ_ERRORS {
+                            eprintln!("[TERMINAL] Giving up on PTY after {} consecutive read errors: {}", consecutive_errors, err);
+                            break;
+                        }
                         eprintln!("[TERMINAL] Error reading from PTY: {}", err);
                         thread::sleep(Duration::from_millis(10));
                     }
                 }
             }
+
+            eprintln!("[TERMINAL] PTY reader thread exiting");
+            reader_exited_clone.store(true, Ordering::Relaxed);
+            crate::system::wakeup::wake();
         });
 
         Terminal {
@@ -250,15 +403,24 @@ impl Terminal {
             width: initial_width,
             height: initial_height,
             shell_config,
+            options,
             application_cursor_keys,
+            keypad_application_mode,
             mouse_tracking_mode,
             mouse_sgr_mode,
             selection: Arc::new(Mutex::new(None)),
             bracketed_paste_mode,
+            focus_reporting,
             cursor_visible,
             command_history: Arc::new(Mutex::new(Vec::new())),
             output_history: Arc::new(Mutex::new(Vec::new())),
+            styled_output_history: Arc::new(Mutex::new(Vec::new())),
             current_command: Arc::new(Mutex::new(String::new())),
+            process_name: None,
+            output_log,
+            default_cursor_style,
+            shutdown,
+            reader_exited,
         }
     }
 
@@ -284,8 +446,28 @@ impl Terminal {
 
         if let Err(err) = self.master.resize(new_size) {
             eprintln!("[TERMINAL] Failed to resize PTY: {}", err);
-        } else {
-            eprintln!("[TERMINAL] Resized PTY to {}x{}", new_width, new_height);
+            return;
+        }
+        match self.master.get_size() {
+            Ok(applied) => eprintln!(
+                "[TERMINAL] Resized PTY to {}x{} (applied {}x{})",
+                new_width, new_height, applied.cols, applied.rows
+            ),
+            Err(err) => eprintln!("[TERMINAL] Resized PTY to {}x{}, size not readable: {}", new_width, new_height, err),
+        }
+
+        // The size ioctl should make the kernel send SIGWINCH to the foreground process group,
+        // but some programs (tmux on macOS) miss it, so deliver it explicitly as well
+        #[cfg(unix)]
+        if let Some(pgid) = self.master.process_group_leader().filter(|&pgid| pgid > 0) {
+            // SAFETY: killpg only sends a signal, a stale process group just makes it fail
+            if unsafe { libc::killpg(pgid, libc::SIGWINCH) } != 0 {
+                eprintln!(
+                    "[TERMINAL] Failed to signal resize to process group {}: {}",
+                    pgid,
+                    std::io::Error::last_os_error()
+                );
+            }
         }
     }
 
@@ -302,6 +484,77 @@ impl Terminal {
         Ok(())
     }
 
+    /// Send `sig` straight to the foreground process group (the shell's own group when it is
+    /// idle), bypassing the PTY, whose Ctrl+C a program in raw mode is free to ignore
+    #[cfg(unix)]
+    pub(crate) fn send_signal(&mut self, sig: i32) {
+        let pgid = self
+            .master
+            .process_group_leader()
+            .filter(|&pgid| pgid > 0)
+            .or_else(|| self.child.process_id().map(|pid| pid as i32));
+        let Some(pgid) = pgid else {
+            eprintln!("[TERMINAL] No process to send signal {} to", sig);
+            return;
+        };
+
+        eprintln!("[TERMINAL] Sending signal {} to process group {}", sig, pgid);
+        // SAFETY: killpg only sends a signal, a stale process group just makes it fail
+        if unsafe { libc::killpg(pgid, sig) } != 0 {
+            eprintln!(
+                "[TERMINAL] Failed to send signal {} to process group {}: {}",
+                sig,
+                pgid,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    /// Windows has no signals: an interrupt (SIGINT) is sent to the console as Ctrl+C and
+    /// everything else terminates the shell
+    #[cfg(not(unix))]
+    pub(crate) fn send_signal(&mut self, sig: i32) {
+        const SIGINT: i32 = 2;
+        if sig == SIGINT {
+            self.write_bytes(&[0x03]);
+        } else if let Err(e) = self.kill() {
+            eprintln!("[TERMINAL] Failed to terminate process for signal {}: {}", sig, e);
+        }
+    }
+
+    /// Replace the shell with a fresh one in the same directory and a cleared screen. The
+    /// terminal stays in place, so its pane and tab are kept; the old shell and its reader
+    /// thread shut down as they do when a terminal is dropped.
+    pub(crate) fn restart(&mut self) {
+        eprintln!("[TERMINAL] Restarting shell");
+        let fresh = self.duplicate();
+        // An output log keeps running across the restart rather than stopping with the old shell
+        let output_log = self.output_log.lock().ok().and_then(|mut output_log| output_log.take());
+        if let (Some(output_log), Ok(mut fresh_log)) = (output_log, fresh.output_log.lock()) {
+            *fresh_log = Some(output_log);
+        }
+        drop(std::mem::replace(self, fresh));
+    }
+
+    /// A new terminal of the same size, shell, scrollback limit, cursor style and options, with its
+    /// shell started in this terminal's current directory. Nothing running is carried over.
+    pub(crate) fn duplicate(&self) -> Terminal {
+        let start_directory = self.get_cwd();
+        let scrollback_limit = self.screen_buffer.lock().map(|sb| sb.scrollback_limit()).unwrap_or_default();
+        let cursor_style = self.default_cursor_style.lock().map(|style| *style).unwrap_or_default();
+
+        eprintln!("[TERMINAL] Starting shell in {:?}", start_directory);
+        Terminal::new_with_scrollback(
+            self.width,
+            self.height,
+            self.shell_config.clone(),
+            scrollback_limit,
+            start_directory,
+            cursor_style,
+            self.options.clone(),
+        )
+    }
+
     pub(crate) fn send_key(&mut self, keys: &[u8]) {
         let is_enter = keys.len() == 1 && keys[0] == b'\r';
 
@@ -332,6 +585,53 @@ impl Terminal {
         }
     }
 
+    /// Write bytes to the PTY verbatim, without key translation, history tracking or newline conversion
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        if let Ok(mut writer) = self.writer.lock() {
+            if let Err(err) = writer.write_all(bytes) {
+                eprintln!("[TERMINAL] Failed to write bytes to PTY: {}", err);
+            }
+            if let Err(err) = writer.flush() {
+                eprintln!("[TERMINAL] Failed to flush PTY writer: {}", err);
+            }
+        }
+    }
+
+    /// Start logging this terminal's output to `path`, or stop with None. A log already
+    /// running is closed first.
+    pub(crate) fn set_logging(&self, path: Option<PathBuf>) -> Result<(), String> {
+        let new_log = path.map(|path| OutputLog::open(&path, self.options.log_strip_escapes)).transpose()?;
+        let mut output_log = self.output_log.lock().map_err(|e| e.to_string())?;
+        *output_log = new_log;
+        Ok(())
+    }
+
+    /// Where this terminal's output is being logged, if it is
+    pub(crate) fn logging_path(&self) -> Option<PathBuf> {
+        self.output_log.lock().ok()?.as_ref().map(|log| log.path().to_path_buf())
+    }
+
+    /// Write out output the log is still holding, called periodically from the main loop
+    pub(crate) fn flush_log(&self) {
+        if let Ok(mut output_log) = self.output_log.lock() {
+            if let Some(output_log) = output_log.as_mut() {
+                output_log.flush();
+            }
+        }
+    }
+
+    /// The behavior this terminal was created with
+    pub(crate) fn options(&self) -> &TerminalOptions {
+        &self.options
+    }
+
+    /// Tell the application it gained (CSI I) or lost (CSI O) focus, if it enabled focus reporting
+    pub(crate) fn send_focus_event(&mut self, focused: bool) {
+        if self.focus_reporting.lock().map(|mode| *mode).unwrap_or(false) {
+            self.write_bytes(if focused { b"\x1b[I" } else { b"\x1b[O" });
+        }
+    }
+
     pub(crate) fn send_text(&mut self, text: &str) {
         if text.contains('\n') || text.contains('\r') {
             if let Ok(mut current_cmd) = self.current_command.lock() {
@@ -340,7 +640,7 @@ impl Terminal {
         }
 
         if let Ok(mut writer) = self.writer.lock() {
-            let converted = text.replace('\n', "\r");
+            let converted = enter_line_endings(text);
             if let Err(err) = writer.write_all(converted.as_bytes()) {
                 eprintln!("[TERMINAL] Failed to write text to PTY: {}", err);
             }
@@ -350,39 +650,25 @@ impl Terminal {
         }
     }
 
+    /// Paste text, wrapped in bracketed paste markers if the application enabled them. A paste
+    /// of more than one chunk is written from a background thread, so a shell that drains it
+    /// slowly doesn't freeze the UI.
     pub(crate) fn send_paste(&mut self, text: &str) {
-        if let Ok(mut writer) = self.writer.lock() {
-            let bracketed_paste = self.bracketed_paste_mode.lock().map(|mode| *mode).unwrap_or(false);
-
-            if bracketed_paste {
-                if let Err(err) = writer.write_all(b"\x1b[200~") {
-                    eprintln!("[TERMINAL] Failed to write bracketed paste start: {}", err);
-                    return;
-                }
+        let bracketed_paste = self.bracketed_paste_mode.lock().map(|mode| *mode).unwrap_or(false);
+        let chunks = paste_chunks(text, bracketed_paste);
 
-                let converted = text.replace('\n', "\r");
-                if let Err(err) = writer.write_all(converted.as_bytes()) {
-                    eprintln!("[TERMINAL] Failed to write text to PTY: {}", err);
-                    return;
-                }
-
-                if let Err(err) = writer.write_all(b"\x1b[201~") {
-                    eprintln!("[TERMINAL] Failed to write bracketed paste end: {}", err);
-                }
-            } else {
-                let converted = text.replace('\n', "\r");
-                if let Err(err) = writer.write_all(converted.as_bytes()) {
-                    eprintln!("[TERMINAL] Failed to write text to PTY: {}", err);
-                }
-            }
-            if let Err(err) = writer.flush() {
-                eprintln!("[TERMINAL] Failed to flush PTY writer: {}", err);
-            }
+        if chunks.len() <= 1 {
+            write_paste_chunks(&self.writer, &chunks);
+        } else {
+            eprintln!("[TERMINAL] Pasting {} bytes in {} chunks", text.len(), chunks.len());
+            let writer = Arc::clone(&self.writer);
+            thread::spawn(move || write_paste_chunks(&writer, &chunks));
         }
     }
 
     pub(crate) fn has_process_exited(&mut self) -> bool {
-        !self.is_alive()
+        // A reader that gave up on a failing PTY leaves the pane unusable, so treat it as exited too
+        !self.is_alive() || self.reader_exited.load(Ordering::Relaxed)
     }
 
     pub(crate) fn send_mouse_event(&mut self, button: u8, col: u32, row: u32, pressed: bool) {
@@ -426,13 +712,22 @@ impl Terminal {
         }
     }
 
+    /// Absolute row of a row of the current view
+    pub(crate) fn absolute_row(&self, view_row: usize) -> usize {
+        self.screen_buffer.lock().map(|sb| sb.view_row_to_absolute(view_row)).unwrap_or(view_row)
+    }
+
+    /// Start a selection at a cell of the current view
     pub(crate) fn start_selection(&mut self, col: usize, row: usize) {
+        let row = self.absolute_row(row);
         if let Ok(mut sel) = self.selection.try_lock() {
             *sel = Some(Selection::new(col, row));
         }
     }
 
+    /// Move the selection end to a cell of the current view
     pub(crate) fn update_selection(&mut self, col: usize, row: usize) {
+        let row = self.absolute_row(row);
         if let Ok(mut selection) = self.selection.try_lock() {
             if let Some(ref mut sel) = *selection {
                 sel.update_end(col, row);
@@ -440,6 +735,22 @@ impl Terminal {
         }
     }
 
+    /// Move the nearer end of the selection to a cell of the current view.
+    /// Returns false if there is no selection to extend.
+    pub(crate) fn extend_selection(&mut self, col: usize, row: usize) -> bool {
+        let (row, width) = match self.screen_buffer.lock() {
+            Ok(sb) => (sb.view_row_to_absolute(row), sb.width()),
+            Err(_) => return false,
+        };
+        if let Ok(mut selection) = self.selection.try_lock() {
+            if let Some(ref mut sel) = *selection {
+                sel.extend_to(col, row, width);
+                return true;
+            }
+        }
+        false
+    }
+
     pub(crate) fn clear_selection(&mut self) {
         if let Ok(mut sel) = self.selection.try_lock() {
             *sel = None;
@@ -458,7 +769,7 @@ impl Terminal {
 
         let is_word_char = |ch: char| -> bool { ch.is_alphanumeric() || ch == '_' };
 
-        let clicked_cell = match screen_buffer.get_cell(col, row) {
+        let clicked_cell = match screen_buffer.get_cell_with_scrollback(col, row) {
             Some(cell) => cell,
             None => return,
         };
@@ -469,7 +780,7 @@ impl Terminal {
 
         let mut start_col = col;
         while start_col > 0 {
-            if let Some(cell) = screen_buffer.get_cell(start_col - 1, row) {
+            if let Some(cell) = screen_buffer.get_cell_with_scrollback(start_col - 1, row) {
                 if is_word_char(cell.ch) && cell.ch != ' ' && cell.ch != '\0' {
                     start_col -= 1;
                 } else {
@@ -483,7 +794,7 @@ impl Terminal {
         let mut end_col = col;
         let width = screen_buffer.width();
         while end_col < width - 1 {
-            if let Some(cell) = screen_buffer.get_cell(end_col + 1, row) {
+            if let Some(cell) = screen_buffer.get_cell_with_scrollback(end_col + 1, row) {
                 if is_word_char(cell.ch) && cell.ch != ' ' && cell.ch != '\0' {
                     end_col += 1;
                 } else {
@@ -494,6 +805,7 @@ impl Terminal {
             }
         }
 
+        let row = screen_buffer.view_row_to_absolute(row);
         drop(screen_buffer);
 
         if let Ok(mut sel) = self.selection.try_lock() {
@@ -511,46 +823,77 @@ impl Terminal {
         if let Some(sel) = *selection {
             let screen_buffer = self.screen_buffer.try_lock().ok()?;
             let (start_col, start_row, end_col, end_row) = sel.normalized();
+            Some(screen_buffer.text_in_range(start_col, start_row, end_col, end_row))
+        } else {
+            None
+        }
+    }
 
-            let mut text = String::new();
+    /// Whether a program other than the shell itself is running in the foreground (an editor, a build, ...)
+    pub(crate) fn has_foreground_job(&self) -> bool {
+        #[cfg(unix)]
+        {
+            // The foreground process group of the PTY differs from the shell's while a job runs
+            crate::terminal::utils::is_foreground_job(self.master.process_group_leader(), self.child.process_id())
+        }
 
-            for row in start_row..=end_row {
-                if row >= screen_buffer.height() {
-                    break;
-                }
+        #[cfg(not(unix))]
+        {
+            // Foreground jobs can't be detected here, assume busy so quitting stays safe
+            true
+        }
+    }
 
-                let line_start = if row == start_row { start_col } else { 0 };
-                let line_end = if row == end_row {
-                    end_col.min(screen_buffer.width() - 1)
-                } else {
-                    screen_buffer.width() - 1
-                };
+    /// Name of the foreground job, if a program other than the shell is running
+    pub(crate) fn foreground_job_name(&self) -> Option<String> {
+        if !self.has_foreground_job() {
+            return None;
+        }
+        Some(self.get_foreground_process_name().unwrap_or_else(|| "a running program".to_string()))
+    }
 
-                let mut line = String::new();
-                for col in line_start..=line_end {
-                    if let Some(cell) = screen_buffer.get_cell_with_scrollback(col, row) {
-                        if cell.width == 0 || cell.ch == '\0' {
-                            continue;
-                        }
+    /// Process ID of the shell running in this terminal
+    pub(crate) fn get_pid(&self) -> Option<u32> {
+        self.child.process_id()
+    }
 
-                        if let Some(ref extended) = cell.extended {
-                            line.push_str(extended);
-                        } else {
-                            line.push(cell.ch);
-                        }
-                    }
-                }
+    /// Foreground process name cached by the last `refresh_process_name`
+    pub(crate) fn process_name(&self) -> Option<&str> {
+        self.process_name.as_deref()
+    }
 
-                let trimmed_line = line.trim_end();
-                text.push_str(trimmed_line);
+    /// Looks up the foreground process name again and caches it, returning whether it changed
+    pub(crate) fn refresh_process_name(&mut self) -> bool {
+        let process_name = self.get_foreground_process_name();
+        if process_name != self.process_name {
+            self.process_name = process_name;
+            true
+        } else {
+            false
+        }
+    }
 
-                if row < end_row {
-                    text.push('\n');
-                }
-            }
+    /// Name of the process in the foreground of this terminal (e.g. "vim"), or the shell when idle
+    pub(crate) fn get_foreground_process_name(&self) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        {
+            // The PTY's foreground process group leader, falling back to the shell itself
+            let pid = self.master.process_group_leader().map(|pid| pid as u32).or_else(|| self.child.process_id())?;
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            crate::terminal::utils::parse_proc_stat_name(&stat)
+        }
 
-            Some(text)
-        } else {
+        #[cfg(target_os = "macos")]
+        {
+            use libproc::libproc::proc_pid::name;
+
+            let pid = self.master.process_group_leader().map(|pid| pid as u32).or_else(|| self.child.process_id())?;
+            name(pid as i32).ok()
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            // Foreground process lookup is not implemented for this platform
             None
         }
     }
@@ -606,6 +949,7 @@ impl Terminal {
         mouse_tracking_mode: &Arc<Mutex<MouseTrackingMode>>,
         mouse_sgr_mode: &Arc<Mutex<bool>>,
         bracketed_paste_mode: &Arc<Mutex<bool>>,
+        focus_reporting: &Arc<Mutex<bool>>,
         cursor_visible: &Arc<Mutex<bool>>,
     ) {
         let bytes = text.as_bytes();
@@ -735,6 +1079,19 @@ impl Terminal {
                                 }
                                 _ => {}
                             },
+                            "1004" => match command {
+                                'h' => {
+                                    if let Ok(mut mode) = focus_reporting.try_lock() {
+                                        *mode = true;
+                                    }
+                                }
+                                'l' => {
+                                    if let Ok(mut mode) = focus_reporting.try_lock() {
+                                        *mode = false;
+                                    }
+                                }
+                                _ => {}
+                            },
                             "25" => match command {
                                 'h' => {
                                     if let Ok(mut visible) = cursor_visible.try_lock() {
@@ -770,72 +1127,37 @@ impl Terminal {
         }
     }
 
+    /// Append a command to the session history, keeping at most `max_entries` (`restoreCommandLines`)
     #[allow(dead_code)]
-    pub(crate) fn add_command_to_history(&self, command: String) {
+    pub(crate) fn add_command_to_history(&self, command: String, max_entries: usize) {
         if let Ok(mut history) = self.command_history.lock() {
             if !command.trim().is_empty() && (history.is_empty() || history.last() != Some(&command)) {
                 history.push(command);
-                if history.len() > MAX_COMMAND_HISTORY {
-                    history.remove(0);
+                if history.len() > max_entries {
+                    let excess = history.len() - max_entries;
+                    history.drain(..excess);
                 }
             }
         }
     }
 
-    pub(crate) fn capture_output_history(&self) {
+    /// Capture the last `max_lines` output rows, both as plain text and with styling
+    pub(crate) fn capture_output_history(&self, max_lines: usize, preserve_blank_lines: bool) {
         if let Ok(sb) = self.screen_buffer.lock() {
-            let mut lines = Vec::new();
-
-            let scrollback = sb.get_scrollback_buffer();
-            for row in scrollback.iter() {
-                let line: String = row
-                    .iter()
-                    .map(|cell| {
-                        if let Some(ref extended) = cell.extended {
-                            extended.to_string()
-                        } else {
-                            cell.ch.to_string()
-                        }
-                    })
-                    .collect();
-                let trimmed = line.trim_end();
-                if !trimmed.is_empty() {
-                    lines.push(trimmed.to_string());
-                }
-            }
-
-            for y in 0..sb.height() {
-                let mut line = String::new();
-                for x in 0..sb.width() {
-                    if let Some(cell) = sb.get_cell(x, y) {
-                        if let Some(ref extended) = cell.extended {
-                            line.push_str(extended);
-                        } else {
-                            line.push(cell.ch);
-                        }
-                    }
-                }
-                let trimmed = line.trim_end();
-                if !trimmed.is_empty() {
-                    lines.push(trimmed.to_string());
-                }
-            }
-
-            let start = if lines.len() > MAX_OUTPUT_HISTORY {
-                lines.len() - MAX_OUTPUT_HISTORY
-            } else {
-                0
-            };
-            let last_lines = lines[start..].to_vec();
+            let (lines, styled_rows) = collect_output_history(&sb, max_lines, preserve_blank_lines);
 
             if let Ok(mut output_history) = self.output_history.lock() {
-                *output_history = last_lines;
+                *output_history = lines;
+            }
+            if let Ok(mut styled_output_history) = self.styled_output_history.lock() {
+                *styled_output_history = styled_rows;
             }
         }
     }
 
-    pub(crate) fn get_command_history(&self) -> Vec<String> {
-        history::read_shell_history(MAX_COMMAND_HISTORY)
+    /// Most recent distinct commands from this terminal's shell history, newest first
+    pub(crate) fn get_command_history(&self, max_entries: usize) -> Vec<String> {
+        history::read_shell_history(&self.shell_config.command, max_entries)
     }
 
     pub(crate) fn set_command_history(&self, history: Vec<String>) {
@@ -848,6 +1170,10 @@ impl Terminal {
         self.output_history.lock().ok().map(|h| h.clone()).unwrap_or_default()
     }
 
+    pub(crate) fn get_styled_output_history(&self) -> Vec<Vec<Cell>> {
+        self.styled_output_history.lock().ok().map(|h| h.clone()).unwrap_or_default()
+    }
+
     pub(crate) fn set_output_history(&self, history: Vec<String>) {
         if let Ok(mut h) = self.output_history.lock() {
             *h = history.clone();
@@ -856,6 +1182,15 @@ impl Terminal {
         self.restore_output_to_scrollback(history);
     }
 
+    /// Restore styled output rows saved by a previous session into the scrollback
+    pub(crate) fn set_styled_output_history(&self, mut rows: Vec<Vec<Cell>>) {
+        // As with plain history, the last row is the prompt that the new shell prints again
+        rows.pop();
+        if let Ok(mut sb) = self.screen_buffer.lock() {
+            sb.restore_styled_to_scrollback(rows);
+        }
+    }
+
     fn restore_output_to_scrollback(&self, lines: Vec<String>) {
         if let Ok(mut sb) = self.screen_buffer.lock() {
             let mut lines_to_restore = lines;
@@ -867,8 +1202,422 @@ impl Terminal {
     }
 }
 
+/// `text` with each line ending, whether `\n`, `\r\n` or a lone `\r`, turned into the single
+/// carriage return typed Enter sends. Windows clipboards use `\r\n`, which would otherwise
+/// press Enter twice.
+fn enter_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\r").replace('\n', "\r")
+}
+
+/// The bytes of a paste in chunks of at most `PASTE_CHUNK_SIZE`. Line endings become carriage
+/// returns, as typed Enter does; bracketed paste markers open the first chunk and close the last.
+fn paste_chunks(text: &str, bracketed_paste: bool) -> Vec<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() + 12);
+    if bracketed_paste {
+        bytes.extend_from_slice(b"\x1b[200~");
+    }
+    bytes.extend_from_slice(enter_line_endings(text).as_bytes());
+    if bracketed_paste {
+        bytes.extend_from_slice(b"\x1b[201~");
+    }
+    bytes.chunks(PASTE_CHUNK_SIZE).map(<[u8]>::to_vec).collect()
+}
+
+/// Write paste chunks in order, taking the writer for one chunk at a time so a keypress
+/// during a long paste waits for a single chunk rather than the whole paste
+fn write_paste_chunks(writer: &Mutex<Box<dyn std::io::Write + Send>>, chunks: &[Vec<u8>]) {
+    for chunk in chunks {
+        let Ok(mut writer) = writer.lock() else {
+            return;
+        };
+        if let Err(err) = writer.write_all(chunk).and_then(|_| writer.flush()) {
+            eprintln!("[TERMINAL] Failed to write paste to PTY: {}", err);
+            return;
+        }
+        drop(writer);
+        thread::yield_now();
+    }
+}
+
+/// Output rows from the scrollback and screen, skipping blank rows before the first output and
+/// after the last. Blank rows in between are kept unless `preserve_blank_lines` is off.
+pub(crate) fn collect_output_history(sb: &ScreenBuffer, max_lines: usize, preserve_blank_lines: bool) -> (Vec<String>, Vec<Vec<Cell>>) {
+    let mut lines: Vec<String> = Vec::new();
+    let mut styled_rows = Vec::new();
+
+    let visible_rows = (0..sb.height()).map(|y| (0..sb.width()).filter_map(|x| sb.get_cell(x, y).cloned()).collect::<Vec<Cell>>());
+    for mut row in sb.get_scrollback_buffer().iter().cloned().chain(visible_rows) {
+        let content_len = row.iter().rposition(|cell| !cell.is_blank()).map_or(0, |i| i + 1);
+        if content_len == 0 && (lines.is_empty() || !preserve_blank_lines) {
+            continue;
+        }
+        // The continuation cell of a wide character holds no text of its own
+        let line: String = row[..content_len]
+            .iter()
+            .filter(|cell| cell.width > 0)
+            .map(|cell| {
+                if let Some(ref extended) = cell.extended {
+                    extended.to_string()
+                } else {
+                    cell.ch.to_string()
+                }
+            })
+            .collect();
+        lines.push(line);
+
+        // Trailing blank cells are restored by padding, so they are not worth storing
+        while row.last().is_some_and(Cell::is_trailing_trimmable) {
+            row.pop();
+        }
+        styled_rows.push(row);
+    }
+
+    // The empty rows below the cursor are not output
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+        styled_rows.pop();
+    }
+
+    let start = lines.len().saturating_sub(max_lines);
+    (lines.split_off(start), styled_rows.split_off(start))
+}
+
 impl Drop for Terminal {
     fn drop(&mut self) {
+        // Signal the reader thread first; killing the child closes the PTY and unblocks its read
+        self.shutdown.store(true, Ordering::Relaxed);
         let _ = self.kill();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::terminal::test_support::silent_shell;
+    use crate::terminal::test_support::{default_shell, new_terminal, SharedSink};
+
+    #[test]
+    fn test_child_env_advertises_truecolor() {
+        let env = child_env(80, 24, false);
+        assert!(env.contains(&("COLORTERM", "truecolor".to_string())));
+        assert!(env.contains(&("TERM", "xterm-256color".to_string())));
+        assert!(env.contains(&("COLUMNS", "80".to_string())));
+        assert!(env.contains(&("LINES", "24".to_string())));
+        assert!(!env.iter().any(|(key, _)| *key == "NO_COLOR"));
+
+        assert!(child_env(80, 24, true).contains(&("NO_COLOR", "1".to_string())));
+    }
+
+    #[test]
+    fn test_child_env_identifies_the_terminal() {
+        let env = child_env(80, 24, false);
+        assert!(env.contains(&("TERM_PROGRAM", "Nisdos".to_string())));
+        assert!(env.contains(&("TERM_PROGRAM_VERSION", env!("CARGO_PKG_VERSION").to_string())));
+    }
+
+    #[test]
+    fn test_drop_stops_reader_thread() {
+        let terminal = new_terminal(default_shell(), None);
+        let reader_exited = Arc::clone(&terminal.reader_exited);
+        assert!(!reader_exited.load(Ordering::Relaxed));
+
+        drop(terminal);
+
+        for _ in 0..200 {
+            if reader_exited.load(Ordering::Relaxed) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("PTY reader thread kept running after the terminal was dropped");
+    }
+
+    #[test]
+    fn test_restart_spawns_live_shell() {
+        let mut terminal = new_terminal(default_shell(), None);
+        let old_pid = terminal.get_pid();
+        let old_reader_exited = Arc::clone(&terminal.reader_exited);
+        let log_path = std::env::temp_dir().join(format!("nist-restart-log-test-{}.log", std::process::id()));
+        terminal.set_logging(Some(log_path.clone())).unwrap();
+
+        terminal.restart();
+
+        assert!(matches!(terminal.child.try_wait(), Ok(None)), "the new shell should be running");
+        assert_ne!(terminal.get_pid(), old_pid);
+        assert_eq!((terminal.width, terminal.height), (80, 24));
+        assert_eq!(terminal.logging_path(), Some(log_path.clone()), "the output log should keep running");
+        terminal.set_logging(None).unwrap();
+        let _ = std::fs::remove_file(&log_path);
+
+        for _ in 0..200 {
+            if old_reader_exited.load(Ordering::Relaxed) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the old PTY reader thread kept running after the restart");
+    }
+
+    #[test]
+    fn test_set_size_resizes_buffer_and_pty() {
+        let mut terminal = new_terminal(default_shell(), None);
+
+        terminal.set_size(100, 40, false);
+
+        {
+            let sb = terminal.screen_buffer.lock().unwrap();
+            assert_eq!((sb.width(), sb.height()), (100, 40));
+        }
+        let applied = terminal.master.get_size().unwrap();
+        assert_eq!((applied.cols, applied.rows), (100, 40));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_logging_captures_output() {
+        // The shell prints nothing, so the log holds only what is written below
+        let terminal = new_terminal(silent_shell(), None);
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let log = OutputLog::new(
+            PathBuf::from("pane.log"),
+            Box::new(SharedSink(Arc::clone(&written))),
+            true,
+            std::time::SystemTime::now(),
+        );
+        *terminal.output_log.lock().unwrap() = Some(log);
+        assert_eq!(terminal.logging_path(), Some(PathBuf::from("pane.log")));
+
+        terminal.output_log.lock().unwrap().as_mut().unwrap().write("\x1b[32mlogged-42\x1b[0m\r\n");
+        assert!(written.lock().unwrap().is_empty(), "output waits for the periodic flush");
+        terminal.flush_log();
+        let log = String::from_utf8(written.lock().unwrap().clone()).unwrap();
+        assert!(log.starts_with("=== Output log started "));
+        assert!(log.lines().any(|line| line == "logged-42"), "output missing from log: {:?}", log);
+
+        terminal.set_logging(None).unwrap();
+        assert_eq!(terminal.logging_path(), None);
+        let log = String::from_utf8(written.lock().unwrap().clone()).unwrap();
+        assert!(log.trim_end().ends_with(" UTC ==="));
+
+        // Starting a log opens the file
+        let path = std::env::temp_dir().join(format!("nist-log-test-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        terminal.set_logging(Some(path.clone())).unwrap();
+        assert_eq!(terminal.logging_path(), Some(path.clone()));
+        terminal.set_logging(None).unwrap();
+        let log = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(log.starts_with("=== Output log started "));
+    }
+
+    #[test]
+    fn test_focus_events_follow_mode_1004() {
+        let mut terminal = new_terminal(default_shell(), None);
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        terminal.writer = Arc::new(Mutex::new(Box::new(SharedSink(Arc::clone(&sent)))));
+
+        // Applications that didn't ask for focus events don't get them
+        terminal.send_focus_event(true);
+        assert!(sent.lock().unwrap().is_empty());
+
+        Terminal::parse_mode_sequences(
+            "\x1b[?1004h",
+            &terminal.application_cursor_keys,
+            &terminal.mouse_tracking_mode,
+            &terminal.mouse_sgr_mode,
+            &terminal.bracketed_paste_mode,
+            &terminal.focus_reporting,
+            &terminal.cursor_visible,
+        );
+        terminal.send_focus_event(false);
+        terminal.send_focus_event(true);
+        assert_eq!(sent.lock().unwrap().as_slice(), b"\x1b[O\x1b[I");
+    }
+
+    #[test]
+    fn test_paste_chunks_bracket_the_whole_paste() {
+        let text = "echo hello\n".repeat(2000);
+        let chunks = paste_chunks(&text, true);
+        assert!(chunks.len() > 1, "a {} byte paste should take several chunks", text.len());
+        assert!(chunks.iter().all(|chunk| chunk.len() <= PASTE_CHUNK_SIZE));
+
+        let pasted = String::from_utf8(chunks.concat()).unwrap();
+        assert_eq!(pasted.matches("\x1b[200~").count(), 1);
+        assert_eq!(pasted.matches("\x1b[201~").count(), 1);
+        assert!(pasted.starts_with("\x1b[200~echo hello\r"));
+        assert!(pasted.ends_with("echo hello\r\x1b[201~"));
+
+        // Without bracketed paste only the newlines change
+        assert_eq!(paste_chunks("a\nb", false), vec![b"a\rb".to_vec()]);
+        assert!(paste_chunks("", false).is_empty());
+    }
+
+    #[test]
+    fn test_pasted_windows_line_endings_press_enter_once() {
+        assert_eq!(paste_chunks("a\r\nb\r\n", false), vec![b"a\rb\r".to_vec()]);
+        assert_eq!(paste_chunks("a\r\nb\r\n", true), vec![b"\x1b[200~a\rb\r\x1b[201~".to_vec()]);
+
+        // Old Mac line endings and mixed ones end up the same
+        assert_eq!(enter_line_endings("a\rb\nc\r\n"), "a\rb\rc\r");
+        assert_eq!(enter_line_endings("a\r\r\nb"), "a\r\rb", "a blank line is kept");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_signal_terminates_child() {
+        let mut terminal = new_terminal(silent_shell(), None);
+        assert!(terminal.is_alive());
+
+        terminal.send_signal(libc::SIGTERM);
+
+        for _ in 0..200 {
+            if !terminal.is_alive() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the child kept running after SIGTERM");
+    }
+
+    #[test]
+    fn test_duplicate_starts_in_source_cwd() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let source = new_terminal(default_shell(), Some(dir.clone()));
+
+        let duplicate = source.duplicate();
+        assert_eq!(source.get_cwd(), Some(dir));
+        assert_eq!(duplicate.get_cwd(), source.get_cwd());
+        assert_eq!((duplicate.width, duplicate.height), (80, 24));
+        assert_ne!(duplicate.get_pid(), source.get_pid(), "the duplicate runs its own shell");
+    }
+
+    #[test]
+    fn test_output_history_follows_restore_setting() {
+        use crate::screen_buffer::CursorStyle;
+        use crate::settings::StateSettings;
+
+        let mut sb = ScreenBuffer::new_with_scrollback(10, 5, 1000, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for i in 0..50 {
+            sb.put_grapheme(&(i % 10).to_string());
+            sb.cursor_x = 0;
+            sb.newline();
+        }
+
+        let mut settings = StateSettings::default();
+        for lines in [3, 20] {
+            settings.restore_output_lines = lines;
+            let (plain, styled) = collect_output_history(&sb, settings.restore_output_lines, settings.preserve_blank_lines);
+            assert_eq!(plain.len(), lines);
+            assert_eq!(styled.len(), lines);
+            assert_eq!(plain.last().map(String::as_str), Some("9"));
+        }
+
+        // A limit above the available output keeps everything
+        let (plain, _) = collect_output_history(&sb, StateSettings::default().restore_output_lines, true);
+        assert_eq!(plain.len(), 50);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_output_history_keeps_the_last_lines() {
+        let mut terminal = new_terminal(silent_shell(), None);
+        terminal.set_size(10, 4, true);
+        {
+            let mut sb = terminal.screen_buffer.lock().unwrap();
+            for line in ["one", "two", "three", "four", "five"] {
+                for ch in line.chars() {
+                    sb.put_grapheme(&ch.to_string());
+                }
+                sb.cursor_x = 0;
+                sb.newline();
+            }
+        }
+
+        terminal.capture_output_history(3, true);
+        assert_eq!(terminal.get_output_history(), vec!["three", "four", "five"]);
+        let styled = terminal.get_styled_output_history();
+        assert_eq!(styled.len(), 3);
+        assert_eq!(styled[0].iter().map(|cell| cell.ch).collect::<String>(), "three");
+    }
+
+    #[test]
+    fn test_output_history_keeps_blank_lines_between_output() {
+        use crate::screen_buffer::CursorStyle;
+
+        let mut sb = ScreenBuffer::new_with_scrollback(10, 4, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        sb.newline();
+        for line in ["one", "", "", "two", "", "three", "", ""] {
+            for ch in line.chars() {
+                sb.put_grapheme(&ch.to_string());
+            }
+            sb.cursor_x = 0;
+            sb.newline();
+        }
+
+        // Leading and trailing blank rows are dropped either way
+        let (plain, styled) = collect_output_history(&sb, 100, true);
+        assert_eq!(plain, vec!["one", "", "", "two", "", "three"]);
+        assert_eq!(styled.len(), plain.len());
+
+        let (plain, styled) = collect_output_history(&sb, 100, false);
+        assert_eq!(plain, vec!["one", "two", "three"]);
+        assert_eq!(styled.len(), plain.len());
+    }
+
+    #[test]
+    fn test_extend_selection_moves_nearest_endpoint() {
+        // Rows 2..=4 on 80-column lines, made by dragging upwards
+        let mut selection = Selection {
+            start_col: 10,
+            start_row: 4,
+            end_col: 5,
+            end_row: 2,
+        };
+
+        // Before the selection: the start moves, the old end becomes the anchor
+        selection.extend_to(0, 1, 80);
+        assert_eq!(selection.normalized(), (0, 1, 10, 4));
+        assert_eq!((selection.end_col, selection.end_row), (0, 1), "dragging continues from the clicked cell");
+
+        // Inside, nearer the end: the end moves
+        selection.extend_to(70, 3, 80);
+        assert_eq!(selection.normalized(), (0, 1, 70, 3));
+
+        // Inside, nearer the start: the start moves and the end stays
+        selection.extend_to(40, 1, 80);
+        assert_eq!(selection.normalized(), (40, 1, 70, 3));
+
+        // After the selection, on the same row as the end
+        selection.extend_to(79, 3, 80);
+        assert_eq!(selection.normalized(), (40, 1, 79, 3));
+    }
+
+    #[test]
+    fn test_selection_in_scrollback_stays_on_content() {
+        use crate::screen_buffer::CursorStyle;
+
+        let mut sb = ScreenBuffer::new_with_scrollback(10, 3, 100, CursorStyle::default(), DEFAULT_TAB_WIDTH);
+        for i in 0..10 {
+            for ch in format!("line{}", i).chars() {
+                sb.put_grapheme(&ch.to_string());
+            }
+            sb.newline();
+            sb.cursor_x = 0;
+        }
+
+        // Select the top two rows of the view while scrolled back
+        sb.scroll_view_up(4);
+        let mut selection = Selection::new(0, sb.view_row_to_absolute(0));
+        selection.update_end(9, sb.view_row_to_absolute(1));
+        let (start_col, start_row, end_col, end_row) = selection.normalized();
+        assert_eq!(sb.text_in_range(start_col, start_row, end_col, end_row), "line4\nline5");
+
+        // One more line back, the highlight follows its content down a row
+        sb.scroll_view_up(1);
+        assert!(!selection.contains(0, sb.view_row_to_absolute(0)));
+        assert!(selection.contains(0, sb.view_row_to_absolute(1)));
+        assert!(selection.contains(9, sb.view_row_to_absolute(2)));
+        assert_eq!(sb.text_in_range(start_col, start_row, end_col, end_row), "line4\nline5");
+    }
+}
@@ -1,6 +1,10 @@
 pub(crate) mod config;
 pub(crate) mod main;
+pub(crate) mod output_log;
+pub(crate) mod recorder;
 pub(crate) mod sequences;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub(crate) mod utils;
 
 pub(crate) use config::{ShellConfig, TerminalLibrary};
@@ -0,0 +1,213 @@
+//! Logging a pane's output to a file, like `script` (toggled with the toggleOutputLog hotkey)
+//!
+//! Unlike `--record-output`, which keeps the raw bytes of every terminal for replay, a log is
+//! started and stopped per pane and is meant to be read: output is appended as decoded text,
+//! with escape sequences stripped unless `terminal.logStripEscapes` is off. The reader thread
+//! writes into a buffer that the main loop flushes every second, so a slow disk doesn't hold up
+//! output and a log catches up even after its pane goes quiet.
+
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A file name for a log started at `time`, in `directory` or else the home directory
+pub(crate) fn new_log_path(directory: Option<&Path>, time: SystemTime) -> PathBuf {
+    let directory = directory
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from))
+        .unwrap_or_default();
+    let stamp = format_timestamp(time).replace(['-', ':'], "").replace(' ', "-");
+    directory.join(format!("nist-{}.log", stamp))
+}
+
+/// `time` as "YYYY-MM-DD HH:MM:SS" in UTC
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Where the escape stripper is, kept across reads as sequences can be split between them
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StripState {
+    Text,
+    Escape,
+    Csi,
+    /// OSC, DCS and the like, ended by BEL or ST
+    String,
+    StringEscape,
+}
+
+/// An open log, fed by the terminal's reader thread
+pub(crate) struct OutputLog {
+    path: PathBuf,
+    sink: BufWriter<Box<dyn Write + Send>>,
+    strip_escapes: bool,
+    state: StripState,
+    /// The last text written didn't end its line
+    line_open: bool,
+}
+
+impl OutputLog {
+    /// Append to the log at `path`, creating it if needed
+    pub(crate) fn open(path: &Path, strip_escapes: bool) -> Result<Self, String> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        eprintln!("[LOG] Logging terminal output to {}", path.display());
+        Ok(OutputLog::new(path.to_path_buf(), Box::new(file), strip_escapes, SystemTime::now()))
+    }
+
+    pub(crate) fn new(path: PathBuf, sink: Box<dyn Write + Send>, strip_escapes: bool, started: SystemTime) -> Self {
+        let mut log = OutputLog {
+            path,
+            sink: BufWriter::new(sink),
+            strip_escapes,
+            state: StripState::Text,
+            line_open: false,
+        };
+        log.write_line(&format!("=== Output log started {} UTC ===", format_timestamp(started)));
+        log
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append output decoded by the reader thread. A failing file stops nothing but the log.
+    pub(crate) fn write(&mut self, text: &str) {
+        let stripped;
+        let text = if self.strip_escapes {
+            stripped = self.strip(text);
+            stripped.as_str()
+        } else {
+            text
+        };
+        if text.is_empty() {
+            return;
+        }
+        self.line_open = !text.ends_with('\n');
+        if let Err(e) = self.sink.write_all(text.as_bytes()) {
+            eprintln!("[LOG] Failed to write to {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Write out buffered output
+    pub(crate) fn flush(&mut self) {
+        if let Err(e) = self.sink.flush() {
+            eprintln!("[LOG] Failed to write to {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if let Err(e) = writeln!(self.sink, "{}", line) {
+            eprintln!("[LOG] Failed to write to {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Text with escape sequences and control characters other than newlines and tabs removed
+    fn strip(&mut self, text: &str) -> String {
+        let mut stripped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            self.state = match (self.state, ch) {
+                (StripState::Text, '\x1b') => StripState::Escape,
+                (StripState::Text, '\n' | '\t') => {
+                    stripped.push(ch);
+                    StripState::Text
+                }
+                (StripState::Text, ch) => {
+                    if !ch.is_control() {
+                        stripped.push(ch);
+                    }
+                    StripState::Text
+                }
+                (StripState::Escape, '[') => StripState::Csi,
+                (StripState::Escape, ']' | 'P' | '_' | '^' | 'X') => StripState::String,
+                // Intermediates, as in charset designations like ESC ( B, wait for the final byte
+                (StripState::Escape, ' '..='/') => StripState::Escape,
+                (StripState::Escape, _) => StripState::Text,
+                (StripState::Csi, '@'..='~') => StripState::Text,
+                (StripState::Csi, _) => StripState::Csi,
+                (StripState::String, '\x07') => StripState::Text,
+                (StripState::String, '\x1b') => StripState::StringEscape,
+                (StripState::String, _) => StripState::String,
+                (StripState::StringEscape, '\\') => StripState::Text,
+                (StripState::StringEscape, _) => StripState::String,
+            };
+        }
+        stripped
+    }
+}
+
+impl Drop for OutputLog {
+    fn drop(&mut self) {
+        // The footer gets a line of its own, even after a prompt that is still waiting for input
+        if self.line_open {
+            self.write_line("");
+        }
+        self.write_line(&format!("=== Output log stopped {} UTC ===", format_timestamp(SystemTime::now())));
+        let _ = self.sink.flush();
+        eprintln!("[LOG] Stopped logging to {}", self.path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::test_support::SharedSink;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn logged(strip_escapes: bool, writes: &[&str]) -> String {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let started = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut log = OutputLog::new(PathBuf::from("test.log"), Box::new(SharedSink(Arc::clone(&written))), strip_escapes, started);
+        for text in writes {
+            log.write(text);
+        }
+        drop(log);
+        let bytes = written.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_log_captures_output_under_a_timestamped_header() {
+        let text = logged(false, &["\x1b[31mred\x1b[0m\r\n", "€ done\r\n"]);
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("=== Output log started 2023-11-14 22:13:20 UTC ==="));
+        assert_eq!(lines.next(), Some("\x1b[31mred\x1b[0m"));
+        assert_eq!(lines.next(), Some("€ done"));
+        assert!(lines.next().unwrap().starts_with("=== Output log stopped "));
+    }
+
+    #[test]
+    fn test_log_strips_escapes_split_across_reads() {
+        let text = logged(
+            true,
+            &[
+                "\x1b[1;3",
+                "1mbold red\x1b[0m\r\n\x1b]0;ti",
+                "tle\x07prompt\x1b(B $ \x1b]8;;url\x1b\\link\x1b]8;;\x1b\\\r\n",
+            ],
+        );
+        let mut lines = text.lines().skip(1);
+        assert_eq!(lines.next(), Some("bold red"));
+        assert_eq!(lines.next(), Some("prompt $ link"));
+        assert!(lines.next().unwrap().starts_with("=== Output log stopped "));
+
+        // An unfinished line doesn't swallow the footer
+        let text = logged(true, &["$ "]);
+        assert_eq!(text.lines().nth(1), Some("$ "));
+    }
+
+    #[test]
+    fn test_timestamps_and_log_names() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29 00:00:00");
+
+        assert_eq!(
+            new_log_path(Some(Path::new("/tmp/logs")), UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            PathBuf::from("/tmp/logs/nist-20231114-221320.log")
+        );
+    }
+}
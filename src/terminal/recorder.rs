@@ -0,0 +1,117 @@
+//! Recording of raw PTY output (`--record-output <file>`)
+//!
+//! The bytes programs send are written to a file exactly as read, before any decoding or
+//! parsing, so a screen that renders wrong can be replayed with `--headless` or attached to
+//! a bug report. Every terminal opened while recording gets its own file: the first writes to
+//! the given path, the next ones to `<path>.2`, `<path>.3` and so on.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often recorded output is flushed while output keeps arriving
+const RECORD_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where recordings go, set from the command line; None when not recording
+static RECORD_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Terminals that have started recording, for numbering their files
+static RECORDED_TERMINALS: AtomicUsize = AtomicUsize::new(0);
+
+/// Record the output of terminals opened from now on to files starting with `path`
+pub(crate) fn set_record_path(path: PathBuf) {
+    eprintln!("[RECORDER] Recording terminal output to {}", path.display());
+    if let Ok(mut record_path) = RECORD_PATH.lock() {
+        *record_path = Some(path);
+    }
+}
+
+/// File for the `index`th recorded terminal
+fn recording_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        path.to_path_buf()
+    } else {
+        let mut numbered = path.as_os_str().to_owned();
+        numbered.push(format!(".{}", index + 1));
+        PathBuf::from(numbered)
+    }
+}
+
+/// A recorder for a new terminal, if output recording is on
+pub(crate) fn open_recorder() -> Option<OutputRecorder> {
+    let path = RECORD_PATH.lock().ok()?.clone()?;
+    let path = recording_path(&path, RECORDED_TERMINALS.fetch_add(1, Ordering::Relaxed));
+    match std::fs::File::create(&path) {
+        Ok(file) => {
+            eprintln!("[RECORDER] Recording a terminal to {}", path.display());
+            Some(OutputRecorder::new(Box::new(file)))
+        }
+        Err(e) => {
+            eprintln!("[RECORDER] Failed to create {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Copies everything a terminal's reader thread receives to a sink
+pub(crate) struct OutputRecorder {
+    sink: Box<dyn Write + Send>,
+    last_flush: Instant,
+}
+
+impl OutputRecorder {
+    pub(crate) fn new(sink: Box<dyn Write + Send>) -> Self {
+        OutputRecorder {
+            sink,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Append bytes read from the PTY. A failing sink stops nothing but the recording.
+    pub(crate) fn record(&mut self, bytes: &[u8]) {
+        if let Err(e) = self.sink.write_all(bytes) {
+            eprintln!("[RECORDER] Failed to record output: {}", e);
+            return;
+        }
+        if self.last_flush.elapsed() >= RECORD_FLUSH_INTERVAL {
+            let _ = self.sink.flush();
+            self.last_flush = Instant::now();
+        }
+    }
+}
+
+impl Drop for OutputRecorder {
+    fn drop(&mut self) {
+        let _ = self.sink.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::test_support::SharedSink;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_recorder_writes_received_bytes_to_sink() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let mut recorder = OutputRecorder::new(Box::new(SharedSink(Arc::clone(&recorded))));
+
+        // Bytes are kept as received, including escapes and a UTF-8 sequence split across reads
+        recorder.record(b"\x1b[31mred\x1b[0m \xe2\x82");
+        recorder.record(b"\xac\r\n");
+        drop(recorder);
+
+        assert_eq!(recorded.lock().unwrap().as_slice(), b"\x1b[31mred\x1b[0m \xe2\x82\xac\r\n");
+    }
+
+    #[test]
+    fn test_recording_paths_are_numbered_per_terminal() {
+        let path = Path::new("/tmp/session.raw");
+        assert_eq!(recording_path(path, 0), PathBuf::from("/tmp/session.raw"));
+        assert_eq!(recording_path(path, 1), PathBuf::from("/tmp/session.raw.2"));
+        assert_eq!(recording_path(path, 2), PathBuf::from("/tmp/session.raw.3"));
+    }
+}
@@ -1,8 +1,11 @@
 use crate::screen_buffer::ScreenBuffer;
+use crate::system::clipboard::ClipboardProvider;
+use base64::Engine;
 use std::io::Write;
 use std::sync::{Arc, Mutex};
 use unicode_segmentation::UnicodeSegmentation;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn process_output(
     text: &str,
     screen_buffer: &Arc<Mutex<ScreenBuffer>>,
@@ -10,8 +13,12 @@ pub(crate) fn process_output(
     writer: &Arc<Mutex<Box<dyn std::io::Write + Send>>>,
     last_command_exit_code: &Arc<Mutex<Option<i32>>>,
     default_cursor_style: &Arc<Mutex<crate::screen_buffer::CursorStyle>>,
+    keypad_application_mode: &Arc<Mutex<bool>>,
+    clipboard: Option<&mut dyn ClipboardProvider>,
 ) -> String {
     let mut incomplete_sequence = String::new();
+    // Selections asked for with OSC 52, answered once the screen buffer is unlocked
+    let mut clipboard_queries = Vec::new();
 
     let mut sb = screen_buffer.lock().unwrap();
     let mut chars = text.chars().peekable();
@@ -49,7 +56,7 @@ pub(crate) fn process_output(
                             }
 
                             // Process complete CSI sequence
-                            process_csi_sequence(&sequence, &mut sb, saved_screen_buffer, writer);
+                            process_csi_sequence(&sequence, &mut sb, saved_screen_buffer, writer, keypad_application_mode);
                         }
                         ']' => {
                             // OSC (Operating System Command) sequence
@@ -91,16 +98,53 @@ pub(crate) fn process_output(
                                     .and_then(|s| s.split('\\').next())
                                 {
                                     if let Ok(exit_code) = exit_code_str.trim().parse::<i32>() {
-                                        eprintln!("[TERMINAL] Command exited with code: {}", exit_code);
-                                        if let Ok(mut last_exit) = last_command_exit_code.lock() {
-                                            *last_exit = Some(exit_code);
-                                        }
-                                        // Reset cursor to default style when command exits
-                                        if let Ok(default_style) = default_cursor_style.lock() {
-                                            sb.cursor_style = *default_style;
-                                            eprintln!("[TERMINAL] Reset cursor to default style: {:?}", *default_style);
+                                        record_command_exit(exit_code, &mut sb, last_command_exit_code, default_cursor_style);
+                                    }
+                                }
+                            }
+
+                            let osc_body = sequence.trim_start_matches("\x1b]").trim_end_matches('\x07').trim_end_matches("\x1b\\");
+
+                            // Shell integration marks: ESC ] 133 ; A BEL starts a prompt,
+                            // ESC ] 133 ; D ; <code> BEL ends a command with its exit code
+                            if let Some(mark) = osc_body.strip_prefix("133;") {
+                                let mut params = mark.split(';');
+                                match params.next() {
+                                    Some("A") => sb.mark_prompt_start(),
+                                    Some("D") => {
+                                        let exit_code = params.next().and_then(|code| code.trim().parse::<i32>().ok());
+                                        match exit_code {
+                                            Some(exit_code) => record_command_exit(exit_code, &mut sb, last_command_exit_code, default_cursor_style),
+                                            None => sb.finish_command(None),
                                         }
                                     }
+                                    _ => {}
+                                }
+                            }
+
+                            // Window title: ESC ] 0 ; <title> BEL or ESC ] 2 ; <title> BEL
+                            if let Some(title) = osc_body.strip_prefix("0;").or_else(|| osc_body.strip_prefix("2;")) {
+                                sb.title = if title.is_empty() { None } else { Some(title.to_string()) };
+                            }
+
+                            // Cursor color: ESC ] 12 ; <color> BEL sets it, ESC ] 112 BEL resets it to the theme default
+                            if let Some(spec) = osc_body.strip_prefix("12;") {
+                                match crate::ansi::parse_color_spec(spec) {
+                                    Some(color) => sb.cursor_color = Some(color),
+                                    None if spec != "?" => eprintln!("[TERMINAL] Ignoring invalid cursor color: {}", spec),
+                                    None => {}
+                                }
+                            } else if osc_body == "112" {
+                                sb.cursor_color = None;
+                            }
+
+                            // Clipboard query: ESC ] 52 ; <selection> ; ? BEL. Without a clipboard (reads
+                            // not allowed) there is no reply, like xterm with disallowed window ops.
+                            if let Some(selection) = osc_body.strip_prefix("52;").and_then(|rest| rest.strip_suffix(";?")) {
+                                if clipboard.is_some() {
+                                    clipboard_queries.push(selection.to_string());
+                                } else {
+                                    eprintln!("[TERMINAL] Ignoring OSC 52 clipboard query, reading is disabled");
                                 }
                             }
 
@@ -225,26 +269,15 @@ pub(crate) fn process_output(
                         }
                         'D' => {
                             // IND (Index) - move cursor down one line
-                            // If at bottom of scroll region, scroll up instead
+                            // If at bottom of scroll region, scroll up instead (a line feed without LNM)
                             chars.next(); // consume 'D'
-
-                            let scroll_bottom = if let Some((_, bottom)) = sb.get_scroll_region() {
-                                bottom
-                            } else {
-                                sb.height() - 1
-                            };
-
-                            if sb.cursor_y == scroll_bottom {
-                                // At bottom of scroll region, scroll up
-                                sb.scroll_up(1);
-                            } else {
-                                sb.move_cursor_down(1);
-                            }
+                            sb.newline();
                         }
                         'M' => {
                             // RI (Reverse Index) - move cursor up one line
                             // If at top of scroll region, scroll down instead
                             chars.next(); // consume 'M'
+                            sb.pending_wrap = false;
 
                             let scroll_top = if let Some((top, _)) = sb.get_scroll_region() { top } else { 0 };
 
@@ -256,10 +289,10 @@ pub(crate) fn process_output(
                             }
                         }
                         'E' => {
-                            // NEL (Next Line)
+                            // NEL (Next Line) - CR + LF, scrolling at the bottom of the scroll region
                             chars.next(); // consume 'E'
-                            sb.cursor_x = 0;
-                            sb.move_cursor_down(1);
+                            sb.carriage_return();
+                            sb.newline();
                         }
                         'H' => {
                             // HTS (Horizontal Tab Set) - Set tab stop at current column
@@ -269,12 +302,31 @@ pub(crate) fn process_output(
                         '=' => {
                             // DECKPAM (Keypad Application Mode)
                             chars.next(); // consume '='
-                                          // We can ignore this for now
+                            *keypad_application_mode.lock().unwrap() = true;
                         }
                         '>' => {
                             // DECKPNM (Keypad Numeric Mode)
                             chars.next(); // consume '>'
-                                          // We can ignore this for now
+                            *keypad_application_mode.lock().unwrap() = false;
+                        }
+                        '#' => {
+                            // DEC line attributes and screen alignment: ESC # <digit>
+                            chars.next(); // consume '#'
+                            if let Some(attr_ch) = chars.next() {
+                                match attr_ch {
+                                    '3' => sb.set_line_attribute(crate::screen_buffer::LineAttribute::DoubleHeightTop),
+                                    '4' => sb.set_line_attribute(crate::screen_buffer::LineAttribute::DoubleHeightBottom),
+                                    '5' => sb.set_line_attribute(crate::screen_buffer::LineAttribute::Normal),
+                                    '6' => sb.set_line_attribute(crate::screen_buffer::LineAttribute::DoubleWidth),
+                                    _ => {
+                                        // DECALN ('8') and others are not supported
+                                    }
+                                }
+                            } else {
+                                sequence.push('#');
+                                incomplete_sequence = sequence;
+                                break;
+                            }
                         }
                         _ => {
                             // Unknown escape sequence, just consume the next character
@@ -289,8 +341,7 @@ pub(crate) fn process_output(
             }
             '\r' => {
                 // Carriage return
-                sb.pending_wrap = false;
-                sb.cursor_x = 0;
+                sb.carriage_return();
                 // If automatic newline mode is enabled, CR acts as CR+LF
                 if sb.get_automatic_newline() {
                     sb.newline();
@@ -310,7 +361,7 @@ pub(crate) fn process_output(
             }
             '\x08' => {
                 // Backspace
-                sb.move_cursor_left(1);
+                sb.backspace();
             }
             '\x0c' => {
                 // Form feed - clear screen
@@ -326,7 +377,8 @@ pub(crate) fn process_output(
                 }
             }
             '\x07' => {
-                // Bell - we can ignore this or implement a visual bell
+                // Bell - picked up by the tab bar to flag background tabs
+                sb.ring_bell();
             }
             '\x0e' => {
                 // SO (Shift Out, Ctrl-N) - Switch to G1 character set
@@ -360,6 +412,14 @@ pub(crate) fn process_output(
             }
         }
     }
+    drop(sb);
+
+    // Reading the clipboard can be slow, so the screen buffer isn't held meanwhile
+    if let Some(clipboard) = clipboard {
+        for selection in &clipboard_queries {
+            reply_clipboard_query(selection, clipboard, writer);
+        }
+    }
 
     incomplete_sequence
 }
@@ -369,6 +429,7 @@ pub(crate) fn process_csi_sequence(
     sb: &mut ScreenBuffer,
     saved_screen_buffer: &Arc<Mutex<Vec<ScreenBuffer>>>,
     writer: &Arc<Mutex<Box<dyn std::io::Write + Send>>>,
+    keypad_application_mode: &Arc<Mutex<bool>>,
 ) {
     use crate::ansi;
 
@@ -537,7 +598,8 @@ pub(crate) fn process_csi_sequence(
                 match arg {
                     0 => sb.clear_from_cursor_to_end(),
                     1 => sb.clear_from_start_to_cursor(),
-                    2 | 3 => sb.clear_screen(),
+                    2 => sb.clear_screen(),
+                    3 => sb.clear_scrollback(),
                     _ => {}
                 }
             } else {
@@ -554,9 +616,13 @@ pub(crate) fn process_csi_sequence(
                     1 => {
                         sb.clear_from_start_to_cursor();
                     }
-                    2 | 3 => {
+                    2 => {
                         sb.clear_screen();
                     }
+                    3 => {
+                        // Only the scrollback, the screen is left as is (xterm)
+                        sb.clear_scrollback();
+                    }
                     _ => {}
                 }
             }
@@ -640,7 +706,11 @@ pub(crate) fn process_csi_sequence(
                             let mut saved_stack = saved_screen_buffer.lock().unwrap();
                             saved_stack.push(sb.clone());
                             let scrollback_limit = sb.scrollback_limit();
-                            *sb = ScreenBuffer::new_with_scrollback(sb.width(), sb.height(), scrollback_limit, sb.cursor_style);
+                            let cursor_color = sb.cursor_color;
+                            let title = sb.title.take();
+                            *sb = ScreenBuffer::new_with_scrollback(sb.width(), sb.height(), scrollback_limit, sb.cursor_style, sb.tab_width());
+                            sb.cursor_color = cursor_color;
+                            sb.title = title;
                         } else {
                             eprintln!("[ALTSCREEN] Switching FROM alternate screen buffer (mode 47)");
                             let mut saved_stack = saved_screen_buffer.lock().unwrap();
@@ -648,6 +718,9 @@ pub(crate) fn process_csi_sequence(
                                 if saved_sb.width() != sb.width() || saved_sb.height() != sb.height() {
                                     saved_sb.resize(sb.width(), sb.height());
                                 }
+                                // The cursor color is per terminal, so keep one set while on the alternate screen
+                                saved_sb.cursor_color = sb.cursor_color;
+                                saved_sb.title = sb.title.take();
                                 *sb = saved_sb;
                             }
                         }
@@ -661,15 +734,23 @@ pub(crate) fn process_csi_sequence(
                             let mut saved_stack = saved_screen_buffer.lock().unwrap();
                             saved_stack.push(sb.clone());
                             let scrollback_limit = sb.scrollback_limit();
-                            *sb = ScreenBuffer::new_with_scrollback(sb.width(), sb.height(), scrollback_limit, sb.cursor_style);
+                            let cursor_color = sb.cursor_color;
+                            let title = sb.title.take();
+                            *sb = ScreenBuffer::new_with_scrollback(sb.width(), sb.height(), scrollback_limit, sb.cursor_style, sb.tab_width());
+                            sb.cursor_color = cursor_color;
+                            sb.title = title;
                         } else {
                             eprintln!("[ALTSCREEN] Switching FROM alternate screen buffer (mode 1047)");
-                            sb.clear_screen();
+                            // The alternate buffer is simply discarded; clearing it here would push its
+                            // content into scrollback, or wipe the main screen when no alternate screen is active
                             let mut saved_stack = saved_screen_buffer.lock().unwrap();
                             if let Some(mut saved_sb) = saved_stack.pop() {
                                 if saved_sb.width() != sb.width() || saved_sb.height() != sb.height() {
                                     saved_sb.resize(sb.width(), sb.height());
                                 }
+                                // The cursor color is per terminal, so keep one set while on the alternate screen
+                                saved_sb.cursor_color = sb.cursor_color;
+                                saved_sb.title = sb.title.take();
                                 *sb = saved_sb;
                             }
                         }
@@ -702,11 +783,15 @@ pub(crate) fn process_csi_sequence(
                             // Create a BRAND NEW empty buffer for alternate screen
                             // This prevents any content from the main screen bleeding through
                             let scrollback_limit = sb.scrollback_limit();
-                            *sb = ScreenBuffer::new_with_scrollback(sb.width(), sb.height(), scrollback_limit, sb.cursor_style);
+                            let cursor_color = sb.cursor_color;
+                            let title = sb.title.take();
+                            *sb = ScreenBuffer::new_with_scrollback(sb.width(), sb.height(), scrollback_limit, sb.cursor_style, sb.tab_width());
+                            sb.cursor_color = cursor_color;
+                            sb.title = title;
                         } else {
                             eprintln!("[ALTSCREEN] Switching FROM alternate screen buffer (restore main + cursor)");
-                            // Per xterm spec, clear the alternate screen before switching back
-                            sb.clear_screen();
+                            // The alternate screen is discarded rather than cleared: clear_screen() would save it
+                            // to scrollback, and without a saved main buffer it would wipe the main screen
 
                             // Restore screen from stack
                             let mut saved_stack = saved_screen_buffer.lock().unwrap();
@@ -714,18 +799,26 @@ pub(crate) fn process_csi_sequence(
                                 // Check if dimensions match, resize saved buffer if needed
                                 if saved_sb.width() != sb.width() || saved_sb.height() != sb.height() {
                                     saved_sb.resize(sb.width(), sb.height());
+                                    // The cursor was saved on entering the alternate screen, so the
+                                    // rewrapped cursor is where the saved position ended up
+                                    saved_sb.save_cursor();
                                 }
+                                // The cursor color is per terminal, so keep one set while on the alternate screen
+                                saved_sb.cursor_color = sb.cursor_color;
+                                saved_sb.title = sb.title.take();
                                 *sb = saved_sb;
                                 // Restore cursor position (implicit DECRC per xterm spec)
                                 // The saved cursor was stored in the saved_sb before we switched to altscreen
                                 sb.restore_cursor();
+                                // Redraw everything, the alternate screen's content is gone
+                                sb.dirty = true;
                             }
                         }
                     }
                     // Note: Mode 25 (cursor visibility) and Mode 1 (application cursor keys)
                     // are already handled by parse_mode_sequences() function
-                    "6" | "?6" => {
-                        // DECOM - Origin mode
+                    "?6" => {
+                        // DECOM - Origin mode (private only; non-private mode 6 is ANSI ERM)
                         // When enabled, cursor positioning is relative to scroll region
                         if final_char == 'h' {
                             sb.set_origin_mode(true);
@@ -758,6 +851,11 @@ pub(crate) fn process_csi_sequence(
                             sb.pending_wrap = false;
                         }
                     }
+                    "?45" => {
+                        // Reverse-wraparound mode (xterm)
+                        // When enabled, backspace at the left margin moves to the end of the previous line
+                        sb.set_reverse_wraparound(final_char == 'h');
+                    }
                     "4" => {
                         // IRM - Insert Mode (standard mode, not DEC private)
                         // When enabled, inserting characters pushes existing ones to the right
@@ -850,28 +948,15 @@ pub(crate) fn process_csi_sequence(
                             }
                         }
                     }
-                    "?45" => {
-                        // Reverse-wraparound Mode
-                        // When enabled, cursor can wrap backwards from left margin to previous line
-                        // This is a complex feature that's rarely used
-                        // For now, treat as no-op
-                        if debug {
-                            if final_char == 'h' {
-                                eprintln!("[TERMINAL] Reverse-wraparound mode enabled (no-op)");
-                            } else {
-                                eprintln!("[TERMINAL] Reverse-wraparound mode disabled (no-op)");
-                            }
-                        }
-                    }
                     "?66" => {
                         // DECNKM - Application Keypad Mode
-                        // Controls whether numeric keypad sends application sequences
-                        // This affects input handling, not output, so we acknowledge but don't act
+                        // Same switch as DECKPAM/DECKPNM: the numeric keypad sends application sequences
+                        *keypad_application_mode.lock().unwrap() = final_char == 'h';
                         if debug {
                             if final_char == 'h' {
-                                eprintln!("[TERMINAL] Application keypad mode enabled (no-op)");
+                                eprintln!("[TERMINAL] Application keypad mode enabled");
                             } else {
-                                eprintln!("[TERMINAL] Application keypad mode disabled (no-op)");
+                                eprintln!("[TERMINAL] Application keypad mode disabled");
                             }
                         }
                     }
@@ -1003,11 +1088,13 @@ pub(crate) fn process_csi_sequence(
             } else {
                 args[0].parse::<usize>().unwrap_or(1)
             };
-            sb.cursor_y = row.saturating_sub(1).min(sb.height() - 1);
+            // Goes through move_cursor_to so origin mode offsets and clamps the row to the region
+            let col = sb.cursor_x;
+            sb.move_cursor_to(col, row.saturating_sub(1));
         }
         'm' => {
             // SGR (Select Graphic Rendition) - colors and text attributes
-            let ([fg, bg], attrs) = ansi::parse_m(sequence);
+            let ([fg, bg], attrs) = ansi::parse_m(sequence, sb.text_attributes());
             if let Some(color) = fg {
                 sb.fg_color = color;
             }
@@ -1015,13 +1102,7 @@ pub(crate) fn process_csi_sequence(
                 sb.bg_color = color;
             }
             if let Some(attributes) = attrs {
-                sb.bold = attributes.bold;
-                sb.italic = attributes.italic;
-                sb.underline = attributes.underline;
-                sb.strikethrough = attributes.strikethrough;
-                sb.blink = attributes.blink;
-                sb.reverse = attributes.reverse;
-                sb.invisible = attributes.invisible;
+                sb.set_text_attributes(attributes);
             }
         }
         'n' => {
@@ -1034,8 +1115,7 @@ pub(crate) fn process_csi_sequence(
                 match param {
                     6 => {
                         // CSI ? 6 n - Report Cursor Position (with '?' prefix)
-                        let row = sb.cursor_y + 1; // 1-based
-                        let col = sb.cursor_x + 1; // 1-based
+                        let (row, col) = sb.cursor_report_position();
                         let response = format!("\x1b[?{};{}R", row, col);
                         if let Ok(mut w) = writer.lock() {
                             let _ = w.write_all(response.as_bytes());
@@ -1107,8 +1187,7 @@ pub(crate) fn process_csi_sequence(
                     }
                     6 => {
                         // Cursor Position Report (CPR)
-                        let row = sb.cursor_y + 1; // 1-based
-                        let col = sb.cursor_x + 1; // 1-based
+                        let (row, col) = sb.cursor_report_position();
                         let response = format!("\x1b[{};{}R", row, col);
 
                         // Send response back through PTY to the application
@@ -1167,26 +1246,20 @@ pub(crate) fn process_csi_sequence(
             // Restore cursor position (ANSI.SYS style)
             sb.restore_cursor();
         }
-        'q' => {
+        'q' if sequence.ends_with(" q") => {
             // DECSCUSR (Set Cursor Style) - CSI Ps SP q
-            // The parameter Ps determines the cursor style
-            let param = if args.is_empty() || args[0].is_empty() {
+            // The parameter Ps determines the cursor style; without the space it's DECLL (keyboard LEDs)
+            let param = if args.is_empty() || args[0].trim().is_empty() {
                 0
             } else {
                 // DECSCUSR uses a space before 'q', so trim it
                 args[0].trim().parse::<u32>().unwrap_or(0)
             };
 
-            use crate::screen_buffer::CursorStyle;
-            sb.cursor_style = match param {
-                1 => CursorStyle::BlinkingBlock,
-                2 => CursorStyle::SteadyBlock,
-                3 => CursorStyle::BlinkingUnderline,
-                4 => CursorStyle::SteadyUnderline,
-                5 => CursorStyle::BlinkingBar,
-                6 => CursorStyle::SteadyBar,
-                _ => CursorStyle::BlinkingBlock, // 0 or unknown defaults to blinking block
-            };
+            match crate::screen_buffer::CursorStyle::from_decscusr(param) {
+                Some(style) => sb.cursor_style = style,
+                None => eprintln!("[TERMINAL] Ignoring unknown cursor style: {}", param),
+            }
         }
         'Z' => {
             // CBT (Cursor Backward Tabulation)
@@ -1278,3 +1351,641 @@ fn parse_mode_sequences_old(sequence: &str, debug: bool) -> Vec<String> {
 
     mode_numbers
 }
+
+/// Decode bytes read from the PTY, holding back a multibyte character cut off at the end of
+/// the read in `pending` so it is decoded whole with the next read. Invalid bytes still become U+FFFD.
+pub(crate) fn decode_utf8(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    pending.extend_from_slice(bytes);
+    let complete = pending.len() - incomplete_utf8_tail(pending);
+    let text = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    text
+}
+
+/// Length of a UTF-8 sequence left unfinished at the end of `bytes`
+fn incomplete_utf8_tail(bytes: &[u8]) -> usize {
+    // The lead byte of a cut-off character is at most 3 bytes from the end
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 != 0x80 {
+            let needed = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if needed > back { back } else { 0 };
+        }
+    }
+    0
+}
+
+/// Largest clipboard text handed to a program asking with OSC 52
+const OSC52_MAX_READ_BYTES: usize = 100_000;
+
+/// Answer an OSC 52 query with the base64-encoded clipboard, or an empty payload when the
+/// clipboard is empty, unreadable or over the size cap
+fn reply_clipboard_query(selection: &str, clipboard: &mut dyn ClipboardProvider, writer: &Arc<Mutex<Box<dyn std::io::Write + Send>>>) {
+    let text = clipboard.get().unwrap_or_default();
+    let payload = if text.len() > OSC52_MAX_READ_BYTES {
+        eprintln!("[TERMINAL] Clipboard too large for OSC 52 reply ({} bytes)", text.len());
+        String::new()
+    } else {
+        base64::engine::general_purpose::STANDARD.encode(text.as_bytes())
+    };
+
+    let response = format!("\x1b]52;{};{}\x1b\\", selection, payload);
+    if let Ok(mut w) = writer.lock() {
+        let _ = w.write_all(response.as_bytes());
+        let _ = w.flush();
+    }
+}
+
+/// Remember a finished command's exit code and close its region in the screen buffer
+fn record_command_exit(
+    exit_code: i32,
+    sb: &mut ScreenBuffer,
+    last_command_exit_code: &Arc<Mutex<Option<i32>>>,
+    default_cursor_style: &Arc<Mutex<crate::screen_buffer::CursorStyle>>,
+) {
+    eprintln!("[TERMINAL] Command exited with code: {}", exit_code);
+    if let Ok(mut last_exit) = last_command_exit_code.lock() {
+        *last_exit = Some(exit_code);
+    }
+    sb.finish_command(Some(exit_code));
+    // Reset cursor to default style when command exits
+    if let Ok(default_style) = default_cursor_style.lock() {
+        sb.cursor_style = *default_style;
+        eprintln!("[TERMINAL] Reset cursor to default style: {:?}", *default_style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen_buffer::{CursorStyle, DEFAULT_TAB_WIDTH};
+    use crate::system::clipboard::MockClipboard;
+
+    /// Writer that records everything sent back to the PTY
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Feed `text` through the parser and return the bytes written back to the PTY
+    fn feed(sb: &Arc<Mutex<ScreenBuffer>>, saved: &Arc<Mutex<Vec<ScreenBuffer>>>, text: &str) -> Vec<u8> {
+        feed_with_clipboard(sb, saved, text, None)
+    }
+
+    /// Like `feed`, with OSC 52 clipboard queries answered from `clipboard`
+    fn feed_with_clipboard(
+        sb: &Arc<Mutex<ScreenBuffer>>,
+        saved: &Arc<Mutex<Vec<ScreenBuffer>>>,
+        text: &str,
+        clipboard: Option<&mut dyn ClipboardProvider>,
+    ) -> Vec<u8> {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let writer: Arc<Mutex<Box<dyn std::io::Write + Send>>> = Arc::new(Mutex::new(Box::new(CaptureWriter(Arc::clone(&captured)))));
+        let exit_code = Arc::new(Mutex::new(None));
+        let cursor_style = Arc::new(Mutex::new(CursorStyle::default()));
+        let keypad_mode = Arc::new(Mutex::new(false));
+
+        process_output(text, sb, saved, &writer, &exit_code, &cursor_style, &keypad_mode, clipboard);
+
+        let bytes = captured.lock().unwrap().clone();
+        bytes
+    }
+
+    fn new_buffers(width: usize, height: usize, cursor_style: CursorStyle) -> (Arc<Mutex<ScreenBuffer>>, Arc<Mutex<Vec<ScreenBuffer>>>) {
+        let sb = Arc::new(Mutex::new(ScreenBuffer::new_with_scrollback(
+            width,
+            height,
+            100,
+            cursor_style,
+            DEFAULT_TAB_WIDTH,
+        )));
+        (sb, Arc::new(Mutex::new(Vec::new())))
+    }
+
+    fn replies_for(text: &str) -> Vec<u8> {
+        let (sb, saved) = new_buffers(80, 24, CursorStyle::default());
+        feed(&sb, &saved, text)
+    }
+
+    fn cursor(sb: &Arc<Mutex<ScreenBuffer>>) -> (usize, usize) {
+        let sb = sb.lock().unwrap();
+        (sb.cursor_x, sb.cursor_y)
+    }
+
+    fn scrollback_text(sb: &ScreenBuffer) -> Vec<String> {
+        sb.get_scrollback_buffer()
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.ch).collect::<String>().trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_dsr_cursor_position_report() {
+        // The same bytes a test client would inject with Terminal::write_bytes
+        let reply = replies_for("\x1b[3;5H\x1b[6n");
+        assert_eq!(reply, b"\x1b[3;5R");
+    }
+
+    #[test]
+    fn test_origin_mode_cursor_position_report() {
+        // Region rows 5-10, origin mode on: CUP and CPR are relative to the region top
+        assert_eq!(replies_for("\x1b[5;10r\x1b[?6h\x1b[3;2H\x1b[6n"), b"\x1b[3;2R");
+        // Rows beyond the region are clamped to its bottom
+        assert_eq!(replies_for("\x1b[5;10r\x1b[?6h\x1b[99;1H\x1b[6n"), b"\x1b[6;1R");
+        // VPA honors origin mode as well
+        assert_eq!(replies_for("\x1b[5;10r\x1b[?6h\x1b[2d\x1b[6n"), b"\x1b[2;1R");
+        // DECSTBM with only a top margin homes the cursor to the region top
+        assert_eq!(replies_for("\x1b[?6h\x1b[5r\x1b[6n"), b"\x1b[1;1R");
+    }
+
+    #[test]
+    fn test_dsr_status_report() {
+        assert_eq!(replies_for("\x1b[5n"), b"\x1b[0n");
+    }
+
+    #[test]
+    fn test_alt_screen_round_trip_preserves_main_scrollback() {
+        let (sb, saved) = new_buffers(10, 3, CursorStyle::SteadyBar);
+        feed(&sb, &saved, "one\r\ntwo\r\nthree\r\nfour\r\nfive");
+        let (scrollback_before, screen_before, cursor_before) = {
+            let sb = sb.lock().unwrap();
+            (scrollback_text(&sb), sb.get_visible_text(), (sb.cursor_x, sb.cursor_y))
+        };
+        assert_eq!(scrollback_before, vec!["one", "two"]);
+
+        // Enter the alternate screen and scroll plenty of lines through it
+        feed(&sb, &saved, "\x1b[?1049h");
+        {
+            let alt = sb.lock().unwrap();
+            assert!(alt.get_scrollback_buffer().is_empty());
+            assert_eq!(alt.cursor_style, CursorStyle::SteadyBar);
+        }
+        feed(&sb, &saved, "a\r\nb\r\nc\r\nd\r\ne\r\n\x1b[2J");
+        sb.lock().unwrap().scroll_view_up(2);
+
+        feed(&sb, &saved, "\x1b[?1049l");
+        let sb = sb.lock().unwrap();
+        assert_eq!(scrollback_text(&sb), scrollback_before);
+        assert_eq!(sb.get_visible_text(), screen_before);
+        assert_eq!((sb.cursor_x, sb.cursor_y), cursor_before);
+        assert!(saved.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_alt_screen_exit_after_resize_fits_new_size() {
+        let (sb, saved) = new_buffers(80, 24, CursorStyle::default());
+        // Scrolling region on rows 5-20, cursor near the bottom right
+        feed(&sb, &saved, "\x1b[5;20r\x1b[23;70Hprompt");
+        feed(&sb, &saved, "\x1b[?1049h");
+
+        // The window grows while a full-screen program runs
+        sb.lock().unwrap().resize(90, 30);
+        sb.lock().unwrap().resize(100, 40);
+        feed(&sb, &saved, "\x1b[?1049l");
+        {
+            let sb = sb.lock().unwrap();
+            assert_eq!((sb.width(), sb.height()), (100, 40));
+            assert!(sb.cursor_x < 100 && sb.cursor_y < 40);
+            assert_eq!(sb.get_scroll_region(), Some((4, 19)));
+            assert!(sb.is_dirty());
+        }
+
+        // Shrinking below the scrolling region drops it and pulls the cursor in
+        feed(&sb, &saved, "\x1b[40;100H\x1b[?1049h");
+        sb.lock().unwrap().resize(30, 10);
+        feed(&sb, &saved, "\x1b[?1049l");
+        let sb = sb.lock().unwrap();
+        assert_eq!((sb.width(), sb.height()), (30, 10));
+        assert!(sb.cursor_x < 30 && sb.cursor_y < 10);
+        assert_eq!(sb.get_scroll_region(), None);
+    }
+
+    #[test]
+    fn test_alt_screen_exit_without_enter_keeps_screen() {
+        let (sb, saved) = new_buffers(10, 3, CursorStyle::default());
+        feed(&sb, &saved, "hello");
+        feed(&sb, &saved, "\x1b[?1049l\x1b[?1047l");
+        let sb = sb.lock().unwrap();
+        assert_eq!(sb.get_visible_text()[0], "hello");
+        assert!(sb.get_scrollback_buffer().is_empty());
+    }
+
+    #[test]
+    fn test_lnm_makes_carriage_return_advance_the_line() {
+        let (sb, saved) = new_buffers(10, 4, CursorStyle::default());
+
+        feed(&sb, &saved, "ab\r");
+        assert_eq!(cursor(&sb), (0, 0));
+
+        feed(&sb, &saved, "\x1b[20hab\r");
+        assert_eq!(cursor(&sb), (0, 1));
+
+        // LF keeps its plain meaning
+        feed(&sb, &saved, "cd\n");
+        assert_eq!(cursor(&sb), (2, 2));
+
+        feed(&sb, &saved, "\x1b[20l\r");
+        assert_eq!(cursor(&sb), (0, 2));
+    }
+
+    #[test]
+    fn test_dec_line_attribute_escapes() {
+        use crate::screen_buffer::LineAttribute;
+
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        feed(&sb, &saved, "\x1b#6wide\r\n\x1b#3big\r\n\x1b#4big");
+        {
+            let sb = sb.lock().unwrap();
+            assert_eq!(sb.line_attribute(0), LineAttribute::DoubleWidth);
+            assert_eq!(sb.line_attribute(1), LineAttribute::DoubleHeightTop);
+            assert_eq!(sb.line_attribute(2), LineAttribute::DoubleHeightBottom);
+            assert_eq!(sb.line_attribute(3), LineAttribute::Normal);
+            // The escape is consumed, not printed
+            assert_eq!(sb.get_visible_text()[0], "wide");
+        }
+
+        feed(&sb, &saved, "\x1b[1;1H\x1b#5");
+        assert_eq!(sb.lock().unwrap().line_attribute(0), LineAttribute::Normal);
+    }
+
+    #[test]
+    fn test_character_insert_delete_and_erase() {
+        let (sb, saved) = new_buffers(10, 3, CursorStyle::default());
+        let first_line = |sb: &Arc<Mutex<ScreenBuffer>>| sb.lock().unwrap().get_visible_text()[0].clone();
+
+        // ICH shifts the rest of the line right, dropping what falls off the edge
+        feed(&sb, &saved, "abcdefghij\x1b[1;3H\x1b[3@");
+        assert_eq!(first_line(&sb), "ab   cdefg");
+        assert_eq!(cursor(&sb), (2, 0));
+
+        // DCH pulls it back left, blanking the end of the line
+        feed(&sb, &saved, "\x1b[2P");
+        assert_eq!(first_line(&sb), "ab cdefg");
+
+        // Without a parameter each of them works on one character
+        feed(&sb, &saved, "\x1b[@");
+        assert_eq!(first_line(&sb), "ab  cdefg");
+        feed(&sb, &saved, "\x1b[P");
+        assert_eq!(first_line(&sb), "ab cdefg");
+
+        // ECH blanks characters in place
+        feed(&sb, &saved, "\x1b[1;5H\x1b[2X");
+        assert_eq!(first_line(&sb), "ab c  fg");
+        // The cursor stays put
+        assert_eq!(cursor(&sb), (4, 0));
+        feed(&sb, &saved, "\x1b[1;7H\x1b[X");
+        assert_eq!(first_line(&sb), "ab c   g");
+    }
+
+    #[test]
+    fn test_line_insert_and_delete() {
+        let (sb, saved) = new_buffers(10, 3, CursorStyle::default());
+        feed(&sb, &saved, "one\r\ntwo\r\nthree\x1b[1;1H\x1b[L");
+        assert_eq!(sb.lock().unwrap().get_visible_text(), vec!["", "one", "two"]);
+
+        feed(&sb, &saved, "\x1b[2M");
+        assert_eq!(sb.lock().unwrap().get_visible_text(), vec!["two", "", ""]);
+    }
+
+    #[test]
+    fn test_reverse_wraparound_backspace() {
+        let (sb, saved) = new_buffers(10, 4, CursorStyle::default());
+
+        // Off by default: backspace stops at the left margin
+        feed(&sb, &saved, "\x1b[2;1H\x08");
+        assert_eq!(cursor(&sb), (0, 1));
+
+        feed(&sb, &saved, "\x1b[?45h\x08");
+        assert_eq!(cursor(&sb), (9, 0));
+
+        // Never wraps above the top line
+        feed(&sb, &saved, "\x1b[1;1H\x08");
+        assert_eq!(cursor(&sb), (0, 0));
+
+        // Needs auto-wrap as well
+        feed(&sb, &saved, "\x1b[?7l\x1b[2;1H\x08");
+        assert_eq!(cursor(&sb), (0, 1));
+
+        feed(&sb, &saved, "\x1b[?7h\x1b[?45l\x1b[2;1H\x08");
+        assert_eq!(cursor(&sb), (0, 1));
+    }
+
+    #[test]
+    fn test_dec_special_graphics_through_designation_and_shifts() {
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        let row = |sb: &Arc<Mutex<ScreenBuffer>>, y: usize| {
+            let sb = sb.lock().unwrap();
+            (0..sb.width())
+                .map(|x| sb.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+
+        // G0 in GL directly, then back to ASCII
+        feed(&sb, &saved, "\x1b(0qx\x1b(Bq\r\n");
+        assert_eq!(row(&sb, 0), "─│q");
+
+        // G1 through SO, until SI
+        feed(&sb, &saved, "\x1b)0q\x0eqj\x0fq\r\n");
+        assert_eq!(row(&sb, 1), "q─┘q");
+
+        // G2 and G3 for a single character with SS2 and SS3
+        feed(&sb, &saved, "\x1b*0\x1b+0\x1bNqq\x1bOlq\r\n");
+        assert_eq!(row(&sb, 2), "─q┌q");
+
+        // Locking shifts to G2 and G3 stay until shifted back
+        feed(&sb, &saved, "\x1bnq\x1bomk\x0fq");
+        assert_eq!(row(&sb, 3), "─└┐q");
+    }
+
+    #[test]
+    fn test_shift_out_and_in_switch_between_g0_and_g1() {
+        let (sb, saved) = new_buffers(20, 2, CursorStyle::default());
+        let cell = |sb: &Arc<Mutex<ScreenBuffer>>, x: usize| sb.lock().unwrap().get_cell(x, 0).unwrap().ch;
+
+        // SO with G1 still ASCII changes nothing
+        feed(&sb, &saved, "\x0eq\x0f");
+        assert_eq!(cell(&sb, 0), 'q');
+
+        // The usual box drawing setup: DEC graphics in G1, SO to draw, SI back to text
+        feed(&sb, &saved, "\x1b)0\x0elqk\x0fok");
+        assert_eq!((1..6).map(|x| cell(&sb, x)).collect::<String>(), "┌─┐ok");
+    }
+
+    #[test]
+    fn test_carriage_return_cancels_pending_wrap() {
+        let (sb, saved) = new_buffers(10, 4, CursorStyle::default());
+        feed(&sb, &saved, "0123456789");
+        assert!(sb.lock().unwrap().pending_wrap);
+
+        feed(&sb, &saved, "\rX");
+        {
+            let sb = sb.lock().unwrap();
+            assert_eq!((sb.cursor_x, sb.cursor_y), (1, 0));
+            assert_eq!(sb.get_visible_text()[0], "X123456789");
+            assert_eq!(sb.get_visible_text()[1], "");
+        }
+
+        // Index and next line at the bottom scroll once, not once plus a wrap
+        feed(&sb, &saved, "\x1b[4;1H0123456789\x1bDa");
+        {
+            let sb = sb.lock().unwrap();
+            assert_eq!((sb.cursor_x, sb.cursor_y), (9, 3));
+            assert_eq!(sb.get_visible_text()[2], "0123456789");
+            assert_eq!(sb.get_visible_text()[3], "         a");
+        }
+        feed(&sb, &saved, "\x1b[4;1H0123456789\x1bEb");
+        {
+            let sb = sb.lock().unwrap();
+            assert_eq!((sb.cursor_x, sb.cursor_y), (1, 3));
+            assert_eq!(sb.get_visible_text()[3], "b");
+        }
+
+        // Restoring a saved cursor starts clean as well
+        feed(&sb, &saved, "\x1b[2J\x1b[1;1H\x1b7\x1b[2;1H0123456789\x1b8Y");
+        let sb = sb.lock().unwrap();
+        assert_eq!((sb.cursor_x, sb.cursor_y), (1, 0));
+        assert_eq!(sb.get_visible_text()[0], "Y");
+        assert_eq!(sb.get_visible_text()[2], "");
+    }
+
+    #[test]
+    fn test_osc_title_survives_alt_screen() {
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        feed(&sb, &saved, "\x1b]0;shell\x07");
+        assert_eq!(sb.lock().unwrap().title.as_deref(), Some("shell"));
+
+        feed(&sb, &saved, "\x1b[?1049h\x1b]2;vim notes.txt\x1b\\");
+        assert_eq!(sb.lock().unwrap().title.as_deref(), Some("vim notes.txt"));
+
+        // The title is per terminal, so it is kept when the program exits the alternate screen
+        feed(&sb, &saved, "\x1b[?1049l");
+        assert_eq!(sb.lock().unwrap().title.as_deref(), Some("vim notes.txt"));
+        assert_eq!(sb.lock().unwrap().get_visible_text()[0], "");
+    }
+
+    #[test]
+    fn test_sgr_reset_clears_all_attributes() {
+        use crate::ansi::{DEFAULT_BG_COLOR, DEFAULT_FG_COLOR};
+
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        feed(&sb, &saved, "\x1b[1;3;4m\x1b[5;7;8;9;31;42mA\x1b[0mB");
+        let sb = sb.lock().unwrap();
+
+        // Attributes set by separate sequences add up
+        let styled = sb.get_cell(0, 0).unwrap();
+        assert!(styled.bold && styled.italic && styled.underline);
+        assert!(styled.blink && styled.reverse && styled.invisible && styled.strikethrough);
+
+        let plain = sb.get_cell(1, 0).unwrap();
+        assert_eq!(plain.ch, 'B');
+        assert!(!plain.bold && !plain.italic && !plain.underline && !plain.strikethrough);
+        assert!(!plain.blink && !plain.reverse && !plain.invisible);
+        assert_eq!(plain.fg_color, DEFAULT_FG_COLOR);
+        assert_eq!(plain.bg_color, DEFAULT_BG_COLOR);
+    }
+
+    #[test]
+    fn test_decscusr_sets_cursor_style() {
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::SteadyBlock);
+        feed(&sb, &saved, "\x1b[5 q");
+        assert_eq!(sb.lock().unwrap().cursor_style, CursorStyle::BlinkingBar);
+
+        feed(&sb, &saved, "\x1b[2 q");
+        assert_eq!(sb.lock().unwrap().cursor_style, CursorStyle::SteadyBlock);
+
+        // Unknown styles and DECLL (no space before q) leave the cursor alone
+        feed(&sb, &saved, "\x1b[9 q\x1b[4q");
+        assert_eq!(sb.lock().unwrap().cursor_style, CursorStyle::SteadyBlock);
+
+        feed(&sb, &saved, "\x1b[ q");
+        assert_eq!(sb.lock().unwrap().cursor_style, CursorStyle::BlinkingBlock);
+    }
+
+    #[test]
+    fn test_ed_3_clears_scrollback() {
+        let (sb, saved) = new_buffers(10, 2, CursorStyle::default());
+        feed(&sb, &saved, "one\r\ntwo\r\nthree\r\nfour");
+        assert_eq!(scrollback_text(&sb.lock().unwrap()), vec!["one", "two"]);
+        sb.lock().unwrap().scroll_view_up(2);
+
+        feed(&sb, &saved, "\x1b[3J");
+        let sb = sb.lock().unwrap();
+        assert!(sb.get_scrollback_buffer().is_empty());
+        assert!(sb.is_at_bottom());
+    }
+
+    #[test]
+    fn test_ed_3_leaves_screen_untouched() {
+        let (sb, saved) = new_buffers(10, 2, CursorStyle::default());
+        feed(&sb, &saved, "one\r\ntwo\r\nthree");
+
+        for erase in ["\x1b[3J", "\x1b[?3J"] {
+            feed(&sb, &saved, erase);
+            let sb = sb.lock().unwrap();
+            assert!(sb.get_scrollback_buffer().is_empty(), "{:?} should empty the scrollback", erase);
+            assert_eq!(sb.text_in_range(0, sb.view_row_to_absolute(0), 9, sb.view_row_to_absolute(1)), "two\nthree");
+            assert_eq!((sb.cursor_x, sb.cursor_y), (5, 1));
+        }
+
+        // `tput clear`: the old screen still lands in scrollback from ED 2 after ED 3 emptied it
+        feed(&sb, &saved, "\x1b[3J\x1b[H\x1b[2J");
+        assert_eq!(scrollback_text(&sb.lock().unwrap()), vec!["two", "three"]);
+    }
+
+    #[test]
+    fn test_osc_52_query_replies_with_clipboard() {
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+
+        let mut clipboard = MockClipboard {
+            text: Some("hello, world".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            feed_with_clipboard(&sb, &saved, "\x1b]52;c;?\x1b\\", Some(&mut clipboard)),
+            b"\x1b]52;c;aGVsbG8sIHdvcmxk\x1b\\"
+        );
+
+        // Padding, and an empty clipboard
+        let mut clipboard = MockClipboard {
+            text: Some("nist".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            feed_with_clipboard(&sb, &saved, "\x1b]52;c;?\x07", Some(&mut clipboard)),
+            b"\x1b]52;c;bmlzdA==\x1b\\"
+        );
+        let mut empty = MockClipboard::default();
+        assert_eq!(feed_with_clipboard(&sb, &saved, "\x1b]52;c;?\x07", Some(&mut empty)), b"\x1b]52;c;\x1b\\");
+
+        // Too large to hand out
+        let mut huge = MockClipboard {
+            text: Some("x".repeat(OSC52_MAX_READ_BYTES + 1)),
+            ..Default::default()
+        };
+        assert_eq!(feed_with_clipboard(&sb, &saved, "\x1b]52;c;?\x07", Some(&mut huge)), b"\x1b]52;c;\x1b\\");
+
+        // Reading disabled: no reply at all
+        assert!(feed(&sb, &saved, "\x1b]52;c;?\x07").is_empty());
+    }
+
+    #[test]
+    fn test_osc_12_sets_cursor_color() {
+        use sdl3::pixels::Color;
+
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        assert_eq!(sb.lock().unwrap().cursor_color, None);
+
+        feed(&sb, &saved, "\x1b]12;#ff8000\x07");
+        assert_eq!(sb.lock().unwrap().cursor_color, Some(Color::RGB(255, 128, 0)));
+
+        // ST terminator and X11 rgb: notation
+        feed(&sb, &saved, "\x1b]12;rgb:00/80/ff\x1b\\");
+        assert_eq!(sb.lock().unwrap().cursor_color, Some(Color::RGB(0, 128, 255)));
+
+        // Invalid colors and queries leave the current color alone
+        feed(&sb, &saved, "\x1b]12;bogus\x07\x1b]12;?\x07");
+        assert_eq!(sb.lock().unwrap().cursor_color, Some(Color::RGB(0, 128, 255)));
+
+        // The color survives a round trip through the alternate screen
+        feed(&sb, &saved, "\x1b[?1049h");
+        assert_eq!(sb.lock().unwrap().cursor_color, Some(Color::RGB(0, 128, 255)));
+        feed(&sb, &saved, "\x1b]12;#000\x07\x1b[?1049l");
+        assert_eq!(sb.lock().unwrap().cursor_color, Some(Color::RGB(0, 0, 0)));
+
+        // OSC 112 resets to the theme default
+        feed(&sb, &saved, "\x1b]112\x07");
+        assert_eq!(sb.lock().unwrap().cursor_color, None);
+        assert!(sb.lock().unwrap().get_visible_text().iter().all(|line| line.is_empty()));
+    }
+
+    #[test]
+    fn test_deckpam_and_deckpnm_toggle_keypad_mode() {
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        let writer: Arc<Mutex<Box<dyn std::io::Write + Send>>> = Arc::new(Mutex::new(Box::new(std::io::sink())));
+        let exit_code = Arc::new(Mutex::new(None));
+        let cursor_style = Arc::new(Mutex::new(CursorStyle::default()));
+        let keypad_mode = Arc::new(Mutex::new(false));
+
+        process_output("\x1b=", &sb, &saved, &writer, &exit_code, &cursor_style, &keypad_mode, None);
+        assert!(*keypad_mode.lock().unwrap());
+
+        process_output("\x1b>ok", &sb, &saved, &writer, &exit_code, &cursor_style, &keypad_mode, None);
+        assert!(!*keypad_mode.lock().unwrap());
+        // Neither escape leaves anything on screen
+        assert_eq!(sb.lock().unwrap().get_visible_text()[0], "ok");
+    }
+
+    #[test]
+    fn test_decnkm_toggles_keypad_mode() {
+        let (sb, saved) = new_buffers(20, 4, CursorStyle::default());
+        let writer: Arc<Mutex<Box<dyn std::io::Write + Send>>> = Arc::new(Mutex::new(Box::new(std::io::sink())));
+        let exit_code = Arc::new(Mutex::new(None));
+        let cursor_style = Arc::new(Mutex::new(CursorStyle::default()));
+        let keypad_mode = Arc::new(Mutex::new(false));
+
+        process_output("\x1b[?66h", &sb, &saved, &writer, &exit_code, &cursor_style, &keypad_mode, None);
+        assert!(*keypad_mode.lock().unwrap());
+
+        process_output("\x1b[?66l", &sb, &saved, &writer, &exit_code, &cursor_style, &keypad_mode, None);
+        assert!(!*keypad_mode.lock().unwrap());
+    }
+
+    #[test]
+    fn test_multibyte_characters_split_across_reads() {
+        let text = "añ€😀b";
+        let bytes = text.as_bytes();
+        for split in 0..=bytes.len() {
+            let (sb, saved) = new_buffers(20, 2, CursorStyle::default());
+            let mut pending = Vec::new();
+            let first = decode_utf8(&mut pending, &bytes[..split]);
+            feed(&sb, &saved, &first);
+            let second = decode_utf8(&mut pending, &bytes[split..]);
+            feed(&sb, &saved, &second);
+
+            assert!(pending.is_empty());
+            assert_eq!(first + &second, text, "split at byte {}", split);
+            assert_eq!(sb.lock().unwrap().get_visible_text()[0], text, "split at byte {}", split);
+        }
+
+        // Bytes that can never form a character are still replaced
+        let mut pending = Vec::new();
+        assert_eq!(decode_utf8(&mut pending, b"a\xffb"), "a\u{FFFD}b");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_command_finished_mark_sets_exit_code() {
+        let (sb, saved) = new_buffers(20, 6, CursorStyle::default());
+        let writer: Arc<Mutex<Box<dyn std::io::Write + Send>>> = Arc::new(Mutex::new(Box::new(std::io::sink())));
+        let exit_code = Arc::new(Mutex::new(None));
+        let cursor_style = Arc::new(Mutex::new(CursorStyle::default()));
+        let keypad_mode = Arc::new(Mutex::new(false));
+
+        // Prompt on row 0, the command prints two lines, then reports failure before the next prompt
+        let output = "\x1b]133;A\x07$ false\r\nno\r\nno\r\n\x1b]133;D;1\x07\x1b]133;A\x07$ ";
+        process_output(output, &sb, &saved, &writer, &exit_code, &cursor_style, &keypad_mode, None);
+
+        assert_eq!(*exit_code.lock().unwrap(), Some(1));
+        let sb = sb.lock().unwrap();
+        for row in 0..3 {
+            assert_eq!(sb.command_exit_code_at(row), Some(1), "row {}", row);
+        }
+        // The new prompt belongs to the next, still running command
+        assert_eq!(sb.command_exit_code_at(3), None);
+    }
+}
@@ -0,0 +1,49 @@
+//! Helpers shared by the tests of modules that need a terminal or a writer to inspect
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::main::TerminalOptions;
+use super::{ShellConfig, Terminal, TerminalLibrary};
+use crate::screen_buffer::CursorStyle;
+
+/// A writer that collects everything written to it in a buffer the test keeps a handle to
+pub(crate) struct SharedSink(pub Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The platform's default shell
+pub(crate) fn default_shell() -> ShellConfig {
+    TerminalLibrary::new().get_default_shell().clone()
+}
+
+/// A "shell" that prints nothing and keeps running, so only what a test writes is on screen
+#[cfg(unix)]
+pub(crate) fn silent_shell() -> ShellConfig {
+    ShellConfig {
+        command: "sleep".to_string(),
+        args: vec!["30".to_string()],
+        keys: default_shell().keys,
+    }
+}
+
+/// An 80x24 terminal with 100 lines of scrollback and default options, running `shell_config`
+/// in `start_dir` (the current directory when None)
+pub(crate) fn new_terminal(shell_config: ShellConfig, start_dir: Option<PathBuf>) -> Terminal {
+    Terminal::new_with_scrollback(80, 24, shell_config, 100, start_dir, CursorStyle::default(), TerminalOptions::default())
+}
+
+/// An 80x24 terminal running the default shell, shared the way panes hold it
+pub(crate) fn test_terminal() -> Arc<Mutex<Terminal>> {
+    Arc::new(Mutex::new(new_terminal(default_shell(), None)))
+}
@@ -5,10 +5,6 @@ use std::path::PathBuf;
 const BASH_INIT_SCRIPT: &str = include_str!("../../static/scripts/bash_init.sh");
 const ZSH_INIT_SCRIPT: &str = include_str!("../../static/scripts/zsh_init.sh");
 
-// History persistence limits
-pub(crate) const MAX_COMMAND_HISTORY: usize = 5; // Maximum number of commands to keep in history
-pub(crate) const MAX_OUTPUT_HISTORY: usize = 100; // Maximum number of output lines to keep in history
-
 /// Create a temporary shell init file that configures exit code reporting
 pub(crate) fn create_shell_init_file(shell_name: &str) -> Option<PathBuf> {
     match shell_name {
@@ -44,3 +40,72 @@ pub(crate) fn create_shell_init_file(shell_name: &str) -> Option<PathBuf> {
         }
     }
 }
+
+/// Extract the process name from the contents of `/proc/<pid>/stat`.
+/// The name sits in parentheses and may itself contain spaces or parentheses,
+/// so it runs up to the last closing parenthesis.
+#[cfg(target_os = "linux")]
+pub(crate) fn parse_proc_stat_name(stat: &str) -> Option<String> {
+    let start = stat.find('(')?;
+    let end = stat.rfind(')')?;
+    (end > start).then(|| stat[start + 1..end].to_string()).filter(|name| !name.is_empty())
+}
+
+/// Whether the PTY's foreground process group belongs to a job rather than the shell.
+/// While the shell waits for input it leads the foreground group itself; if either
+/// side is unknown, assume a job is running so closing stays safe.
+#[cfg(unix)]
+pub(crate) fn is_foreground_job(foreground_pgid: Option<i32>, shell_pid: Option<u32>) -> bool {
+    match (foreground_pgid, shell_pid) {
+        (Some(pgid), Some(shell_pid)) => pgid as u32 != shell_pid,
+        _ => true,
+    }
+}
+
+/// Signals offered in the pane context menu, as (name, number)
+#[cfg(unix)]
+pub(crate) const MENU_SIGNALS: [(&str, i32); 4] = [
+    ("SIGINT", libc::SIGINT),
+    ("SIGTERM", libc::SIGTERM),
+    ("SIGKILL", libc::SIGKILL),
+    ("SIGQUIT", libc::SIGQUIT),
+];
+
+/// Windows has no signals of its own; the POSIX numbers are kept (SIGINT and SIGTERM match the
+/// C runtime's) and `Terminal::send_signal` maps them to what the console can do
+#[cfg(not(unix))]
+pub(crate) const MENU_SIGNALS: [(&str, i32); 4] = [("SIGINT", 2), ("SIGTERM", 15), ("SIGKILL", 9), ("SIGQUIT", 3)];
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    #[test]
+    fn test_is_foreground_job() {
+        use super::is_foreground_job;
+
+        // Idle shell leads the foreground group
+        assert!(!is_foreground_job(Some(4120), Some(4120)));
+        // A job (e.g. vim started from the shell) took over the terminal
+        assert!(is_foreground_job(Some(4187), Some(4120)));
+        // Unknown group or shell counts as busy
+        assert!(is_foreground_job(None, Some(4120)));
+        assert!(is_foreground_job(Some(4120), None));
+        assert!(is_foreground_job(None, None));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_proc_stat_name() {
+        use super::parse_proc_stat_name;
+
+        let stat = "48213 (vim) S 48190 48213 48190 34817 48213 4194304 1546 0 0 0 9 3 0 0 20 0 1 0 8409733 27156480 2437";
+        assert_eq!(parse_proc_stat_name(stat), Some("vim".to_string()));
+
+        // Names with spaces and parentheses
+        let stat = "1201 (tmux: server (1)) S 1 1201 1201 0 -1 4194624 2109 0 0 0 41 27 0 0 20 0 1 0 3321 10240000 1024";
+        assert_eq!(parse_proc_stat_name(stat), Some("tmux: server (1)".to_string()));
+
+        assert_eq!(parse_proc_stat_name(""), None);
+        assert_eq!(parse_proc_stat_name("12 () S"), None);
+    }
+}
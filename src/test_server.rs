@@ -1,3 +1,17 @@
+//! TCP test server for driving the terminal from automated UI tests
+//!
+//! Enabled with the `test-server` feature and `--test-port <PORT>`. Each request is a single
+//! JSON object tagged with `type`; each response is one JSON line. The commands most tests need:
+//!
+//! - `{"type": "send_input", "data": "echo hi\r"}` writes `data` to the active pane verbatim
+//!   (`{"type": "key", "bytes": [27, 91, 65]}` does the same for raw bytes) and replies `{"type": "ok"}`
+//! - `{"type": "get_screen"}` replies with the visible rows of the active pane:
+//!   `{"type": "screen", "lines": [...], "cursor_x": 0, "cursor_y": 0, "width": 80, "height": 24}`
+//!   where lines have trailing blanks trimmed and the cursor is 0-based
+//! - `{"type": "get_buffer"}` replies with a full snapshot including cell attributes and scrollback
+//!
+//! Failures are reported as `{"type": "error", "message": "..."}`.
+
 use crate::pane_layout::SplitDirection;
 use crate::screen_buffer::ScreenBuffer;
 use crate::tab_gui::TabBarGui;
@@ -20,8 +34,12 @@ pub enum TestCommand {
     Text { text: String },
     #[serde(rename = "paste")]
     Paste { text: String },
+    #[serde(rename = "send_input")]
+    SendInput { data: String },
     #[serde(rename = "get_buffer")]
     GetBuffer,
+    #[serde(rename = "get_screen")]
+    GetScreen,
     #[serde(rename = "resize")]
     Resize { width: u32, height: u32 },
     #[serde(rename = "shutdown")]
@@ -144,6 +162,14 @@ pub enum TestResponse {
     Ok,
     #[serde(rename = "buffer")]
     Buffer { buffer: ScreenBufferSnapshot },
+    #[serde(rename = "screen")]
+    Screen {
+        lines: Vec<String>,
+        cursor_x: usize,
+        cursor_y: usize,
+        width: usize,
+        height: usize,
+    },
     #[serde(rename = "error")]
     Error { message: String },
     #[serde(rename = "tab_created")]
@@ -282,6 +308,9 @@ pub struct TestServer {
     _tab_bar_height: u32,
     window_width: Arc<Mutex<u32>>,
     window_height: Arc<Mutex<u32>>,
+    /// Keeps the primary selection set by simulated mouse selections
+    #[cfg(target_os = "linux")]
+    clipboard: crate::system::clipboard::SystemClipboard,
 }
 
 impl TestServer {
@@ -310,6 +339,8 @@ impl TestServer {
             _tab_bar_height: tab_bar_height,
             window_width: Arc::new(Mutex::new(window_width)),
             window_height: Arc::new(Mutex::new(window_height)),
+            #[cfg(target_os = "linux")]
+            clipboard: crate::system::clipboard::SystemClipboard::default(),
         })
     }
 
@@ -396,7 +427,7 @@ impl TestServer {
                 if let Ok(gui) = self.tab_bar_gui.lock() {
                     if let Some(terminal) = gui.get_active_terminal() {
                         if let Ok(mut t) = terminal.lock() {
-                            t.send_key(&bytes);
+                            t.write_bytes(&bytes);
                             thread::sleep(std::time::Duration::from_millis(50));
                             return TestResponse::Ok;
                         }
@@ -434,6 +465,20 @@ impl TestServer {
                     message: "Failed to access terminal".to_string(),
                 }
             }
+            TestCommand::SendInput { data } => {
+                if let Ok(gui) = self.tab_bar_gui.lock() {
+                    if let Some(terminal) = gui.get_active_terminal() {
+                        if let Ok(mut t) = terminal.lock() {
+                            t.write_bytes(data.as_bytes());
+                            thread::sleep(std::time::Duration::from_millis(50));
+                            return TestResponse::Ok;
+                        }
+                    }
+                }
+                TestResponse::Error {
+                    message: "Failed to access terminal".to_string(),
+                }
+            }
             TestCommand::GetBuffer => {
                 // Get buffer from active pane (not from tab list)
                 if let Ok(mut gui) = self.tab_bar_gui.lock() {
@@ -453,6 +498,29 @@ impl TestServer {
                     message: "Failed to access buffer".to_string(),
                 }
             }
+            TestCommand::GetScreen => {
+                if let Ok(mut gui) = self.tab_bar_gui.lock() {
+                    if let Some(pane_layout) = gui.get_active_pane_layout() {
+                        let active_pane_id = pane_layout.active_pane();
+                        if let Some(terminal) = pane_layout.root.find_terminal(active_pane_id) {
+                            if let Ok(t) = terminal.lock() {
+                                if let Ok(screen_buffer) = t.screen_buffer.lock() {
+                                    return TestResponse::Screen {
+                                        lines: screen_buffer.get_visible_text(),
+                                        cursor_x: screen_buffer.cursor_x,
+                                        cursor_y: screen_buffer.cursor_y,
+                                        width: screen_buffer.width(),
+                                        height: screen_buffer.height(),
+                                    };
+                                }
+                            }
+                        }
+                    }
+                }
+                TestResponse::Error {
+                    message: "Failed to access screen".to_string(),
+                }
+            }
             TestCommand::Resize { width, height } => {
                 let active_idx = *self.active_tab.lock().unwrap();
                 if let Ok(terminals) = self.terminals.lock() {
@@ -503,6 +571,7 @@ impl TestServer {
                     DEFAULT_SCROLLBACK_LINES,
                     start_dir,
                     crate::screen_buffer::CursorStyle::default(),
+                    crate::terminal::main::TerminalOptions::default(),
                 )));
 
                 // Determine tab name
@@ -535,6 +604,7 @@ impl TestServer {
                 if let Ok(mut gui) = self.tab_bar_gui.lock() {
                     if index < gui.tab_states.len() {
                         gui.tab_states[index].name = name;
+                        gui.tab_states[index].custom_name = true;
                         gui.tab_states[index].temp_name = gui.tab_states[index].name.clone();
                         return TestResponse::Ok;
                     }
@@ -702,8 +772,9 @@ impl TestServer {
                         let pane_area_height = window_height.saturating_sub(tab_bar_height);
                         let pane_rects = pane_layout.pane_layout.get_pane_rects(0, tab_bar_height as i32, window_width, pane_area_height);
 
-                        for (_pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
-                            let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), self.char_width, self.char_height);
+                        for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
+                            let (char_width, char_height) = pane_layout.pane_layout.pane_char_size(pane_id, self.char_width, self.char_height);
+                            let (cols, rows) = crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), char_width, char_height);
 
                             if let Ok(mut t) = terminal.lock() {
                                 if t.width != cols || t.height != rows {
@@ -790,65 +861,19 @@ impl TestServer {
                         let pane_rects = pane_layout.get_pane_rects(0, tab_bar_height as i32, window_width, pane_area_height);
 
                         // Find the active pane's dimensions
-                        let can_split = if let Some((_, rect, _, _, _)) = pane_rects.iter().find(|(id, _, _, _, _)| *id == pane_layout.active_pane) {
-                            let (current_cols, current_rows) =
-                                crate::ui::render::calculate_terminal_size(rect.width(), rect.height(), self.char_width, self.char_height);
-                            eprintln!(
-                                "[TEST_SERVER] Split: pane rect={}x{}, calculated={}x{} chars",
-                                rect.width(),
-                                rect.height(),
-                                current_cols,
-                                current_rows
-                            );
-
-                            // Calculate dimensions after split (accounting for 2-pixel divider)
-                            let divider_chars_h = (2.0 / self.char_width).ceil() as u32;
-                            let divider_chars_v = (2.0 / self.char_height).ceil() as u32;
-
-                            match split_dir {
-                                SplitDirection::Horizontal => {
-                                    // Each pane will be roughly half width
-                                    let split_width = (current_cols.saturating_sub(divider_chars_h)) / 2;
-                                    eprintln!(
-                                        "[TEST_SERVER] Horizontal split: current_cols={}, divider_chars_h={}, split_width={}",
-                                        current_cols, divider_chars_h, split_width
-                                    );
-                                    if split_width >= 10 && current_rows >= 5 {
-                                        true
-                                    } else {
-                                        eprintln!(
-                                            "[TEST_SERVER] Cannot split horizontally: resulting width {} would be less than 10 chars",
-                                            split_width
-                                        );
-                                        false
-                                    }
-                                }
-                                SplitDirection::Vertical => {
-                                    // Each pane will be roughly half height
-                                    let split_height = (current_rows.saturating_sub(divider_chars_v)) / 2;
-                                    eprintln!(
-                                        "[TEST_SERVER] Vertical split: current_rows={}, divider_chars_v={}, split_height={}",
-                                        current_rows, divider_chars_v, split_height
-                                    );
-                                    if split_height >= 5 && current_cols >= 10 {
-                                        true
-                                    } else {
-                                        eprintln!(
-                                            "[TEST_SERVER] Cannot split vertically: resulting height {} would be less than 5 chars",
-                                            split_height
-                                        );
-                                        false
-                                    }
-                                }
+                        let can_split = match pane_rects.iter().find(|(id, _, _, _, _)| *id == pane_layout.active_pane) {
+                            Some((_, rect, _, _, _)) => {
+                                eprintln!("[TEST_SERVER] Split: pane rect={}x{}", rect.width(), rect.height());
+                                let (char_width, char_height) = pane_layout.pane_char_size(pane_layout.active_pane, self.char_width, self.char_height);
+                                crate::pane_layout::PaneLayout::can_split(split_dir, char_width, char_height, *rect, pane_layout.divider_width)
+                                    .map_err(|reason| reason.to_string())
                             }
-                        } else {
-                            false
+                            None => Err("Active pane not found".to_string()),
                         };
 
-                        if !can_split {
-                            return TestResponse::Error {
-                                message: "Pane too small to split (minimum: 10 chars wide, 5 chars tall)".to_string(),
-                            };
+                        if let Err(message) = can_split {
+                            eprintln!("[TEST_SERVER] Cannot split: {}", message);
+                            return TestResponse::Error { message };
                         }
 
                         let new_terminal = Arc::new(Mutex::new(Terminal::new_with_scrollback(
@@ -858,6 +883,7 @@ impl TestServer {
                             DEFAULT_SCROLLBACK_LINES,
                             start_dir,
                             crate::screen_buffer::CursorStyle::default(),
+                            crate::terminal::main::TerminalOptions::default(),
                         )));
 
                         pane_layout.split_active_pane(split_dir, new_terminal.clone());
@@ -876,8 +902,9 @@ impl TestServer {
                         eprintln!("[TEST_SERVER] Resizing {} terminals after split", pane_rects.len());
 
                         for (pane_id, rect, terminal, _is_active, _is_selected) in pane_rects {
-                            let cols = (rect.width() as f32 / self.char_width).floor() as u32;
-                            let rows = (rect.height() as f32 / self.char_height).floor() as u32;
+                            let (char_width, char_height) = pane_layout.pane_char_size(pane_id, self.char_width, self.char_height);
+                            let cols = (rect.width() as f32 / char_width).floor() as u32;
+                            let rows = (rect.height() as f32 / char_height).floor() as u32;
 
                             if let Ok(mut t) = terminal.lock() {
                                 if t.width != cols || t.height != rows {
@@ -980,7 +1007,7 @@ impl TestServer {
                                     // Mouse up - check if this is a single click (no drag)
                                     let selection = *t.selection.lock().unwrap();
                                     if let Some(sel) = selection {
-                                        if sel.start_col == cell_col && sel.start_row == cell_row && sel.end_col == cell_col && sel.end_row == cell_row {
+                                        if sel == crate::terminal::main::Selection::new(cell_col, t.absolute_row(cell_row)) {
                                             // Single point selection (no drag) - clear it
                                             t.clear_selection();
                                         } else {
@@ -992,28 +1019,11 @@ impl TestServer {
                                             {
                                                 if let Some(text) = t.get_selected_text() {
                                                     if !text.is_empty() {
-                                                        use arboard::{Clipboard, LinuxClipboardKind, SetExtLinux};
+                                                        use crate::system::clipboard::ClipboardProvider;
                                                         drop(t); // Drop terminal lock
 
-                                                        // Store clipboard in PaneLayout to keep PRIMARY selection alive
-                                                        if let Ok(mut gui) = self.tab_bar_gui.lock() {
-                                                            if let Some(pane_layout) = gui.get_active_pane_layout() {
-                                                                match Clipboard::new() {
-                                                                    Ok(mut clipboard) => {
-                                                                        let text_copy = text.clone();
-                                                                        if let Err(e) = clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text_copy) {
-                                                                            eprintln!("[TEST_SERVER] Failed to copy to PRIMARY: {}", e);
-                                                                        } else {
-                                                                            pane_layout.primary_clipboard = Some(clipboard);
-                                                                            eprintln!("[TEST_SERVER] Copied to PRIMARY clipboard: {} chars", text.len());
-                                                                        }
-                                                                    }
-                                                                    Err(e) => {
-                                                                        eprintln!("[TEST_SERVER] Failed to create clipboard: {}", e);
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
+                                                        self.clipboard.clone().set_primary(&text);
+                                                        eprintln!("[TEST_SERVER] Copied to PRIMARY clipboard: {} chars", text.len());
 
                                                         thread::sleep(std::time::Duration::from_millis(50));
                                                         return TestResponse::Ok;
@@ -1176,3 +1186,65 @@ impl TestServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    fn send(stream: &mut TcpStream, reader: &mut BufReader<TcpStream>, json: &str) -> serde_json::Value {
+        stream.write_all(json.as_bytes()).unwrap();
+        stream.flush().unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn test_send_input_then_get_screen() {
+        let shell_config = TerminalLibrary::new().get_default_shell().clone();
+        let terminal = Arc::new(Mutex::new(Terminal::new_with_scrollback(
+            80,
+            24,
+            shell_config,
+            DEFAULT_SCROLLBACK_LINES,
+            None,
+            crate::screen_buffer::CursorStyle::default(),
+            crate::terminal::main::TerminalOptions::default(),
+        )));
+        let mut gui = TabBarGui::new();
+        gui.add_tab(Arc::clone(&terminal), "Test".to_string());
+
+        let server = TestServer::new(0, vec![terminal], Arc::new(Mutex::new(gui)), 10.0, 20.0, 40, 800, 480).unwrap();
+        let addr = server.listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            // The typed command line contains "ab%s", so only the command's output matches "abcd"
+            let response = send(&mut stream, &mut reader, r#"{"type": "send_input", "data": "printf 'ab%s\\n' cd\r"}"#);
+            assert_eq!(response["type"], "ok");
+
+            for _ in 0..100 {
+                let screen = send(&mut stream, &mut reader, r#"{"type": "get_screen"}"#);
+                assert_eq!(screen["type"], "screen");
+                assert_eq!(screen["width"], 80);
+                assert_eq!(screen["height"], 24);
+                let lines = screen["lines"].as_array().unwrap();
+                assert_eq!(lines.len(), 24);
+                if lines.iter().any(|line| line == "abcd") {
+                    return true;
+                }
+                thread::sleep(std::time::Duration::from_millis(50));
+            }
+            false
+        });
+
+        while !client.is_finished() {
+            server.handle_connections().unwrap();
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(client.join().unwrap(), "command output never appeared on screen");
+    }
+}
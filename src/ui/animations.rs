@@ -1,7 +1,7 @@
 use sdl3::rect::Rect;
 use std::time::{Duration, Instant};
 
-/// Animation that plays when text is copied (Ctrl+Shift+C)
+/// Animation that plays when text is copied
 /// Shows the selection area expanding and fading out
 #[derive(Clone)]
 pub struct CopyAnimation {
@@ -59,3 +59,71 @@ impl CopyAnimation {
         opacity.max(10.0) as u8 // Minimum 10 for "nearly transparent"
     }
 }
+
+/// Brief message drawn over the active pane that dismisses itself
+#[derive(Clone)]
+pub struct Toast {
+    pub message: String,
+    pub start_time: Instant,
+    pub duration: Duration,
+}
+
+impl Toast {
+    /// Create a toast shown for one second
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            start_time: Instant::now(),
+            duration: Duration::from_secs(1),
+        }
+    }
+
+    /// Check if the toast should be dismissed
+    pub fn is_expired(&self) -> bool {
+        self.start_time.elapsed() >= self.duration
+    }
+}
+
+/// Grid size shown while the window or a pane divider is being resized
+#[derive(Clone)]
+pub struct ResizeOverlay {
+    /// When the size last changed
+    pub last_change: Instant,
+    /// How long the overlay stays after the last change, fading out over its final part
+    pub duration: Duration,
+    pub fade_duration: Duration,
+}
+
+impl ResizeOverlay {
+    /// Create an overlay that lingers for one second after the last change
+    pub fn new() -> Self {
+        Self {
+            last_change: Instant::now(),
+            duration: Duration::from_secs(1),
+            fade_duration: Duration::from_millis(300),
+        }
+    }
+
+    /// Keep the overlay up while the resize is still in progress
+    pub fn touch(&mut self) {
+        self.last_change = Instant::now();
+    }
+
+    /// Check if the overlay has faded out
+    pub fn is_expired(&self) -> bool {
+        self.last_change.elapsed() >= self.duration
+    }
+
+    /// Get the current opacity (255 until the fade starts, then down to 0)
+    pub fn current_opacity(&self) -> u8 {
+        let remaining = self.duration.saturating_sub(self.last_change.elapsed());
+        let fraction = (remaining.as_secs_f32() / self.fade_duration.as_secs_f32()).min(1.0);
+        (255.0 * fraction) as u8
+    }
+}
+
+impl Default for ResizeOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
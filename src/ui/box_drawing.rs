@@ -0,0 +1,419 @@
+//! Pixel-exact rendering of box-drawing and block-element characters
+//!
+//! Font glyphs for U+2500–U+257F (Box Drawing) and U+2580–U+259F (Block Elements)
+//! rarely match the terminal cell exactly, which leaves visible gaps between adjacent
+//! characters in TUI borders (mc, htop, etc.). Instead of blending the glyph, these
+//! characters are decomposed into simple primitives computed from the cell size so
+//! that neighbouring cells connect seamlessly.
+
+use sdl3::pixels::Color;
+use sdl3::rect::Rect;
+use sdl3::render::{BlendMode, Canvas};
+use sdl3::video::Window;
+
+/// Line weight of a single arm of a box-drawing character
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+/// A drawing primitive produced for a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    /// Solid rectangle in the foreground color
+    Fill(Rect),
+    /// Rectangle in the foreground color with the given alpha (shade characters)
+    Shade(Rect, u8),
+    /// One pixel line between two points (diagonals)
+    Line((i32, i32), (i32, i32)),
+}
+
+/// Arms of a box-drawing character as (up, right, down, left)
+fn box_arms(ch: char) -> Option<(Weight, Weight, Weight, Weight)> {
+    use Weight::{Double as D, Heavy as H, Light as L, None as N};
+
+    let arms = match ch as u32 {
+        0x2500 => (N, L, N, L),
+        0x2501 => (N, H, N, H),
+        0x2502 => (L, N, L, N),
+        0x2503 => (H, N, H, N),
+        0x250C => (N, L, L, N),
+        0x250D => (N, H, L, N),
+        0x250E => (N, L, H, N),
+        0x250F => (N, H, H, N),
+        0x2510 => (N, N, L, L),
+        0x2511 => (N, N, L, H),
+        0x2512 => (N, N, H, L),
+        0x2513 => (N, N, H, H),
+        0x2514 => (L, L, N, N),
+        0x2515 => (L, H, N, N),
+        0x2516 => (H, L, N, N),
+        0x2517 => (H, H, N, N),
+        0x2518 => (L, N, N, L),
+        0x2519 => (L, N, N, H),
+        0x251A => (H, N, N, L),
+        0x251B => (H, N, N, H),
+        0x251C => (L, L, L, N),
+        0x251D => (L, H, L, N),
+        0x251E => (H, L, L, N),
+        0x251F => (L, L, H, N),
+        0x2520 => (H, L, H, N),
+        0x2521 => (H, H, L, N),
+        0x2522 => (L, H, H, N),
+        0x2523 => (H, H, H, N),
+        0x2524 => (L, N, L, L),
+        0x2525 => (L, N, L, H),
+        0x2526 => (H, N, L, L),
+        0x2527 => (L, N, H, L),
+        0x2528 => (H, N, H, L),
+        0x2529 => (H, N, L, H),
+        0x252A => (L, N, H, H),
+        0x252B => (H, N, H, H),
+        0x252C => (N, L, L, L),
+        0x252D => (N, L, L, H),
+        0x252E => (N, H, L, L),
+        0x252F => (N, H, L, H),
+        0x2530 => (N, L, H, L),
+        0x2531 => (N, L, H, H),
+        0x2532 => (N, H, H, L),
+        0x2533 => (N, H, H, H),
+        0x2534 => (L, L, N, L),
+        0x2535 => (L, L, N, H),
+        0x2536 => (L, H, N, L),
+        0x2537 => (L, H, N, H),
+        0x2538 => (H, L, N, L),
+        0x2539 => (H, L, N, H),
+        0x253A => (H, H, N, L),
+        0x253B => (H, H, N, H),
+        0x253C => (L, L, L, L),
+        0x253D => (L, L, L, H),
+        0x253E => (L, H, L, L),
+        0x253F => (L, H, L, H),
+        0x2540 => (H, L, L, L),
+        0x2541 => (L, L, H, L),
+        0x2542 => (H, L, H, L),
+        0x2543 => (H, L, L, H),
+        0x2544 => (H, H, L, L),
+        0x2545 => (L, L, H, H),
+        0x2546 => (L, H, H, L),
+        0x2547 => (H, H, L, H),
+        0x2548 => (L, H, H, H),
+        0x2549 => (H, L, H, H),
+        0x254A => (H, H, H, L),
+        0x254B => (H, H, H, H),
+        0x2550 => (N, D, N, D),
+        0x2551 => (D, N, D, N),
+        0x2552 => (N, D, L, N),
+        0x2553 => (N, L, D, N),
+        0x2554 => (N, D, D, N),
+        0x2555 => (N, N, L, D),
+        0x2556 => (N, N, D, L),
+        0x2557 => (N, N, D, D),
+        0x2558 => (L, D, N, N),
+        0x2559 => (D, L, N, N),
+        0x255A => (D, D, N, N),
+        0x255B => (L, N, N, D),
+        0x255C => (D, N, N, L),
+        0x255D => (D, N, N, D),
+        0x255E => (L, D, L, N),
+        0x255F => (D, L, D, N),
+        0x2560 => (D, D, D, N),
+        0x2561 => (L, N, L, D),
+        0x2562 => (D, N, D, L),
+        0x2563 => (D, N, D, D),
+        0x2564 => (N, D, L, D),
+        0x2565 => (N, L, D, L),
+        0x2566 => (N, D, D, D),
+        0x2567 => (L, D, N, D),
+        0x2568 => (D, L, N, L),
+        0x2569 => (D, D, N, D),
+        0x256A => (L, D, L, D),
+        0x256B => (D, L, D, L),
+        0x256C => (D, D, D, D),
+        // Rounded corners are drawn as square light corners so they still connect
+        0x256D => (N, L, L, N),
+        0x256E => (N, N, L, L),
+        0x256F => (L, N, N, L),
+        0x2570 => (L, L, N, N),
+        0x2574 => (N, N, N, L),
+        0x2575 => (L, N, N, N),
+        0x2576 => (N, L, N, N),
+        0x2577 => (N, N, L, N),
+        0x2578 => (N, N, N, H),
+        0x2579 => (H, N, N, N),
+        0x257A => (N, H, N, N),
+        0x257B => (N, N, H, N),
+        0x257C => (N, H, N, L),
+        0x257D => (L, N, H, N),
+        0x257E => (N, L, N, H),
+        0x257F => (H, N, L, N),
+        _ => return None,
+    };
+    Some(arms)
+}
+
+/// Dashed lines as (horizontal, heavy, number of dashes)
+fn dashed_line(ch: char) -> Option<(bool, bool, i32)> {
+    match ch as u32 {
+        0x2504 => Some((true, false, 3)),
+        0x2505 => Some((true, true, 3)),
+        0x2506 => Some((false, false, 3)),
+        0x2507 => Some((false, true, 3)),
+        0x2508 => Some((true, false, 4)),
+        0x2509 => Some((true, true, 4)),
+        0x250A => Some((false, false, 4)),
+        0x250B => Some((false, true, 4)),
+        0x254C => Some((true, false, 2)),
+        0x254D => Some((true, true, 2)),
+        0x254E => Some((false, false, 2)),
+        0x254F => Some((false, true, 2)),
+        _ => None,
+    }
+}
+
+/// Thickness of a light stroke for the given cell size
+fn light_thickness(cell_width: u32, cell_height: u32) -> i32 {
+    ((cell_width.min(cell_height) as f32 / 8.0).round() as i32).max(1)
+}
+
+/// Build a rect from edge coordinates, returning None when it is empty
+fn rect_from_edges(left: i32, top: i32, right: i32, bottom: i32) -> Option<Rect> {
+    if right > left && bottom > top {
+        Some(Rect::new(left, top, (right - left) as u32, (bottom - top) as u32))
+    } else {
+        None
+    }
+}
+
+/// Compute the primitives needed to draw `ch` into the cell at (x, y) of size cell_width × cell_height.
+/// Returns None if the codepoint is not covered, in which case the font glyph should be used.
+pub fn cell_primitives(ch: char, x: i32, y: i32, cell_width: u32, cell_height: u32) -> Option<Vec<Primitive>> {
+    if cell_width == 0 || cell_height == 0 {
+        return None;
+    }
+
+    let w = cell_width as i32;
+    let h = cell_height as i32;
+    let mut primitives = Vec::new();
+
+    if (0x2580..=0x259F).contains(&(ch as u32)) {
+        // Fractions of the cell measured in eighths
+        let lower = |eighths: i32| rect_from_edges(x, y + h - h * eighths / 8, x + w, y + h);
+        let left = |eighths: i32| rect_from_edges(x, y, x + w * eighths / 8, y + h);
+        let half_w = w / 2;
+        let half_h = h / 2;
+        let upper_left = rect_from_edges(x, y, x + half_w, y + half_h);
+        let upper_right = rect_from_edges(x + half_w, y, x + w, y + half_h);
+        let lower_left = rect_from_edges(x, y + half_h, x + half_w, y + h);
+        let lower_right = rect_from_edges(x + half_w, y + half_h, x + w, y + h);
+        let full = rect_from_edges(x, y, x + w, y + h);
+
+        let rects: Vec<Option<Rect>> = match ch as u32 {
+            0x2580 => vec![rect_from_edges(x, y, x + w, y + half_h)],
+            0x2581..=0x2588 => vec![lower(ch as i32 - 0x2580)],
+            0x2589..=0x258F => vec![left(0x2590 - ch as i32)],
+            0x2590 => vec![rect_from_edges(x + half_w, y, x + w, y + h)],
+            0x2591 => return full.map(|r| vec![Primitive::Shade(r, 64)]),
+            0x2592 => return full.map(|r| vec![Primitive::Shade(r, 128)]),
+            0x2593 => return full.map(|r| vec![Primitive::Shade(r, 192)]),
+            0x2594 => vec![rect_from_edges(x, y, x + w, y + (h / 8).max(1))],
+            0x2595 => vec![rect_from_edges(x + w - (w / 8).max(1), y, x + w, y + h)],
+            0x2596 => vec![lower_left],
+            0x2597 => vec![lower_right],
+            0x2598 => vec![upper_left],
+            0x2599 => vec![upper_left, lower_left, lower_right],
+            0x259A => vec![upper_left, lower_right],
+            0x259B => vec![upper_left, upper_right, lower_left],
+            0x259C => vec![upper_left, upper_right, lower_right],
+            0x259D => vec![upper_right],
+            0x259E => vec![upper_right, lower_left],
+            0x259F => vec![upper_right, lower_left, lower_right],
+            _ => return None,
+        };
+        primitives.extend(rects.into_iter().flatten().map(Primitive::Fill));
+        return Some(primitives);
+    }
+
+    let light = light_thickness(cell_width, cell_height);
+    let heavy = light * 2;
+    // Center of the cell
+    let cx = x + w / 2;
+    let cy = y + h / 2;
+
+    if let Some((horizontal, is_heavy, dashes)) = dashed_line(ch) {
+        let t = if is_heavy { heavy } else { light };
+        let length = if horizontal { w } else { h };
+        let segment = length / dashes;
+        let gap = (segment / 4).max(1);
+        for i in 0..dashes {
+            let start = i * segment + gap / 2;
+            let end = (i + 1) * segment - (gap - gap / 2);
+            let rect = if horizontal {
+                rect_from_edges(x + start, cy - t / 2, x + end, cy - t / 2 + t)
+            } else {
+                rect_from_edges(cx - t / 2, y + start, cx - t / 2 + t, y + end)
+            };
+            primitives.extend(rect.map(Primitive::Fill));
+        }
+        return Some(primitives);
+    }
+
+    match ch as u32 {
+        0x2571 => return Some(vec![Primitive::Line((x + w - 1, y), (x, y + h - 1))]),
+        0x2572 => return Some(vec![Primitive::Line((x, y), (x + w - 1, y + h - 1))]),
+        0x2573 => {
+            return Some(vec![
+                Primitive::Line((x + w - 1, y), (x, y + h - 1)),
+                Primitive::Line((x, y), (x + w - 1, y + h - 1)),
+            ])
+        }
+        _ => {}
+    }
+
+    let (up, right, down, left) = box_arms(ch)?;
+
+    // Offsets (start, end) of a stroke relative to the cell center
+    let stroke_span = |weight: Weight| match weight {
+        Weight::None => None,
+        Weight::Light => Some((-(light / 2), light - light / 2)),
+        Weight::Heavy => Some((-(heavy / 2), heavy - heavy / 2)),
+        Weight::Double => Some((-(light / 2) - light, 2 * light - light / 2)),
+    };
+    // Combined span of the strokes crossing the center, so arms overlap there without gaps
+    let combined_span = |a: Weight, b: Weight| match (stroke_span(a), stroke_span(b)) {
+        (Some((s1, e1)), Some((s2, e2))) => (s1.min(s2), e1.max(e2)),
+        (Some(span), None) | (None, Some(span)) => span,
+        (None, None) => (0, 0),
+    };
+    let (vertical_start, vertical_end) = combined_span(up, down);
+    let (horizontal_start, horizontal_end) = combined_span(left, right);
+
+    // Strokes for a vertical arm spanning from y0 to y1
+    let mut vertical_arm = |weight: Weight, y0: i32, y1: i32| {
+        let rects = match weight {
+            Weight::None => vec![],
+            Weight::Light => vec![rect_from_edges(cx - light / 2, y0, cx - light / 2 + light, y1)],
+            Weight::Heavy => vec![rect_from_edges(cx - heavy / 2, y0, cx - heavy / 2 + heavy, y1)],
+            Weight::Double => vec![
+                rect_from_edges(cx - light / 2 - light, y0, cx - light / 2, y1),
+                rect_from_edges(cx - light / 2 + light, y0, cx - light / 2 + 2 * light, y1),
+            ],
+        };
+        primitives.extend(rects.into_iter().flatten().map(Primitive::Fill));
+    };
+    vertical_arm(up, y, cy + horizontal_end);
+    vertical_arm(down, cy + horizontal_start, y + h);
+
+    let mut horizontal_arm = |weight: Weight, x0: i32, x1: i32| {
+        let rects = match weight {
+            Weight::None => vec![],
+            Weight::Light => vec![rect_from_edges(x0, cy - light / 2, x1, cy - light / 2 + light)],
+            Weight::Heavy => vec![rect_from_edges(x0, cy - heavy / 2, x1, cy - heavy / 2 + heavy)],
+            Weight::Double => vec![
+                rect_from_edges(x0, cy - light / 2 - light, x1, cy - light / 2),
+                rect_from_edges(x0, cy - light / 2 + light, x1, cy - light / 2 + 2 * light),
+            ],
+        };
+        primitives.extend(rects.into_iter().flatten().map(Primitive::Fill));
+    };
+    horizontal_arm(left, x, cx + vertical_end);
+    horizontal_arm(right, cx + vertical_start, x + w);
+
+    Some(primitives)
+}
+
+/// Draw a box-drawing or block-element character filling the cell exactly.
+/// Returns Ok(false) if the character is not covered and should be rendered from the font instead.
+pub fn draw_box_char(canvas: &mut Canvas<Window>, ch: char, x: i32, y: i32, cell_width: u32, cell_height: u32, color: Color) -> Result<bool, String> {
+    let Some(primitives) = cell_primitives(ch, x, y, cell_width, cell_height) else {
+        return Ok(false);
+    };
+
+    for primitive in primitives {
+        match primitive {
+            Primitive::Fill(rect) => {
+                canvas.set_draw_color(color);
+                canvas.fill_rect(rect).map_err(|e| e.to_string())?;
+            }
+            Primitive::Shade(rect, alpha) => {
+                canvas.set_blend_mode(BlendMode::Blend);
+                canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, alpha));
+                canvas.fill_rect(rect).map_err(|e| e.to_string())?;
+            }
+            Primitive::Line(start, end) => {
+                canvas.set_draw_color(color);
+                canvas.draw_line(start, end).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fills(primitives: &[Primitive]) -> Vec<Rect> {
+        primitives
+            .iter()
+            .filter_map(|p| match p {
+                Primitive::Fill(r) => Some(*r),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_full_block_fills_cell() {
+        let primitives = cell_primitives('█', 10, 20, 9, 18).unwrap();
+        assert_eq!(fills(&primitives), vec![Rect::new(10, 20, 9, 18)]);
+    }
+
+    #[test]
+    fn test_half_blocks() {
+        let upper = cell_primitives('▀', 0, 0, 10, 20).unwrap();
+        assert_eq!(fills(&upper), vec![Rect::new(0, 0, 10, 10)]);
+
+        let lower = cell_primitives('▄', 0, 0, 10, 20).unwrap();
+        assert_eq!(fills(&lower), vec![Rect::new(0, 10, 10, 10)]);
+
+        let left = cell_primitives('▌', 0, 0, 10, 20).unwrap();
+        assert_eq!(fills(&left), vec![Rect::new(0, 0, 5, 20)]);
+    }
+
+    #[test]
+    fn test_horizontal_line_spans_full_width() {
+        let primitives = cell_primitives('─', 5, 5, 9, 18).unwrap();
+        let rects = fills(&primitives);
+        let min_x = rects.iter().map(|r| r.x()).min().unwrap();
+        let max_x = rects.iter().map(|r| r.x() + r.width() as i32).max().unwrap();
+        assert_eq!(min_x, 5);
+        assert_eq!(max_x, 14);
+    }
+
+    #[test]
+    fn test_vertical_line_spans_full_height() {
+        let primitives = cell_primitives('│', 0, 0, 9, 18).unwrap();
+        let rects = fills(&primitives);
+        let min_y = rects.iter().map(|r| r.y()).min().unwrap();
+        let max_y = rects.iter().map(|r| r.y() + r.height() as i32).max().unwrap();
+        assert_eq!(min_y, 0);
+        assert_eq!(max_y, 18);
+    }
+
+    #[test]
+    fn test_shade_uses_alpha() {
+        let primitives = cell_primitives('▒', 0, 0, 8, 16).unwrap();
+        assert_eq!(primitives, vec![Primitive::Shade(Rect::new(0, 0, 8, 16), 128)]);
+    }
+
+    #[test]
+    fn test_uncovered_codepoints_fall_back() {
+        assert!(cell_primitives('A', 0, 0, 8, 16).is_none());
+        assert!(cell_primitives('─', 0, 0, 0, 16).is_none());
+    }
+}
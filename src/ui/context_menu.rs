@@ -33,7 +33,7 @@
 //! ## Rendering the Menu
 //!
 //! ```ignore
-//! menu.render(canvas, texture_creator, font)?;
+//! menu.render(canvas, texture_creator, font, emoji_font)?;
 //! ```
 //!
 //! ## Handling Clicks
@@ -196,8 +196,8 @@ impl<A: Clone> ContextMenu<A> {
         None
     }
 
-    /// Render the context menu
-    pub fn render<T>(&self, canvas: &mut Canvas<Window>, texture_creator: &TextureCreator<T>, font: &Font) -> Result<(), String> {
+    /// Render the context menu, with emoji in captions taken from `emoji_font`
+    pub fn render<T>(&self, canvas: &mut Canvas<Window>, texture_creator: &TextureCreator<T>, font: &Font, emoji_font: &Font) -> Result<(), String> {
         let menu_rect = self.get_rect();
 
         // Draw background
@@ -242,7 +242,7 @@ impl<A: Clone> ContextMenu<A> {
                 Color::RGB(100, 100, 100) // Grayed out
             };
 
-            if let Ok(surface) = font.render(&item.caption).blended(text_color) {
+            if let Some(surface) = crate::sdl_renderer::render_mixed_text(font, emoji_font, &item.caption, text_color) {
                 if let Ok(texture) = texture_creator.create_texture_from_surface::<&Surface>(&surface) {
                     let text_y = item_y + ((self.item_height as i32 - surface.height() as i32).max(0) / 2);
                     let text_rect = Rect::new(self.position.0 + 52, text_y, surface.width(), surface.height());
@@ -5,7 +5,7 @@
 
 use crate::ai::agent::generate_command;
 use crate::history;
-use crate::settings::Settings;
+use crate::settings::{GeneralSettings, Settings};
 use crate::terminal::Terminal;
 use crate::ui::filtered_list::{FilteredList, ListRow};
 use crate::ui::text_input::TextInput;
@@ -63,7 +63,7 @@ fn wrap_text(text: &str, font: &Font, max_width: u32) -> Vec<String> {
     lines
 }
 
-/// Shows a custom confirmation dialog with Yes/No buttons
+/// Shows a custom confirmation dialog with Yes/No buttons. The message may span several lines.
 /// Returns true if user clicked Yes, false if No or closed the dialog
 pub fn show_confirmation_dialog(canvas: &mut Canvas<Window>, event_pump: &mut EventPump, font: &Font, scale_factor: f32, title: &str, message: &str) -> bool {
     let texture_creator = &canvas.texture_creator();
@@ -77,12 +77,16 @@ pub fn show_confirmation_dialog(canvas: &mut Canvas<Window>, event_pump: &mut Ev
 
     // Calculate text dimensions first to determine required dialog size
     let title_surface = font.render(title).blended(TEXT_COLOR).ok();
-    let message_surface = font.render(message).blended(TEXT_COLOR).ok();
+    // Empty lines still take up a line of height
+    let message_surfaces: Vec<_> = message
+        .lines()
+        .filter_map(|line| font.render(if line.is_empty() { " " } else { line }).blended(TEXT_COLOR).ok())
+        .collect();
 
     let title_width = title_surface.as_ref().map(|s| s.width()).unwrap_or(0);
     let title_height = title_surface.as_ref().map(|s| s.height()).unwrap_or(0);
-    let message_width = message_surface.as_ref().map(|s| s.width()).unwrap_or(0);
-    let message_height = message_surface.as_ref().map(|s| s.height()).unwrap_or(0);
+    let message_width = message_surfaces.iter().map(|s| s.width()).max().unwrap_or(0);
+    let message_height: u32 = message_surfaces.iter().map(|s| s.height()).sum();
 
     // Dialog dimensions - dynamic based on text content with minimum sizes
     let button_width = (100.0 * scale_factor) as u32;
@@ -213,14 +217,15 @@ pub fn show_confirmation_dialog(canvas: &mut Canvas<Window>, event_pump: &mut Ev
             }
         }
 
-        // Draw message
-        if let Some(ref msg_surf) = message_surface {
+        // Draw message, one line below the other
+        let mut msg_y = dialog_y + padding + title_height as i32 + text_spacing;
+        for msg_surf in &message_surfaces {
             if let Ok(msg_texture) = texture_creator.create_texture_from_surface(msg_surf) {
-                let msg_x = dialog_x + (dialog_width as i32 - message_width as i32) / 2;
-                let msg_y = dialog_y + padding + title_height as i32 + text_spacing;
-                let msg_rect = Rect::new(msg_x, msg_y, message_width, message_height);
+                let msg_x = dialog_x + (dialog_width as i32 - msg_surf.width() as i32) / 2;
+                let msg_rect = Rect::new(msg_x, msg_y, msg_surf.width(), msg_surf.height());
                 let _ = canvas.copy(&msg_texture, None, msg_rect);
             }
+            msg_y += msg_surf.height() as i32;
         }
 
         // Draw No button
@@ -277,6 +282,96 @@ pub fn confirm_quit(canvas: &mut Canvas<Window>, event_pump: &mut EventPump, fon
     )
 }
 
+/// Shows a confirmation dialog naming the jobs that closing a tab or pane would kill
+pub fn confirm_close_with_jobs(canvas: &mut Canvas<Window>, event_pump: &mut EventPump, font: &Font, scale_factor: f32, jobs: &[String]) -> bool {
+    let verb = if jobs.len() == 1 { "is" } else { "are" };
+    show_confirmation_dialog(
+        canvas,
+        event_pump,
+        font,
+        scale_factor,
+        "Close with running jobs?",
+        &format!("{} {} still running and will be killed.\nClose anyway?", jobs.join(", "), verb),
+    )
+}
+
+/// Decide whether to close a tab or pane, asking the user if one of its terminals runs a job
+/// and `confirmCloseWithJobs` is set
+pub fn should_close(
+    canvas: &mut Canvas<Window>,
+    event_pump: &mut EventPump,
+    font: &Font,
+    scale_factor: f32,
+    general: &GeneralSettings,
+    terminals: &[Arc<Mutex<Terminal>>],
+) -> bool {
+    if !general.confirm_close_with_jobs {
+        return true;
+    }
+    let jobs = crate::tab_gui::running_jobs(terminals);
+    if jobs.is_empty() {
+        return true;
+    }
+    eprintln!("[DIALOG] Closing would kill running jobs: {:?}", jobs);
+    confirm_close_with_jobs(canvas, event_pump, font, scale_factor, &jobs)
+}
+
+/// Number of pasted lines shown in the multi-line paste confirmation
+const PASTE_PREVIEW_LINES: usize = 5;
+/// Pasted lines longer than this are cut off in the preview
+const PASTE_PREVIEW_LINE_CHARS: usize = 60;
+
+/// Preview of pasted text for the confirmation dialog: the first few lines, each cut to a
+/// readable length, followed by a count of the lines left out
+fn paste_preview(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut preview: Vec<String> = lines
+        .iter()
+        .take(PASTE_PREVIEW_LINES)
+        .map(|line| {
+            if line.chars().count() > PASTE_PREVIEW_LINE_CHARS {
+                format!("{}...", line.chars().take(PASTE_PREVIEW_LINE_CHARS).collect::<String>())
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if lines.len() > PASTE_PREVIEW_LINES {
+        preview.push(format!("(+{} more lines)", lines.len() - PASTE_PREVIEW_LINES));
+    }
+    preview.join("\n")
+}
+
+/// Shows a confirmation dialog before pasting text that contains newlines or is very large
+pub fn confirm_paste(canvas: &mut Canvas<Window>, event_pump: &mut EventPump, font: &Font, scale_factor: f32, text: &str) -> bool {
+    let title = if crate::input::keyboard::is_large_paste(text) {
+        format!("Paste {:.1} MiB of text?", text.len() as f64 / (1024.0 * 1024.0))
+    } else {
+        let lines = text.lines().count();
+        format!("Paste {} line{}?", lines, if lines == 1 { "" } else { "s" })
+    };
+    show_confirmation_dialog(canvas, event_pump, font, scale_factor, &title, &paste_preview(text))
+}
+
+/// Decide whether to quit, asking the user only if the settings call for it.
+/// Terminals are checked for running programs only when `confirmQuitOnlyIfProcessesRunning` is set.
+pub fn should_quit(
+    canvas: &mut Canvas<Window>,
+    event_pump: &mut EventPump,
+    font: &Font,
+    scale_factor: f32,
+    general: &GeneralSettings,
+    terminals: &[Arc<Mutex<Terminal>>],
+) -> bool {
+    let processes_running = || terminals.iter().any(|terminal| terminal.lock().map(|t| t.has_foreground_job()).unwrap_or(true));
+
+    if !general.needs_quit_confirmation(processes_running) {
+        eprintln!("[DIALOG] Quitting without confirmation");
+        return true;
+    }
+    confirm_quit(canvas, event_pump, font, scale_factor)
+}
+
 /// Show terminal history search dialog at screen center
 /// Returns Ok(()) if user selected an item, Err if cancelled
 pub fn terminal_history_search_dialog(
@@ -299,15 +394,19 @@ pub fn terminal_history_search_dialog(
     eprintln!("[DIALOG] Starting terminal history search dialog");
     eprintln!("[DIALOG] Terminal history items: {}", terminal_history.len());
 
-    // 1. Read shell history and combine with terminal history
-    let shell_history = history::read_shell_history(1000);
+    // 1. Read the history of the terminal's shell and combine with terminal history
+    let shell_history = match terminal.as_ref().and_then(|t| t.lock().ok()) {
+        Some(t) => t.get_command_history(1000),
+        None => history::read_shell_history(&history::login_shell(), 1000),
+    };
     eprintln!("[DIALOG] Shell history items: {}", shell_history.len());
 
     // 2. Combine and deduplicate (keep newest first)
+    // Both histories are read newest first, combine_and_deduplicate expects oldest first
     // Use a large limit so we have the full history for filtering
     let combined_history = history::combine_and_deduplicate(
-        shell_history,
-        terminal_history,
+        shell_history.into_iter().rev().collect(),
+        terminal_history.into_iter().rev().collect(),
         1000, // max_rows - large enough for full searchable history
     );
 
@@ -812,3 +911,21 @@ pub fn ai_command_dialog(
         canvas.present();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paste_preview_limits_lines() {
+        assert_eq!(paste_preview("cd /tmp\nls"), "cd /tmp\nls");
+
+        let long_line = "x".repeat(80);
+        let text = format!("{}\n2\n3\n4\n5\n6\n7", long_line);
+        let preview = paste_preview(&text);
+        let lines: Vec<&str> = preview.lines().collect();
+        assert_eq!(lines.len(), PASTE_PREVIEW_LINES + 1);
+        assert_eq!(lines[0], format!("{}...", "x".repeat(PASTE_PREVIEW_LINE_CHARS)));
+        assert_eq!(lines[PASTE_PREVIEW_LINES], "(+2 more lines)");
+    }
+}
@@ -0,0 +1,239 @@
+//! Glyph atlas for terminal text
+//!
+//! Rasterized glyphs are packed into a few large textures instead of one texture each, so a
+//! screen full of text is drawn as source-rect copies from the same texture. SDL batches
+//! consecutive copies that share a texture into a single draw call, which a texture per glyph
+//! would break up on every cell.
+
+use sdl3::pixels::PixelFormat;
+use sdl3::rect::Rect;
+use sdl3::render::{BlendMode, Texture, TextureCreator};
+use sdl3::surface::Surface;
+use std::collections::{HashMap, HashSet};
+
+/// Width and height of an atlas page, larger glyphs get a page of their own size
+const PAGE_SIZE: u32 = 1024;
+
+/// Pages kept before the atlas starts over, so scripts cycling through many glyphs don't grow it forever
+const MAX_PAGES: usize = 4;
+
+/// Transparent gap around each glyph so scaled copies don't pick up a neighbour's edge
+const GLYPH_PADDING: u32 = 1;
+
+/// Where a glyph lives in the atlas
+#[derive(Debug, Clone, Copy)]
+pub struct AtlasGlyph {
+    pub page: usize,
+    pub rect: Rect,
+}
+
+/// Packs rectangles into rows ("shelves") filled left to right, a new shelf starting below the
+/// tallest rectangle of the previous one. Glyphs of one font have similar heights, so little
+/// space is wasted.
+#[derive(Debug, Clone)]
+struct ShelfPacker {
+    width: u32,
+    height: u32,
+    shelf_y: u32,
+    shelf_height: u32,
+    cursor_x: u32,
+}
+
+impl ShelfPacker {
+    fn new(width: u32, height: u32) -> Self {
+        ShelfPacker {
+            width,
+            height,
+            shelf_y: 0,
+            shelf_height: 0,
+            cursor_x: 0,
+        }
+    }
+
+    /// Top-left corner for a `width` x `height` rectangle, or None if the page is full
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let padded_width = width + GLYPH_PADDING;
+        let padded_height = height + GLYPH_PADDING;
+        if padded_width > self.width || padded_height > self.height {
+            return None;
+        }
+
+        if self.cursor_x + padded_width > self.width {
+            self.shelf_y += self.shelf_height;
+            self.shelf_height = 0;
+            self.cursor_x = 0;
+        }
+        if self.shelf_y + padded_height > self.height {
+            return None;
+        }
+
+        let position = (self.cursor_x, self.shelf_y);
+        self.cursor_x += padded_width;
+        self.shelf_height = self.shelf_height.max(padded_height);
+        Some(position)
+    }
+
+    fn reset(&mut self) {
+        *self = ShelfPacker::new(self.width, self.height);
+    }
+}
+
+/// White glyphs packed into shared textures, tinted with color modulation when drawn
+pub struct GlyphAtlas<'a> {
+    pages: Vec<Texture<'a>>,
+    packers: Vec<ShelfPacker>,
+    glyphs: HashMap<String, AtlasGlyph>,
+    /// Graphemes no font has, so they aren't rasterized again on every frame
+    missing: HashSet<String>,
+}
+
+impl<'a> Default for GlyphAtlas<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> GlyphAtlas<'a> {
+    pub fn new() -> Self {
+        GlyphAtlas {
+            pages: Vec::new(),
+            packers: Vec::new(),
+            glyphs: HashMap::new(),
+            missing: HashSet::new(),
+        }
+    }
+
+    /// The cached glyph for a grapheme
+    pub fn get(&self, key: &str) -> Option<AtlasGlyph> {
+        self.glyphs.get(key).copied()
+    }
+
+    /// Texture holding the glyphs of a page
+    pub fn page_mut(&mut self, page: usize) -> &mut Texture<'a> {
+        &mut self.pages[page]
+    }
+
+    /// Whether no font could draw a grapheme
+    pub fn is_missing(&self, key: &str) -> bool {
+        self.missing.contains(key)
+    }
+
+    pub fn mark_missing(&mut self, key: &str) {
+        self.missing.insert(key.to_string());
+    }
+
+    /// Number of cached glyphs
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Copy a rasterized glyph into the atlas. When every page is full the atlas starts over;
+    /// glyphs still on screen are rasterized again on the next frame.
+    pub fn insert<T>(&mut self, texture_creator: &'a TextureCreator<T>, key: &str, surface: &Surface) -> Result<AtlasGlyph, String> {
+        let surface = surface.convert_format(PixelFormat::RGBA32).map_err(|e| e.to_string())?;
+        let (width, height) = (surface.width(), surface.height());
+
+        let slot = match self.allocate(width, height) {
+            Some(slot) => slot,
+            None if self.pages.len() < MAX_PAGES => {
+                self.add_page(texture_creator, width, height)?;
+                self.allocate(width, height).ok_or("glyph does not fit a new atlas page")?
+            }
+            None => {
+                eprintln!("[RENDER] Glyph atlas full with {} glyphs, starting over", self.glyphs.len());
+                self.clear();
+                match self.allocate(width, height) {
+                    Some(slot) => slot,
+                    None => {
+                        self.add_page(texture_creator, width, height)?;
+                        self.allocate(width, height).ok_or("glyph does not fit a new atlas page")?
+                    }
+                }
+            }
+        };
+
+        let (page, x, y) = slot;
+        let rect = Rect::new(x as i32, y as i32, width, height);
+        let pitch = surface.pitch() as usize;
+        surface
+            .with_lock(|pixels| self.pages[page].update(rect, pixels, pitch))
+            .map_err(|e| e.to_string())?;
+
+        let glyph = AtlasGlyph { page, rect };
+        self.glyphs.insert(key.to_string(), glyph);
+        Ok(glyph)
+    }
+
+    /// Forget all glyphs, keeping the pages for reuse (the font changed, or the atlas filled up)
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.missing.clear();
+        for packer in &mut self.packers {
+            packer.reset();
+        }
+        // Old glyph pixels would otherwise show through the padding of new ones
+        for page in &mut self.pages {
+            let query = page.query();
+            let zeros = vec![0u8; (query.width * query.height * 4) as usize];
+            if let Err(e) = page.update(None, &zeros, query.width as usize * 4) {
+                eprintln!("[RENDER] Failed to clear glyph atlas page: {}", e);
+            }
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+        self.packers
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, packer)| packer.allocate(width, height).map(|(x, y)| (page, x, y)))
+    }
+
+    fn add_page<T>(&mut self, texture_creator: &'a TextureCreator<T>, glyph_width: u32, glyph_height: u32) -> Result<(), String> {
+        let width = PAGE_SIZE.max(glyph_width + GLYPH_PADDING);
+        let height = PAGE_SIZE.max(glyph_height + GLYPH_PADDING);
+        let mut page = texture_creator
+            .create_texture_static(PixelFormat::RGBA32, width, height)
+            .map_err(|e| format!("Failed to create glyph atlas page: {}", e))?;
+        page.set_blend_mode(BlendMode::Blend);
+
+        // Static textures start with undefined contents
+        let zeros = vec![0u8; (width * height * 4) as usize];
+        page.update(None, &zeros, width as usize * 4).map_err(|e| e.to_string())?;
+
+        eprintln!("[RENDER] Added glyph atlas page {} ({}x{})", self.pages.len(), width, height);
+        self.pages.push(page);
+        self.packers.push(ShelfPacker::new(width, height));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shelf_packer_fills_rows_then_pages_up() {
+        let mut packer = ShelfPacker::new(32, 24);
+
+        // Three 9x10 glyphs fit a row (10 wide with padding), the fourth starts a new shelf
+        assert_eq!(packer.allocate(9, 10), Some((0, 0)));
+        assert_eq!(packer.allocate(9, 10), Some((10, 0)));
+        assert_eq!(packer.allocate(9, 10), Some((20, 0)));
+        assert_eq!(packer.allocate(9, 10), Some((0, 11)));
+
+        // No room for a third shelf
+        assert_eq!(packer.allocate(9, 10), Some((10, 11)));
+        assert_eq!(packer.allocate(9, 10), Some((20, 11)));
+        assert_eq!(packer.allocate(9, 10), None);
+
+        // Too large for the page at all
+        assert_eq!(packer.allocate(40, 5), None);
+
+        packer.reset();
+        assert_eq!(packer.allocate(9, 10), Some((0, 0)));
+    }
+}
@@ -1,6 +1,8 @@
 pub mod animations;
+pub mod box_drawing;
 pub mod context_menu;
 pub mod dialogs;
 pub mod filtered_list;
+pub mod glyph_atlas;
 pub mod render;
 pub mod text_input;
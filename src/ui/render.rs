@@ -3,22 +3,27 @@
 //! This module handles rendering of terminal content with performance optimizations:
 //! - Only renders the active tab (inactive tabs are not rendered)
 //! - Only renders visible terminal content (no off-screen scrollback rendering)
-//! - Uses glyph caching to avoid re-rendering characters
+//! - Draws glyphs from a shared atlas texture so SDL can batch them
+//! - Fills runs of same-colored cell backgrounds with one rect
 //! - Targets 60 FPS max via VSync
 
 use sdl3::pixels::Color;
 use sdl3::rect::Rect;
 use sdl3::render::{BlendMode, Canvas, TextureCreator};
+use sdl3::surface::Surface;
 use sdl3::ttf::Font;
 use sdl3::video::Window;
-use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthChar;
 
 use crate::ansi::DEFAULT_BG_COLOR;
-use crate::screen_buffer::{is_block_or_box_drawing, is_cjk_grapheme, is_emoji_grapheme, is_special_symbol};
+use crate::screen_buffer::{is_block_or_box_drawing, is_cjk_grapheme, is_emoji_grapheme, is_private_use_symbol, is_special_symbol, LineAttribute};
 use crate::sdl_renderer;
-use crate::tab_gui::TabBarGui;
+use crate::tab_gui::{Composition, TabBarGui};
+use crate::ui::box_drawing;
 use crate::ui::context_menu::ContextMenu;
+use crate::ui::glyph_atlas::{AtlasGlyph, GlyphAtlas};
 
 /// Get the platform-specific pane padding in pixels
 #[inline]
@@ -29,6 +34,74 @@ pub fn get_pane_padding() -> u32 {
     return 4;
 }
 
+/// How characters that no font has are drawn (`terminal.missingGlyph`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingGlyph {
+    /// A '□' in the cell
+    Box,
+    /// Nothing, the cell stays empty
+    Blank,
+    /// The codepoint in hex digits inside a small frame
+    Hex,
+}
+
+impl MissingGlyph {
+    /// Convert from settings string ("box", "blank" or "hex"), the box for anything else
+    pub fn from_settings_string(s: &str) -> Self {
+        match s {
+            "blank" => MissingGlyph::Blank,
+            "hex" => MissingGlyph::Hex,
+            _ => MissingGlyph::Box,
+        }
+    }
+}
+
+/// Colors of the pane dividers, the active pane border and the pane title headers
+/// (`theme.divider*`, `theme.activePaneBorderColor` and `theme.paneHeader*` settings)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaneColors {
+    pub divider: Color,
+    /// A divider under the pointer, brighter to show it can be dragged
+    pub divider_hover: Color,
+    pub active_border: Color,
+    pub header: Color,
+    /// The active pane's header stands out
+    pub header_active: Color,
+    pub header_text: Color,
+    pub header_active_text: Color,
+}
+
+impl PaneColors {
+    pub const DEFAULT: PaneColors = PaneColors {
+        divider: Color::RGB(60, 60, 60),
+        divider_hover: Color::RGB(110, 140, 170),
+        active_border: Color::RGB(50, 90, 130),
+        header: Color::RGB(30, 30, 30),
+        header_active: Color::RGB(50, 90, 130),
+        header_text: Color::RGB(150, 150, 150),
+        header_active_text: Color::RGB(230, 230, 230),
+    };
+
+    /// The theme's pane colors, the defaults for any it doesn't set
+    pub fn from_theme(theme: &crate::settings::ThemeSettings) -> Self {
+        let defaults = PaneColors::DEFAULT;
+        PaneColors {
+            divider: theme.divider_color().unwrap_or(defaults.divider),
+            divider_hover: theme.divider_hover_color().unwrap_or(defaults.divider_hover),
+            active_border: theme.active_pane_border_color().unwrap_or(defaults.active_border),
+            header: theme.pane_header_color().unwrap_or(defaults.header),
+            header_active: theme.pane_header_active_color().unwrap_or(defaults.header_active),
+            header_text: theme.pane_header_text_color().unwrap_or(defaults.header_text),
+            header_active_text: theme.pane_header_active_text_color().unwrap_or(defaults.header_active_text),
+        }
+    }
+}
+
+/// Width of the bar marking finished commands in the pane's left margin
+const COMMAND_GUTTER_WIDTH: u32 = 3;
+const COMMAND_SUCCESS_COLOR: Color = Color::RGB(60, 170, 90);
+const COMMAND_FAILURE_COLOR: Color = Color::RGB(200, 70, 70);
+
 /// Calculate usable dimensions after accounting for padding
 #[inline]
 pub fn get_usable_dimensions(rect_width: u32, rect_height: u32) -> (u32, u32) {
@@ -59,6 +132,47 @@ pub fn adjust_mouse_coords_for_padding(mouse_x: i32, mouse_y: i32, rect_x: i32,
     ((mouse_x - rect_x).saturating_sub(padding), (mouse_y - rect_y).saturating_sub(padding))
 }
 
+/// How often the pane drawing time is summarized in the log
+const FRAME_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Time spent drawing panes, summarized in the log while frames are being drawn
+pub struct FrameStats {
+    frames: u32,
+    total: Duration,
+    worst: Duration,
+    since: Instant,
+}
+
+impl FrameStats {
+    pub fn new(now: Instant) -> Self {
+        FrameStats {
+            frames: 0,
+            total: Duration::ZERO,
+            worst: Duration::ZERO,
+            since: now,
+        }
+    }
+
+    /// Record one frame, returning a summary once per interval
+    pub fn record(&mut self, elapsed: Duration, now: Instant) -> Option<String> {
+        self.frames += 1;
+        self.total += elapsed;
+        self.worst = self.worst.max(elapsed);
+        if now.duration_since(self.since) < FRAME_STATS_INTERVAL {
+            return None;
+        }
+
+        let summary = format!(
+            "{} frames, avg {:.2} ms, worst {:.2} ms",
+            self.frames,
+            self.total.as_secs_f64() * 1000.0 / self.frames as f64,
+            self.worst.as_secs_f64() * 1000.0
+        );
+        *self = FrameStats::new(now);
+        Some(summary)
+    }
+}
+
 /// Render the entire frame including tab bar and active tab's panes
 /// Returns true if any terminal content was dirty and needed re-rendering
 pub fn render_frame<'a, T>(
@@ -69,18 +183,27 @@ pub fn render_frame<'a, T>(
     tab_font: &Font,
     button_font: &Font,
     cpu_font: &Font,
+    ui_emoji_font: &Font,
     terminal_font: &Font,
     emoji_font: &Font,
     unicode_fallback_font: &Font,
     cjk_font: &Font,
     context_menu_font: &Font,
+    context_menu_emoji_font: &Font,
     cpu_usage: f32,
+    memory_text: Option<&str>,
     tab_bar_height: u32,
     scale_factor: f32,
     char_width: f32,
     char_height: f32,
     cursor_visible: bool,
-    glyph_cache: &mut HashMap<String, sdl3::render::Texture<'a>>,
+    cursor_color: Option<Color>,
+    cursor_text_color: Option<Color>,
+    hovered_divider: Option<crate::pane_layout::PaneId>,
+    glyph_atlas: &mut GlyphAtlas<'a>,
+    missing_glyph: MissingGlyph,
+    pane_colors: &PaneColors,
+    frame_stats: &mut FrameStats,
 ) -> Result<bool, String> {
     // Clear screen with terminal background color
     canvas.set_draw_color(DEFAULT_BG_COLOR);
@@ -90,11 +213,18 @@ pub fn render_frame<'a, T>(
     let (window_w, window_h) = canvas.window().size_in_pixels();
 
     // Update and render tab bar
-    let (tab_names, active_tab_idx, editing_tab_idx, editing_state) = {
+    let (tab_names, tab_activity, active_tab_idx, editing_tab_idx, editing_state) = {
         let gui = tab_bar_gui.lock().unwrap();
-        (gui.get_tab_names(), gui.active_tab, gui.get_editing_tab_index(), gui.get_editing_state())
+        (
+            gui.get_tab_names(),
+            gui.get_tab_activity(),
+            gui.active_tab,
+            gui.get_editing_tab_index(),
+            gui.get_editing_state(),
+        )
     };
     tab_bar.set_tabs(tab_names);
+    tab_bar.tab_activity = tab_activity;
     tab_bar.set_active_tab(active_tab_idx);
     // Sync editing state from TabBarGui to TabBar for rendering
     tab_bar.editing_tab = editing_tab_idx;
@@ -102,7 +232,17 @@ pub fn render_frame<'a, T>(
         tab_bar.edit_text = edit_text;
         tab_bar.edit_cursor_pos = cursor_pos;
     }
-    tab_bar.render(canvas, tab_font, button_font, cpu_font, texture_creator, window_w, cpu_usage)?;
+    tab_bar.render(
+        canvas,
+        tab_font,
+        button_font,
+        cpu_font,
+        ui_emoji_font,
+        texture_creator,
+        window_w,
+        cpu_usage,
+        memory_text,
+    )?;
 
     // Calculate pane area (tab_bar_height is already in physical pixels)
     let pane_area_y = tab_bar_height as i32;
@@ -110,18 +250,35 @@ pub fn render_frame<'a, T>(
 
     // Get active tab's pane layout data (quickly, then release lock)
     // OPTIMIZATION: Only render the active tab, not inactive tabs
-    let (pane_rects, pane_count, dividers, context_menu, copy_animation_data) = {
+    let (pane_rects, pane_scales, pane_count, header_height, dividers, context_menu, copy_animation_data, toast, resize_overlay, composition) = {
         let mut gui = tab_bar_gui.lock().unwrap();
+        let tab_context_menu = gui.tab_context_menu.as_ref().map(|(_, menu)| menu.clone());
+        let composition = gui.composition.clone();
 
         match gui.get_active_pane_layout() {
             Some(pane_layout) => {
                 let pane_rects = pane_layout.get_pane_rects(0, pane_area_y, window_w, pane_area_height);
+                let pane_scales: Vec<f32> = pane_rects.iter().map(|(pane_id, _, _, _, _)| pane_layout.font_scale(*pane_id)).collect();
                 let pane_count = pane_rects.len();
+                let header_height = pane_layout.header_height;
                 let dividers = pane_layout.get_divider_rects(0, pane_area_y, window_w, pane_area_height);
-                let context_menu = pane_layout.context_menu.clone();
+                let context_menu = tab_context_menu.or_else(|| pane_layout.context_menu.clone());
                 let copy_animation_data = pane_layout.copy_animation.clone();
-
-                (pane_rects, pane_count, dividers, context_menu, copy_animation_data)
+                let toast = pane_layout.toast.clone();
+                let resize_overlay = pane_layout.resize_overlay.clone();
+
+                (
+                    pane_rects,
+                    pane_scales,
+                    pane_count,
+                    header_height,
+                    dividers,
+                    context_menu,
+                    copy_animation_data,
+                    toast,
+                    resize_overlay,
+                    composition,
+                )
             }
             None => {
                 // No active tab, just present empty screen
@@ -132,8 +289,15 @@ pub fn render_frame<'a, T>(
     };
 
     // Render each pane in the active tab (inactive tabs are NOT rendered)
+    let active_rect = pane_rects.iter().find(|(_, _, _, is_active, _)| *is_active).map(|(_, rect, _, _, _)| *rect);
+    let active_scale = pane_rects
+        .iter()
+        .zip(&pane_scales)
+        .find(|((_, _, _, is_active, _), _)| *is_active)
+        .map_or(1.0, |(_, scale)| *scale);
     let mut any_dirty = false;
-    for (_pane_id, rect, terminal, is_active, is_selected) in pane_rects {
+    let panes_start = Instant::now();
+    for ((_pane_id, rect, terminal, is_active, is_selected), font_scale) in pane_rects.into_iter().zip(pane_scales) {
         let was_dirty = render_pane(
             canvas,
             texture_creator,
@@ -147,21 +311,53 @@ pub fn render_frame<'a, T>(
             is_active,
             is_selected,
             pane_count,
+            header_height,
             char_width,
             char_height,
+            font_scale,
             cursor_visible,
-            glyph_cache,
+            cursor_color,
+            cursor_text_color,
+            if is_active { composition.as_ref() } else { None },
+            glyph_atlas,
+            missing_glyph,
+            pane_colors,
             scale_factor,
         )?;
         any_dirty = any_dirty || was_dirty;
     }
+    if let Some(summary) = frame_stats.record(panes_start.elapsed(), Instant::now()) {
+        eprintln!("[RENDER] Pane drawing: {} ({} glyphs in atlas)", summary, glyph_atlas.len());
+    }
 
     // Render dividers between panes
-    render_dividers(canvas, &dividers)?;
+    render_dividers(canvas, &dividers, hovered_divider, pane_colors)?;
+
+    // Render toast over the active pane
+    if let (Some(toast), Some(rect)) = (toast.as_ref(), active_rect) {
+        if !toast.is_expired() {
+            render_toast(canvas, texture_creator, terminal_font, rect, &toast.message, get_pane_padding())?;
+        }
+    }
+
+    // Render the grid size over the active pane while it is being resized
+    if let (Some(overlay), Some(rect)) = (resize_overlay.as_ref(), active_rect) {
+        if !overlay.is_expired() {
+            let (cols, rows) = calculate_terminal_size(rect.width(), rect.height(), char_width * active_scale, char_height * active_scale);
+            render_resize_overlay(
+                canvas,
+                texture_creator,
+                terminal_font,
+                rect,
+                &format_grid_size(cols, rows),
+                overlay.current_opacity(),
+            )?;
+        }
+    }
 
     // Render context menu if open
     if let Some(ref menu) = context_menu {
-        render_context_menu(canvas, texture_creator, context_menu_font, menu)?;
+        render_context_menu(canvas, texture_creator, context_menu_font, context_menu_emoji_font, menu)?;
     }
 
     // Render copy animation if active
@@ -195,15 +391,26 @@ fn render_pane<'a, T>(
     is_active: bool,
     is_selected: bool,
     pane_count: usize,
+    header_height: u32,
     char_width: f32,
     char_height: f32,
+    font_scale: f32,
     cursor_visible: bool,
-    glyph_cache: &mut HashMap<String, sdl3::render::Texture<'a>>,
+    cursor_color: Option<Color>,
+    cursor_text_color: Option<Color>,
+    composition: Option<&Composition>,
+    glyph_atlas: &mut GlyphAtlas<'a>,
+    missing_glyph: MissingGlyph,
+    pane_colors: &PaneColors,
     scale_factor: f32,
 ) -> Result<bool, String> {
     let t = terminal.lock().unwrap();
     let mut sb = t.screen_buffer.lock().unwrap();
 
+    // A zoomed pane draws the same glyphs scaled into larger (or smaller) cells
+    let char_width = char_width * font_scale;
+    let char_height = char_height * font_scale;
+
     // No need to clear pane background - terminal cells will paint their own backgrounds
     // This optimizes rendering by avoiding redundant fills
 
@@ -223,6 +430,8 @@ fn render_pane<'a, T>(
 
     // Get selection for highlighting (cached once per frame to avoid locking in cell loop)
     let selection_snapshot = *t.selection.lock().unwrap();
+    // Selections are kept in absolute rows, this is the one at the top of the view
+    let view_top_row = sb.view_row_to_absolute(0);
 
     // Check if we should show cursor (for skipping cursor cell in main loop)
     let terminal_cursor_visible_check = t.cursor_visible.lock().unwrap();
@@ -233,127 +442,181 @@ fn render_pane<'a, T>(
 
     // Render cells that fit in both the rect and the screen buffer
     for row in 0..rows {
-        for col in 0..cols {
-            // Skip rendering cursor position if we'll render it as a block cursor later
-            use crate::screen_buffer::CursorStyle;
-            let is_bar_cursor = matches!(sb.cursor_style, CursorStyle::BlinkingBar | CursorStyle::SteadyBar);
-            if should_show_cursor_check && !is_bar_cursor && col == sb.cursor_x && row == sb.cursor_y {
-                continue;
-            }
+        // DEC double-width/double-height rows draw each cell two columns wide
+        let line_attr = sb.line_attribute(row);
+        let col_scale = if line_attr.is_double_width() { 2 } else { 1 };
+        let row_y = rect.y() + pane_padding as i32 + (row as f32 * char_height) as i32;
+
+        // Double-height rows draw glyphs twice as tall, showing only the top or bottom half
+        let (glyph_y_offset, glyph_height, glyph_scale) = match line_attr {
+            LineAttribute::DoubleHeightTop => (0, char_height as u32 * 2, (2.0 * font_scale, 2.0 * font_scale)),
+            LineAttribute::DoubleHeightBottom => (-(char_height as i32), char_height as u32 * 2, (2.0 * font_scale, 2.0 * font_scale)),
+            LineAttribute::DoubleWidth => (0, char_height as u32, (2.0 * font_scale, font_scale)),
+            LineAttribute::Normal => (0, char_height as u32, (font_scale, font_scale)),
+        };
+        let is_double_height = matches!(line_attr, LineAttribute::DoubleHeightTop | LineAttribute::DoubleHeightBottom);
+        // Keep the caller's clip rect so it can be put back after the row
+        let previous_clip = is_double_height.then(|| canvas.clip_rect());
+        if let Some(previous_clip) = previous_clip {
+            canvas.set_clip_rect(previous_clip.intersect_rect(Rect::new(rect.x(), row_y, rect.width(), char_height as u32)));
+        }
 
-            if let Some(cell) = sb.get_cell_with_scrollback(col, row) {
-                // Skip continuation cells (used by double-width emojis)
-                if cell.width == 0 || cell.ch == '\0' {
-                    continue;
+        // Skip rendering cursor position if we'll render it as a block cursor later
+        use crate::screen_buffer::CursorStyle;
+        let is_bar_cursor = matches!(sb.cursor_style, CursorStyle::BlinkingBar | CursorStyle::SteadyBar);
+        let skipped_cursor_col = (should_show_cursor_check && !is_bar_cursor && row == sb.cursor_y).then_some(sb.cursor_x);
+
+        // Cells to draw on this row, skipping continuation cells (used by double-width emojis)
+        let row_cells: Vec<(usize, &crate::screen_buffer::Cell)> = (0..cols)
+            .take_while(|col| col * col_scale < cols)
+            .filter(|col| Some(*col) != skipped_cursor_col)
+            .filter_map(|col| sb.get_cell_with_scrollback(col, row).map(|cell| (col, cell)))
+            .filter(|(_, cell)| cell.width != 0 && cell.ch != '\0')
+            .collect();
+
+        // Left edge of a column, a run of cells spans from its first column to past its last
+        let col_x = |col: usize| rect.x() + pane_padding as i32 + ((col * col_scale) as f32 * char_width) as i32;
+
+        // Apply reverse video mode if enabled (swap fg/bg globally), then the cell's own reverse
+        let cell_colors = |cell: &crate::screen_buffer::Cell| {
+            let (cell_fg, cell_bg) = if sb.reverse_video_mode {
+                (cell.bg_color, cell.fg_color)
+            } else {
+                (cell.fg_color, cell.bg_color)
+            };
+            if cell.reverse {
+                (cell_bg, cell_fg)
+            } else {
+                (cell_fg, cell_bg)
+            }
+        };
+
+        // Backgrounds first, one rect per run of adjacent cells with the same color. Drawing
+        // them before any glyph also keeps glyphs that overhang their cell from being cut off.
+        let mut background_run: Option<(Color, usize, usize)> = None;
+        for &(col, cell) in &row_cells {
+            let is_selected = selection_snapshot.as_ref().is_some_and(|sel| sel.contains(col, view_top_row + row));
+            let (_, actual_bg) = cell_colors(cell);
+
+            // Draw background only if it differs from the default that we already filled
+            let background = if is_selected {
+                Some(Color::RGB(70, 130, 180))
+            } else if actual_bg.r != DEFAULT_BG_COLOR.r || actual_bg.g != DEFAULT_BG_COLOR.g || actual_bg.b != DEFAULT_BG_COLOR.b {
+                Some(Color::RGB(actual_bg.r, actual_bg.g, actual_bg.b))
+            } else {
+                None
+            };
+            let end_col = col + cell.width as usize;
+
+            match (background_run.as_mut(), background) {
+                (Some((run_color, _, run_end)), Some(color)) if *run_color == color && *run_end == col => *run_end = end_col,
+                _ => {
+                    if let Some((run_color, run_start, run_end)) = background_run.take() {
+                        canvas.set_draw_color(run_color);
+                        canvas
+                            .fill_rect(Rect::new(
+                                col_x(run_start),
+                                row_y,
+                                (col_x(run_end) - col_x(run_start)) as u32,
+                                char_height as u32,
+                            ))
+                            .map_err(|e| e.to_string())?;
+                    }
+                    background_run = background.map(|color| (color, col, end_col));
                 }
+            }
+        }
+        if let Some((run_color, run_start, run_end)) = background_run {
+            canvas.set_draw_color(run_color);
+            canvas
+                .fill_rect(Rect::new(
+                    col_x(run_start),
+                    row_y,
+                    (col_x(run_end) - col_x(run_start)) as u32,
+                    char_height as u32,
+                ))
+                .map_err(|e| e.to_string())?;
+        }
 
-                let x = rect.x() + pane_padding as i32 + (col as f32 * char_width) as i32;
-                let y = rect.y() + pane_padding as i32 + (row as f32 * char_height) as i32;
-
-                // Calculate actual width for this character (1 or 2 cells)
-                let actual_cell_width = char_width * cell.width as f32;
-
-                // Check if cell is selected
-                let is_selected = if let Some(ref sel) = selection_snapshot {
-                    sel.contains(col, row)
-                } else {
-                    false
-                };
-
-                // Apply reverse video mode if enabled (swap fg/bg globally)
-                let (cell_fg, cell_bg) = if sb.reverse_video_mode {
-                    (cell.bg_color, cell.fg_color)
-                } else {
-                    (cell.fg_color, cell.bg_color)
-                };
-
-                // Render background (selection highlight or cell background)
-                // Need to consider reverse attribute when determining the actual background color
-                let actual_bg = if cell.reverse {
-                    // When reverse is true, foreground becomes background
-                    cell_fg
-                } else {
-                    cell_bg
-                };
-
-                if is_selected {
-                    canvas.set_draw_color(Color::RGB(70, 130, 180));
-                    let cell_rect = Rect::new(x, y, actual_cell_width as u32, char_height as u32);
-                    canvas.fill_rect(cell_rect).map_err(|e| e.to_string())?;
-                } else if actual_bg.r != crate::ansi::DEFAULT_BG_COLOR.r
-                    || actual_bg.g != crate::ansi::DEFAULT_BG_COLOR.g
-                    || actual_bg.b != crate::ansi::DEFAULT_BG_COLOR.b
-                // || cell.reverse
-                {
-                    // Draw background only if it differs from the default that we already filled
-                    // This optimizes rendering and prevents artifacts from stale reverse video attributes
-                    canvas.set_draw_color(Color::RGB(actual_bg.r, actual_bg.g, actual_bg.b));
-                    let cell_rect = Rect::new(x, y, actual_cell_width as u32, char_height as u32);
-                    canvas.fill_rect(cell_rect).map_err(|e| e.to_string())?;
-                }
+        // Then the glyphs, consecutive copies from the atlas are batched by SDL
+        for &(col, cell) in &row_cells {
+            // OPTIMIZATION: Render character if not space (skip spaces with default bg) and not invisible
+            if cell.ch == ' ' || cell.invisible {
+                continue;
+            }
 
-                // OPTIMIZATION: Render character if not space (skip spaces with default bg) and not invisible
-                if cell.ch != ' ' && !cell.invisible {
-                    // Use extended grapheme if present, otherwise use single char
-                    let char_str;
-                    let text = if let Some(ref extended) = cell.extended {
-                        extended.as_ref()
-                    } else {
-                        char_str = cell.ch.to_string();
-                        char_str.as_str()
-                    };
+            // Use extended grapheme if present, otherwise use single char
+            let char_str;
+            let text = if let Some(ref extended) = cell.extended {
+                extended.as_ref()
+            } else {
+                char_str = cell.ch.to_string();
+                char_str.as_str()
+            };
+
+            // Calculate actual width for this character (1 or 2 cells, doubled on double-width rows)
+            let actual_cell_width = char_width * (cell.width as usize * col_scale) as f32;
+            let (fg, _) = cell_colors(cell);
+
+            render_glyph(
+                canvas,
+                texture_creator,
+                font,
+                emoji_font,
+                unicode_fallback_font,
+                cjk_font,
+                glyph_atlas,
+                missing_glyph,
+                text,
+                col_x(col),
+                row_y + glyph_y_offset,
+                fg.r,
+                fg.g,
+                fg.b,
+                actual_cell_width as u32,
+                glyph_height,
+                glyph_scale,
+                scale_factor,
+                cell.bold,
+                cell.underline,
+                cell.strikethrough,
+            )?;
+        }
 
-                    // Handle reverse video attribute (per-cell reverse, applied after global reverse)
-                    // Note: background was already drawn above (lines 280-298) using actual_bg
-                    let (fg_r, fg_g, fg_b) = if cell.reverse {
-                        (cell_bg.r, cell_bg.g, cell_bg.b)
-                    } else {
-                        (cell_fg.r, cell_fg.g, cell_fg.b)
-                    };
+        if let Some(previous_clip) = previous_clip {
+            canvas.set_clip_rect(previous_clip);
+        }
 
-                    render_glyph(
-                        canvas,
-                        texture_creator,
-                        font,
-                        emoji_font,
-                        unicode_fallback_font,
-                        cjk_font,
-                        glyph_cache,
-                        text,
-                        x,
-                        y,
-                        fg_r,
-                        fg_g,
-                        fg_b,
-                        actual_cell_width as u32,
-                        char_height as u32,
-                        scale_factor,
-                        cell.bold,
-                        cell.underline,
-                        cell.strikethrough,
-                    )?;
-                }
-            }
+        // Green or red bar beside the rows of a command that succeeded or failed
+        if let Some(exit_code) = sb.command_exit_code_at(view_top_row + row) {
+            canvas.set_draw_color(if exit_code == 0 { COMMAND_SUCCESS_COLOR } else { COMMAND_FAILURE_COLOR });
+            canvas
+                .fill_rect(Rect::new(rect.x(), row_y, COMMAND_GUTTER_WIDTH.min(pane_padding), char_height.ceil() as u32))
+                .map_err(|e| e.to_string())?;
         }
     }
 
     // Render cursor if active pane, visible (blink state), and enabled by terminal (ANSI code)
     if should_show_cursor_check {
-        let cursor_x = rect.x() + pane_padding as i32 + (sb.cursor_x as f32 * char_width) as i32;
+        let cursor_col_scale = if sb.line_attribute(sb.cursor_y).is_double_width() { 2 } else { 1 };
+        let cursor_x = rect.x() + pane_padding as i32 + ((sb.cursor_x * cursor_col_scale) as f32 * char_width) as i32;
         let cursor_y = rect.y() + pane_padding as i32 + (sb.cursor_y as f32 * char_height) as i32;
 
+        // A color set by the application (OSC 12) takes precedence over the theme
+        let cursor_color = sb.cursor_color.or(cursor_color);
+
         // Cursor style from DECSCUSR control codes
         use crate::screen_buffer::CursorStyle;
         match sb.cursor_style {
             CursorStyle::BlinkingBar | CursorStyle::SteadyBar => {
                 // Bar cursor: thin vertical line
-                canvas.set_draw_color(Color::RGB(200, 200, 200));
+                canvas.set_draw_color(cursor_color.unwrap_or(Color::RGB(200, 200, 200)));
                 let cursor_rect = Rect::new(cursor_x, cursor_y, 2, char_height as u32);
                 canvas.fill_rect(cursor_rect).map_err(|e| e.to_string())?;
             }
             CursorStyle::BlinkingUnderline | CursorStyle::SteadyUnderline => {
                 // Underline cursor: horizontal line at bottom
-                canvas.set_draw_color(Color::RGB(200, 200, 200));
+                canvas.set_draw_color(cursor_color.unwrap_or(Color::RGB(200, 200, 200)));
                 let underline_height = (char_height * 0.15).max(2.0) as u32; // 15% of char height, minimum 2px
                 let cursor_rect = Rect::new(
                     cursor_x,
@@ -366,8 +629,10 @@ fn render_pane<'a, T>(
             CursorStyle::BlinkingBlock | CursorStyle::SteadyBlock => {
                 // Block cursor: use reverse video (invert fg/bg colors)
                 if let Some(cell) = sb.get_cell_with_scrollback(sb.cursor_x, sb.cursor_y) {
-                    // Draw background with inverted color (use foreground color, or white if fg is default)
-                    let cursor_bg = if cell.fg_color.r == 255 && cell.fg_color.g == 255 && cell.fg_color.b == 255 {
+                    // Draw background with the cursor color, or inverted color (use foreground color, or white if fg is default)
+                    let cursor_bg = if let Some(color) = cursor_color {
+                        color
+                    } else if cell.fg_color.r == 255 && cell.fg_color.g == 255 && cell.fg_color.b == 255 {
                         Color::RGB(255, 255, 255) // Use white for cursor background
                     } else {
                         cell.fg_color
@@ -386,8 +651,10 @@ fn render_pane<'a, T>(
                         char_str.as_str()
                     };
 
-                    // Use background color for text, or dark gray if bg is default black
-                    let text_color = if cell.bg_color.r == 0 && cell.bg_color.g == 0 && cell.bg_color.b == 0 {
+                    // Use the theme cursor text color, else background color, or dark gray if bg is default black
+                    let text_color = if let Some(color) = cursor_text_color {
+                        color
+                    } else if cell.bg_color.r == 0 && cell.bg_color.g == 0 && cell.bg_color.b == 0 {
                         Color::RGB(50, 50, 50) // Dark gray text on white cursor background
                     } else {
                         cell.bg_color
@@ -400,7 +667,8 @@ fn render_pane<'a, T>(
                         emoji_font,
                         unicode_fallback_font,
                         cjk_font,
-                        glyph_cache,
+                        glyph_atlas,
+                        missing_glyph,
                         text,
                         cursor_x,
                         cursor_y,
@@ -409,6 +677,7 @@ fn render_pane<'a, T>(
                         text_color.b,
                         char_width as u32,
                         char_height as u32,
+                        (font_scale, font_scale),
                         scale_factor,
                         cell.bold,
                         cell.underline,
@@ -416,7 +685,7 @@ fn render_pane<'a, T>(
                     )?;
                 } else {
                     // Fallback if cell doesn't exist
-                    canvas.set_draw_color(Color::RGB(200, 200, 200));
+                    canvas.set_draw_color(cursor_color.unwrap_or(Color::RGB(200, 200, 200)));
                     let cursor_rect = Rect::new(cursor_x, cursor_y, char_width as u32, char_height as u32);
                     canvas.fill_rect(cursor_rect).map_err(|e| e.to_string())?;
                 }
@@ -424,21 +693,91 @@ fn render_pane<'a, T>(
         }
     }
 
+    // Draw the input method's pre-edit text over the cursor, underlined until it is committed
+    if let Some(composition) = composition.filter(|_| is_at_bottom) {
+        let start_x = rect.x() + pane_padding as i32 + (sb.cursor_x as f32 * char_width) as i32;
+        let cursor_y = rect.y() + pane_padding as i32 + (sb.cursor_y as f32 * char_height) as i32;
+        let text_color = Color::RGB(230, 230, 230);
+
+        let mut x = start_x;
+        let mut composition_cursor_x = start_x;
+        for (index, ch) in composition.text.chars().enumerate() {
+            if index == composition.cursor {
+                composition_cursor_x = x;
+            }
+            let cell_width = (UnicodeWidthChar::width(ch).unwrap_or(1).max(1) as f32 * char_width) as u32;
+            canvas.set_draw_color(DEFAULT_BG_COLOR);
+            canvas
+                .fill_rect(Rect::new(x, cursor_y, cell_width, char_height as u32))
+                .map_err(|e| e.to_string())?;
+            render_glyph(
+                canvas,
+                texture_creator,
+                font,
+                emoji_font,
+                unicode_fallback_font,
+                cjk_font,
+                glyph_atlas,
+                missing_glyph,
+                &ch.to_string(),
+                x,
+                cursor_y,
+                text_color.r,
+                text_color.g,
+                text_color.b,
+                cell_width,
+                char_height as u32,
+                (font_scale, font_scale),
+                scale_factor,
+                false,
+                true,
+                false,
+            )?;
+            x += cell_width as i32;
+        }
+        if composition.cursor >= composition.text.chars().count() {
+            composition_cursor_x = x;
+        }
+
+        // Thin bar where the next composed character will go
+        canvas.set_draw_color(cursor_color.unwrap_or(Color::RGB(200, 200, 200)));
+        canvas
+            .fill_rect(Rect::new(composition_cursor_x, cursor_y, 2, char_height as u32))
+            .map_err(|e| e.to_string())?;
+    }
+
     // Show scroll position indicator when viewing scrollback
     if !sb.is_at_bottom() {
         render_scrollback_indicator(canvas, texture_creator, font, rect, sb.scroll_offset, pane_padding)?;
     }
 
+    // Point out output that arrived below the scrolled-back view
+    if sb.has_new_output_below() {
+        render_new_output_badge(canvas, texture_creator, font, new_output_badge_rect(rect, char_width, char_height))?;
+    }
+
     let was_dirty = sb.is_dirty();
     sb.clear_dirty();
 
     // Check if dirty flag was set again during render (race condition)
     let still_dirty = sb.is_dirty();
 
+    // The header shows the title the application set, or else what is running
+    let header_title = if header_height > 0 && pane_count > 1 {
+        Some(sb.title.clone().or_else(|| t.process_name().map(str::to_string)).unwrap_or_default())
+    } else {
+        None
+    };
+
     // Release locks
     drop(sb);
     drop(t);
 
+    if let Some(title) = header_title {
+        let header_rect = Rect::new(rect.x(), rect.y() - header_height as i32, rect.width(), header_height);
+        render_pane_header(canvas, texture_creator, font, header_rect, &title, is_active, pane_colors)?;
+    }
+
     // Draw border for selected panes (green) or active pane (blue)
     if is_selected && pane_count > 1 {
         // Selected panes get a green border
@@ -472,14 +811,14 @@ fn render_pane<'a, T>(
             .map_err(|e| e.to_string())?;
     } else if is_active && pane_count > 1 {
         // Active pane gets a blue border
-        canvas.set_draw_color(Color::RGB(50, 90, 130));
+        canvas.set_draw_color(pane_colors.active_border);
         canvas.draw_rect(rect).map_err(|e| e.to_string())?;
     }
 
     Ok(was_dirty || still_dirty)
 }
 
-/// Render a single glyph with caching
+/// Render a single glyph from the atlas, rasterizing it on first use
 fn render_glyph<'a, T>(
     canvas: &mut Canvas<Window>,
     texture_creator: &'a TextureCreator<T>,
@@ -487,7 +826,8 @@ fn render_glyph<'a, T>(
     emoji_font: &Font,
     unicode_fallback_font: &Font,
     cjk_font: &Font,
-    glyph_cache: &mut HashMap<String, sdl3::render::Texture<'a>>,
+    glyph_atlas: &mut GlyphAtlas<'a>,
+    missing_glyph: MissingGlyph,
     text: &str,
     x: i32,
     y: i32,
@@ -496,298 +836,281 @@ fn render_glyph<'a, T>(
     b: u8,
     cell_width: u32,
     cell_height: u32,
+    glyph_scale: (f32, f32),
     _scale_factor: f32,
     bold: bool,
     underline: bool,
     strikethrough: bool,
 ) -> Result<(), String> {
-    let cache_key = text.to_string();
-
-    // Check cache first
-    if let Some(cached_texture) = glyph_cache.get_mut(&cache_key) {
-        // Apply color modulation to the white texture
-        cached_texture.set_color_mod(r, g, b);
-        let query = cached_texture.query();
-
-        // Check if this is an emoji - if so, scale it to fit in cell
-        let is_likely_emoji = is_emoji_grapheme(text);
-
-        // Check if this is a special symbol that needs scaling
-        let is_special_missing_symbol = text.chars().count() == 1 && text.chars().next().map_or(false, is_special_symbol);
-
-        // Check if this is a block/box drawing character that needs cell-filling
-        let is_block_box_char = text.chars().count() == 1 && text.chars().next().map_or(false, is_block_or_box_drawing);
-
-        if is_block_box_char {
-            // Stretch block/box drawing characters to fill the entire cell for ASCII art
-            // No aspect ratio preservation - these characters are designed to be stretched
-            let char_rect = Rect::new(x, y, cell_width, cell_height);
-            canvas.copy(cached_texture, None, char_rect).map_err(|e| e.to_string())?;
-        } else if is_likely_emoji {
-            // Scale emoji to fill available space (double-width emojis get 2x cell_width)
-            // Use the smaller of width or height to maintain square aspect ratio
-            let target_size = cell_width.min(cell_height);
-
-            let emoji_width = query.width;
-            let emoji_height = query.height;
-
-            // Calculate scaling to fit the target size while maintaining aspect ratio
-            let scale_x = target_size as f32 / emoji_width as f32;
-            let scale_y = target_size as f32 / emoji_height as f32;
-            let scale = scale_x.min(scale_y);
-
-            let scaled_width = (emoji_width as f32 * scale) as u32;
-            let scaled_height = (emoji_height as f32 * scale) as u32;
-
-            // Center the emoji in the cell (horizontally and vertically)
-            let offset_x = (cell_width as i32 - scaled_width as i32) / 2;
-            let offset_y = (cell_height as i32 - scaled_height as i32) / 2;
-
-            let char_rect = Rect::new(x + offset_x, y + offset_y, scaled_width, scaled_height);
-            canvas.copy(cached_texture, None, char_rect).map_err(|e| e.to_string())?;
-        } else if is_special_missing_symbol {
-            // Scale up special symbols to make them more visible (2.0x larger than default)
-            let target_size = (cell_width.min(cell_height) as f32 * 2.0) as u32;
-
-            let symbol_width = query.width;
-            let symbol_height = query.height;
-
-            // Calculate scaling to fit the target size while maintaining aspect ratio
-            let scale_x = target_size as f32 / symbol_width as f32;
-            let scale_y = target_size as f32 / symbol_height as f32;
-            let scale = scale_x.min(scale_y);
-
-            let scaled_width = (symbol_width as f32 * scale) as u32;
-            let scaled_height = (symbol_height as f32 * scale) as u32;
-
-            // Center the symbol in the cell
-            let offset_x = (cell_width as i32 - scaled_width as i32) / 2;
-            let offset_y = (cell_height as i32 - scaled_height as i32) / 2;
-
-            let char_rect = Rect::new(x + offset_x, y + offset_y, scaled_width, scaled_height);
-            canvas.copy(cached_texture, None, char_rect).map_err(|e| e.to_string())?;
-        } else {
-            // Regular character - use original size
-            let char_rect = Rect::new(x, y, query.width, query.height);
-            canvas.copy(cached_texture, None, char_rect).map_err(|e| e.to_string())?;
+    // Box-drawing and block elements are drawn with primitives so adjacent cells connect seamlessly
+    let mut chars = text.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        if is_block_or_box_drawing(ch) && box_drawing::draw_box_char(canvas, ch, x, y, cell_width, cell_height, Color::RGB(r, g, b))? {
+            draw_text_decorations(canvas, x, y, cell_width, cell_height, r, g, b, bold, underline, strikethrough)?;
+            return Ok(());
         }
-
-        // Draw decorations (underline, strikethrough) for cached glyphs
-        draw_text_decorations(canvas, x, y, cell_width, cell_height, r, g, b, bold, underline, strikethrough)?;
-
-        return Ok(());
     }
 
-    // Render all glyphs in white for color modulation
-    let render_color = Color::RGB(255, 255, 255);
-
-    // Check if this is an emoji character - if so, try emoji font FIRST
-    let is_likely_emoji = is_emoji_grapheme(text);
-
-    // Check if this is a CJK character - if so, try CJK font FIRST
-    let is_likely_cjk = is_cjk_grapheme(text);
-
-    if is_likely_emoji {
-        // Try emoji font first for emoji characters
-        let emoji_result = emoji_font.render(text).blended(render_color);
-        if let Ok(surface) = emoji_result {
-            if surface.width() > 0 && surface.height() > 0 {
-                if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
-                    // Scale emoji to fill available space (double-width emojis get 2x cell_width)
-                    // Use the smaller of width or height to maintain square aspect ratio
-                    let target_size = cell_width.min(cell_height);
-
-                    let emoji_width = surface.width();
-                    let emoji_height = surface.height();
-
-                    // Calculate scaling to fit the target size while maintaining aspect ratio
-                    let scale_x = target_size as f32 / emoji_width as f32;
-                    let scale_y = target_size as f32 / emoji_height as f32;
-                    let scale = scale_x.min(scale_y);
-
-                    let scaled_width = (emoji_width as f32 * scale) as u32;
-                    let scaled_height = (emoji_height as f32 * scale) as u32;
-
-                    // Center the emoji in the cell (horizontally and vertically)
-                    let offset_x = (cell_width as i32 - scaled_width as i32) / 2;
-                    let offset_y = (cell_height as i32 - scaled_height as i32) / 2;
-
-                    let char_rect = Rect::new(x + offset_x, y + offset_y, scaled_width, scaled_height);
-                    // Note: Emojis already rendered in white, color mod applied to cache lookup above
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    // Cache the texture for next frame
-                    glyph_cache.insert(cache_key.clone(), texture);
-                    return Ok(());
+    if glyph_atlas.get(text).is_none() && !glyph_atlas.is_missing(text) {
+        match rasterize_glyph(font, emoji_font, unicode_fallback_font, cjk_font, text) {
+            Some(surface) => {
+                if let Err(e) = glyph_atlas.insert(texture_creator, text, &surface) {
+                    eprintln!("[RENDER] Failed to add {:?} to the glyph atlas: {}", text, e);
                 }
             }
+            None => glyph_atlas.mark_missing(text),
         }
     }
 
-    // Try CJK font first for CJK characters (Chinese, Japanese, Korean)
-    if is_likely_cjk {
-        let cjk_result = cjk_font.render(text).blended(render_color);
-        if let Ok(surface) = cjk_result {
-            if surface.width() > 0 && surface.height() > 0 {
-                if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
-                    let char_rect = Rect::new(x, y, surface.width(), surface.height());
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    glyph_cache.insert(cache_key.clone(), texture);
-                    return Ok(());
-                }
-            }
-        }
+    if let Some(glyph) = glyph_atlas.get(text) {
+        let char_rect = glyph_dest_rect(text, x, y, cell_width, cell_height, glyph.rect.width(), glyph.rect.height(), glyph_scale);
+        copy_glyph(canvas, glyph_atlas, glyph, char_rect, Color::RGB(r, g, b))?;
+    } else if glyph_atlas.is_missing(text) {
+        draw_missing_glyph(
+            canvas,
+            texture_creator,
+            font,
+            glyph_atlas,
+            missing_glyph,
+            text,
+            x,
+            y,
+            Color::RGB(r, g, b),
+            cell_width,
+            cell_height,
+            glyph_scale,
+        )?;
     }
 
-    // Check if this is a symbol from ranges that are often missing from terminal fonts
-    // but present in FreeMono: Miscellaneous Technical, Dingbats, Geometric Shapes, etc.
-    let is_special_missing_symbol = text.chars().count() == 1 && text.chars().next().map_or(false, is_special_symbol);
+    draw_text_decorations(canvas, x, y, cell_width, cell_height, r, g, b, bold, underline, strikethrough)?;
 
-    // Check if this is a block/box drawing character that needs cell-filling
-    let is_block_box_char = text.chars().count() == 1 && text.chars().next().map_or(false, is_block_or_box_drawing);
+    Ok(())
+}
 
-    // For block/box drawing characters, try unicode fallback font FIRST and scale to fill cell
-    if is_block_box_char && !is_likely_emoji {
-        let unicode_fallback_result = unicode_fallback_font.render(text).blended(render_color);
-        if let Ok(unicode_surface) = unicode_fallback_result {
-            if unicode_surface.width() > 0 && unicode_surface.height() > 0 {
-                if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&unicode_surface) {
-                    // Stretch to fill the entire cell for ASCII art
-                    // No aspect ratio preservation - these characters are designed to be stretched
-                    let char_rect = Rect::new(x, y, cell_width, cell_height);
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    glyph_cache.insert(cache_key, texture);
-                    return Ok(());
-                }
-            }
+/// Copy a glyph from the atlas. Glyphs are white in the atlas, color modulation tints them.
+fn copy_glyph(canvas: &mut Canvas<Window>, glyph_atlas: &mut GlyphAtlas, glyph: AtlasGlyph, dest: Rect, color: Color) -> Result<(), String> {
+    let page = glyph_atlas.page_mut(glyph.page);
+    page.set_color_mod(color.r, color.g, color.b);
+    canvas.copy(page, glyph.rect, dest).map_err(|e| e.to_string())
+}
+
+/// Text in the main font from the atlas under `key`, rasterized on first use
+fn text_glyph<'a, T>(texture_creator: &'a TextureCreator<T>, font: &Font, glyph_atlas: &mut GlyphAtlas<'a>, key: &str, text: &str) -> Option<AtlasGlyph> {
+    if let Some(glyph) = glyph_atlas.get(key) {
+        return Some(glyph);
+    }
+    let surface = font
+        .render(text)
+        .blended(Color::RGB(255, 255, 255))
+        .ok()
+        .filter(|surface| surface.width() > 0 && surface.height() > 0)?;
+    glyph_atlas
+        .insert(texture_creator, key, &surface)
+        .map_err(|e| eprintln!("[RENDER] Failed to add {:?} to the glyph atlas: {}", key, e))
+        .ok()
+}
+
+/// Draw a grapheme that no font has, the way `terminal.missingGlyph` (`style`) asks for
+#[allow(clippy::too_many_arguments)]
+fn draw_missing_glyph<'a, T>(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &'a TextureCreator<T>,
+    font: &Font,
+    glyph_atlas: &mut GlyphAtlas<'a>,
+    style: MissingGlyph,
+    text: &str,
+    x: i32,
+    y: i32,
+    color: Color,
+    cell_width: u32,
+    cell_height: u32,
+    glyph_scale: (f32, f32),
+) -> Result<(), String> {
+    match style {
+        MissingGlyph::Blank => Ok(()),
+        MissingGlyph::Box => {
+            let Some(glyph) = text_glyph(texture_creator, font, glyph_atlas, MISSING_BOX_KEY, "□") else {
+                return Ok(());
+            };
+            let dest = glyph_dest_rect("□", x, y, cell_width, cell_height, glyph.rect.width(), glyph.rect.height(), glyph_scale);
+            copy_glyph(canvas, glyph_atlas, glyph, dest, color)
         }
-    } else if is_special_missing_symbol && !is_likely_emoji {
-        let unicode_fallback_result = unicode_fallback_font.render(text).blended(render_color);
-        if let Ok(unicode_surface) = unicode_fallback_result {
-            if unicode_surface.width() > 0 && unicode_surface.height() > 0 {
-                if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&unicode_surface) {
-                    // Scale up special symbols to make them more visible (2.0x larger than default)
-                    let target_size = (cell_width.min(cell_height) as f32 * 2.0) as u32;
-
-                    let symbol_width = unicode_surface.width();
-                    let symbol_height = unicode_surface.height();
-
-                    // Calculate scaling to fit the target size while maintaining aspect ratio
-                    let scale_x = target_size as f32 / symbol_width as f32;
-                    let scale_y = target_size as f32 / symbol_height as f32;
-                    let scale = scale_x.min(scale_y);
-
-                    let scaled_width = (symbol_width as f32 * scale) as u32;
-                    let scaled_height = (symbol_height as f32 * scale) as u32;
-
-                    // Center the symbol in the cell
-                    let offset_x = (cell_width as i32 - scaled_width as i32) / 2;
-                    let offset_y = (cell_height as i32 - scaled_height as i32) / 2;
-
-                    let char_rect = Rect::new(x + offset_x, y + offset_y, scaled_width, scaled_height);
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    glyph_cache.insert(cache_key, texture);
-                    return Ok(());
+        MissingGlyph::Hex => {
+            let Some(ch) = text.chars().next() else {
+                return Ok(());
+            };
+            canvas.set_draw_color(color);
+            canvas.draw_rect(Rect::new(x, y, cell_width, cell_height)).map_err(|e| e.to_string())?;
+
+            // The digits in two rows inside the frame, shrunk to fit
+            let (inner_width, row_height) = (cell_width.saturating_sub(2).max(1), (cell_height.saturating_sub(2) / 2).max(1));
+            let hex = codepoint_hex(ch);
+            let (top, bottom) = hex.split_at(hex.len().div_ceil(2));
+            for (row, digits) in [top, bottom].into_iter().enumerate() {
+                if let Some(glyph) = text_glyph(texture_creator, font, glyph_atlas, &hex_digits_key(digits), digits) {
+                    let row_y = y + 1 + (row as u32 * row_height) as i32;
+                    let dest = fit_symbol_rect(x + 1, row_y, inner_width, row_height, glyph.rect.width(), glyph.rect.height());
+                    copy_glyph(canvas, glyph_atlas, glyph, dest, color)?;
                 }
             }
+            Ok(())
         }
     }
+}
 
-    // Not in cache, render and cache it (try main font for non-emoji or if emoji font failed)
-    // For single characters use render_char, for grapheme clusters use render
-    let render_result = if text.chars().count() == 1 {
-        font.render_char(text.chars().next().unwrap()).blended(render_color)
-    } else {
-        font.render(text).blended(render_color)
+/// A character's codepoint as at least four uppercase hex digits, e.g. "00E9" for 'é'
+fn codepoint_hex(ch: char) -> String {
+    format!("{:04X}", ch as u32)
+}
+
+/// Atlas key of the missing glyph box. Keys of missing glyph parts start with a NUL, which
+/// never reaches the screen as text, so they can't collide with a real grapheme's glyph.
+const MISSING_BOX_KEY: &str = "\0missing:box";
+
+/// Atlas key of a row of hex digits in a missing glyph frame
+fn hex_digits_key(digits: &str) -> String {
+    format!("\0missing:hex:{}", digits)
+}
+
+/// Rasterize a grapheme in white, trying the font most likely to have it first. None when no
+/// font has it.
+fn rasterize_glyph(font: &Font, emoji_font: &Font, unicode_fallback_font: &Font, cjk_font: &Font, text: &str) -> Option<Surface<'static>> {
+    // Render all glyphs in white for color modulation
+    let render_color = Color::RGB(255, 255, 255);
+    let render = |font: &Font| {
+        font.render(text)
+            .blended(render_color)
+            .ok()
+            .filter(|surface| surface.width() > 0 && surface.height() > 0)
     };
 
-    if let Ok(surface) = render_result {
-        if surface.width() > 0 && surface.height() > 0 {
-            if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
-                // If this is a block/box drawing character, stretch to fill entire cell
-                if is_block_box_char {
-                    // Stretch to fill the entire cell for ASCII art
-                    // No aspect ratio preservation - these characters are designed to be stretched
-                    let char_rect = Rect::new(x, y, cell_width, cell_height);
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    glyph_cache.insert(cache_key, texture);
-                    return Ok(());
-                } else {
-                    let char_rect = Rect::new(x, y, surface.width(), surface.height());
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    // Cache the texture for next frame
-                    glyph_cache.insert(cache_key, texture);
-                    return Ok(());
-                }
-            }
+    let single_char = {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(ch),
+            _ => None,
         }
+    };
 
-        // Main font produced empty surface - try fallback fonts
-        if !is_likely_emoji {
-            // Try emoji font for non-emoji characters (might be symbols with emoji variants)
-            let emoji_fallback_result = emoji_font.render(text).blended(render_color);
-            if let Ok(emoji_surface) = emoji_fallback_result {
-                if emoji_surface.width() > 0 && emoji_surface.height() > 0 {
-                    if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&emoji_surface) {
-                        let char_rect = Rect::new(x, y, emoji_surface.width(), emoji_surface.height());
-                        canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                        glyph_cache.insert(cache_key, texture);
-                        return Ok(());
-                    }
-                }
-            }
+    // Emoji and CJK characters try their own fonts first
+    let is_likely_emoji = is_emoji_grapheme(text);
+    if is_likely_emoji {
+        if let Some(surface) = render(emoji_font) {
+            return Some(surface);
         }
-
-        // Try CJK font for CJK characters (Chinese, Japanese, Korean)
-        let cjk_fallback_result = cjk_font.render(text).blended(render_color);
-        if let Ok(cjk_surface) = cjk_fallback_result {
-            if cjk_surface.width() > 0 && cjk_surface.height() > 0 {
-                if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&cjk_surface) {
-                    let char_rect = Rect::new(x, y, cjk_surface.width(), cjk_surface.height());
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    glyph_cache.insert(cache_key, texture);
-                    return Ok(());
-                }
-            }
+    }
+    let is_likely_cjk = is_cjk_grapheme(text);
+    if is_likely_cjk {
+        if let Some(surface) = render(cjk_font) {
+            return Some(surface);
         }
+    }
 
-        // Try Unicode fallback font (for all characters that failed emoji/main/CJK fonts)
-        // Skip if we already tried it above for the 3 special symbols
-        if !is_special_missing_symbol {
-            let unicode_fallback_result = unicode_fallback_font.render(text).blended(render_color);
-            if let Ok(unicode_surface) = unicode_fallback_result {
-                if unicode_surface.width() > 0 && unicode_surface.height() > 0 {
-                    if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&unicode_surface) {
-                        let char_rect = Rect::new(x, y, unicode_surface.width(), unicode_surface.height());
-                        canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                        glyph_cache.insert(cache_key, texture);
-                        return Ok(());
-                    }
-                }
-            }
+    // Symbols from ranges that are often missing from terminal fonts but present in FreeMono:
+    // Miscellaneous Technical, Dingbats, Geometric Shapes, block and box drawing, etc.
+    // Powerline and Nerd Font glyphs only exist in the (patched) main font, so they skip it.
+    let is_special_missing_symbol = single_char.is_some_and(is_special_symbol);
+    let is_private_use = single_char.is_some_and(is_private_use_symbol);
+    let is_block_box_char = single_char.is_some_and(is_block_or_box_drawing);
+    let tried_unicode_fallback = !is_likely_emoji && (is_block_box_char || (is_special_missing_symbol && !is_private_use));
+    if tried_unicode_fallback {
+        if let Some(surface) = render(unicode_fallback_font) {
+            return Some(surface);
         }
+    }
 
-        // Character not supported in any font, try fallback '□'
-        let fallback_key = "□".to_string();
-        if let Some(cached_fallback) = glyph_cache.get_mut(&fallback_key) {
-            cached_fallback.set_color_mod(r, g, b);
-            let query = cached_fallback.query();
-            let char_rect = Rect::new(x, y, query.width, query.height);
-            canvas.copy(cached_fallback, None, char_rect).map_err(|e| e.to_string())?;
-        } else if let Ok(fallback_surface) = font.render_char('□').blended(render_color) {
-            if fallback_surface.width() > 0 && fallback_surface.height() > 0 {
-                if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&fallback_surface) {
-                    let char_rect = Rect::new(x, y, fallback_surface.width(), fallback_surface.height());
-                    canvas.copy(&texture, None, char_rect).map_err(|e| e.to_string())?;
-                    glyph_cache.insert(fallback_key, texture);
-                }
-            }
+    // The main font, using render_char for single characters
+    let main_result = match single_char {
+        Some(ch) => font
+            .render_char(ch)
+            .blended(render_color)
+            .ok()
+            .filter(|surface| surface.width() > 0 && surface.height() > 0),
+        None => render(font),
+    };
+    if main_result.is_some() {
+        return main_result;
+    }
+
+    // Fallback fonts, the emoji font first as some symbols only have emoji variants
+    if !is_likely_emoji {
+        if let Some(surface) = render(emoji_font) {
+            return Some(surface);
+        }
+    }
+    if let Some(surface) = render(cjk_font) {
+        return Some(surface);
+    }
+    if !tried_unicode_fallback {
+        if let Some(surface) = render(unicode_fallback_font) {
+            return Some(surface);
         }
     }
 
-    // Draw decorations for non-cached glyphs
-    draw_text_decorations(canvas, x, y, cell_width, cell_height, r, g, b, bold, underline, strikethrough)?;
+    // Character not supported in any font
+    None
+}
 
-    Ok(())
+/// Where a glyph of the given size is drawn for a cell: block and box drawing characters fill
+/// the cell, emoji and symbols are fitted into it, text keeps its natural size
+#[allow(clippy::too_many_arguments)]
+fn glyph_dest_rect(text: &str, x: i32, y: i32, cell_width: u32, cell_height: u32, glyph_width: u32, glyph_height: u32, glyph_scale: (f32, f32)) -> Rect {
+    let mut chars = text.chars();
+    let single_char = match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    };
+
+    if single_char.is_some_and(is_block_or_box_drawing) {
+        // Stretch block/box drawing characters to fill the entire cell for ASCII art
+        // No aspect ratio preservation - these characters are designed to be stretched
+        Rect::new(x, y, cell_width, cell_height)
+    } else if is_emoji_grapheme(text) {
+        // Scale emoji to fill available space (double-width emojis get 2x cell_width)
+        // Use the smaller of width or height to maintain square aspect ratio
+        let target_size = cell_width.min(cell_height);
+        let scale = (target_size as f32 / glyph_width as f32).min(target_size as f32 / glyph_height as f32);
+
+        let scaled_width = (glyph_width as f32 * scale) as u32;
+        let scaled_height = (glyph_height as f32 * scale) as u32;
+
+        // Center the emoji in the cell (horizontally and vertically)
+        let offset_x = (cell_width as i32 - scaled_width as i32) / 2;
+        let offset_y = (cell_height as i32 - scaled_height as i32) / 2;
+        Rect::new(x + offset_x, y + offset_y, scaled_width, scaled_height)
+    } else if single_char.is_some_and(is_special_symbol) {
+        // Fit symbols (arrows, Powerline separators, Nerd Font icons) to the cell height
+        fit_symbol_rect(x, y, cell_width, cell_height, glyph_width, glyph_height)
+    } else {
+        // Regular character - use original size
+        scaled_glyph_rect(x, y, glyph_width, glyph_height, glyph_scale)
+    }
+}
+
+/// Destination rect for a glyph drawn at its natural size, stretched on double-width/height rows
+/// and by the pane's font scale
+fn scaled_glyph_rect(x: i32, y: i32, glyph_width: u32, glyph_height: u32, glyph_scale: (f32, f32)) -> Rect {
+    Rect::new(
+        x,
+        y,
+        (glyph_width as f32 * glyph_scale.0).round() as u32,
+        (glyph_height as f32 * glyph_scale.1).round() as u32,
+    )
+}
+
+/// Destination rect for a symbol glyph (arrows, Powerline separators, Nerd Font icons).
+/// The glyph is scaled to the cell height preserving its aspect ratio, shrunk further if it
+/// would overflow the cell width, and centered horizontally.
+fn fit_symbol_rect(x: i32, y: i32, cell_width: u32, cell_height: u32, glyph_width: u32, glyph_height: u32) -> Rect {
+    let scale = (cell_height as f32 / glyph_height as f32).min(cell_width as f32 / glyph_width as f32);
+
+    let scaled_width = ((glyph_width as f32 * scale).round() as u32).max(1);
+    let scaled_height = ((glyph_height as f32 * scale).round() as u32).max(1);
+
+    let offset_x = (cell_width as i32 - scaled_width as i32) / 2;
+    let offset_y = (cell_height as i32 - scaled_height as i32) / 2;
+
+    Rect::new(x + offset_x, y + offset_y, scaled_width, scaled_height)
 }
 
 /// Draw text decorations (underline, strikethrough, bold effect)
@@ -842,6 +1165,9 @@ fn draw_text_decorations(
     Ok(())
 }
 
+/// Text color for overlays drawn on top of pane content
+const OVERLAY_TEXT_COLOR: Color = Color::RGB(255, 200, 0);
+
 /// Render scrollback position indicator
 fn render_scrollback_indicator<T>(
     canvas: &mut Canvas<Window>,
@@ -852,9 +1178,8 @@ fn render_scrollback_indicator<T>(
     pane_padding: u32,
 ) -> Result<(), String> {
     let scroll_text = format!("[Scrollback: {} lines]", scroll_offset);
-    let text_color = Color::RGB(255, 200, 0);
 
-    if let Ok(surface) = font.render(&scroll_text).blended(text_color) {
+    if let Ok(surface) = font.render(&scroll_text).blended(OVERLAY_TEXT_COLOR) {
         if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
             let text_width = surface.width();
             let text_height = surface.height();
@@ -871,10 +1196,159 @@ fn render_scrollback_indicator<T>(
     Ok(())
 }
 
-/// Render dividers between panes
-fn render_dividers(canvas: &mut Canvas<Window>, dividers: &[(crate::pane_layout::PaneId, Rect, crate::pane_layout::SplitDirection)]) -> Result<(), String> {
-    for (_split_id, rect, _direction) in dividers {
-        canvas.set_draw_color(Color::RGB(60, 60, 60));
+/// Label of the badge shown when output arrives while the view is scrolled back
+const NEW_OUTPUT_BADGE_TEXT: &str = "new output ↓";
+
+/// Where the "new output" badge sits: centered at the bottom of the pane, sized in cells so
+/// mouse handling can hit-test it without a font
+pub fn new_output_badge_rect(rect: Rect, char_width: f32, char_height: f32) -> Rect {
+    let width = ((NEW_OUTPUT_BADGE_TEXT.chars().count() + 2) as f32 * char_width) as u32;
+    let height = char_height as u32 + 4;
+    let x = rect.x() + (rect.width() as i32 - width as i32) / 2;
+    let y = rect.y() + rect.height() as i32 - height as i32 - 5 - get_pane_padding() as i32;
+    Rect::new(x, y, width, height)
+}
+
+/// Render the clickable "new output" badge
+fn render_new_output_badge<T>(canvas: &mut Canvas<Window>, texture_creator: &TextureCreator<T>, font: &Font, badge_rect: Rect) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(50, 90, 130));
+    canvas.fill_rect(badge_rect).map_err(|e| e.to_string())?;
+
+    if let Ok(surface) = font.render(NEW_OUTPUT_BADGE_TEXT).blended(Color::RGB(230, 230, 230)) {
+        if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
+            let text_x = badge_rect.x() + (badge_rect.width() as i32 - surface.width() as i32) / 2;
+            let text_y = badge_rect.y() + (badge_rect.height() as i32 - surface.height() as i32) / 2;
+            let text_rect = Rect::new(text_x, text_y, surface.width(), surface.height());
+            canvas.copy(&texture, None, text_rect).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Text of the resize overlay
+pub fn format_grid_size(cols: u32, rows: u32) -> String {
+    format!("{} × {}", cols, rows)
+}
+
+/// Render the resize overlay centered in the pane, styled like the scrollback indicator
+fn render_resize_overlay<T>(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<T>,
+    font: &Font,
+    rect: Rect,
+    text: &str,
+    opacity: u8,
+) -> Result<(), String> {
+    if let Ok(surface) = font.render(text).blended(OVERLAY_TEXT_COLOR) {
+        if let Ok(mut texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
+            let text_width = surface.width();
+            let text_height = surface.height();
+            let text_x = rect.x() + (rect.width() as i32 - text_width as i32) / 2;
+            let text_y = rect.y() + (rect.height() as i32 - text_height as i32) / 2;
+
+            // Box behind the text so it stays readable over pane content
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(DEFAULT_BG_COLOR.r, DEFAULT_BG_COLOR.g, DEFAULT_BG_COLOR.b, opacity));
+            canvas
+                .fill_rect(Rect::new(text_x - 12, text_y - 6, text_width + 24, text_height + 12))
+                .map_err(|e| e.to_string())?;
+
+            texture.set_alpha_mod(opacity);
+            let text_rect = Rect::new(text_x, text_y, text_width, text_height);
+            canvas.copy(&texture, None, text_rect).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Render a toast message at the top-right of the pane, styled like the scrollback indicator
+fn render_toast<T>(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<T>,
+    font: &Font,
+    rect: Rect,
+    message: &str,
+    pane_padding: u32,
+) -> Result<(), String> {
+    let toast_text = format!("[{}]", message);
+
+    if let Ok(surface) = font.render(&toast_text).blended(OVERLAY_TEXT_COLOR) {
+        if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
+            let text_width = surface.width();
+            let text_height = surface.height();
+
+            // Position at top-right of the pane, clear of the scrollback indicator
+            let toast_x = rect.x() + rect.width() as i32 - text_width as i32 - 10 - pane_padding as i32;
+            let toast_y = rect.y() + 5 + pane_padding as i32;
+
+            // Clear the content underneath so the message stays readable
+            canvas.set_draw_color(DEFAULT_BG_COLOR);
+            canvas
+                .fill_rect(Rect::new(toast_x - 4, toast_y - 2, text_width + 8, text_height + 4))
+                .map_err(|e| e.to_string())?;
+
+            let text_rect = Rect::new(toast_x, toast_y, text_width, text_height);
+            canvas.copy(&texture, None, text_rect).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Render the title header above a pane
+fn render_pane_header<T>(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<T>,
+    font: &Font,
+    rect: Rect,
+    title: &str,
+    is_active: bool,
+    colors: &PaneColors,
+) -> Result<(), String> {
+    let (bg_color, text_color) = if is_active {
+        (colors.header_active, colors.header_active_text)
+    } else {
+        (colors.header, colors.header_text)
+    };
+    canvas.set_draw_color(bg_color);
+    canvas.fill_rect(rect).map_err(|e| e.to_string())?;
+
+    if title.is_empty() {
+        return Ok(());
+    }
+
+    if let Ok(surface) = font.render(title).blended(text_color) {
+        if let Ok(texture) = texture_creator.create_texture_from_surface::<&sdl3::surface::Surface>(&surface) {
+            // Long titles are cut off at the pane's edge
+            let text_x = rect.x() + get_pane_padding() as i32;
+            let text_y = rect.y() + (rect.height() as i32 - surface.height() as i32) / 2;
+            let previous_clip = canvas.clip_rect();
+            canvas.set_clip_rect(previous_clip.intersect_rect(rect));
+            let result = canvas.copy(&texture, None, Rect::new(text_x, text_y, surface.width(), surface.height()));
+            canvas.set_clip_rect(previous_clip);
+            result.map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Render dividers between panes, highlighting the one under the pointer
+fn render_dividers(
+    canvas: &mut Canvas<Window>,
+    dividers: &[(crate::pane_layout::PaneId, Rect, crate::pane_layout::SplitDirection)],
+    hovered_divider: Option<crate::pane_layout::PaneId>,
+    colors: &PaneColors,
+) -> Result<(), String> {
+    for (split_id, rect, _direction) in dividers {
+        let color = if hovered_divider == Some(*split_id) {
+            colors.divider_hover
+        } else {
+            colors.divider
+        };
+        canvas.set_draw_color(color);
         canvas.fill_rect(*rect).map_err(|e| e.to_string())?;
     }
     Ok(())
@@ -885,9 +1359,10 @@ fn render_context_menu<T>(
     canvas: &mut Canvas<Window>,
     texture_creator: &TextureCreator<T>,
     menu_font: &Font,
+    menu_emoji_font: &Font,
     menu: &ContextMenu<String>,
 ) -> Result<(), String> {
-    menu.render(canvas, texture_creator, menu_font)?;
+    menu.render(canvas, texture_creator, menu_font, menu_emoji_font)?;
     Ok(())
 }
 
@@ -906,3 +1381,56 @@ fn render_copy_animation(canvas: &mut Canvas<Window>, animation: &crate::ui::ani
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_glyph_codepoint_hex() {
+        assert_eq!(codepoint_hex('A'), "0041");
+        assert_eq!(codepoint_hex('é'), "00E9");
+        assert_eq!(codepoint_hex('\u{E0B0}'), "E0B0");
+        assert_eq!(codepoint_hex('\u{1FAE8}'), "1FAE8");
+
+        // The frame's digit rows live apart from the glyphs of text like "00"
+        assert_ne!(hex_digits_key("00"), "00");
+        assert_ne!(MISSING_BOX_KEY, "□");
+
+        assert_eq!(MissingGlyph::from_settings_string("hex"), MissingGlyph::Hex);
+        assert_eq!(MissingGlyph::from_settings_string("blank"), MissingGlyph::Blank);
+        assert_eq!(MissingGlyph::from_settings_string("tofu"), MissingGlyph::Box);
+    }
+
+    #[test]
+    fn test_font_zoom_changes_terminal_size() {
+        // After a font size change the pane rect stays the same but the cell grows,
+        // so the resize that follows must give the terminal fewer columns and rows.
+        // The rect leaves the same grid with either platform's pane padding.
+        assert_eq!(calculate_terminal_size(812, 412, 8.0, 16.0), (100, 25));
+        assert_eq!(calculate_terminal_size(812, 412, 10.0, 20.0), (80, 20));
+    }
+
+    #[test]
+    fn test_frame_stats_summarize_once_per_interval() {
+        let start = Instant::now();
+        let mut stats = FrameStats::new(start);
+
+        assert_eq!(stats.record(Duration::from_millis(2), start + Duration::from_secs(1)), None);
+        assert_eq!(stats.record(Duration::from_millis(6), start + Duration::from_secs(5)), None);
+        let summary = stats.record(Duration::from_millis(4), start + FRAME_STATS_INTERVAL);
+        assert_eq!(summary.as_deref(), Some("3 frames, avg 4.00 ms, worst 6.00 ms"));
+
+        // The next interval starts from scratch
+        assert_eq!(
+            stats.record(Duration::from_millis(1), start + FRAME_STATS_INTERVAL + Duration::from_secs(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format_grid_size() {
+        assert_eq!(format_grid_size(80, 24), "80 × 24");
+        assert_eq!(format_grid_size(2, 2), "2 × 2");
+    }
+}
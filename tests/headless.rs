@@ -0,0 +1,21 @@
+//! Runs the binary in headless mode and checks the screen it prints.
+
+use std::process::Command;
+
+#[test]
+fn test_headless_script_prints_screen() {
+    let script = std::env::temp_dir().join(format!("nist-headless-{}.txt", std::process::id()));
+    // Plain text, a cursor move, an erase and colored output
+    std::fs::write(&script, b"hello\r\nworld\x1b[3;5Hmoved\x1b[1;3H\x1b[K\x1b[31mred\x1b[0m").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_nist"))
+        .args(["--headless", script.to_str().unwrap(), "--cols", "20", "--rows", "4"])
+        .output()
+        .expect("failed to run nist");
+    let _ = std::fs::remove_file(&script);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines, vec!["hered", "world", "    moved", ""]);
+}